@@ -1,13 +1,19 @@
 // src/assets/mod.rs
-use std::collections::HashMap;
+pub mod watcher;
+
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 use crate::{
+    crypto,
     errors::CacaoError,
-    renderer::{Texture, Sprite},
+    renderer::{FilterMode, Texture, Sprite},
     game::AssetType,
 };
 
+pub use watcher::FileWatcher;
+
 pub struct AssetManager {
     sprites: HashMap<String, Arc<Sprite>>,
     textures: HashMap<String, Arc<Texture>>,
@@ -15,9 +21,36 @@ pub struct AssetManager {
     scripts: HashMap<String, String>,
     fonts: HashMap<String, Arc<Font>>,
     data_files: HashMap<String, Vec<u8>>,
+    // Filter mode new sprite textures are created with
+    sprite_filter: FilterMode,
     
     // Asset loading state
     loading_tasks: Vec<tokio::task::JoinHandle<()>>,
+    loaded_sender: mpsc::Sender<LoadedAsset>,
+    loaded_receiver: mpsc::Receiver<LoadedAsset>,
+    loading_total: usize,
+    loading_completed: usize,
+
+    // Hot reloading
+    asset_watcher: FileWatcher,
+    watched_assets: HashMap<PathBuf, AssetType>,
+}
+
+// Editors often save in several writes; wait for the file to settle before reloading
+const HOT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// An asset read (and for images, decoded) by a background task, waiting to be
+/// finished on the main thread by `process_loaded_assets`.
+struct LoadedAsset {
+    path: PathBuf,
+    asset_type: AssetType,
+    data: Result<LoadedData, String>,
+}
+
+enum LoadedData {
+    Image(image::DynamicImage),
+    Bytes(Vec<u8>),
+    Music(AudioClip),
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +59,22 @@ pub struct AudioClip {
     pub format: AudioFormat,
     pub sample_rate: u32,
     pub channels: u16,
+    pub frame_count: u64,
+    /// Set for music assets, which are decoded from disk as they play instead of kept in `data`
+    pub stream_path: Option<PathBuf>,
+}
+
+impl AudioClip {
+    pub fn duration_secs(&self) -> f32 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.frame_count as f32 / self.sample_rate as f32
+    }
+
+    pub fn is_streamed(&self) -> bool {
+        self.stream_path.is_some()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +84,16 @@ pub enum AudioFormat {
     Mp3,
 }
 
+impl AudioFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Font {
     pub data: Vec<u8>,
@@ -44,6 +103,7 @@ pub struct Font {
 
 impl AssetManager {
     pub fn new() -> Self {
+        let (loaded_sender, loaded_receiver) = mpsc::channel();
         Self {
             sprites: HashMap::new(),
             textures: HashMap::new(),
@@ -51,41 +111,71 @@ impl AssetManager {
             scripts: HashMap::new(),
             fonts: HashMap::new(),
             data_files: HashMap::new(),
+            sprite_filter: FilterMode::default(),
             loading_tasks: Vec::new(),
+            loaded_sender,
+            loaded_receiver,
+            loading_total: 0,
+            loading_completed: 0,
+            asset_watcher: FileWatcher::with_debounce(HOT_RELOAD_DEBOUNCE),
+            watched_assets: HashMap::new(),
         }
     }
 
     pub async fn load_asset(&mut self, path: &Path, asset_type: AssetType, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(), CacaoError> {
-        let file_name = path.file_name()
-            .ok_or_else(|| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid file path")))?
-            .to_string_lossy()
-            .to_string();
+        if let AssetType::Music = asset_type {
+            let file_name = asset_file_name(path)?;
+            let audio_clip = load_music_from_file(path)?;
+            self.audio_clips.insert(file_name.clone(), Arc::new(audio_clip));
+            log::info!("Registered streamed music: {}", file_name);
+            return Ok(());
+        }
+
+        let bytes = tokio::fs::read(path).await?;
+        self.load_asset_bytes(path, bytes, asset_type, device, queue)
+    }
+
+    /// Loads an asset that is already in memory, e.g. read out of a .gaem archive.
+    /// `path` only names the asset and picks the decoder from its extension.
+    pub fn load_asset_bytes(&mut self, path: &Path, bytes: Vec<u8>, asset_type: AssetType, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(), CacaoError> {
+        if !matches!(asset_type, AssetType::Sprite) {
+            return self.store_asset_bytes(path, bytes, asset_type);
+        }
+
+        let file_name = asset_file_name(path)?;
+        let texture = self.load_texture_from_bytes(path, &bytes, device, queue)?;
+        self.sprites.insert(file_name.clone(), Arc::new(Sprite::new(texture)));
+        log::info!("Loaded sprite: {}", file_name);
+        Ok(())
+    }
+
+    /// Stores every asset type that doesn't need the GPU.
+    fn store_asset_bytes(&mut self, path: &Path, bytes: Vec<u8>, asset_type: AssetType) -> Result<(), CacaoError> {
+        let file_name = asset_file_name(path)?;
 
         match asset_type {
             AssetType::Sprite => {
-                let texture = self.load_texture_from_file(path, device, queue).await?;
-                let sprite = Arc::new(Sprite::new(texture));
-                self.sprites.insert(file_name.clone(), sprite);
-                log::info!("Loaded sprite: {}", file_name);
+                return Err(CacaoError::RenderError(format!("Sprite {} needs a GPU to load", file_name)));
             }
-            AssetType::Audio => {
-                let audio_clip = self.load_audio_from_file(path).await?;
+            // Music that is already in memory can't be streamed from disk, so keep it as a clip
+            AssetType::Audio | AssetType::Music => {
+                let audio_clip = self.load_audio_from_bytes(path, bytes)?;
                 self.audio_clips.insert(file_name.clone(), Arc::new(audio_clip));
                 log::info!("Loaded audio: {}", file_name);
             }
             AssetType::Script => {
-                let script_content = tokio::fs::read_to_string(path).await?;
+                let script_content = String::from_utf8(bytes)
+                    .map_err(|e| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
                 self.scripts.insert(file_name.clone(), script_content);
                 log::info!("Loaded script: {}", file_name);
             }
             AssetType::Font => {
-                let font = self.load_font_from_file(path).await?;
+                let font = self.load_font_from_bytes(path, bytes);
                 self.fonts.insert(file_name.clone(), Arc::new(font));
                 log::info!("Loaded font: {}", file_name);
             }
             AssetType::Data => {
-                let data = tokio::fs::read(path).await?;
-                self.data_files.insert(file_name.clone(), data);
+                self.data_files.insert(file_name.clone(), bytes);
                 log::info!("Loaded data file: {}", file_name);
             }
         }
@@ -93,32 +183,30 @@ impl AssetManager {
         Ok(())
     }
 
-    async fn load_texture_from_file(&self, path: &Path, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Texture, CacaoError> {
-        let bytes = tokio::fs::read(path).await?;
-        let img = image::load_from_memory(&bytes)
-            .map_err(|e| CacaoError::RenderError(format!("Failed to load image: {}", e)))?;
-        
-        Texture::from_image(device, queue, &img, Some("loaded_texture"))
+    /// Applies to sprites loaded from now on; already loaded ones keep their filter.
+    pub fn set_sprite_filter(&mut self, filter: FilterMode) {
+        self.sprite_filter = filter;
     }
 
-    async fn load_audio_from_file(&self, path: &Path) -> Result<AudioClip, CacaoError> {
-        let bytes = tokio::fs::read(path).await?;
-        let extension = path.extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        let format = match extension.as_str() {
-            "wav" => AudioFormat::Wav,
-            "ogg" => AudioFormat::Ogg,
-            "mp3" => AudioFormat::Mp3,
-            _ => return Err(CacaoError::AudioError(format!("Unsupported audio format: {}", extension))),
-        };
+    fn load_texture_from_bytes(&self, path: &Path, bytes: &[u8], device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Texture, CacaoError> {
+        let img = image::load_from_memory(bytes)
+            .map_err(|e| CacaoError::RenderError(format!("Failed to decode image {}: {}", path.display(), e)))?;
+
+        let label = path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("loaded_texture");
+
+        Texture::from_image_filtered(device, queue, &img, Some(label), self.sprite_filter)
+    }
+
+    fn load_audio_from_bytes(&self, path: &Path, bytes: Vec<u8>) -> Result<AudioClip, CacaoError> {
+        let (format, extension) = audio_format_of(path)?;
 
-        let (sample_rate, channels) = if matches!(format, AudioFormat::Wav) {
+        let (sample_rate, channels, frame_count) = if matches!(format, AudioFormat::Wav) {
             parse_wav_header(&bytes)?
         } else {
-            (44100, 2)
+            probe_audio(Box::new(std::io::Cursor::new(bytes.clone())), &extension)
+                .map_err(|e| CacaoError::AudioError(format!("Failed to read {}: {}", path.display(), e)))?
         };
 
         Ok(AudioClip {
@@ -126,21 +214,148 @@ impl AssetManager {
             format,
             sample_rate,
             channels,
+            frame_count,
+            stream_path: None,
         })
     }
 
-    async fn load_font_from_file(&self, path: &Path) -> Result<Font, CacaoError> {
-        let bytes = tokio::fs::read(path).await?;
+    fn load_font_from_bytes(&self, path: &Path, bytes: Vec<u8>) -> Font {
         let name = path.file_stem()
             .and_then(|stem| stem.to_str())
             .unwrap_or("unknown")
             .to_string();
 
-        Ok(Font {
+        Font {
             data: bytes,
             name,
             size: 16.0,
-        })
+        }
+    }
+
+    /// Loads an asset file on a background task. `checksum` is the expected SHA-256, if any.
+    /// Call `process_loaded_assets` every frame to finish the loads that are done.
+    pub fn queue_asset(&mut self, path: PathBuf, asset_type: AssetType, checksum: Option<String>) {
+        if let AssetType::Music = asset_type {
+            // Music stays on disk, so only checksum and probe it
+            self.spawn_load(path.clone(), asset_type, move || {
+                if let Some(expected) = checksum {
+                    verify_file_checksum(&path, &expected)?;
+                }
+                load_music_from_file(&path).map(LoadedData::Music).map_err(|e| e.to_string())
+            });
+            return;
+        }
+
+        let read_path = path.clone();
+        self.queue_asset_with(path, asset_type, checksum, move || std::fs::read(read_path));
+    }
+
+    /// Like `queue_asset`, but the bytes come from `read`, e.g. a range of a .gaem archive.
+    /// `path` only names the asset.
+    pub fn queue_asset_with<F>(&mut self, path: PathBuf, asset_type: AssetType, checksum: Option<String>, read: F)
+    where
+        F: FnOnce() -> std::io::Result<Vec<u8>> + Send + 'static,
+    {
+        let task_path = path.clone();
+        let task_type = asset_type.clone();
+        self.spawn_load(path, asset_type, move || {
+            let bytes = read().map_err(|e| format!("Failed to read {}: {}", task_path.display(), e))?;
+            if let Some(expected) = checksum {
+                if crypto::hash_data(&bytes) != expected {
+                    return Err(format!("Asset checksum mismatch: {}", task_path.display()));
+                }
+            }
+
+            // Decoding images is the slow part, so do it here rather than on the main thread
+            match task_type {
+                AssetType::Sprite => image::load_from_memory(&bytes)
+                    .map(LoadedData::Image)
+                    .map_err(|e| format!("Failed to decode image {}: {}", task_path.display(), e)),
+                _ => Ok(LoadedData::Bytes(bytes)),
+            }
+        });
+    }
+
+    fn spawn_load<F>(&mut self, path: PathBuf, asset_type: AssetType, load: F)
+    where
+        F: FnOnce() -> Result<LoadedData, String> + Send + 'static,
+    {
+        let sender = self.loaded_sender.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let data = load();
+            // The receiver is gone if loading was cancelled, which is fine
+            let _ = sender.send(LoadedAsset { path, asset_type, data });
+        });
+
+        self.loading_tasks.push(task);
+        self.loading_total += 1;
+    }
+
+    /// Finishes background loads that are done, uploading textures on this thread.
+    /// Returns the first asset that failed; the caller should `cancel_loading`.
+    pub fn process_loaded_assets(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(), CacaoError> {
+        self.finish_loaded_assets(Some((device, queue)))
+    }
+
+    /// `process_loaded_assets` for runs without a GPU; sprites are decoded and then dropped.
+    pub fn process_loaded_assets_headless(&mut self) -> Result<(), CacaoError> {
+        self.finish_loaded_assets(None)
+    }
+
+    fn finish_loaded_assets(&mut self, gpu: Option<(&wgpu::Device, &wgpu::Queue)>) -> Result<(), CacaoError> {
+        while let Ok(loaded) = self.loaded_receiver.try_recv() {
+            self.loading_completed += 1;
+
+            let data = loaded.data.map_err(CacaoError::GameLoadError)?;
+            match data {
+                LoadedData::Image(img) => {
+                    let file_name = asset_file_name(&loaded.path)?;
+                    let Some((device, queue)) = gpu else {
+                        log::debug!("Skipping sprite without a GPU: {}", file_name);
+                        continue;
+                    };
+                    let texture = Texture::from_image_filtered(device, queue, &img, Some(&file_name), self.sprite_filter)?;
+                    self.sprites.insert(file_name.clone(), Arc::new(Sprite::new(texture)));
+                    log::info!("Loaded sprite: {}", file_name);
+                }
+                LoadedData::Music(audio_clip) => {
+                    let file_name = asset_file_name(&loaded.path)?;
+                    self.audio_clips.insert(file_name.clone(), Arc::new(audio_clip));
+                    log::info!("Registered streamed music: {}", file_name);
+                }
+                LoadedData::Bytes(bytes) => {
+                    self.store_asset_bytes(&loaded.path, bytes, loaded.asset_type)?;
+                }
+            }
+        }
+
+        self.loading_tasks.retain(|task| !task.is_finished());
+        Ok(())
+    }
+
+    /// Fraction of queued assets that have finished loading, 1.0 when nothing is queued.
+    pub fn loading_progress(&self) -> f32 {
+        if self.loading_total == 0 {
+            return 1.0;
+        }
+        self.loading_completed as f32 / self.loading_total as f32
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading_completed < self.loading_total
+    }
+
+    /// Drops all queued loads. Tasks already running finish into a closed channel.
+    pub fn cancel_loading(&mut self) {
+        for task in self.loading_tasks.drain(..) {
+            task.abort();
+        }
+
+        let (loaded_sender, loaded_receiver) = mpsc::channel();
+        self.loaded_sender = loaded_sender;
+        self.loaded_receiver = loaded_receiver;
+        self.loading_total = 0;
+        self.loading_completed = 0;
     }
 
     // Asset getters
@@ -186,6 +401,9 @@ impl AssetManager {
         self.scripts.clear();
         self.fonts.clear();
         self.data_files.clear();
+        self.asset_watcher.clear();
+        self.watched_assets.clear();
+        self.cancel_loading();
         log::info!("Cleared all assets");
     }
 
@@ -197,12 +415,18 @@ impl AssetManager {
         let mut font_memory = 0;
         let mut data_memory = 0;
 
+        // Sprites and textures can share a GPU texture, so each one is only counted once
+        let mut counted_textures = HashSet::new();
         for sprite in self.sprites.values() {
-            sprite_memory += (sprite.width * sprite.height * 4.0) as usize;
+            if counted_textures.insert(sprite.texture.id()) {
+                sprite_memory += sprite.texture.memory_size();
+            }
         }
 
         for texture in self.textures.values() {
-            texture_memory += (texture.width() * texture.height() * 4) as usize; // Assuming RGBA8
+            if counted_textures.insert(texture.id()) {
+                texture_memory += texture.memory_size();
+            }
         }
 
         for audio in self.audio_clips.values() {
@@ -250,10 +474,64 @@ impl AssetManager {
         Ok(())
     }
 
+    /// Watches the sprites and audio clips in `watch_directory` that are already loaded,
+    /// so `reload_changed_assets` can swap in new versions when they change on disk.
     pub fn enable_hot_reloading(&mut self, watch_directory: PathBuf) -> Result<(), CacaoError> {
-        log::info!("Hot reloading enabled for directory: {}", watch_directory.display());
+        if !watch_directory.is_dir() {
+            return Err(CacaoError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Not a directory: {}", watch_directory.display()),
+            )));
+        }
+
+        for entry in walkdir::WalkDir::new(&watch_directory).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let (Some(file_name), Some(asset_type)) = (path.file_name().and_then(|name| name.to_str()), determine_asset_type(path)) else {
+                continue;
+            };
+
+            let loaded = match asset_type {
+                AssetType::Sprite => self.sprites.contains_key(file_name),
+                // Streamed music is read from disk on every play already
+                AssetType::Audio => self.audio_clips.get(file_name).is_some_and(|clip| !clip.is_streamed()),
+                _ => false,
+            };
+            if loaded {
+                self.asset_watcher.watch(path);
+                self.watched_assets.insert(path.to_path_buf(), asset_type);
+            }
+        }
+
+        log::info!("Hot reloading {} assets in {}", self.watched_assets.len(), watch_directory.display());
         Ok(())
     }
+
+    /// Reloads watched assets that changed on disk and returns their names. The new version
+    /// replaces the old `Arc`, so it's drawn or played the next time it's looked up.
+    /// An asset that fails to decode keeps its previous version.
+    pub fn reload_changed_assets(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<String> {
+        let mut reloaded = Vec::new();
+
+        for path in self.asset_watcher.poll() {
+            let Some(asset_type) = self.watched_assets.get(&path).cloned() else {
+                continue;
+            };
+
+            let result = std::fs::read(&path)
+                .map_err(CacaoError::from)
+                .and_then(|bytes| self.load_asset_bytes(&path, bytes, asset_type, device, queue));
+
+            match result {
+                Ok(()) => {
+                    log::info!("🔄 Reloaded asset: {}", path.display());
+                    reloaded.push(asset_file_name(&path).unwrap_or_default());
+                }
+                Err(e) => log::warn!("⚠️ Failed to reload {}, keeping the previous version: {}", path.display(), e),
+            }
+        }
+
+        reloaded
+    }
 }
 
 #[derive(Debug)]
@@ -277,8 +555,8 @@ pub struct AssetMemoryInfo {
     pub total_memory: usize,
 }
 
-fn parse_wav_header(data: &[u8]) -> Result<(u32, u16), CacaoError> {
-    if data.len() < 44 {
+fn parse_wav_header(data: &[u8]) -> Result<(u32, u16, u64), CacaoError> {
+    if data.len() < 12 {
         return Err(CacaoError::AudioError("Invalid WAV file: too short".to_string()));
     }
 
@@ -290,13 +568,153 @@ fn parse_wav_header(data: &[u8]) -> Result<(u32, u16), CacaoError> {
         return Err(CacaoError::AudioError("Invalid WAV file: not WAVE format".to_string()));
     }
 
-    let sample_rate = u32::from_le_bytes([data[24], data[25], data[26], data[27]]);
-    let channels = u16::from_le_bytes([data[22], data[23]]);
+    // Walk the chunk list, other chunks (LIST, fact, ...) may come before fmt/data
+    let mut fmt: Option<&[u8]> = None;
+    let mut data_size: Option<u64> = None;
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let id = &data[offset..offset + 4];
+        let size = u32::from_le_bytes([data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]]) as usize;
+        let body_start = offset + 8;
+        let body_end = body_start.saturating_add(size).min(data.len());
+
+        match id {
+            b"fmt " => fmt = Some(&data[body_start..body_end]),
+            // Truncated files still report what is actually there
+            b"data" => data_size = Some((body_end - body_start) as u64),
+            _ => {}
+        }
+        if fmt.is_some() && data_size.is_some() {
+            break;
+        }
+
+        // Chunks are padded to an even size
+        offset = body_start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    let fmt = fmt.ok_or_else(|| CacaoError::AudioError("Invalid WAV file: missing fmt chunk".to_string()))?;
+    if fmt.len() < 16 {
+        return Err(CacaoError::AudioError(format!("Invalid WAV file: fmt chunk is {} bytes, expected at least 16", fmt.len())));
+    }
+
+    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+    let block_align = u16::from_le_bytes([fmt[12], fmt[13]]);
+    let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+
+    let bytes_per_frame = if block_align > 0 {
+        block_align as u64
+    } else {
+        channels as u64 * (bits_per_sample as u64).div_ceil(8)
+    };
+    let frame_count = data_size.unwrap_or(0).checked_div(bytes_per_frame).unwrap_or(0);
+
+    Ok((sample_rate, channels, frame_count))
+}
+
+/// Reads only the stream metadata; the samples stay on disk until the track is played.
+fn load_music_from_file(path: &Path) -> Result<AudioClip, CacaoError> {
+    let (format, extension) = audio_format_of(path)?;
+
+    let file = std::fs::File::open(path)?;
+    let (sample_rate, channels, frame_count) = probe_audio(Box::new(file), &extension)
+        .map_err(|e| CacaoError::AudioError(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    Ok(AudioClip {
+        data: Vec::new(),
+        format,
+        sample_rate,
+        channels,
+        frame_count,
+        stream_path: Some(path.to_path_buf()),
+    })
+}
+
+fn verify_file_checksum(path: &Path, expected: &str) -> Result<(), String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if format!("{:x}", hasher.finalize()) != expected {
+        return Err(format!("Asset checksum mismatch: {}", path.display()));
+    }
+    Ok(())
+}
+
+fn asset_file_name(path: &Path) -> Result<String, CacaoError> {
+    let file_name = path.file_name()
+        .ok_or_else(|| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid file path")))?;
+    Ok(file_name.to_string_lossy().to_string())
+}
+
+fn audio_format_of(path: &Path) -> Result<(AudioFormat, String), CacaoError> {
+    let extension = path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let format = match extension.as_str() {
+        "wav" => AudioFormat::Wav,
+        "ogg" => AudioFormat::Ogg,
+        "mp3" => AudioFormat::Mp3,
+        _ => return Err(CacaoError::AudioError(format!("Unsupported audio format: {}", extension))),
+    };
+
+    Ok((format, extension))
+}
+
+/// Reads sample rate, channel count and length of an audio stream without decoding it.
+fn probe_audio(source: Box<dyn symphonia::core::io::MediaSource>, extension: &str) -> Result<(u32, u16, u64), symphonia::core::errors::Error> {
+    use symphonia::core::{
+        errors::Error,
+        formats::FormatOptions,
+        io::MediaSourceStream,
+        meta::MetadataOptions,
+        probe::Hint,
+    };
+
+    let stream = MediaSourceStream::new(source, Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
 
-    Ok((sample_rate, channels))
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())?;
+    let mut reader = probed.format;
+
+    let track = reader.default_track()
+        .ok_or(Error::Unsupported("no audio track"))?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+
+    let sample_rate = params.sample_rate.ok_or(Error::Unsupported("unknown sample rate"))?;
+    let channels = params.channels.map(|c| c.count() as u16).unwrap_or(2);
+
+    // MP3 files without a Xing/Info header don't report a length, so count the packets
+    let frame_count = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            loop {
+                match reader.next_packet() {
+                    Ok(packet) if packet.track_id() == track_id => frames += packet.dur,
+                    Ok(_) => {}
+                    Err(Error::IoError(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            frames
+        }
+    };
+
+    Ok((sample_rate, channels, frame_count))
 }
 
-fn determine_asset_type(path: &Path) -> Option<AssetType> {
+/// Guesses an asset's type from its file extension.
+pub fn determine_asset_type(path: &Path) -> Option<AssetType> {
     let extension = path.extension()?.to_str()?.to_lowercase();
     
     match extension.as_str() {
@@ -307,4 +725,338 @@ fn determine_asset_type(path: &Path) -> Option<AssetType> {
         "json" | "xml" | "yaml" | "toml" | "csv" => Some(AssetType::Data),
         _ => None,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    // 16-bit PCM
+    fn fmt_chunk(sample_rate: u32, channels: u16) -> Vec<u8> {
+        let block_align = channels * 2;
+        let mut body = Vec::new();
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&sample_rate.to_le_bytes());
+        body.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&16u16.to_le_bytes());
+        chunk(b"fmt ", &body)
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn reads_a_plain_wav_header() {
+        let data = wav(&[fmt_chunk(44_100, 2), chunk(b"data", &[0; 400])]);
+        assert_eq!(parse_wav_header(&data).unwrap(), (44_100, 2, 100));
+    }
+
+    #[test]
+    fn skips_chunks_before_fmt_and_data() {
+        // The odd-sized LIST chunk is padded, so the walk has to account for that
+        let data = wav(&[chunk(b"LIST", b"INFOabc"), fmt_chunk(22_050, 1), chunk(b"fact", &[0; 4]), chunk(b"data", &[0; 20])]);
+        assert_eq!(parse_wav_header(&data).unwrap(), (22_050, 1, 10));
+    }
+
+    #[test]
+    fn truncated_data_counts_what_is_there() {
+        let mut data = wav(&[fmt_chunk(8_000, 1), chunk(b"data", &[0; 100])]);
+        data.truncate(data.len() - 60);
+        assert_eq!(parse_wav_header(&data).unwrap(), (8_000, 1, 20));
+    }
+
+    #[test]
+    fn rejects_files_without_a_fmt_chunk() {
+        assert!(parse_wav_header(&wav(&[chunk(b"data", &[0; 4])])).is_err());
+        assert!(parse_wav_header(b"RIFF\0\0\0\0WAVX").is_err());
+        assert!(parse_wav_header(b"RIFF").is_err());
+    }
+
+
+    // MPEG-1 layer III, 128 kbps, 32 kHz, mono frames with empty side info, so each
+    // decodes to 1152 samples of silence
+    fn mp3(frames: usize) -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xFB, 0x98, 0xC0];
+        frame.resize(144 * 128_000 / 32_000, 0);
+        frame.repeat(frames)
+    }
+
+    // Writes values least significant bit first, the way Vorbis headers are packed
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, width: usize) -> &mut Self {
+            for i in 0..width {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if value >> i & 1 == 1 {
+                    *self.bytes.last_mut().unwrap() |= 1 << (self.bits % 8);
+                }
+                self.bits += 1;
+            }
+            self
+        }
+    }
+
+    fn ogg_crc(data: &[u8]) -> u32 {
+        let mut crc = 0u32;
+        for &byte in data {
+            crc ^= (byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+            }
+        }
+        crc
+    }
+
+    fn ogg_page(flags: u8, granule: u64, sequence: u32, packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut lacing = Vec::new();
+        for packet in packets {
+            lacing.extend(std::iter::repeat_n(255, packet.len() / 255));
+            lacing.push((packet.len() % 255) as u8);
+        }
+
+        let mut page = b"OggS\0".to_vec();
+        page.push(flags);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&1u32.to_le_bytes());
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        page.extend_from_slice(&packets.concat());
+
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    // An Ogg Vorbis stream with valid headers and `samples` worth of short blocks. The
+    // setup header describes a do-nothing codebook/floor/residue, so the audio packets
+    // are one byte each; it's only meant for reading the stream info back.
+    fn ogg_vorbis(sample_rate: u32, channels: u8, samples: u64) -> Vec<u8> {
+        let mut ident = vec![1];
+        ident.extend_from_slice(b"vorbis");
+        ident.extend_from_slice(&0u32.to_le_bytes());
+        ident.push(channels);
+        ident.extend_from_slice(&sample_rate.to_le_bytes());
+        ident.extend_from_slice(&[0; 12]);
+        // 256 and 2048 sample blocks
+        ident.push(0xB8);
+        ident.push(1);
+
+        let mut comment = vec![3];
+        comment.extend_from_slice(b"vorbis");
+        comment.extend_from_slice(&5u32.to_le_bytes());
+        comment.extend_from_slice(b"cacao");
+        comment.extend_from_slice(&0u32.to_le_bytes());
+        comment.push(1);
+
+        let mut bits = BitWriter::default();
+        bits.write(0, 8)
+            // One codebook with a single one-bit entry and no lookup table
+            .write(0x564342, 24).write(1, 16).write(1, 24).write(0, 1).write(0, 1).write(0, 5).write(0, 4)
+            .write(0, 6).write(0, 16)
+            // One empty floor 1
+            .write(0, 6).write(1, 16).write(0, 5).write(0, 2).write(0, 4)
+            // One residue, one mapping, one short-block mode
+            .write(0, 6).write(0, 16).write(0, 24).write(0, 24).write(0, 24).write(0, 6).write(0, 8).write(0, 3).write(0, 1)
+            .write(0, 6).write(0, 16).write(0, 1).write(0, 1).write(0, 2).write(0, 24)
+            .write(0, 6).write(0, 1).write(0, 16).write(0, 16).write(0, 8)
+            .write(1, 1);
+        let mut setup = vec![5];
+        setup.extend_from_slice(b"vorbis");
+        setup.extend_from_slice(&bits.bytes);
+
+        // Every short block after the first adds half a block of output
+        let blocks = samples / 128 + 1;
+        let audio = vec![vec![0u8]; blocks as usize];
+        let (first, last) = audio.split_at(audio.len() / 2);
+
+        [
+            ogg_page(0x02, 0, 0, &[ident]),
+            ogg_page(0x00, 0, 1, &[comment, setup]),
+            ogg_page(0x00, (first.len() as u64 - 1) * 128, 2, first),
+            ogg_page(0x04, samples, 3, last),
+        ].concat()
+    }
+
+    fn probe(bytes: Vec<u8>, extension: &str) -> (u32, u16, u64) {
+        probe_audio(Box::new(std::io::Cursor::new(bytes)), extension).unwrap()
+    }
+
+    #[test]
+    fn probes_mp3_without_a_length_header_by_counting_frames() {
+        assert_eq!(probe(mp3(20), "mp3"), (32_000, 1, 20 * 1152));
+    }
+
+    #[test]
+    fn probes_ogg_vorbis_from_its_headers() {
+        assert_eq!(probe(ogg_vorbis(22_050, 2, 12_800), "ogg"), (22_050, 2, 12_800));
+    }
+
+    #[test]
+    fn probe_errors_on_garbage() {
+        assert!(probe_audio(Box::new(std::io::Cursor::new(vec![0x42; 256])), "ogg").is_err());
+    }
+
+    #[test]
+    fn music_files_are_probed_but_left_on_disk() {
+        let dir = std::env::temp_dir().join(format!("cacao_music_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("theme.mp3");
+        std::fs::write(&path, mp3(50)).unwrap();
+
+        let clip = load_music_from_file(&path).unwrap();
+        assert!(clip.data.is_empty());
+        assert!(matches!(clip.format, AudioFormat::Mp3));
+        assert_eq!(clip.stream_path.as_deref(), Some(path.as_path()));
+        assert!((clip.duration_secs() - 50.0 * 1152.0 / 32_000.0).abs() < 1e-4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn loaded_sounds_keep_their_bytes_and_stream_info() {
+        let assets = AssetManager::new();
+        let clip = assets.load_audio_from_bytes(Path::new("jump.ogg"), ogg_vorbis(44_100, 1, 4_410)).unwrap();
+        assert!(matches!(clip.format, AudioFormat::Ogg));
+        assert_eq!((clip.sample_rate, clip.channels), (44_100, 1));
+        assert!((clip.duration_secs() - 0.1).abs() < 1e-4);
+        assert!(!clip.is_streamed());
+    }
+
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::new(width, height).write_to(&mut bytes, image::ImageOutputFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    // Rewrites `path` with a later modification time than the watcher last saw
+    fn rewrite(path: &Path, bytes: &[u8], seconds_later: u64) {
+        std::fs::write(path, bytes).unwrap();
+        let modified = std::time::SystemTime::now() + Duration::from_secs(seconds_later);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn changed_sprites_reload_and_bad_edits_keep_the_old_one() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let dir = std::env::temp_dir().join(format!("cacao_hot_reload_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hero.png");
+        std::fs::write(&path, png_bytes(4, 4)).unwrap();
+
+        let mut assets = AssetManager::new();
+        assets.load_asset_bytes(&path, png_bytes(4, 4), AssetType::Sprite, &device, &queue).unwrap();
+        assets.enable_hot_reloading(dir.clone()).unwrap();
+
+        rewrite(&path, &png_bytes(8, 2), 10);
+        // Waits for the edit to settle first
+        assert!(assets.reload_changed_assets(&device, &queue).is_empty());
+        std::thread::sleep(HOT_RELOAD_DEBOUNCE);
+        assert_eq!(assets.reload_changed_assets(&device, &queue), vec!["hero.png".to_string()]);
+        let sprite = assets.get_sprite("hero.png").unwrap();
+        assert_eq!((sprite.width, sprite.height), (8.0, 2.0));
+
+        rewrite(&path, b"half-saved", 20);
+        assets.reload_changed_assets(&device, &queue);
+        std::thread::sleep(HOT_RELOAD_DEBOUNCE);
+        assert!(assets.reload_changed_assets(&device, &queue).is_empty());
+        assert_eq!(assets.get_sprite("hero.png").unwrap().width, 8.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn loading_progress_counts_finished_assets() {
+        let mut assets = AssetManager::new();
+        assert_eq!((assets.loading_progress(), assets.is_loading()), (1.0, false));
+
+        let (release, held) = std::sync::mpsc::channel::<()>();
+        assets.queue_asset_with("a.json".into(), AssetType::Data, None, || Ok(b"1".to_vec()));
+        assets.queue_asset_with("b.json".into(), AssetType::Data, None, || Ok(b"2".to_vec()));
+        assets.queue_asset_with("c.json".into(), AssetType::Data, None, move || {
+            held.recv().ok();
+            Ok(b"3".to_vec())
+        });
+
+        while assets.loading_progress() < 2.0 / 3.0 {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(assets.is_loading());
+        assert_eq!(assets.loading_progress(), 2.0 / 3.0);
+        assert!(assets.get_data_file("a.json").is_some() && assets.get_data_file("c.json").is_none());
+
+        release.send(()).unwrap();
+        while assets.is_loading() {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(assets.loading_progress(), 1.0);
+        assert_eq!(assets.get_data_file("c.json").map(Vec::as_slice), Some(b"3".as_slice()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_loads_are_reported_and_can_be_cancelled() {
+        let mut assets = AssetManager::new();
+        assets.queue_asset_with("level.json".into(), AssetType::Data, Some("not the checksum".to_string()), || Ok(b"{}".to_vec()));
+
+        let error = loop {
+            if let Err(e) = assets.process_loaded_assets_headless() {
+                break e;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        };
+        assert!(error.to_string().contains("Asset checksum mismatch: level.json"), "{}", error);
+
+        assets.cancel_loading();
+        assert_eq!((assets.loading_progress(), assets.is_loading()), (1.0, false));
+    }
+
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn memory_usage_adds_up_each_kind_of_asset() {
+        let mut assets = AssetManager::new();
+        assets.queue_asset_with("level.json".into(), AssetType::Data, None, || Ok(vec![0; 1500]));
+        assets.queue_asset_with("main.lua".into(), AssetType::Script, None, || Ok(b"function update(dt) end".to_vec()));
+        while assets.is_loading() {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        let memory = assets.get_memory_usage();
+        assert_eq!(memory.data_memory, 1500);
+        assert_eq!(memory.script_memory, 23);
+        assert_eq!((memory.sprite_memory, memory.audio_memory), (0, 0));
+        assert_eq!(memory.total_memory, 1523);
+
+        assets.clear_assets();
+        assert_eq!(assets.get_memory_usage().total_memory, 0);
+    }
+}
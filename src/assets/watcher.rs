@@ -0,0 +1,130 @@
+// src/assets/watcher.rs
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Polls file modification times so changed files can be reloaded.
+pub struct FileWatcher {
+    files: HashMap<PathBuf, WatchedFile>,
+    debounce: Duration,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct WatchedFile {
+    modified: Option<SystemTime>,
+    // When an unreported change was last seen
+    pending_since: Option<Instant>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self::with_debounce(Duration::ZERO)
+    }
+
+    /// Only reports a file once it has stopped changing for `debounce`,
+    /// so editors that write a file in several steps trigger one reload.
+    pub fn with_debounce(debounce: Duration) -> Self {
+        Self { files: HashMap::new(), debounce }
+    }
+
+    pub fn watch(&mut self, path: &Path) {
+        self.files.insert(path.to_path_buf(), WatchedFile {
+            modified: modified_time(path),
+            pending_since: None,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    /// Returns the watched files that changed since the last poll.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let mut changed = Vec::new();
+
+        for (path, file) in self.files.iter_mut() {
+            let modified = modified_time(path);
+            // A missing file (mid-save by some editors) isn't a change yet
+            if modified.is_some() && modified != file.modified {
+                file.modified = modified;
+                file.pending_since = Some(now);
+            }
+
+            if file.pending_since.is_some_and(|since| now.duration_since(since) >= self.debounce) {
+                file.pending_since = None;
+                changed.push(path.clone());
+            }
+        }
+
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A watched file in a fresh temp dir, stamped at `modified`
+    fn watched_file(modified: SystemTime) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cacao_watcher_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.lua");
+        std::fs::write(&path, "x = 1").unwrap();
+        touch(&path, modified);
+        path
+    }
+
+    fn touch(path: &Path, modified: SystemTime) {
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn changed_files_are_reported_once() {
+        let start = SystemTime::now() - Duration::from_secs(60);
+        let path = watched_file(start);
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(watcher.poll().is_empty());
+
+        touch(&path, start + Duration::from_secs(1));
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        // Mid-save deletions aren't a change; the file coming back is
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().is_empty());
+        std::fs::write(&path, "x = 2").unwrap();
+        touch(&path, start + Duration::from_secs(2));
+        assert_eq!(watcher.poll(), vec![path]);
+    }
+
+    #[test]
+    fn debounced_files_wait_until_they_stop_changing() {
+        let start = SystemTime::now() - Duration::from_secs(60);
+        let path = watched_file(start);
+        let mut watcher = FileWatcher::with_debounce(Duration::from_millis(300));
+        watcher.watch(&path);
+
+        touch(&path, start + Duration::from_secs(1));
+        assert!(watcher.poll().is_empty());
+        std::thread::sleep(Duration::from_millis(250));
+        // Another write restarts the wait
+        touch(&path, start + Duration::from_secs(2));
+        assert!(watcher.poll().is_empty());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(watcher.poll().is_empty());
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(watcher.poll(), vec![path]);
+    }
+}
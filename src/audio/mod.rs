@@ -1,16 +1,73 @@
 // src/audio/mod.rs
-use std::collections::HashMap;
+pub mod synth;
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use std::time::Duration;
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 use crate::{errors::CacaoError, assets::AudioClip};
 
+pub use synth::{Tone, Waveform, DEFAULT_ATTACK, DEFAULT_RELEASE};
+
+const MIN_PITCH: f32 = 0.25;
+const MAX_PITCH: f32 = 4.0;
+const DEFAULT_MAX_SOUNDS: usize = 32;
+
 pub struct AudioSystem {
-    _stream: OutputStream,
-    stream_handle: OutputStreamHandle,
+    // None when running silently without an output device
+    _stream: Option<OutputStream>,
+    stream_handle: Option<OutputStreamHandle>,
     sound_sinks: HashMap<String, Sink>,
+    // Sound ids oldest first, for picking which one to drop at the cap
+    sound_order: VecDeque<String>,
+    max_sounds: usize,
     music_sink: Option<Sink>,
     master_volume: f32,
+    // Silences everything without touching master_volume
+    muted: bool,
     sound_volume: f32,
     music_volume: f32,
+    music_gain: f32,
+    music_fade: Option<Fade>,
+    stop_music_after_fade: bool,
+    outgoing_music: Option<(Sink, Fade)>,
+    // What pause_all paused, so resume_all leaves the game's own pauses alone
+    paused_sounds: Vec<String>,
+    music_paused_by_pause_all: bool,
+}
+
+/// Linear gain ramp advanced by `AudioSystem::update`.
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Fade {
+    fn new(from: f32, to: f32, duration: Duration) -> Self {
+        Self { from, to, duration: duration.as_secs_f32(), elapsed: 0.0 }
+    }
+
+    fn advance(&mut self, dt: f32) -> f32 {
+        self.elapsed += dt;
+        self.gain()
+    }
+
+    fn gain(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        let t = (self.elapsed / self.duration).min(1.0);
+        self.from + (self.to - self.from) * t
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
 }
 
 impl AudioSystem {
@@ -18,70 +75,230 @@ impl AudioSystem {
         let (stream, stream_handle) = OutputStream::try_default()
             .map_err(|e| CacaoError::AudioError(format!("Failed to create audio output stream: {}", e)))?;
 
-        Ok(Self {
+        Ok(Self::with_output(Some(stream), Some(stream_handle)))
+    }
+
+    /// An audio system with no output device. Everything behaves as usual, but sounds
+    /// are never heard and never finish, so headless runs and CI don't need a sound card.
+    pub fn new_silent() -> Self {
+        Self::with_output(None, None)
+    }
+
+    fn with_output(stream: Option<OutputStream>, stream_handle: Option<OutputStreamHandle>) -> Self {
+        Self {
             _stream: stream,
             stream_handle,
             sound_sinks: HashMap::new(),
+            sound_order: VecDeque::new(),
+            max_sounds: DEFAULT_MAX_SOUNDS,
             music_sink: None,
             master_volume: 1.0,
+            muted: false,
             sound_volume: 1.0,
             music_volume: 1.0,
-        })
+            music_gain: 1.0,
+            music_fade: None,
+            stop_music_after_fade: false,
+            outgoing_music: None,
+            paused_sounds: Vec::new(),
+            music_paused_by_pause_all: false,
+        }
+    }
+
+    fn new_sink(&self) -> Result<Sink, rodio::PlayError> {
+        match &self.stream_handle {
+            Some(stream_handle) => Sink::try_new(stream_handle),
+            None => Ok(Sink::new_idle().0),
+        }
     }
 
     pub fn play_sound(&mut self, audio_clip: &AudioClip, loop_sound: bool) -> Result<String, CacaoError> {
-        let sink = Sink::try_new(&self.stream_handle)
-            .map_err(|e| CacaoError::AudioError(format!("Failed to create audio sink: {}", e)))?;
+        self.play_sound_with_pitch(audio_clip, loop_sound, 1.0)
+    }
+
+    /// Plays a sound sped up or slowed down; 2.0 is an octave up, 0.5 an octave down.
+    pub fn play_sound_with_pitch(&mut self, audio_clip: &AudioClip, loop_sound: bool, speed: f32) -> Result<String, CacaoError> {
+        if audio_clip.is_streamed() {
+            return Err(CacaoError::AudioError("Streamed music can't be played as a sound".to_string()));
+        }
 
-        let cursor = std::io::Cursor::new(audio_clip.data.clone());
-        let source = Decoder::new(cursor)
-            .map_err(|e| CacaoError::AudioError(format!("Failed to decode audio: {}", e)))?;
+        let sink = self.new_sink()
+            .map_err(|e| CacaoError::AudioError(format!("Failed to create audio sink: {}", e)))?;
 
+        let source = pitched_source(audio_clip, speed)?;
         if loop_sound {
             sink.append(source.repeat_infinite());
         } else {
             sink.append(source);
         }
 
-        sink.set_volume(self.master_volume * self.sound_volume);
+        Ok(self.start_sound(sink))
+    }
+
+    /// Plays a generated tone rather than a clip loaded from a file. Counts as a sound
+    /// for volume, the sound cap and `stop_sound`.
+    pub fn play_tone(&mut self, tone: Tone) -> Result<String, CacaoError> {
+        let sink = self.new_sink()
+            .map_err(|e| CacaoError::AudioError(format!("Failed to create audio sink: {}", e)))?;
+        sink.append(tone);
+        Ok(self.start_sound(sink))
+    }
+
+    // Plays a sink that already has its source queued and tracks it as a sound
+    fn start_sound(&mut self, sink: Sink) -> String {
+        sink.set_volume(self.output_volume() * self.sound_volume);
         sink.play();
 
+        self.make_room();
+
         // Generate a unique ID for this sound instance
         let sound_id = uuid::Uuid::new_v4().to_string();
         self.sound_sinks.insert(sound_id.clone(), sink);
-
-        Ok(sound_id)
+        self.sound_order.push_back(sound_id.clone());
+        sound_id
     }
 
     pub fn play_music(&mut self, audio_clip: &AudioClip, loop_music: bool) -> Result<(), CacaoError> {
-        // Stop current music if playing
-        if let Some(ref music_sink) = self.music_sink {
-            music_sink.stop();
+        if let Some(path) = &audio_clip.stream_path {
+            return self.play_music_streaming(path, loop_music);
         }
 
-        let sink = Sink::try_new(&self.stream_handle)
+        // Stop current music if playing
+        self.stop_music();
+
+        let sink = self.create_music_sink(audio_clip, loop_music)?;
+        sink.set_volume(self.output_volume() * self.music_volume);
+        sink.play();
+
+        self.music_sink = Some(sink);
+        Ok(())
+    }
+
+    /// Plays a track decoded straight from disk, so only the read buffer is held in memory.
+    pub fn play_music_streaming(&mut self, path: &Path, loop_music: bool) -> Result<(), CacaoError> {
+        self.stop_music();
+
+        let sink = self.new_sink()
             .map_err(|e| CacaoError::AudioError(format!("Failed to create music sink: {}", e)))?;
+        append_streamed(&sink, path, loop_music)?;
+        sink.set_volume(self.output_volume() * self.music_volume);
+        sink.play();
 
-        let cursor = std::io::Cursor::new(audio_clip.data.clone());
-        let source = Decoder::new(cursor)
-            .map_err(|e| CacaoError::AudioError(format!("Failed to decode music: {}", e)))?;
+        self.music_sink = Some(sink);
+        Ok(())
+    }
 
-        if loop_music {
-            sink.append(source.repeat_infinite());
-        } else {
-            sink.append(source);
+    /// Starts a track from silence, crossfading out whatever was playing before.
+    pub fn play_music_faded(&mut self, audio_clip: &AudioClip, loop_music: bool, fade_in: Duration) -> Result<(), CacaoError> {
+        let sink = self.create_music_sink(audio_clip, loop_music)?;
+
+        if let Some((old_sink, _)) = self.outgoing_music.take() {
+            old_sink.stop();
+        }
+        if let Some(old_sink) = self.music_sink.take() {
+            self.outgoing_music = Some((old_sink, Fade::new(self.music_gain, 0.0, fade_in)));
+        }
+
+        self.music_gain = 0.0;
+        self.music_fade = Some(Fade::new(0.0, 1.0, fade_in));
+        self.stop_music_after_fade = false;
+
+        sink.set_volume(0.0);
+        sink.play();
+        self.music_sink = Some(sink);
+        Ok(())
+    }
+
+    pub fn fade_out_music(&mut self, duration: Duration) {
+        if self.music_sink.is_some() {
+            self.music_fade = Some(Fade::new(self.music_gain, 0.0, duration));
+            self.stop_music_after_fade = true;
+        }
+    }
+
+    /// Steps any running music fades. Called once per frame by the engine.
+    pub fn update(&mut self, dt: f32) {
+        if let Some(fade) = self.music_fade.as_mut() {
+            self.music_gain = fade.advance(dt);
+            let finished = fade.is_finished();
+            self.update_music_volume();
+
+            if finished {
+                self.music_fade = None;
+                if self.stop_music_after_fade {
+                    self.stop_music();
+                }
+            }
+        }
+
+        let volume = self.output_volume() * self.music_volume;
+        if let Some((sink, fade)) = self.outgoing_music.as_mut() {
+            let gain = fade.advance(dt);
+            sink.set_volume(volume * gain);
+            if fade.is_finished() {
+                sink.stop();
+                self.outgoing_music = None;
+            }
         }
+    }
+
+    /// Plays `intro` once, then repeats `loop_clip` forever. Both are queued on the same
+    /// sink so the loop starts on the very next sample. An intro with no data is skipped.
+    pub fn play_music_with_intro(&mut self, intro: &AudioClip, loop_clip: &AudioClip) -> Result<(), CacaoError> {
+        self.stop_music();
+
+        let sink = self.new_sink()
+            .map_err(|e| CacaoError::AudioError(format!("Failed to create music sink: {}", e)))?;
+        if intro.is_streamed() || !intro.data.is_empty() {
+            append_music(&sink, intro, false)?;
+        }
+        append_music(&sink, loop_clip, true)?;
 
-        sink.set_volume(self.master_volume * self.music_volume);
+        sink.set_volume(self.output_volume() * self.music_volume);
         sink.play();
 
         self.music_sink = Some(sink);
         Ok(())
     }
 
+    fn create_music_sink(&self, audio_clip: &AudioClip, loop_music: bool) -> Result<Sink, CacaoError> {
+        let sink = self.new_sink()
+            .map_err(|e| CacaoError::AudioError(format!("Failed to create music sink: {}", e)))?;
+        append_music(&sink, audio_clip, loop_music)?;
+        Ok(sink)
+    }
+
     pub fn stop_sound(&mut self, sound_id: &str) {
         if let Some(sink) = self.sound_sinks.remove(sound_id) {
             sink.stop();
+            self.sound_order.retain(|id| id != sound_id);
+        }
+    }
+
+    /// How many sounds can play at once. Going over drops finished sounds first, then
+    /// stops the oldest one still playing.
+    pub fn set_max_sounds(&mut self, max_sounds: usize) {
+        self.max_sounds = max_sounds.max(1);
+    }
+
+    pub fn get_max_sounds(&self) -> usize {
+        self.max_sounds
+    }
+
+    // Gets below the cap so one more sound fits
+    fn make_room(&mut self) {
+        if self.sound_sinks.len() < self.max_sounds {
+            return;
+        }
+        self.cleanup_finished_sounds();
+
+        while self.sound_sinks.len() >= self.max_sounds {
+            let Some(oldest) = self.sound_order.pop_front() else {
+                break;
+            };
+            if let Some(sink) = self.sound_sinks.remove(&oldest) {
+                sink.stop();
+            }
         }
     }
 
@@ -90,12 +307,22 @@ impl AudioSystem {
             music_sink.stop();
         }
         self.music_sink = None;
+
+        if let Some((sink, _)) = self.outgoing_music.take() {
+            sink.stop();
+        }
+        self.music_gain = 1.0;
+        self.music_fade = None;
+        self.stop_music_after_fade = false;
+        self.music_paused_by_pause_all = false;
     }
 
     pub fn stop_all_sounds(&mut self) {
         for (_, sink) in self.sound_sinks.drain() {
             sink.stop();
         }
+        self.sound_order.clear();
+        self.paused_sounds.clear();
     }
 
     pub fn stop_all(&mut self) {
@@ -103,6 +330,47 @@ impl AudioSystem {
         self.stop_music();
     }
 
+    /// Cuts every sound and lets the music fade out over `duration`. Nothing counts as
+    /// playing afterwards, even while the fade finishes.
+    pub fn fade_out_all(&mut self, duration: Duration) {
+        self.stop_all_sounds();
+        if let Some((old_sink, _)) = self.outgoing_music.take() {
+            old_sink.stop();
+        }
+        if let Some(sink) = self.music_sink.take() {
+            self.outgoing_music = Some((sink, Fade::new(self.music_gain, 0.0, duration)));
+        }
+        self.music_gain = 1.0;
+        self.music_fade = None;
+        self.stop_music_after_fade = false;
+        self.music_paused_by_pause_all = false;
+    }
+
+    /// Pauses everything that's playing until `resume_all`.
+    pub fn pause_all(&mut self) {
+        for (id, sink) in &self.sound_sinks {
+            if !sink.is_paused() {
+                sink.pause();
+                self.paused_sounds.push(id.clone());
+            }
+        }
+        if let Some(music_sink) = self.music_sink.as_ref().filter(|sink| !sink.is_paused()) {
+            music_sink.pause();
+            self.music_paused_by_pause_all = true;
+        }
+    }
+
+    pub fn resume_all(&mut self) {
+        for id in self.paused_sounds.drain(..) {
+            if let Some(sink) = self.sound_sinks.get(&id) {
+                sink.play();
+            }
+        }
+        if std::mem::take(&mut self.music_paused_by_pause_all) {
+            self.resume_music();
+        }
+    }
+
     pub fn pause_sound(&mut self, sound_id: &str) {
         if let Some(sink) = self.sound_sinks.get(sound_id) {
             sink.pause();
@@ -147,6 +415,25 @@ impl AudioSystem {
         self.master_volume
     }
 
+    /// Muting keeps the master volume as it is, so unmuting goes straight back to it.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.update_all_volumes();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    // The master volume as actually heard
+    fn output_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume
+        }
+    }
+
     pub fn get_sound_volume(&self) -> f32 {
         self.sound_volume
     }
@@ -161,7 +448,7 @@ impl AudioSystem {
     }
 
     fn update_sound_volumes(&self) {
-        let volume = self.master_volume * self.sound_volume;
+        let volume = self.output_volume() * self.sound_volume;
         for sink in self.sound_sinks.values() {
             sink.set_volume(volume);
         }
@@ -169,7 +456,7 @@ impl AudioSystem {
 
     fn update_music_volume(&self) {
         if let Some(ref music_sink) = self.music_sink {
-            music_sink.set_volume(self.master_volume * self.music_volume);
+            music_sink.set_volume(self.output_volume() * self.music_volume * self.music_gain);
         }
     }
 
@@ -187,9 +474,332 @@ impl AudioSystem {
 
     pub fn cleanup_finished_sounds(&mut self) {
         self.sound_sinks.retain(|_, sink| !sink.empty());
+        let sinks = &self.sound_sinks;
+        self.sound_order.retain(|id| sinks.contains_key(id));
     }
 
     pub fn get_active_sound_count(&self) -> usize {
         self.sound_sinks.len()
     }
-}
\ No newline at end of file
+}
+
+// Decodes a sound clip sped up by `speed`, kept within the supported pitch range
+fn pitched_source(audio_clip: &AudioClip, speed: f32) -> Result<rodio::source::Speed<Decoder<std::io::Cursor<Vec<u8>>>>, CacaoError> {
+    let cursor = std::io::Cursor::new(audio_clip.data.clone());
+    let source = Decoder::new(cursor)
+        .map_err(|e| CacaoError::AudioError(format!("Failed to decode audio: {}", e)))?;
+    Ok(source.speed(speed.clamp(MIN_PITCH, MAX_PITCH)))
+}
+
+// Queues a music clip on `sink`, streaming it from disk when it's a streamed asset
+fn append_music(sink: &Sink, audio_clip: &AudioClip, loop_music: bool) -> Result<(), CacaoError> {
+    if let Some(path) = &audio_clip.stream_path {
+        return append_streamed(sink, path, loop_music);
+    }
+
+    let cursor = std::io::Cursor::new(audio_clip.data.clone());
+    let source = Decoder::new(cursor)
+        .map_err(|e| CacaoError::AudioError(format!("Failed to decode music: {}", e)))?;
+    if loop_music {
+        sink.append(source.repeat_infinite());
+    } else {
+        sink.append(source);
+    }
+    Ok(())
+}
+
+fn append_streamed(sink: &Sink, path: &Path, loop_music: bool) -> Result<(), CacaoError> {
+    let file = File::open(path)
+        .map_err(|e| CacaoError::AudioError(format!("Failed to open music {}: {}", path.display(), e)))?;
+    append_reader(sink, BufReader::new(file), loop_music)
+}
+
+// Samples are pulled from `reader` as the sink plays, never read up front
+fn append_reader<R>(sink: &Sink, reader: R, loop_music: bool) -> Result<(), CacaoError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    // Looping re-seeks the file rather than buffering decoded samples like `repeat_infinite`
+    if loop_music {
+        let source = Decoder::new_looped(reader)
+            .map_err(|e| CacaoError::AudioError(format!("Failed to decode music: {}", e)))?;
+        sink.append(source);
+    } else {
+        let source = Decoder::new(reader)
+            .map_err(|e| CacaoError::AudioError(format!("Failed to decode music: {}", e)))?;
+        sink.append(source);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_steps_linearly_and_holds_at_the_end() {
+        let mut fade = Fade::new(1.0, 0.0, Duration::from_secs(2));
+        assert_eq!(fade.gain(), 1.0);
+        assert_eq!(fade.advance(0.5), 0.75);
+        assert_eq!(fade.advance(1.0), 0.25);
+        assert!(!fade.is_finished());
+
+        assert_eq!(fade.advance(1.0), 0.0);
+        assert!(fade.is_finished());
+        assert_eq!(fade.advance(1.0), 0.0);
+    }
+
+    #[test]
+    fn zero_length_fade_jumps_to_the_end() {
+        let mut fade = Fade::new(0.0, 1.0, Duration::ZERO);
+        assert_eq!(fade.gain(), 1.0);
+        assert!(fade.is_finished());
+        assert_eq!(fade.advance(0.016), 1.0);
+    }
+
+    // Counts every byte the decoder pulls out of the underlying data
+    struct CountingReader {
+        inner: std::io::Cursor<Vec<u8>>,
+        read: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read.fetch_add(n, std::sync::atomic::Ordering::SeqCst);
+            Ok(n)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    // Ten seconds of 16-bit mono silence, about 880 KB
+    fn long_wav() -> Vec<u8> {
+        let samples = 44_100 * 10;
+        let data_len = samples * 2;
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(36 + data_len as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&44_100u32.to_le_bytes());
+        bytes.extend_from_slice(&(44_100u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data_len as u32).to_le_bytes());
+        bytes.resize(44 + data_len, 0);
+        bytes
+    }
+
+    #[test]
+    fn streamed_music_starts_without_reading_the_whole_file() {
+        let wav = long_wav();
+        let len = wav.len();
+        let read = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let reader = CountingReader { inner: std::io::Cursor::new(wav), read: read.clone() };
+
+        let (sink, mut output) = Sink::new_idle();
+        append_reader(&sink, reader, false).unwrap();
+
+        // A tenth of a second of playback
+        assert_eq!(output.by_ref().take(4_410).count(), 4_410);
+        let bytes_read = read.load(std::sync::atomic::Ordering::SeqCst);
+        assert!(bytes_read > 0);
+        assert!(bytes_read < len / 10, "read {} of {} bytes", bytes_read, len);
+    }
+
+    fn wav_clip() -> AudioClip {
+        AudioClip {
+            data: long_wav(),
+            format: crate::assets::AudioFormat::Wav,
+            sample_rate: 44_100,
+            channels: 1,
+            frame_count: 44_100 * 10,
+            stream_path: None,
+        }
+    }
+
+    fn music_volume(audio: &AudioSystem) -> f32 {
+        audio.music_sink.as_ref().expect("music should be playing").volume()
+    }
+
+    #[test]
+    fn faded_music_rises_from_silence_to_the_music_volume() {
+        let mut audio = AudioSystem::new_silent();
+        audio.set_music_volume(0.8);
+        audio.play_music_faded(&wav_clip(), true, Duration::from_secs(1)).unwrap();
+        assert_eq!(music_volume(&audio), 0.0);
+
+        let mut volumes = Vec::new();
+        for _ in 0..12 {
+            audio.update(0.1);
+            volumes.push(music_volume(&audio));
+        }
+
+        assert!(volumes.windows(2).all(|pair| pair[0] <= pair[1]), "not monotonic: {:?}", volumes);
+        assert!(volumes[0] > 0.0 && volumes[0] < 0.8);
+        assert!((volumes[11] - 0.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn fading_out_music_stops_it_at_the_end() {
+        let mut audio = AudioSystem::new_silent();
+        audio.play_music(&wav_clip(), true).unwrap();
+        audio.fade_out_music(Duration::from_millis(500));
+
+        audio.update(0.25);
+        assert!((music_volume(&audio) - 0.5).abs() < 1e-5);
+        assert!(audio.is_music_playing());
+
+        audio.update(0.25);
+        assert!(!audio.is_music_playing());
+    }
+
+    #[test]
+    fn faded_music_crossfades_the_old_track_out() {
+        let mut audio = AudioSystem::new_silent();
+        audio.play_music(&wav_clip(), true).unwrap();
+        audio.play_music_faded(&wav_clip(), true, Duration::from_secs(1)).unwrap();
+
+        audio.update(0.5);
+        let (old_sink, _) = audio.outgoing_music.as_ref().expect("old track should still be fading");
+        assert!((old_sink.volume() - 0.5).abs() < 1e-5);
+        assert!((music_volume(&audio) - 0.5).abs() < 1e-5);
+
+        audio.update(0.5);
+        assert!(audio.outgoing_music.is_none());
+    }
+
+
+    fn sound_volume(audio: &AudioSystem, id: &str) -> f32 {
+        audio.sound_sinks[id].volume()
+    }
+
+    #[test]
+    fn sounds_play_until_stopped() {
+        let mut audio = AudioSystem::new_silent();
+        let id = audio.play_sound(&wav_clip(), false).unwrap();
+        assert!(audio.is_sound_playing(&id));
+        assert_eq!(audio.get_active_sound_count(), 1);
+
+        audio.stop_sound(&id);
+        assert!(!audio.is_sound_playing(&id));
+        assert_eq!(audio.get_active_sound_count(), 0);
+    }
+
+    #[test]
+    fn pitch_changes_speed_within_limits() {
+        let clip = wav_clip();
+        let duration = |speed| pitched_source(&clip, speed).unwrap().total_duration().unwrap().as_secs_f32();
+
+        assert!((duration(1.0) - 10.0).abs() < 1e-3);
+        assert!((duration(2.0) - 5.0).abs() < 1e-3);
+        assert!((duration(100.0) - 10.0 / MAX_PITCH).abs() < 1e-3);
+        assert!((duration(0.0) - 10.0 / MIN_PITCH).abs() < 1e-3);
+    }
+
+    #[test]
+    fn the_oldest_sound_is_stopped_at_the_cap() {
+        let mut audio = AudioSystem::new_silent();
+        assert_eq!(audio.get_max_sounds(), DEFAULT_MAX_SOUNDS);
+        let clip = wav_clip();
+
+        let ids: Vec<String> = (0..DEFAULT_MAX_SOUNDS + 1).map(|_| audio.play_sound(&clip, false).unwrap()).collect();
+        assert_eq!(audio.get_active_sound_count(), DEFAULT_MAX_SOUNDS);
+        assert!(!audio.is_sound_playing(&ids[0]));
+        assert!(ids[1..].iter().all(|id| audio.is_sound_playing(id)));
+
+        // Lowering the cap trims on the next sound
+        audio.set_max_sounds(2);
+        audio.play_sound(&clip, false).unwrap();
+        assert_eq!(audio.get_active_sound_count(), 2);
+        assert!(audio.is_sound_playing(&ids[DEFAULT_MAX_SOUNDS]));
+
+        audio.set_max_sounds(0);
+        assert_eq!(audio.get_max_sounds(), 1);
+    }
+
+    #[test]
+    fn muting_keeps_the_master_volume() {
+        let mut audio = AudioSystem::new_silent();
+        audio.set_master_volume(0.6);
+        let id = audio.play_sound(&wav_clip(), false).unwrap();
+        audio.play_music(&wav_clip(), true).unwrap();
+
+        audio.set_muted(true);
+        assert!(audio.is_muted());
+        assert_eq!(audio.get_master_volume(), 0.6);
+        assert_eq!((sound_volume(&audio, &id), music_volume(&audio)), (0.0, 0.0));
+
+        audio.set_muted(false);
+        assert_eq!((sound_volume(&audio, &id), music_volume(&audio)), (0.6, 0.6));
+    }
+
+    #[test]
+    fn volumes_clamp_and_multiply_with_the_master_volume() {
+        let mut audio = AudioSystem::new_silent();
+        let id = audio.play_sound(&wav_clip(), false).unwrap();
+        audio.play_music(&wav_clip(), true).unwrap();
+
+        audio.set_master_volume(1.5);
+        audio.set_sound_volume(0.5);
+        audio.set_music_volume(-1.0);
+        assert_eq!((audio.get_master_volume(), audio.get_sound_volume(), audio.get_music_volume()), (1.0, 0.5, 0.0));
+
+        audio.set_master_volume(0.5);
+        audio.set_music_volume(0.4);
+        assert_eq!((sound_volume(&audio, &id), music_volume(&audio)), (0.25, 0.2));
+    }
+
+    #[test]
+    fn resume_all_leaves_the_games_own_pauses_alone() {
+        let mut audio = AudioSystem::new_silent();
+        let clip = wav_clip();
+        let (playing, paused) = (audio.play_sound(&clip, false).unwrap(), audio.play_sound(&clip, false).unwrap());
+        audio.play_music(&clip, true).unwrap();
+        audio.pause_sound(&paused);
+
+        audio.pause_all();
+        assert!(!audio.is_sound_playing(&playing) && !audio.is_music_playing());
+
+        audio.resume_all();
+        assert!(audio.is_sound_playing(&playing) && audio.is_music_playing());
+        assert!(!audio.is_sound_playing(&paused));
+    }
+
+    #[test]
+    fn fade_out_all_cuts_sounds_and_fades_the_music() {
+        let mut audio = AudioSystem::new_silent();
+        let clip = wav_clip();
+        audio.play_sound(&clip, false).unwrap();
+        audio.play_music(&clip, true).unwrap();
+
+        audio.fade_out_all(Duration::from_secs(1));
+        assert_eq!(audio.get_active_sound_count(), 0);
+        assert!(!audio.is_music_playing());
+
+        audio.update(0.5);
+        let (sink, _) = audio.outgoing_music.as_ref().expect("music should still be fading");
+        assert!((sink.volume() - 0.5).abs() < 1e-5);
+        audio.update(0.5);
+        assert!(audio.outgoing_music.is_none());
+    }
+
+    #[test]
+    fn intros_are_queued_ahead_of_the_loop() {
+        let mut audio = AudioSystem::new_silent();
+        audio.play_music_with_intro(&wav_clip(), &wav_clip()).unwrap();
+        assert_eq!(audio.music_sink.as_ref().unwrap().len(), 2);
+
+        let empty_intro = AudioClip { data: Vec::new(), ..wav_clip() };
+        audio.play_music_with_intro(&empty_intro, &wav_clip()).unwrap();
+        assert_eq!(audio.music_sink.as_ref().unwrap().len(), 1);
+        assert!(audio.is_music_playing());
+    }
+}
@@ -0,0 +1,160 @@
+// src/audio/synth.rs
+use std::time::Duration;
+use rodio::Source;
+
+const TONE_SAMPLE_RATE: u32 = 44_100;
+// Raw square waves at full scale are painfully loud next to normal sound effects
+const TONE_AMPLITUDE: f32 = 0.25;
+/// Fade in used by `Tone::new`.
+pub const DEFAULT_ATTACK: Duration = Duration::from_millis(5);
+/// Fade out used by `Tone::new`.
+pub const DEFAULT_RELEASE: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    // One cycle, with `phase` running from 0 to 1
+    fn sample(&self, phase: f64) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * std::f64::consts::TAU).sin() as f32,
+            Waveform::Triangle => (1.0 - 4.0 * (phase - 0.5).abs()) as f32,
+        }
+    }
+}
+
+/// A generated beep: a plain waveform with a short fade in and out so it doesn't click.
+#[derive(Debug, Clone)]
+pub struct Tone {
+    frequency: f64,
+    waveform: Waveform,
+    position: usize,
+    length: usize,
+    attack: usize,
+    release: usize,
+}
+
+impl Tone {
+    pub fn new(frequency: f32, duration: Duration, waveform: Waveform) -> Self {
+        let mut tone = Self {
+            frequency: frequency.max(0.0) as f64,
+            waveform,
+            position: 0,
+            length: samples_in(duration),
+            attack: 0,
+            release: 0,
+        };
+        tone.set_envelope(DEFAULT_ATTACK, DEFAULT_RELEASE);
+        tone
+    }
+
+    /// Sets how long the tone takes to fade in and out. Both are shortened to fit
+    /// when the tone itself is shorter.
+    pub fn with_envelope(mut self, attack: Duration, release: Duration) -> Self {
+        self.set_envelope(attack, release);
+        self
+    }
+
+    fn set_envelope(&mut self, attack: Duration, release: Duration) {
+        let (attack, release) = (samples_in(attack), samples_in(release));
+        let total = attack + release;
+        if total > self.length {
+            self.attack = attack * self.length / total;
+            self.release = self.length - self.attack;
+        } else {
+            self.attack = attack;
+            self.release = release;
+        }
+    }
+
+    fn envelope(&self) -> f32 {
+        let remaining = self.length - self.position;
+        if self.position < self.attack {
+            self.position as f32 / self.attack as f32
+        } else if remaining <= self.release {
+            remaining as f32 / self.release as f32
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Iterator for Tone {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.length {
+            return None;
+        }
+        let phase = (self.position as f64 * self.frequency / TONE_SAMPLE_RATE as f64).fract();
+        let value = self.waveform.sample(phase) * self.envelope() * TONE_AMPLITUDE;
+        self.position += 1;
+        Some(value)
+    }
+}
+
+impl Source for Tone {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.length - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        TONE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f64(self.length as f64 / TONE_SAMPLE_RATE as f64))
+    }
+}
+
+fn samples_in(duration: Duration) -> usize {
+    (duration.as_secs_f64() * TONE_SAMPLE_RATE as f64).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn length_follows_the_duration() {
+        let tone = Tone::new(440.0, Duration::from_millis(100), Waveform::Square);
+        assert_eq!(tone.length, 4_410);
+        assert_eq!(tone.count(), 4_410);
+        assert_eq!(Tone::new(440.0, Duration::ZERO, Waveform::Square).count(), 0);
+    }
+
+    #[test]
+    fn envelope_fades_in_and_out() {
+        let attack = Duration::from_millis(10);
+        let samples: Vec<f32> = Tone::new(440.0, Duration::from_millis(100), Waveform::Square)
+            .with_envelope(attack, attack)
+            .collect();
+        let fade = samples_in(attack);
+
+        assert_eq!(samples[0], 0.0);
+        assert!(peak(&samples[..fade / 4]) < TONE_AMPLITUDE / 2.0);
+        assert_eq!(peak(&samples[fade..samples.len() - fade]), TONE_AMPLITUDE);
+        assert!(peak(&samples[samples.len() - fade / 4..]) < TONE_AMPLITUDE / 2.0);
+    }
+
+    #[test]
+    fn envelope_longer_than_the_tone_is_scaled_down() {
+        let tone = Tone::new(440.0, Duration::from_millis(10), Waveform::Sine)
+            .with_envelope(Duration::from_millis(30), Duration::from_millis(10));
+        assert_eq!(tone.attack + tone.release, tone.length);
+        assert!(tone.attack.abs_diff(3 * tone.release) <= 3, "{} / {}", tone.attack, tone.release);
+    }
+}
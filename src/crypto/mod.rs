@@ -1,9 +1,15 @@
 // src/crypto/mod.rs
 use aes_gcm::{Aes256Gcm, Nonce, aead::{Aead, KeyInit}};
+use hmac::{Hmac, Mac};
 use sha2::{Sha256, Digest};
 use rand::RngCore;
+use std::io::Read;
 use crate::errors::CacaoError;
 
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_LEN: usize = 32;
+
 pub fn encrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError> {
     let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| CacaoError::CryptoError(format!("Failed to init cipher: {:?}", e)))?;
@@ -39,6 +45,42 @@ pub fn decrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError>
     Ok(decrypted)
 }
 
+/// AES key for a game's encrypted assets and `cacao.crypto`, derived from its secret key.
+pub fn derive_asset_key(secret_key: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"cacao_asset_key");
+    hasher.update(secret_key.as_bytes());
+    hasher.finalize().into()
+}
+
+/// HMAC-SHA256 over everything `reader` yields.
+pub fn sign_reader(reader: &mut impl Read, key: &[u8]) -> Result<[u8; SIGNATURE_LEN], CacaoError> {
+    let mac = mac_reader(reader, key)?;
+    Ok(mac.finalize().into_bytes().into())
+}
+
+/// Checks an HMAC-SHA256 signature in constant time.
+pub fn verify_reader(reader: &mut impl Read, key: &[u8], signature: &[u8]) -> Result<bool, CacaoError> {
+    let mac = mac_reader(reader, key)?;
+    Ok(mac.verify_slice(signature).is_ok())
+}
+
+fn mac_reader(reader: &mut impl Read, key: &[u8]) -> Result<HmacSha256, CacaoError> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key)
+        .map_err(|e| CacaoError::CryptoError(format!("Failed to init signer: {}", e)))?;
+
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        mac.update(&buffer[..read]);
+    }
+
+    Ok(mac)
+}
+
 pub fn hash_data(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
@@ -0,0 +1,60 @@
+// src/engine/frame_stats.rs
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Rolling window of recent frame times for the debug overlay.
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    window: usize,
+}
+
+impl FrameStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(window),
+            window: window.max(1),
+        }
+    }
+
+    pub fn record(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == self.window {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    pub fn average_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    /// Frames per second over the window, or 0 before the first frame.
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time().as_secs_f32();
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fps_averages_the_latest_frames() {
+        let mut stats = FrameStats::new(4);
+        assert_eq!(stats.fps(), 0.0);
+
+        // A slow frame that has rolled out of the window no longer counts
+        stats.record(Duration::from_millis(100));
+        for _ in 0..4 {
+            stats.record(Duration::from_millis(20));
+        }
+        assert_eq!(stats.average_frame_time(), Duration::from_millis(20));
+        assert!((stats.fps() - 50.0).abs() < 1e-3);
+
+        stats.record(Duration::from_millis(60));
+        assert_eq!(stats.average_frame_time(), Duration::from_millis(30));
+    }
+}
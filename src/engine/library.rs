@@ -0,0 +1,170 @@
+// src/engine/library.rs
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::CacaoError;
+use super::GameEntry;
+
+/// How many of the latest launches count as "Recently Played".
+const RECENT_GAMES: usize = 3;
+
+/// What the library remembers about each game between launches, saved to `library.json`
+/// and keyed by `GameInfo::id`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryHistory {
+    // Unix seconds of each game's last launch
+    last_played: HashMap<Uuid, u64>,
+    favorites: HashSet<Uuid>,
+    // Total seconds spent in each game
+    playtime: HashMap<Uuid, u64>,
+}
+
+impl LibraryHistory {
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(history) => history,
+            Err(e) => {
+                log::warn!("⚠️ Failed to parse {}: {}, starting a fresh library history", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CacaoError> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn record_launch(&mut self, id: Uuid, time: SystemTime) {
+        let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.last_played.insert(id, seconds);
+    }
+
+    pub fn add_playtime(&mut self, id: Uuid, played: Duration) {
+        *self.playtime.entry(id).or_insert(0) += played.as_secs();
+    }
+
+    /// Seconds played in total, or `None` if the game has never been played.
+    pub fn playtime(&self, id: &Uuid) -> Option<u64> {
+        self.playtime.get(id).copied()
+    }
+
+    /// Returns whether the game is a favorite now.
+    pub fn toggle_favorite(&mut self, id: Uuid) -> bool {
+        if self.favorites.remove(&id) {
+            false
+        } else {
+            self.favorites.insert(id);
+            true
+        }
+    }
+
+    pub fn is_favorite(&self, id: &Uuid) -> bool {
+        self.favorites.contains(id)
+    }
+
+    /// True for the few most recently launched games.
+    pub fn is_recent(&self, id: &Uuid) -> bool {
+        self.recent_games().contains(id)
+    }
+
+    fn recent_games(&self) -> Vec<Uuid> {
+        let mut played: Vec<(&Uuid, &u64)> = self.last_played.iter().collect();
+        played.sort_by(|a, b| b.1.cmp(a.1));
+        played.into_iter().take(RECENT_GAMES).map(|(id, _)| *id).collect()
+    }
+
+    /// Recently played games first (latest on top), then favorites, then everything
+    /// else, each group by title.
+    pub fn sort_games(&self, games: &mut [GameEntry]) {
+        let recent = self.recent_games();
+        games.sort_by_cached_key(|game| {
+            let id = game.info.id;
+            match recent.iter().position(|recent_id| *recent_id == id) {
+                Some(rank) => (0, rank, String::new()),
+                None if self.is_favorite(&id) => (1, 0, game.info.title.to_lowercase()),
+                None => (2, 0, game.info.title.to_lowercase()),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameInfo;
+    use std::path::PathBuf;
+
+    fn entry(title: &str) -> GameEntry {
+        GameEntry {
+            info: GameInfo::new(title.to_string(), "Tester".to_string()),
+            file_path: PathBuf::from(format!("{}.gaem", title)),
+            banner_loaded: false,
+            banner: None,
+        }
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn recents_come_first_then_favorites_then_the_rest_by_title() {
+        let mut games: Vec<GameEntry> = ["delta", "Alpha", "echo", "bravo", "charlie", "foxtrot"].into_iter().map(entry).collect();
+        let id = |games: &[GameEntry], title: &str| games.iter().find(|game| game.info.title == title).unwrap().info.id;
+
+        let mut history = LibraryHistory::default();
+        for (title, seconds) in [("echo", 10), ("delta", 30), ("charlie", 20), ("foxtrot", 5)] {
+            history.record_launch(id(&games, title), at(seconds));
+        }
+        assert!(history.toggle_favorite(id(&games, "bravo")));
+
+        history.sort_games(&mut games);
+        let titles: Vec<&str> = games.iter().map(|game| game.info.title.as_str()).collect();
+        // foxtrot was played, but not among the latest three
+        assert_eq!(titles, ["delta", "charlie", "echo", "bravo", "Alpha", "foxtrot"]);
+        assert!(!history.is_recent(&id(&games, "foxtrot")));
+    }
+
+    #[test]
+    fn favorites_toggle() {
+        let mut history = LibraryHistory::default();
+        let game = Uuid::new_v4();
+        assert!(history.toggle_favorite(game));
+        assert!(history.is_favorite(&game));
+        assert!(!history.toggle_favorite(game));
+        assert!(!history.is_favorite(&game));
+    }
+
+    #[test]
+    fn history_survives_a_save_and_load() {
+        let path = std::env::temp_dir().join(format!("cacao_library_{}.json", Uuid::new_v4()));
+        let game = Uuid::new_v4();
+        let mut history = LibraryHistory::default();
+        assert_eq!(history.playtime(&game), None);
+        history.add_playtime(game, Duration::from_secs(90));
+        history.add_playtime(game, Duration::from_millis(30_500));
+        history.toggle_favorite(game);
+        history.record_launch(game, at(100));
+        history.save(&path).unwrap();
+
+        let loaded = LibraryHistory::load(&path);
+        assert_eq!(loaded.playtime(&game), Some(120));
+        assert!(loaded.is_favorite(&game) && loaded.is_recent(&game));
+
+        // A damaged file starts over rather than failing
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq!(LibraryHistory::load(&path).playtime(&game), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,29 +1,111 @@
 // ============================================================================
-// FILE: src/engine/mod.rs - FULLY FIXED ALL COMPILER ERRORS
+// FILE: src/engine/mod.rs
 // ============================================================================
+mod frame_stats;
+mod library;
+mod settings;
+mod timestep;
+mod transition;
+
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use winit::{
-    event::{Event, WindowEvent, VirtualKeyCode},
+    event::{ElementState, Event, KeyboardInput, MouseButton, WindowEvent, VirtualKeyCode},
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    window::{Fullscreen, Icon, Window, WindowBuilder},
 };
 
 use crate::{
-    assets::AssetManager,
+    assets::{AssetManager, FileWatcher},
     audio::AudioSystem,
     errors::CacaoError,
-    game::{Game, GameInfo, GameLoader},
-    input::InputManager,
-    renderer::Renderer,
+    game::{Game, GameEvent, GameInfo, GameLoader, ScriptSandbox},
+    input::{GamepadChange, InputManager},
+    renderer::{BlendMode, ParticleConfig, ParticleSystem, PostEffect, Renderer, Sprite, TextAlign, Texture},
     saves::SaveManager,
 };
-
-#[derive(Debug, Clone, PartialEq)]
+use frame_stats::FrameStats;
+use library::LibraryHistory;
+use settings::Settings;
+use timestep::FixedTimestep;
+use transition::TransitionStyle;
+
+const SETTINGS_ROWS: [&str; 8] = ["Master Volume", "Music Volume", "SFX Volume", "Resolution", "Fullscreen", "VSync", "Frame Limit", "Controls"];
+const VOLUME_ROWS: usize = 3;
+const RESOLUTION_ROW: usize = 3;
+const FULLSCREEN_ROW: usize = 4;
+const VSYNC_ROW: usize = 5;
+const FRAME_LIMIT_ROW: usize = 6;
+const CONTROLS_ROW: usize = 7;
+const RESOLUTIONS: [[u32; 2]; 4] = [[1280, 720], [1600, 900], [1920, 1080], [2560, 1440]];
+// `target_fps` value that turns the frame limit off
+const UNCAPPED_FPS: u32 = 0;
+const FPS_OPTIONS: [u32; 5] = [30, 60, 120, 144, UNCAPPED_FPS];
+const VOLUME_STEP: f32 = 0.1;
+const PAUSE_ROWS: [&str; 3] = ["Resume", "Settings", "Quit to Menu"];
+// The theme editor has a red, green and blue row for each of these
+const THEME_EDITOR_COLORS: [&str; 4] = ["Background", "Accent", "Text", "Card"];
+const COLOR_STEP: f32 = 0.05;
+const MAX_CUSTOM_THEMES: usize = 3;
+// Glowing dots kept alive behind the menu at any moment
+const MENU_PARTICLES: usize = 150;
+// Frames the debug overlay averages FPS over
+const FRAME_STATS_WINDOW: usize = 60;
+
+// Menus are laid out in a fixed 1280x720 space regardless of window size
+const LAYOUT_WIDTH: f32 = 1280.0;
+const LAYOUT_HEIGHT: f32 = 720.0;
+// Game list cards are this far apart, and this many fit on screen at once
+const GAME_CARD_SPACING: f32 = 120.0;
+const GAMES_PER_PAGE: usize = 5;
+const MAIN_MENU_ROWS: [f32; 5] = [300.0, 350.0, 390.0, 430.0, 470.0];
+const ISSUES_PER_PAGE: usize = 5;
+
+// Key games are signed and unlocked with
+pub(crate) const GAME_SECRET_KEY: &str = "default_key";
+
+const ENGINE_TITLE: &str = "Cacao Engine";
+const WINDOW_ICON_PNG: &[u8] = include_bytes!("../../assets/icon.png");
+
+// How often the running game's script and assets are checked for edits, in seconds
+const HOT_RELOAD_POLL_INTERVAL: f32 = 0.5;
+// How long the game's music takes to fade when going back to the menu
+const UNLOAD_MUSIC_FADE: Duration = Duration::from_millis(500);
+// Lets a game file finish copying into the games folder before the library rescans
+const LIBRARY_WATCH_DEBOUNCE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 enum Theme {
     Animated,
     Dark,
     Wii,
+    Custom(ThemeColors),
+}
+
+/// A palette made in the theme editor. The secondary text and selected card colors are
+/// mixed from these.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ThemeColors {
+    name: String,
+    background: [f32; 4],
+    accent: [f32; 4],
+    text: [f32; 4],
+    card: [f32; 4],
+}
+
+impl ThemeColors {
+    fn channel_mut(&mut self, row: usize) -> &mut f32 {
+        let color = match row / 3 {
+            0 => &mut self.background,
+            1 => &mut self.accent,
+            2 => &mut self.text,
+            _ => &mut self.card,
+        };
+        &mut color[row % 3]
+    }
 }
 
 impl Theme {
@@ -32,21 +114,30 @@ impl Theme {
             Theme::Animated => "Animated Dreams",
             Theme::Dark => "Dark Minimalist",
             Theme::Wii => "Wii Classic",
+            Theme::Custom(colors) => &colors.name,
         }
     }
 
-    // FIXED: Return slice instead of array
-    fn all() -> &'static [Theme] {
-        &[Theme::Animated, Theme::Dark, Theme::Wii]
+    /// The built-in themes followed by the user's saved ones.
+    fn all(custom: &[ThemeColors]) -> Vec<Theme> {
+        [Theme::Animated, Theme::Dark, Theme::Wii]
+            .into_iter()
+            .chain(custom.iter().cloned().map(Theme::Custom))
+            .collect()
     }
 
-    // FIXED: Better bounds checking
-    fn from_index(index: usize) -> Theme {
-        match index {
-            0 => Theme::Animated,
-            1 => Theme::Dark,
-            2 => Theme::Wii,
-            _ => Theme::Animated,
+    fn from_index(index: usize, custom: &[ThemeColors]) -> Theme {
+        Self::all(custom).into_iter().nth(index).unwrap_or(Theme::Animated)
+    }
+
+    /// This theme's palette, as a starting point for the editor.
+    fn colors(&self) -> ThemeColors {
+        ThemeColors {
+            name: self.name().to_string(),
+            background: self.background_color(),
+            accent: self.accent_color(),
+            text: self.text_color(),
+            card: self.card_color(),
         }
     }
 
@@ -55,6 +146,7 @@ impl Theme {
             Theme::Animated => [0.05, 0.02, 0.15, 1.0],
             Theme::Dark => [0.08, 0.08, 0.08, 1.0],
             Theme::Wii => [0.95, 0.95, 0.95, 1.0],
+            Theme::Custom(colors) => colors.background,
         }
     }
 
@@ -63,6 +155,7 @@ impl Theme {
             Theme::Animated => [1.0, 0.6, 0.2, 1.0],
             Theme::Dark => [0.3, 0.7, 1.0, 1.0],
             Theme::Wii => [0.4, 0.7, 1.0, 1.0],
+            Theme::Custom(colors) => colors.accent,
         }
     }
 
@@ -71,6 +164,7 @@ impl Theme {
             Theme::Animated => [0.9, 0.9, 0.9, 1.0],
             Theme::Dark => [0.95, 0.95, 0.95, 1.0],
             Theme::Wii => [0.2, 0.2, 0.2, 1.0],
+            Theme::Custom(colors) => colors.text,
         }
     }
 
@@ -79,6 +173,8 @@ impl Theme {
             Theme::Animated => [0.7, 0.7, 0.8, 1.0],
             Theme::Dark => [0.6, 0.6, 0.6, 1.0],
             Theme::Wii => [0.4, 0.4, 0.4, 1.0],
+            // Text faded a third of the way into the background
+            Theme::Custom(colors) => mix_color(colors.text, colors.background, 0.35),
         }
     }
 
@@ -87,6 +183,7 @@ impl Theme {
             Theme::Animated => [0.15, 0.12, 0.20, 0.7],
             Theme::Dark => [0.12, 0.12, 0.12, 0.9],
             Theme::Wii => [1.0, 1.0, 1.0, 0.95],
+            Theme::Custom(colors) => colors.card,
         }
     }
 
@@ -95,6 +192,7 @@ impl Theme {
             Theme::Animated => [0.25, 0.20, 0.35, 0.9],
             Theme::Dark => [0.18, 0.18, 0.22, 1.0],
             Theme::Wii => [0.85, 0.92, 1.0, 1.0],
+            Theme::Custom(colors) => mix_color(colors.card, colors.accent, 0.25),
         }
     }
 
@@ -102,20 +200,47 @@ impl Theme {
         matches!(self, Theme::Animated)
     }
 
+    fn shows_scanlines(&self) -> bool {
+        matches!(self, Theme::Wii)
+    }
+
+    /// Fullscreen effects laid over the menus for this theme's look.
+    fn overlay_effects(&self) -> Vec<PostEffect> {
+        let mut effects = Vec::new();
+        if self.shows_scanlines() {
+            effects.push(PostEffect::Scanlines { strength: 0.12, spacing: 3.0 });
+        }
+        match self {
+            Theme::Animated => effects.push(PostEffect::Vignette { strength: 0.6 }),
+            Theme::Wii => effects.push(PostEffect::Vignette { strength: 0.25 }),
+            Theme::Dark | Theme::Custom(_) => {}
+        }
+        effects
+    }
+
     fn font_name(&self) -> &str {
         match self {
             Theme::Animated => "PressStart2P",
             Theme::Dark => "Roboto",
             Theme::Wii => "RodinNTLG",
+            Theme::Custom(_) => "Roboto",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 struct GameEntry {
     info: GameInfo,
     file_path: PathBuf,
     banner_loaded: bool,
+    banner: Option<Sprite>,
+}
+
+/// A .gaem file that didn't make it into the library, and why.
+#[derive(Debug, Clone)]
+struct LibraryIssue {
+    path: PathBuf,
+    error: String,
 }
 
 #[derive(Debug, Clone)]
@@ -125,35 +250,97 @@ enum MenuState {
     GameDetails(usize),
     Settings,
     ThemeSelector,
+    /// Editing `colors`; `slot` is the saved custom theme being changed, `None` for a new one
+    ThemeEditor {
+        colors: ThemeColors,
+        slot: Option<usize>,
+        row: usize,
+    },
     About,
+    LibraryIssues,
+    Controls {
+        selected: usize,
+        // Waiting for the key to bind to the selected action
+        waiting: bool,
+        warning: Option<String>,
+    },
 }
 
+impl MenuState {
+    /// How many screens deep this is from the main menu, to tell forward from back.
+    fn depth(&self) -> u8 {
+        match self {
+            MenuState::MainMenu => 0,
+            MenuState::GameList | MenuState::Settings | MenuState::ThemeSelector | MenuState::About | MenuState::LibraryIssues => 1,
+            MenuState::GameDetails(_) | MenuState::Controls { .. } | MenuState::ThemeEditor { .. } => 2,
+        }
+    }
+}
+
+// There's only ever one of these, so the big Menu variant costs nothing
+#[allow(clippy::large_enum_variant)]
 enum EngineState {
     Menu {
         state: MenuState,
         games: Vec<GameEntry>,
+        // Game files that failed to parse, shown on the Library Issues screen
+        library_issues: Vec<LibraryIssue>,
         selected_index: usize,
         scroll_offset: f32,
         transition_progress: f32,
-        particles: Vec<MenuParticle>,
+        particles: ParticleSystem,
         theme_selector_index: usize,
+        selected_setting_index: usize,
+        // Game library filter, typed while the list is open
+        search_query: String,
     },
     Playing,
+    /// Gameplay frozen behind the pause menu. `settings` holds the selected row while
+    /// the settings screen is open from it.
+    Paused {
+        selected: usize,
+        settings: Option<usize>,
+    },
     Loading {
         progress: f32,
         status: String,
     },
+    Error {
+        message: String,
+        source: String,
+    },
 }
 
-#[derive(Clone)]
-struct MenuParticle {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    size: f32,
-    color: [f32; 4],
-    lifetime: f32,
+impl EngineState {
+    /// The error screen for `error`, which happened while doing `source`.
+    fn error(source: &str, error: &CacaoError) -> Self {
+        EngineState::Error {
+            message: error.to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+/// What a frame of main menu input asks for.
+#[derive(Debug)]
+enum MainMenuAction {
+    Open(MenuState),
+    ConfirmExit,
+}
+
+/// What a frame of pause menu input asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum PauseAction {
+    Resume,
+    OpenSettings,
+    QuitToMenu,
+}
+
+/// Where the settings screen wants to go after a frame of input.
+enum SettingsAction {
+    Stay,
+    OpenControls,
+    Back,
 }
 
 pub struct CacaoEngine {
@@ -166,48 +353,103 @@ pub struct CacaoEngine {
     saves: SaveManager,
     game_loader: GameLoader,
     current_game: Option<Game>,
+    // Game whose assets are still loading in the background
+    loading_game: Option<Game>,
 
     state: EngineState,
-    _games_dir: PathBuf,
+    games_dir: PathBuf,
     _saves_dir: PathBuf,
 
     last_frame: Instant,
-    target_fps: u32,
+    target_frame_time: Duration,
+    timestep: FixedTimestep,
     frame_count: u64,
+    frame_stats: FrameStats,
+    show_debug_overlay: bool,
+    // F12 asks for one; it's taken from the next rendered frame
+    screenshot_requested: bool,
+    screenshots_dir: PathBuf,
     
     menu_animation_time: f32,
+    // The last menu change went up towards the main menu
+    menu_transition_back: bool,
     current_theme: Theme,
+    settings: Settings,
+    settings_path: PathBuf,
+    controls_path: PathBuf,
+    library: LibraryHistory,
+    library_path: PathBuf,
+    // The running game and when it started, for playtime
+    play_session: Option<(Uuid, Instant)>,
+
+    script_watcher: FileWatcher,
+    hot_reload_timer: f32,
+    // Fires when files are added to or removed from the games folder
+    library_watcher: FileWatcher,
+    script_error: Option<String>,
+    // Closing the window mid-game asks first; the event loop exits once this is set
+    quit_confirmation: bool,
+    exit_requested: bool,
+    // Audio was muted because the window lost focus, not by the player
+    muted_for_focus: bool,
 }
 
 impl CacaoEngine {
+    /// Creates the engine with `games/`, `saves/` and its own files in the working directory.
     pub async fn new() -> Result<Self, CacaoError> {
+        let current_dir = std::env::current_dir()?;
+        Self::with_dirs(current_dir.join("games"), current_dir.join("saves"), current_dir).await
+    }
+
+    /// Creates the engine with the game library and save files kept in the given
+    /// directories, creating them if needed. Settings, controls, library history and
+    /// screenshots go in `data_dir`.
+    pub async fn with_dirs(games_dir: PathBuf, saves_dir: PathBuf, data_dir: PathBuf) -> Result<Self, CacaoError> {
         log::info!("🎮 Initializing Cacao Engine...");
 
+        std::fs::create_dir_all(&data_dir)?;
+        let settings_path = data_dir.join("settings.toml");
+        let settings = Settings::load(&settings_path);
+
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new()
-            .with_title("Cacao Engine")
-            .with_inner_size(winit::dpi::LogicalSize::new(1280, 720))
+            .with_title(ENGINE_TITLE)
+            .with_window_icon(load_window_icon())
+            .with_inner_size(winit::dpi::LogicalSize::new(settings.resolution[0], settings.resolution[1]))
+            .with_fullscreen(settings.fullscreen.then_some(Fullscreen::Borderless(None)))
             .build(&event_loop)
             .map_err(|e| CacaoError::RenderError(format!("Window creation failed: {}", e)))?;
 
-        let renderer = Renderer::new(&window).await?;
-        let audio = AudioSystem::new()?;
-        let input = InputManager::new();
-
-        let games_dir = std::env::current_dir()?.join("games");
-        let saves_dir = std::env::current_dir()?.join("saves");
+        let mut renderer = Renderer::new(&window).await?;
+        renderer.set_present_mode(settings.present_mode());
+        let mut audio = AudioSystem::new()?;
+        audio.set_master_volume(settings.master_volume);
+        audio.set_music_volume(settings.music_volume);
+        audio.set_sound_volume(settings.sfx_volume);
+        let controls_path = data_dir.join("controls.toml");
+        let mut input = InputManager::new();
+        input.setup_default_mappings();
+        input.load_mappings(&controls_path);
+        input.set_gamepad_deadzone(settings.gamepad_deadzone);
 
         std::fs::create_dir_all(&games_dir)?;
         std::fs::create_dir_all(&saves_dir)?;
 
         log::info!("📁 Games directory: {}", games_dir.display());
         log::info!("💾 Saves directory: {}", saves_dir.display());
+        log::info!("🗂️ Data directory: {}", data_dir.display());
 
         let assets = AssetManager::new();
         let saves = SaveManager::new(saves_dir.clone());
-        let game_loader = GameLoader::new(games_dir.clone());
-
-        let games = Self::discover_games(&game_loader)?;
+        let mut game_loader = GameLoader::new(games_dir.clone(), GAME_SECRET_KEY.to_string());
+        game_loader.set_ignore_engine_version(settings.ignore_engine_version);
+
+        let library_path = data_dir.join("library.json");
+        let library = LibraryHistory::load(&library_path);
+        let (mut games, library_issues) = Self::discover_games(&game_loader, &renderer)?;
+        let mut library_watcher = FileWatcher::with_debounce(LIBRARY_WATCH_DEBOUNCE);
+        library_watcher.watch(&games_dir);
+        library.sort_games(&mut games);
         log::info!("🎯 Found {} games", games.len());
 
         let particles = Self::generate_particles();
@@ -215,11 +457,14 @@ impl CacaoEngine {
         let state = EngineState::Menu {
             state: MenuState::MainMenu,
             games: games.clone(),
+            library_issues,
             selected_index: 0,
             scroll_offset: 0.0,
             transition_progress: 0.0,
             particles,
-            theme_selector_index: 0,
+            theme_selector_index: Self::theme_index(&settings.theme, &settings.custom_themes),
+            selected_setting_index: 0,
+            search_query: String::new(),
         };
 
         Ok(Self {
@@ -232,68 +477,100 @@ impl CacaoEngine {
             saves,
             game_loader,
             current_game: None,
+            loading_game: None,
             state,
-            _games_dir: games_dir,
+            library_watcher,
+            games_dir,
             _saves_dir: saves_dir,
             last_frame: Instant::now(),
-            target_fps: 60,
+            target_frame_time: frame_time(settings.target_fps),
+            timestep: FixedTimestep::new(settings.logic_rate),
             frame_count: 0,
+            frame_stats: FrameStats::new(FRAME_STATS_WINDOW),
+            show_debug_overlay: false,
+            screenshot_requested: false,
+            screenshots_dir: data_dir.join("screenshots"),
             menu_animation_time: 0.0,
-            current_theme: Theme::Animated,
+            menu_transition_back: false,
+            current_theme: settings.theme.clone(),
+            settings,
+            settings_path,
+            controls_path,
+            library,
+            library_path,
+            play_session: None,
+            script_watcher: FileWatcher::new(),
+            hot_reload_timer: 0.0,
+            script_error: None,
+            quit_confirmation: false,
+            exit_requested: false,
+            muted_for_focus: false,
         })
     }
 
-    fn generate_particles() -> Vec<MenuParticle> {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        (0..150).map(|_| {
-            MenuParticle {
-                x: rng.gen_range(0.0..1280.0),
-                y: rng.gen_range(0.0..720.0),
-                vx: rng.gen_range(-20.0..20.0),
-                vy: rng.gen_range(-20.0..20.0),
-                size: rng.gen_range(2.0..6.0),
-                color: [
-                    rng.gen_range(0.5..1.0),
-                    rng.gen_range(0.3..0.7),
-                    rng.gen_range(0.8..1.0),
-                    rng.gen_range(0.3..0.7),
-                ],
-                lifetime: rng.gen_range(0.0..10.0),
-            }
-        }).collect()
+    fn theme_index(theme: &Theme, custom: &[ThemeColors]) -> usize {
+        Theme::all(custom).iter().position(|t| t == theme).unwrap_or(0)
+    }
+
+    /// Slow glowing dots drifting across the whole menu, each replaced as soon as it fades out.
+    fn generate_particles() -> ParticleSystem {
+        let config = ParticleConfig {
+            lifetime: (6.0, 14.0),
+            velocity_x: (-20.0, 20.0),
+            velocity_y: (-20.0, 20.0),
+            size: (2.0, 6.0),
+            color_min: [0.5, 0.3, 0.8, 0.2],
+            color_max: [1.0, 0.7, 1.0, 0.5],
+            gravity: 0.0,
+            spread: (1280.0, 720.0),
+            ..ParticleConfig::default()
+        };
+        let mut particles = ParticleSystem::new();
+        particles.start_pool(640.0, 360.0, config, MENU_PARTICLES);
+        particles
     }
 
-    fn discover_games(loader: &GameLoader) -> Result<Vec<GameEntry>, CacaoError> {
+    /// Reads every .gaem in the games folder. Files that can't be read are returned
+    /// alongside the games instead of being dropped.
+    fn discover_games(loader: &GameLoader, renderer: &Renderer) -> Result<(Vec<GameEntry>, Vec<LibraryIssue>), CacaoError> {
         log::info!("🔍 Searching for games...");
         let game_files = loader.discover_games()?;
         log::info!("📦 Found {} .gaem files", game_files.len());
         
         let mut entries = Vec::new();
+        let mut issues = Vec::new();
 
         for path in game_files {
-            match loader.parse_gaem_file_engine(&path) {
-                Ok(info) => {
+            match loader.read_library_info(&path) {
+                Ok((info, banner_bytes)) => {
                     log::info!("✅ Found game: {} by {}", info.title, info.author);
+                    // A broken banner only costs the artwork, not the game
+                    let banner = banner_bytes.and_then(|bytes| {
+                        Texture::from_bytes(renderer.get_device(), renderer.get_queue(), &bytes, "Game Banner")
+                            .map(Sprite::new)
+                            .map_err(|e| log::warn!("⚠️ Couldn't load banner for {}: {}", info.title, e))
+                            .ok()
+                    });
                     entries.push(GameEntry {
                         info,
                         file_path: path,
-                        banner_loaded: false,
+                        banner_loaded: banner.is_some(),
+                        banner,
                     });
                 }
                 Err(e) => {
                     log::warn!("❌ Failed to parse game file {:?}: {}", path, e);
+                    issues.push(LibraryIssue { path, error: e.to_string() });
                 }
             }
         }
 
         log::info!("🎮 Successfully loaded {} games", entries.len());
-        Ok(entries)
+        Ok((entries, issues))
     }
 
     pub async fn run(mut self) -> ! {
         let event_loop = self.event_loop.take().unwrap();
-        let target_frame_time = Duration::from_millis(1000 / self.target_fps as u64);
 
         event_loop.run(move |event, _, control_flow| {
             match event {
@@ -303,16 +580,37 @@ impl CacaoEngine {
                 } if window_id == self.window.id() => {
                     match event {
                         WindowEvent::CloseRequested => {
-                            log::info!("👋 Goodbye!");
-                            *control_flow = ControlFlow::Exit;
+                            if close_needs_confirmation(&self.state, self.quit_confirmation) {
+                                self.quit_confirmation = true;
+                            } else {
+                                self.quit_game();
+                                self.exit_requested = true;
+                            }
                         }
                         WindowEvent::Resized(physical_size) => {
                             self.renderer.resize(*physical_size);
+                            self.resize_game(*physical_size);
                         }
                         WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                             self.renderer.resize(**new_inner_size);
+                            self.resize_game(**new_inner_size);
+                        }
+                        WindowEvent::Focused(focused) => {
+                            self.handle_focus_change(*focused);
                         }
                         _ => {
+                            // Toggled here rather than in update so it fires once per press even
+                            // on frames where no game logic step consumes input
+                            if let WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F3), .. }, .. } = event {
+                                if !self.input.is_key_pressed(VirtualKeyCode::F3) {
+                                    self.show_debug_overlay = !self.show_debug_overlay;
+                                }
+                            }
+                            if let WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F12), .. }, .. } = event {
+                                if !self.input.is_key_pressed(VirtualKeyCode::F12) {
+                                    self.screenshot_requested = true;
+                                }
+                            }
                             self.input.handle_window_event(event);
                         }
                     }
@@ -321,7 +619,8 @@ impl CacaoEngine {
                     let now = Instant::now();
                     let delta_time = now.duration_since(self.last_frame);
 
-                    if delta_time >= target_frame_time {
+                    if delta_time >= self.target_frame_time {
+                        self.frame_stats.record(delta_time);
                         self.update(delta_time);
                         match self.render() { 
                             Ok(_) => {}
@@ -333,6 +632,10 @@ impl CacaoEngine {
                         self.frame_count += 1;
                     }
                 }
+                Event::MainEventsCleared if self.exit_requested => {
+                    log::info!("👋 Goodbye!");
+                    *control_flow = ControlFlow::Exit;
+                }
                 Event::MainEventsCleared => {
                     self.window.request_redraw();
                 }
@@ -342,130 +645,316 @@ impl CacaoEngine {
     }
 
     fn update(&mut self, delta_time: Duration) {
-        self.input.update();
         let dt = delta_time.as_secs_f32();
         self.menu_animation_time += dt;
+        self.audio.update(dt);
+        self.renderer.get_camera().update(dt);
+
+        for change in self.input.take_gamepad_changes() {
+            self.push_game_event(match change {
+                GamepadChange::Connected(name) => GameEvent::GamepadConnected { name },
+                GamepadChange::Disconnected(name) => GameEvent::GamepadDisconnected { name },
+            });
+        }
+
+        // The game is paused while the quit prompt is up
+        if self.quit_confirmation {
+            match quit_prompt_answer(&self.input) {
+                Some(true) => {
+                    self.quit_game();
+                    self.exit_requested = true;
+                }
+                Some(false) => self.quit_confirmation = false,
+                None => {}
+            }
+            self.input.update();
+            return;
+        }
+
+        if self.input.is_key_just_pressed(VirtualKeyCode::M) && self.mute_key_free() {
+            let muted = !self.audio.is_muted();
+            self.audio.set_muted(muted);
+            log::info!("{}", if muted { "🔇 Audio muted" } else { "🔊 Audio unmuted" });
+        }
 
-        let should_unload = matches!(self.state, EngineState::Playing) 
+        if matches!(self.state, EngineState::Playing) && self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
+            self.pause_game();
+            self.input.update();
+            return;
+        }
+
+        let should_unload = matches!(self.state, EngineState::Error { .. })
             && self.input.is_key_just_pressed(VirtualKeyCode::Escape);
 
         if should_unload {
             self.unload_game();
+            self.input.update();
             return;
         }
 
-        let needs_load_game = if let EngineState::Menu { state, games, selected_index, scroll_offset, transition_progress, particles, theme_selector_index } = &mut self.state {
+        let mouse = self.menu_mouse_position();
+        let mouse_moved = self.input.get_mouse_delta() != Vec2::ZERO;
+        let clicked = self.input.is_mouse_button_just_pressed(MouseButton::Left);
+
+        let mut settings_row = None;
+        let mut refresh_library = false;
+        let needs_load_game = if let EngineState::Menu { state, games, library_issues, selected_index, scroll_offset, transition_progress, particles, theme_selector_index, selected_setting_index, search_query } = &mut self.state {
             if self.current_theme.should_show_particles() {
-                for particle in particles.iter_mut() {
-                    particle.x += particle.vx * dt;
-                    particle.y += particle.vy * dt;
-                    particle.lifetime += dt;
-
-                    if particle.x < 0.0 { particle.x = 1280.0; }
-                    if particle.x > 1280.0 { particle.x = 0.0; }
-                    if particle.y < 0.0 { particle.y = 720.0; }
-                    if particle.y > 720.0 { particle.y = 0.0; }
-
-                    let pulse = (particle.lifetime * 2.0).sin() * 0.3 + 0.7;
-                    particle.color[3] = pulse * 0.5;
-                }
+                particles.update(dt);
             }
 
             *transition_progress = (*transition_progress + dt * 3.0).min(1.0);
 
+            // Drained every menu frame so text typed elsewhere in the menus doesn't pile up
+            let typed = self.input.take_text_input();
+
             let mut load_game_path: Option<PathBuf> = None;
+            let depth_before = state.depth();
 
             match state {
                 MenuState::MainMenu => {
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) {
-                        *state = MenuState::GameList;
-                        *transition_progress = 0.0;
-                    }
-                    if self.input.is_key_just_pressed(VirtualKeyCode::S) {
-                        *state = MenuState::Settings;
-                        *transition_progress = 0.0;
-                    }
-                    if self.input.is_key_just_pressed(VirtualKeyCode::T) {
-                        *state = MenuState::ThemeSelector;
-                        *transition_progress = 0.0;
-                    }
-                    if self.input.is_key_just_pressed(VirtualKeyCode::A) {
-                        *state = MenuState::About;
-                        *transition_progress = 0.0;
+                    let clicked_row = if clicked { main_menu_row_at(mouse) } else { None };
+                    match main_menu_action(&self.input, clicked_row, !library_issues.is_empty()) {
+                        Some(MainMenuAction::Open(screen)) => {
+                            *state = screen;
+                            *transition_progress = 0.0;
+                        }
+                        Some(MainMenuAction::ConfirmExit) => self.quit_confirmation = true,
+                        None => {}
                     }
                 }
                 MenuState::GameList => {
-                    if !games.is_empty() {
-                        if self.input.is_key_just_pressed(VirtualKeyCode::Up) {
-                            if *selected_index > 0 {
-                                *selected_index -= 1;
-                            }
-                        }
-                        if self.input.is_key_just_pressed(VirtualKeyCode::Down) {
-                            if *selected_index < games.len() - 1 {
-                                *selected_index += 1;
-                            }
-                        }
+                    if self.input.is_key_just_pressed(VirtualKeyCode::F5) {
+                        refresh_library = true;
+                    }
+
+                    // Typing narrows the list; selection and scrolling work on the filtered view
+                    let erased = self.input.is_key_repeated(VirtualKeyCode::Back) && search_query.pop().is_some();
+                    if !typed.is_empty() || erased {
+                        search_query.push_str(&typed);
+                        *selected_index = 0;
+                    }
+                    let visible = filter_games(games, search_query);
+
+                    if !visible.is_empty() {
+                        *selected_index = game_list_selection(&self.input, *selected_index, visible.len());
                         if self.input.is_key_just_pressed(VirtualKeyCode::Return) {
-                            *state = MenuState::GameDetails(*selected_index);
+                            *state = MenuState::GameDetails(visible[*selected_index]);
                             *transition_progress = 0.0;
                         }
+
+                        // Card rectangles match render_game_list
+                        let hovered_card = (0..visible.len()).find(|&i| {
+                            let y = 150.0 - *scroll_offset + i as f32 * GAME_CARD_SPACING;
+                            (100.0..=700.0).contains(&y) && point_in_rect(mouse, 80.0, y, 1104.0, 96.0)
+                        });
+                        if let Some(i) = hovered_card {
+                            if mouse_moved {
+                                *selected_index = i;
+                            }
+                            if clicked {
+                                *selected_index = i;
+                                *state = MenuState::GameDetails(visible[i]);
+                                *transition_progress = 0.0;
+                            }
+                        }
                     }
                     if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
-                        *state = MenuState::MainMenu;
-                        *transition_progress = 0.0;
+                        // The first Escape clears the search, the next one leaves the list
+                        if search_query.is_empty() {
+                            *state = MenuState::MainMenu;
+                            *transition_progress = 0.0;
+                        } else {
+                            search_query.clear();
+                            *selected_index = 0;
+                        }
                     }
 
-                    let target_scroll = (*selected_index as f32 * 120.0).max(0.0);
+                    let target_scroll = (*selected_index as f32 * GAME_CARD_SPACING).max(0.0);
                     *scroll_offset += (target_scroll - *scroll_offset) * dt * 10.0;
                 }
                 MenuState::GameDetails(idx) => {
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) {
+                    let play_clicked = clicked && point_in_rect(mouse, 500.0, 640.0, 280.0, 60.0);
+                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) || play_clicked {
                         if let Some(game) = games.get(*idx) {
                             load_game_path = Some(game.file_path.clone());
                         }
                     }
+                    // F works here rather than in the list, where letters type into the search
+                    if self.input.is_key_just_pressed(VirtualKeyCode::F) {
+                        if let Some(game) = games.get(*idx) {
+                            let file_path = game.file_path.clone();
+                            let favorite = self.library.toggle_favorite(game.info.id);
+                            log::info!("{} {}", if favorite { "⭐ Favorited" } else { "☆ Unfavorited" }, game.info.title);
+                            if let Err(e) = self.library.save(&self.library_path) {
+                                log::error!("❌ Failed to save library history: {}", e);
+                            }
+
+                            // Keep showing the same game after it moves in the list
+                            self.library.sort_games(games);
+                            *idx = games.iter().position(|game| game.file_path == file_path).unwrap_or(0);
+                            *selected_index = filter_games(games, search_query).iter().position(|&i| i == *idx).unwrap_or(0);
+                        }
+                    }
                     if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
                         *state = MenuState::GameList;
                         *transition_progress = 0.0;
                     }
                 }
                 MenuState::ThemeSelector => {
-                    // FIXED: Use len() on slice
-                    let num_themes = Theme::all().len();
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Up) {
+                    let themes = Theme::all(&self.settings.custom_themes);
+                    let num_themes = themes.len();
+                    if self.input.is_key_repeated(VirtualKeyCode::Up) {
                         if *theme_selector_index > 0 {
                             *theme_selector_index -= 1;
                         }
                     }
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Down) {
+                    if self.input.is_key_repeated(VirtualKeyCode::Down) {
                         if *theme_selector_index < num_themes - 1 {
                             *theme_selector_index += 1;
                         }
                     }
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) {
-                        self.current_theme = Theme::from_index(*theme_selector_index);
+
+                    let hovered_theme = (0..num_themes)
+                        .find(|&i| point_in_rect(mouse, 100.0, 220.0 + i as f32 * 70.0, 500.0, 50.0));
+                    if let Some(i) = hovered_theme {
+                        if mouse_moved || clicked {
+                            *theme_selector_index = i;
+                        }
+                    }
+
+                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) || (clicked && hovered_theme.is_some()) {
+                        self.current_theme = Theme::from_index(*theme_selector_index, &self.settings.custom_themes);
                         log::info!("🎨 Theme changed to: {}", self.current_theme.name());
+                        self.settings.theme = self.current_theme.clone();
+                        if let Err(e) = self.settings.save(&self.settings_path) {
+                            log::error!("❌ Failed to save settings: {}", e);
+                        }
                         *state = MenuState::MainMenu;
                         *transition_progress = 0.0;
                     }
+                    // Editing a built-in theme starts a new custom one from its colors
+                    if self.input.is_key_just_pressed(VirtualKeyCode::E) {
+                        let highlighted = &themes[(*theme_selector_index).min(num_themes - 1)];
+                        if let Some((colors, slot)) = theme_to_edit(highlighted, &self.settings.custom_themes) {
+                            *state = MenuState::ThemeEditor { colors, slot, row: 0 };
+                            *transition_progress = 0.0;
+                        } else {
+                            log::warn!("⚠️ Only {} custom themes can be saved; edit one of them instead", MAX_CUSTOM_THEMES);
+                        }
+                    }
+                    if self.input.is_key_just_pressed(VirtualKeyCode::O) {
+                        self.settings.theme_overlay = !self.settings.theme_overlay;
+                        log::info!("📺 Theme overlay {}", if self.settings.theme_overlay { "on" } else { "off" });
+                        if let Err(e) = self.settings.save(&self.settings_path) {
+                            log::error!("❌ Failed to save settings: {}", e);
+                        }
+                    }
+                    if self.input.is_key_just_pressed(VirtualKeyCode::T) {
+                        self.settings.menu_transition = self.settings.menu_transition.next();
+                        log::info!("🎞️ Menu transition changed to: {}", self.settings.menu_transition.name());
+                        if let Err(e) = self.settings.save(&self.settings_path) {
+                            log::error!("❌ Failed to save settings: {}", e);
+                        }
+                    }
                     if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
                         *state = MenuState::MainMenu;
                         *transition_progress = 0.0;
                     }
                 }
-                MenuState::Settings => {
-                    if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
-                        *state = MenuState::MainMenu;
+                MenuState::ThemeEditor { colors, slot, row } => {
+                    let rows = THEME_EDITOR_COLORS.len() * 3;
+                    if self.input.is_key_repeated(VirtualKeyCode::Up) && *row > 0 {
+                        *row -= 1;
+                    }
+                    if self.input.is_key_repeated(VirtualKeyCode::Down) && *row + 1 < rows {
+                        *row += 1;
+                    }
+
+                    let mut step = 0.0;
+                    if self.input.is_key_repeated(VirtualKeyCode::Left) {
+                        step -= COLOR_STEP;
+                    }
+                    if self.input.is_key_repeated(VirtualKeyCode::Right) {
+                        step += COLOR_STEP;
+                    }
+                    if step != 0.0 {
+                        let channel = colors.channel_mut(*row);
+                        *channel = step_channel(*channel, step);
+                    }
+
+                    if self.input.is_key_just_pressed(VirtualKeyCode::Return) {
+                        save_custom_theme(&mut self.settings.custom_themes, *slot, colors.clone());
+                        self.current_theme = Theme::Custom(colors.clone());
+                        self.settings.theme = self.current_theme.clone();
+                        log::info!("🎨 Saved custom theme: {}", colors.name);
+                        if let Err(e) = self.settings.save(&self.settings_path) {
+                            log::error!("❌ Failed to save settings: {}", e);
+                        }
+                        *theme_selector_index = Self::theme_index(&self.current_theme, &self.settings.custom_themes);
+                        *state = MenuState::ThemeSelector;
+                        *transition_progress = 0.0;
+                    } else if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
+                        *state = MenuState::ThemeSelector;
                         *transition_progress = 0.0;
                     }
                 }
-                MenuState::About => {
+                MenuState::Settings => {
+                    // Handled below, once the menu state is no longer borrowed
+                    settings_row = Some(*selected_setting_index);
+                }
+                MenuState::About | MenuState::LibraryIssues => {
                     if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
                         *state = MenuState::MainMenu;
                         *transition_progress = 0.0;
                     }
                 }
+                MenuState::Controls { selected, waiting, warning } => {
+                    let actions = self.input.get_action_names();
+                    if *waiting {
+                        if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
+                            *waiting = false;
+                        } else if let Some(key) = self.input.get_just_pressed_keys().first().copied() {
+                            if let Some(action) = actions.get(*selected) {
+                                let conflicts = self.input.rebind_key(action, key);
+                                *warning = (!conflicts.is_empty())
+                                    .then(|| format!("{:?} is also bound to {}", key, conflicts.join(", ")));
+
+                                if let Err(e) = self.input.save_mappings(&self.controls_path) {
+                                    log::error!("❌ Failed to save controls: {}", e);
+                                }
+                            }
+                            *waiting = false;
+                        }
+                    } else {
+                        if self.input.is_key_repeated(VirtualKeyCode::Up) && *selected > 0 {
+                            *selected -= 1;
+                        }
+                        if self.input.is_key_repeated(VirtualKeyCode::Down) && *selected + 1 < actions.len() {
+                            *selected += 1;
+                        }
+
+                        let hovered_row = (0..actions.len())
+                            .find(|&i| point_in_rect(mouse, 100.0, controls_row_y(i) - 8.0, 1080.0, 36.0));
+                        if let Some(i) = hovered_row {
+                            if mouse_moved || clicked {
+                                *selected = i;
+                            }
+                        }
+
+                        if self.input.is_key_just_pressed(VirtualKeyCode::Return) || (clicked && hovered_row.is_some()) {
+                            *waiting = true;
+                            *warning = None;
+                        } else if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
+                            *state = MenuState::Settings;
+                            *transition_progress = 0.0;
+                        }
+                    }
+                }
+            }
+
+            if state.depth() != depth_before {
+                self.menu_transition_back = state.depth() < depth_before;
             }
 
             load_game_path
@@ -473,142 +962,618 @@ impl CacaoEngine {
             None
         };
 
+        if let Some(mut row) = settings_row {
+            let action = self.update_settings(&mut row, mouse, mouse_moved, clicked);
+            if let EngineState::Menu { state, transition_progress, selected_setting_index, .. } = &mut self.state {
+                *selected_setting_index = row;
+                match action {
+                    SettingsAction::Stay => {}
+                    SettingsAction::OpenControls => {
+                        *state = MenuState::Controls { selected: 0, waiting: false, warning: None };
+                        *transition_progress = 0.0;
+                        self.menu_transition_back = false;
+                    }
+                    SettingsAction::Back => {
+                        *state = MenuState::MainMenu;
+                        *transition_progress = 0.0;
+                        self.menu_transition_back = true;
+                    }
+                }
+            }
+        }
+
+        // The folder's own timestamp changes when games are added or removed
+        let library_changed = !self.library_watcher.poll().is_empty();
+        if (refresh_library || library_changed) && matches!(self.state, EngineState::Menu { .. }) {
+            self.refresh_library();
+        }
+
         if let Some(game_path) = needs_load_game {
             if let Err(e) = self.start_loading_game(&game_path) {
-                log::error!("❌ Failed to load game: {}", e);
+                self.show_error("Loading game", e);
             }
         }
 
-        match &mut self.state {
+        match self.state {
             EngineState::Playing => {
-                if let Some(ref mut game) = self.current_game {
-                    game.update(delta_time, &mut self.input, &mut self.audio, &mut self.saves);
-                }
-            }
-            EngineState::Loading { progress, .. } => {
-                *progress += dt * 0.5;
-                if *progress >= 1.0 {
-                    self.state = EngineState::Playing;
+                self.poll_hot_reload(dt);
+                let step = self.timestep.step();
+                for _ in 0..self.timestep.advance(delta_time) {
+                    let result = match self.current_game.as_mut() {
+                        Some(game) => game.update(step, &mut self.input, &mut self.audio, &self.assets, self.renderer.get_camera(), &mut self.saves),
+                        None => Ok(()),
+                    };
+                    // Each press reaches exactly one logic step
+                    self.input.update();
+                    if let Err(e) = result {
+                        self.show_error("Game update", e);
+                        break;
+                    }
                 }
+                // When no step ran this frame, input is kept for the next one
+                return;
             }
+            EngineState::Paused { .. } => self.update_pause_menu(mouse, mouse_moved, clicked),
+            EngineState::Loading { .. } => self.update_loading(),
             _ => {}
         }
+
+        // Clear "just pressed" state only after the menu and game have seen it
+        self.input.update();
     }
 
-    fn start_loading_game(&mut self, game_path: &Path) -> Result<(), CacaoError> {
-        self.state = EngineState::Loading {
-            progress: 0.0,
-            status: "Loading game...".to_string(),
+    /// The game gets no updates here, so its clock and timers stand still until it resumes.
+    fn update_pause_menu(&mut self, mouse: Vec2, mouse_moved: bool, clicked: bool) {
+        let EngineState::Paused { mut selected, settings } = self.state else {
+            return;
         };
 
-        pollster::block_on(self.load_game_internal(game_path))?;
-        Ok(())
+        if let Some(mut row) = settings {
+            let action = self.update_settings(&mut row, mouse, mouse_moved, clicked);
+            // Rebinding controls stays a main menu screen
+            let settings = match action {
+                SettingsAction::Back => None,
+                SettingsAction::Stay | SettingsAction::OpenControls => Some(row),
+            };
+            self.state = EngineState::Paused { selected, settings };
+            return;
+        }
+
+        let action = pause_menu_action(&self.input, &mut selected);
+        self.state = EngineState::Paused { selected, settings: None };
+        match action {
+            Some(PauseAction::Resume) => self.resume_game(),
+            Some(PauseAction::OpenSettings) => self.state = EngineState::Paused { selected, settings: Some(0) },
+            Some(PauseAction::QuitToMenu) => self.unload_game(),
+            None => {}
+        }
     }
 
-    async fn load_game_internal(&mut self, game_path: &Path) -> Result<(), CacaoError> {
-        let device = self.renderer.get_device();
-        let queue = self.renderer.get_queue();
+    fn pause_game(&mut self) {
+        log::info!("⏸️ Game paused");
+        self.audio.pause_all();
+        self.state = EngineState::Paused { selected: 0, settings: None };
+    }
 
-        let mut game = self
-            .game_loader
-            .load_game(game_path, &mut self.assets, device, queue)
-            .await?;
+    /// Tells the game, and unless the player turned it off, pauses it and goes quiet while
+    /// the window is in the background. Coming back unmutes, but the game stays paused.
+    fn handle_focus_change(&mut self, focused: bool) {
+        self.push_game_event(if focused { GameEvent::FocusGained } else { GameEvent::FocusLost });
 
-        let secret_key = "default_key".to_string();
-        game.initialize(secret_key)?;
+        let pause = mute_for_focus(&mut self.audio, &mut self.muted_for_focus, focused, self.settings.pause_on_focus_loss);
+        if pause && matches!(self.state, EngineState::Playing) {
+            self.pause_game();
+        }
+    }
 
-        self.current_game = Some(game);
+    fn resume_game(&mut self) {
+        log::info!("▶️ Game resumed");
+        self.audio.resume_all();
         self.state = EngineState::Playing;
-
-        Ok(())
     }
 
-    fn unload_game(&mut self) {
-        log::info!("📤 Unloading game...");
-        self.current_game = None;
-        self.assets.clear_assets();
-
-        let games = Self::discover_games(&self.game_loader).unwrap_or_default();
-        let particles = Self::generate_particles();
-        
-        self.state = EngineState::Menu {
-            state: MenuState::MainMenu,
-            games,
-            selected_index: 0,
-            scroll_offset: 0.0,
-            transition_progress: 0.0,
-            particles,
-            theme_selector_index: 0,
-        };
+    /// Input for the settings screen, shared by the main menu and the pause menu.
+    fn update_settings(&mut self, selected: &mut usize, mouse: Vec2, mouse_moved: bool, clicked: bool) -> SettingsAction {
+        if self.input.is_key_repeated(VirtualKeyCode::Up) && *selected > 0 {
+            *selected -= 1;
+        }
+        if self.input.is_key_repeated(VirtualKeyCode::Down) && *selected < SETTINGS_ROWS.len() - 1 {
+            *selected += 1;
+        }
 
-        self.window.set_title("Cacao Engine");
-    }
+        let mut step = 0.0;
+        if self.input.is_key_just_pressed(VirtualKeyCode::Left) {
+            step -= VOLUME_STEP;
+        }
+        if self.input.is_key_just_pressed(VirtualKeyCode::Right) {
+            step += VOLUME_STEP;
+        }
 
-    fn render(&mut self) -> Result<(), CacaoError> {
-        self.renderer.begin_frame()?;
+        // Row rectangles match render_settings; clicking the slider track jumps to that value
+        let mut clicked_volume = None;
+        let mut clicked_row = false;
+        let hovered_row = (0..SETTINGS_ROWS.len())
+            .find(|&i| point_in_rect(mouse, 100.0, settings_row_y(i) - 8.0, 640.0, 36.0));
+        if let Some(i) = hovered_row {
+            if mouse_moved || clicked {
+                *selected = i;
+            }
+            clicked_row = clicked;
+            if clicked && i < VOLUME_ROWS && (360.0..=640.0).contains(&mouse.x) {
+                clicked_volume = Some((mouse.x - 360.0) / 280.0);
+            }
+        }
 
-        match &self.state {
-            EngineState::Menu { state, games, selected_index, scroll_offset, transition_progress, particles, .. } => {
-                let state_clone = state.clone();
-                let games_clone = games.clone();
-                let selected = *selected_index;
-                let scroll = *scroll_offset;
-                let progress = *transition_progress;
-                let particles_clone = particles.clone();
-                
-                self.render_stunning_menu(&state_clone, &games_clone, selected, scroll, progress, &particles_clone)?;
+        let toggled = step != 0.0 || clicked_row || self.input.is_key_just_pressed(VirtualKeyCode::Return);
+        if *selected == RESOLUTION_ROW {
+            if toggled {
+                let [width, height] = cycle_option(&RESOLUTIONS, self.settings.resolution, step < 0.0);
+                self.set_resolution(width, height);
             }
-            EngineState::Playing => {
-                if let Some(ref game) = self.current_game {
-                    game.render(&mut self.renderer)?;
+        } else if *selected == FULLSCREEN_ROW {
+            if toggled {
+                self.set_fullscreen(!self.settings.fullscreen);
+            }
+        } else if *selected == FRAME_LIMIT_ROW {
+            if toggled {
+                self.set_target_fps(cycle_option(&FPS_OPTIONS, self.settings.target_fps, step < 0.0));
+            }
+        } else if *selected == CONTROLS_ROW {
+            if clicked_row || self.input.is_key_just_pressed(VirtualKeyCode::Return) {
+                return SettingsAction::OpenControls;
+            }
+        } else if *selected == VSYNC_ROW {
+            if toggled {
+                self.settings.vsync = !self.settings.vsync;
+                let mode = self.renderer.set_present_mode(self.settings.present_mode());
+                log::info!("🖥️ Present mode set to {:?}", mode);
+
+                if let Err(e) = self.settings.save(&self.settings_path) {
+                    log::error!("❌ Failed to save settings: {}", e);
                 }
             }
-            EngineState::Loading { progress, status } => {
-                let p = *progress;
-                let s = status.clone();
-                self.render_loading_screen(p, &s)?;
+        } else if step != 0.0 || clicked_volume.is_some() {
+            let volume = match *selected {
+                0 => &mut self.settings.master_volume,
+                1 => &mut self.settings.music_volume,
+                _ => &mut self.settings.sfx_volume,
+            };
+            *volume = snap_volume(clicked_volume.unwrap_or(*volume + step));
+
+            self.audio.set_master_volume(self.settings.master_volume);
+            self.audio.set_music_volume(self.settings.music_volume);
+            self.audio.set_sound_volume(self.settings.sfx_volume);
+
+            if let Err(e) = self.settings.save(&self.settings_path) {
+                log::error!("❌ Failed to save settings: {}", e);
             }
         }
 
-        self.renderer.end_frame()?;
+        if self.input.is_key_just_pressed(VirtualKeyCode::Escape) {
+            return SettingsAction::Back;
+        }
+        SettingsAction::Stay
+    }
+
+    fn start_loading_game(&mut self, game_path: &Path) -> Result<(), CacaoError> {
+        let game = self.game_loader.queue_game(game_path, &mut self.assets)?;
+
+        self.state = EngineState::Loading {
+            progress: 0.0,
+            status: format!("Loading {}...", game.get_info().title),
+        };
+        self.loading_game = Some(game);
         Ok(())
     }
 
-    fn render_stunning_menu(
-        &mut self,
-        menu_state: &MenuState,
-        games: &[GameEntry],
-        selected_index: usize,
-        scroll_offset: f32,
-        progress: f32,
-        particles: &[MenuParticle],
-    ) -> Result<(), CacaoError> {
-        let theme = self.current_theme.clone();
-        
-        if matches!(theme, Theme::Animated) {
-            let time = self.menu_animation_time;
-            let bg_color1 = [
-                0.05 + (time * 0.5).sin() * 0.02,
-                0.02 + (time * 0.3).sin() * 0.02,
-                0.15 + (time * 0.4).sin() * 0.03,
-                1.0
-            ];
-            self.renderer.clear_screen(bg_color1);
-        } else {
-            self.renderer.clear_screen(theme.background_color());
+    /// Finishes whatever background asset loads are done and starts the game once all are.
+    fn update_loading(&mut self) {
+        let result = self.assets.process_loaded_assets(self.renderer.get_device(), self.renderer.get_queue());
+        if let EngineState::Loading { progress, .. } = &mut self.state {
+            *progress = self.assets.loading_progress();
         }
 
-        if theme.should_show_particles() {
-            for particle in particles {
-                self.renderer.draw_circle(
-                    particle.x,
-                    particle.y,
-                    particle.size,
-                    16,
-                    particle.color
-                )?;
+        let result = result.and_then(|()| {
+            if self.assets.is_loading() {
+                return Ok(());
             }
-        }
+            self.finish_loading_game()
+        });
+
+        if let Err(e) = result {
+            self.show_error("Loading game", e);
+        }
+    }
+
+    fn finish_loading_game(&mut self) -> Result<(), CacaoError> {
+        let Some(mut game) = self.loading_game.take() else {
+            return Ok(());
+        };
+
+        for font_file in self.assets.list_assets().fonts {
+            if let Some(font) = self.assets.get_font(&font_file) {
+                if let Err(e) = self.renderer.load_font(&font.name, &font.data) {
+                    log::warn!("⚠️ Failed to load font {}: {}", font_file, e);
+                }
+            }
+        }
+
+        if self.settings.script_sandbox == ScriptSandbox::Trusted {
+            log::warn!("⚠️ Running game scripts without the sandbox");
+        }
+        game.set_sandbox(self.settings.script_sandbox);
+        let window_size = self.window.inner_size();
+        game.set_window_size(window_size.width, window_size.height);
+        game.initialize(GAME_SECRET_KEY.to_string(), &mut self.audio, &self.assets, self.renderer.get_camera(), &mut self.saves)?;
+
+        let script_path = game.script_path();
+        if script_path.is_file() {
+            self.script_watcher.watch(&script_path);
+            log::info!("👀 Watching {} for changes", script_path.display());
+        }
+        if let Some(game_folder) = script_path.parent().filter(|folder| folder.is_dir()) {
+            if let Err(e) = self.assets.enable_hot_reloading(game_folder.to_path_buf()) {
+                log::warn!("⚠️ Asset hot reloading unavailable: {}", e);
+            }
+        }
+
+        self.window.set_title(&window_title(game.get_info()));
+        self.library.record_launch(game.get_info().id, SystemTime::now());
+        self.save_library();
+        self.play_session = Some((game.get_info().id, Instant::now()));
+        self.current_game = Some(game);
+        self.timestep.reset();
+        self.state = EngineState::Playing;
+
+        Ok(())
+    }
+
+    /// Reloads changed sprites and audio, and re-runs the game's main script when it changes
+    /// on disk. A broken script keeps the old one running and shows the error on top of the
+    /// game until it's fixed.
+    fn poll_hot_reload(&mut self, dt: f32) {
+        self.hot_reload_timer += dt;
+        if self.hot_reload_timer < HOT_RELOAD_POLL_INTERVAL {
+            return;
+        }
+        self.hot_reload_timer = 0.0;
+
+        self.assets.reload_changed_assets(self.renderer.get_device(), self.renderer.get_queue());
+
+        for path in self.script_watcher.poll() {
+            let Some(game) = self.current_game.as_mut() else {
+                return;
+            };
+
+            let result = std::fs::read_to_string(&path)
+                .map_err(CacaoError::from)
+                .and_then(|script| game.reload_script(&script, &mut self.audio, &self.assets, self.renderer.get_camera(), &mut self.saves));
+
+            match result {
+                Ok(()) => {
+                    log::info!("🔄 Reloaded {}", path.display());
+                    self.script_error = None;
+                }
+                Err(e) => {
+                    log::error!("❌ Script reload failed, keeping the previous version: {}", e);
+                    self.script_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    /// Stops the current game and shows `error` until the player presses Escape.
+    fn show_error(&mut self, source: &str, error: CacaoError) {
+        log::error!("❌ {} failed: {}", source, error);
+
+        self.end_play_session();
+        self.current_game = None;
+        self.loading_game = None;
+        self.audio.stop_all();
+        self.assets.clear_assets();
+        self.script_watcher.clear();
+        self.script_error = None;
+
+        self.state = EngineState::error(source, &error);
+    }
+
+    // Dropped when no game is running; a paused game gets them once it polls again
+    fn push_game_event(&self, event: GameEvent) {
+        if let Some(game) = &self.current_game {
+            game.push_event(event);
+        }
+    }
+
+    fn resize_game(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        // Minimizing reports 0x0; the renderer ignores it, so the game does too
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let result = match &self.current_game {
+            Some(game) => game.resize(size.width, size.height, &mut self.audio, &self.assets, self.renderer.get_camera(), &mut self.saves),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            self.show_error("Game resize", e);
+        }
+    }
+
+    /// Gives the running game its `on_quit` call and writes its save.
+    fn quit_game(&mut self) {
+        if let Some(game) = &self.current_game {
+            if let Err(e) = game.quit(&mut self.audio, &self.assets, self.renderer.get_camera(), &mut self.saves) {
+                log::error!("❌ Failed to shut the game down cleanly: {}", e);
+            }
+        }
+        self.end_play_session();
+    }
+
+    /// Adds the time since the game started to its playtime. Safe to call more than once.
+    fn end_play_session(&mut self) {
+        if let Some((id, started)) = self.play_session.take() {
+            self.library.add_playtime(id, started.elapsed());
+            self.save_library();
+        }
+    }
+
+    // M belongs to the game while it runs, and is just a letter in the library search or a rebind
+    fn mute_key_free(&self) -> bool {
+        !matches!(
+            self.state,
+            EngineState::Playing
+                | EngineState::Menu { state: MenuState::GameList | MenuState::Controls { waiting: true, .. }, .. }
+        )
+    }
+
+    /// Rescans the games folder from the menu. The highlighted game stays selected if
+    /// it's still there, and an open details page follows its game or closes.
+    fn refresh_library(&mut self) {
+        let (mut found, issues) = match Self::discover_games(&self.game_loader, &self.renderer) {
+            Ok(result) => result,
+            Err(e) => {
+                log::error!("❌ Failed to refresh the game library: {}", e);
+                return;
+            }
+        };
+        self.library.sort_games(&mut found);
+        self.library_watcher.watch(&self.games_dir);
+
+        if let EngineState::Menu { state, games, library_issues, selected_index, search_query, .. } = &mut self.state {
+            *selected_index = apply_rescan(state, games, found, !issues.is_empty(), search_query, *selected_index);
+            *library_issues = issues;
+            log::info!("🔄 Library refreshed: {} games", games.len());
+        }
+    }
+
+    fn unload_game(&mut self) {
+        log::info!("📤 Unloading game...");
+        self.quit_game();
+        self.audio.fade_out_all(UNLOAD_MUSIC_FADE);
+        // Games can change the volumes from script; the menu goes back to the player's own
+        self.audio.set_master_volume(self.settings.master_volume);
+        self.audio.set_music_volume(self.settings.music_volume);
+        self.audio.set_sound_volume(self.settings.sfx_volume);
+        self.current_game = None;
+        self.loading_game = None;
+        self.script_watcher.clear();
+        self.hot_reload_timer = 0.0;
+        self.script_error = None;
+        self.assets.clear_assets();
+        self.renderer.clear_texture_cache();
+
+        let (mut games, library_issues) = Self::discover_games(&self.game_loader, &self.renderer).unwrap_or_default();
+        self.library.sort_games(&mut games);
+        self.library_watcher.watch(&self.games_dir);
+        let particles = Self::generate_particles();
+        
+        self.state = EngineState::Menu {
+            state: MenuState::MainMenu,
+            games,
+            library_issues,
+            selected_index: 0,
+            scroll_offset: 0.0,
+            transition_progress: 0.0,
+            particles,
+            theme_selector_index: Self::theme_index(&self.current_theme, &self.settings.custom_themes),
+            selected_setting_index: 0,
+            search_query: String::new(),
+        };
+
+        self.window.set_title(ENGINE_TITLE);
+    }
+
+    /// Switches between borderless fullscreen and a window, and remembers the choice.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+        self.settings.fullscreen = fullscreen;
+        log::info!("🖥️ Fullscreen {}", if fullscreen { "on" } else { "off" });
+        self.save_settings();
+    }
+
+    /// Changes the logical resolution games are drawn at. In windowed mode the window is
+    /// resized to match; the surface follows through the `Resized` event.
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.settings.resolution = [width, height];
+        if !self.settings.fullscreen {
+            self.window.set_inner_size(winit::dpi::LogicalSize::new(width, height));
+        }
+        log::info!("🖥️ Resolution set to {}x{}", width, height);
+        self.save_settings();
+    }
+
+    /// Caps how often frames are drawn; `UNCAPPED_FPS` draws as fast as possible. Game logic
+    /// still steps at the fixed logic rate either way.
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.settings.target_fps = fps;
+        self.target_frame_time = frame_time(fps);
+        log::info!("🖥️ Frame limit set to {}", fps_label(fps));
+        self.save_settings();
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.settings.save(&self.settings_path) {
+            log::error!("❌ Failed to save settings: {}", e);
+        }
+    }
+
+    fn save_library(&self) {
+        if let Err(e) = self.library.save(&self.library_path) {
+            log::error!("❌ Failed to save library history: {}", e);
+        }
+    }
+
+    fn render(&mut self) -> Result<(), CacaoError> {
+        if !self.renderer.begin_frame()? {
+            return Ok(());
+        }
+
+        // Menus keep their fixed layout and games get the chosen resolution, both stretched to the window
+        let shows_game = matches!(self.state, EngineState::Playing | EngineState::Paused { settings: None, .. });
+        let [width, height] = if shows_game {
+            self.settings.resolution.map(|v| v as f32)
+        } else {
+            [LAYOUT_WIDTH, LAYOUT_HEIGHT]
+        };
+        self.renderer.set_logical_size(width, height);
+
+        // Theme overlays dress up the engine's own screens, never a game
+        let mut effects = self.settings.post_effects.clone();
+        if !shows_game && self.settings.theme_overlay {
+            effects.extend(self.current_theme.overlay_effects());
+        }
+        self.renderer.set_post_effects(&effects);
+
+        // Menu layout is in pixels from the top-left, not around the game camera
+        if !shows_game {
+            self.renderer.begin_screen_space();
+            self.use_theme_font();
+        } else {
+            self.renderer.set_font("default");
+        }
+
+        match &self.state {
+            EngineState::Menu { state, games, selected_index, scroll_offset, transition_progress, particles, .. } => {
+                let state_clone = state.clone();
+                let games_clone = games.clone();
+                let selected = *selected_index;
+                let scroll = *scroll_offset;
+                let progress = *transition_progress;
+                let particles_clone = particles.clone();
+                
+                self.render_stunning_menu(&state_clone, &games_clone, selected, scroll, progress, &particles_clone)?;
+            }
+            EngineState::Playing => {
+                let result = match &self.current_game {
+                    Some(game) => game.render(&mut self.renderer, &self.assets, self.timestep.alpha()),
+                    None => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.show_error("Game render", e);
+                } else if let Some(error) = self.script_error.clone() {
+                    self.render_script_error(&error)?;
+                }
+            }
+            EngineState::Paused { selected, settings: None } => {
+                let selected = *selected;
+                // The game draws its frame again with the same interpolation, so nothing moves
+                let result = match &self.current_game {
+                    Some(game) => game.render(&mut self.renderer, &self.assets, self.timestep.alpha()),
+                    None => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.show_error("Game render", e);
+                } else {
+                    self.render_pause_menu(selected)?;
+                }
+            }
+            EngineState::Paused { settings: Some(_), .. } => {
+                let theme = self.current_theme.clone();
+                self.renderer.clear_screen(theme.background_color());
+                self.render_settings(1.0, &theme)?;
+            }
+            EngineState::Loading { progress, status } => {
+                let p = *progress;
+                let s = status.clone();
+                self.render_loading_screen(p, &s)?;
+            }
+            EngineState::Error { message, source } => {
+                let message = message.clone();
+                let source = source.clone();
+                self.render_error_screen(&message, &source)?;
+            }
+        }
+
+        if self.quit_confirmation {
+            self.render_quit_confirmation()?;
+        }
+
+        // Taken before the debug overlay so shared shots stay clean
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.save_screenshot();
+        }
+
+        if self.show_debug_overlay {
+            self.render_debug_overlay()?;
+        }
+
+        self.renderer.end_frame()?;
+        Ok(())
+    }
+
+    // Themes name their font, but it's only there once a game has shipped it
+    fn use_theme_font(&mut self) {
+        if !self.renderer.set_font(self.current_theme.font_name()) {
+            self.renderer.set_font("default");
+        }
+    }
+
+    fn save_screenshot(&mut self) {
+        let result = self.renderer.capture_frame().and_then(|image| {
+            std::fs::create_dir_all(&self.screenshots_dir)?;
+            let path = self.screenshots_dir.join(screenshot_file_name(SystemTime::now()));
+            image.save(&path)
+                .map_err(|e| CacaoError::RenderError(format!("Failed to write {}: {}", path.display(), e)))?;
+            Ok(path)
+        });
+        match result {
+            Ok(path) => log::info!("📸 Screenshot saved to {}", path.display()),
+            Err(e) => log::error!("❌ Screenshot failed: {}", e),
+        }
+    }
+
+    fn render_stunning_menu(
+        &mut self,
+        menu_state: &MenuState,
+        games: &[GameEntry],
+        selected_index: usize,
+        scroll_offset: f32,
+        progress: f32,
+        particles: &ParticleSystem,
+    ) -> Result<(), CacaoError> {
+        let theme = self.current_theme.clone();
+        
+        if matches!(theme, Theme::Animated) {
+            let time = self.menu_animation_time;
+            let bg_color1 = [
+                0.05 + (time * 0.5).sin() * 0.02,
+                0.02 + (time * 0.3).sin() * 0.02,
+                0.15 + (time * 0.4).sin() * 0.03,
+                1.0
+            ];
+            self.renderer.clear_screen(bg_color1);
+        } else {
+            self.renderer.clear_screen(theme.background_color());
+        }
+
+        if theme.should_show_particles() {
+            // Glowing particles sit behind the menu on their own layer
+            self.renderer.set_layer(-1);
+            self.renderer.set_blend_mode(BlendMode::Additive);
+            particles.render(&mut self.renderer)?;
+            self.renderer.set_blend_mode(BlendMode::Alpha);
+            self.renderer.set_layer(0);
+        }
 
         if matches!(theme, Theme::Wii) {
             for i in 0..10 {
@@ -621,34 +1586,60 @@ impl CacaoEngine {
         }
 
         let alpha = progress.min(1.0);
+        let offset = self.settings.menu_transition.offset(progress, self.menu_transition_back);
+        self.renderer.set_offset(offset.x, offset.y);
 
         match menu_state {
             MenuState::MainMenu => {
-                self.render_main_menu(alpha, &theme)?;
+                let issue_count = self.library_issues().len();
+                self.render_main_menu(issue_count, alpha, &theme)?;
             }
             MenuState::GameList => {
                 self.render_game_list(games, selected_index, scroll_offset, alpha, &theme)?;
             }
             MenuState::GameDetails(idx) => {
                 if let Some(game) = games.get(*idx) {
-                    self.render_game_details(&game.info, alpha, &theme)?;
+                    self.render_game_details(game, alpha, &theme)?;
                 }
             }
             MenuState::ThemeSelector => {
                 self.render_theme_selector(alpha, &theme)?;
             }
+            MenuState::ThemeEditor { colors, row, .. } => {
+                self.render_theme_editor(colors, *row, alpha, &theme)?;
+            }
             MenuState::Settings => {
                 self.render_settings(alpha, &theme)?;
             }
             MenuState::About => {
                 self.render_about(alpha, &theme)?;
             }
+            MenuState::LibraryIssues => {
+                let issues = self.library_issues();
+                self.render_library_issues(&issues, alpha, &theme)?;
+            }
+            MenuState::Controls { selected, waiting, warning } => {
+                self.render_controls(*selected, *waiting, warning.as_deref(), alpha, &theme)?;
+            }
         }
 
+        self.renderer.set_offset(0.0, 0.0);
         Ok(())
     }
 
-    fn render_main_menu(&mut self, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+    fn library_issues(&self) -> Vec<LibraryIssue> {
+        match &self.state {
+            EngineState::Menu { library_issues, .. } => library_issues.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Cursor position translated from window pixels into the fixed menu layout.
+    fn menu_mouse_position(&self) -> Vec2 {
+        window_to_layout(self.input.get_mouse_position(), self.window.inner_size())
+    }
+
+    fn render_main_menu(&mut self, issue_count: usize, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
         let title_color = theme.accent_color(); 
         let text_color = theme.text_color();
         let accent_color = theme.accent_color();
@@ -660,23 +1651,25 @@ impl CacaoEngine {
         for i in 0..3 {
             let offset = (i as f32 + 1.0) * 2.0;
             let glow_alpha = alpha * (0.3 - i as f32 * 0.1);
-            self.renderer.draw_text(
+            self.renderer.draw_text_aligned(
                 "CACAO ENGINE",
-                320.0 + offset,
+                LAYOUT_WIDTH / 2.0 + offset,
                 100.0 + offset,
                 title_size,
-                [title_color[0], title_color[1], title_color[2], glow_alpha]
+                [title_color[0], title_color[1], title_color[2], glow_alpha],
+                TextAlign::Center
             )?;
         }
         
-        self.renderer.draw_text("CACAO ENGINE", 320.0, 100.0, title_size, [title_color[0], title_color[1], title_color[2], title_color[3] * alpha])?;
+        self.renderer.draw_text_aligned("CACAO ENGINE", LAYOUT_WIDTH / 2.0, 100.0, title_size, [title_color[0], title_color[1], title_color[2], title_color[3] * alpha], TextAlign::Center)?;
         
-        self.renderer.draw_text(
+        self.renderer.draw_text_aligned(
             "v1.0.0 - The Ultimate Game Engine",
-            380.0,
+            LAYOUT_WIDTH / 2.0,
             180.0,
             20.0,
-            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.8]
+            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.8],
+            TextAlign::Center
         )?;
 
         self.renderer.draw_rect(200.0, 220.0, 880.0, 3.0, [accent_color[0], accent_color[1], accent_color[2], accent_color[3] * alpha])?;
@@ -684,11 +1677,25 @@ impl CacaoEngine {
         let base_y = 300.0;
         let bounce = (self.menu_animation_time * 4.0).sin().abs() * 5.0;
         
+        let hovered_row = main_menu_row_at(self.menu_mouse_position()).filter(|&row| row < 4 || issue_count > 0);
+        if let Some(row) = hovered_row {
+            self.renderer.draw_rect(440.0, MAIN_MENU_ROWS[row] - 6.0, 440.0, 40.0, [accent_color[0], accent_color[1], accent_color[2], 0.15 * alpha])?;
+        }
+        let row_color = |row: usize| {
+            let color = if hovered_row == Some(row) { accent_color } else { text_color };
+            [color[0], color[1], color[2], color[3] * alpha]
+        };
+
         self.renderer.draw_text("▶ [ENTER] PLAY GAMES", 450.0, base_y + bounce, 28.0, [accent_color[0], accent_color[1], accent_color[2], accent_color[3] * alpha])?;
-        self.renderer.draw_text("  [S] Settings", 450.0, base_y + 50.0, 24.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
-        self.renderer.draw_text("  [T] Themes", 450.0, base_y + 90.0, 24.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
-        self.renderer.draw_text("  [A] About", 450.0, base_y + 130.0, 24.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
-        self.renderer.draw_text("  [ESC] Exit", 450.0, base_y + 170.0, 24.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
+        self.renderer.draw_text("  [S] Settings", 450.0, base_y + 50.0, 24.0, row_color(1))?;
+        self.renderer.draw_text("  [T] Themes", 450.0, base_y + 90.0, 24.0, row_color(2))?;
+        self.renderer.draw_text("  [A] About", 450.0, base_y + 130.0, 24.0, row_color(3))?;
+        let mut exit_y = base_y + 170.0;
+        if issue_count > 0 {
+            self.renderer.draw_text(&format!("  [L] Library Issues ({})", issue_count), 450.0, exit_y, 24.0, row_color(4))?;
+            exit_y += 40.0;
+        }
+        self.renderer.draw_text("  [ESC] Exit", 450.0, exit_y, 24.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
 
         let footer_alpha = alpha * ((self.menu_animation_time * 1.5).sin() * 0.3 + 0.7);
         self.renderer.draw_text(
@@ -718,6 +1725,17 @@ impl CacaoEngine {
         self.renderer.draw_text("GAME LIBRARY", 80.0, 50.0, 48.0, header_color)?;
         self.renderer.draw_rect(80.0, 110.0, 1120.0, 2.0, header_color)?;
 
+        let search_query = match &self.state {
+            EngineState::Menu { search_query, .. } => search_query.clone(),
+            _ => String::new(),
+        };
+        if !games.is_empty() {
+            let search_text = if search_query.is_empty() { "Type to search".to_string() } else { format!("Search: {}_", search_query) };
+            let search_alpha = if search_query.is_empty() { 0.5 } else { 1.0 };
+            self.renderer.draw_text(&search_text, 760.0, 70.0, 20.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha * search_alpha])?;
+        }
+        let visible: Vec<&GameEntry> = filter_games(games, &search_query).into_iter().map(|i| &games[i]).collect();
+
         if games.is_empty() {
             self.renderer.draw_text(
                 "No games found!",
@@ -733,11 +1751,19 @@ impl CacaoEngine {
                 16.0,
                 [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
             )?;
+        } else if visible.is_empty() {
+            self.renderer.draw_text(
+                &format!("No games match \"{}\"", search_query),
+                450.0,
+                300.0,
+                24.0,
+                [text_color[0], text_color[1], text_color[2], text_color[3] * alpha * 0.8]
+            )?;
         } else {
             let start_y = 150.0 - scroll_offset;
             
-            for (i, game) in games.iter().enumerate() {
-                let y = start_y + (i as f32 * 120.0);
+            for (i, game) in visible.iter().enumerate() {
+                let y = start_y + (i as f32 * GAME_CARD_SPACING);
                 
                 if y < 100.0 || y > 700.0 {
                     continue;
@@ -761,7 +1787,9 @@ impl CacaoEngine {
                     self.renderer.draw_rect(88.0, y + 8.0, 1104.0, 96.0, [0.0, 0.0, 0.0, alpha * 0.5])?;
                 }
                 
-                self.renderer.draw_rect(80.0, y, 1104.0, 96.0, card_color)?;
+                // Cards darken a little towards the bottom
+                let card_bottom = mix_color(card_color, [0.0, 0.0, 0.0, card_color[3]], 0.2);
+                self.renderer.draw_rect_gradient(80.0, y, 1104.0, 96.0, card_color, card_bottom)?;
                 
                 let border_color = if is_selected {
                     accent
@@ -770,6 +1798,17 @@ impl CacaoEngine {
                 };
                 self.renderer.draw_rect_outline(80.0, y, 1104.0, 96.0, 2.0, border_color)?;
 
+                if self.library.is_favorite(&game.info.id) {
+                    self.renderer.draw_text("*", 90.0, y + 20.0, 24.0, [accent[0], accent[1], accent[2], accent[3] * alpha])?;
+                }
+                if self.library.is_recent(&game.info.id) {
+                    self.renderer.draw_text("RECENTLY PLAYED", 860.0, y + 24.0, 14.0, [accent[0], accent[1], accent[2], accent[3] * alpha * 0.8])?;
+                }
+
+                if let Some(banner) = game.banner.as_ref().filter(|_| game.banner_loaded) {
+                    self.draw_banner(banner, 1020.0, y + 8.0, 144.0, 80.0)?;
+                }
+
                 if is_selected {
                     let indicator_x = 50.0 + ((self.menu_animation_time * 4.0).sin() * 5.0);
                     self.renderer.draw_text(
@@ -804,11 +1843,18 @@ impl CacaoEngine {
                     [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.8]
                 )?;
             }
+
+            // Scrollbar, only once the list is longer than the screen
+            let (track_y, track_height) = (150.0, 500.0);
+            if let Some((thumb_y, thumb_height)) = scrollbar_thumb(visible.len(), scroll_offset, track_height) {
+                self.renderer.draw_rect(1200.0, track_y, 6.0, track_height, [secondary_text[0], secondary_text[1], secondary_text[2], 0.2 * alpha])?;
+                self.renderer.draw_rect(1200.0, track_y + thumb_y, 6.0, thumb_height, [accent[0], accent[1], accent[2], 0.8 * alpha])?;
+            }
         }
 
         self.renderer.draw_text(
-            "↑↓ Navigate • [ENTER] Select • [ESC] Back",
-            350.0,
+            "↑↓ Navigate • [PGUP/PGDN] Page • [ENTER] Select • [F5] Refresh • [ESC] Back",
+            220.0,
             680.0,
             16.0,
             [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
@@ -817,7 +1863,14 @@ impl CacaoEngine {
         Ok(())
     }
 
-    fn render_game_details(&mut self, info: &GameInfo, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+    /// Scales a banner to fit inside the box, centred, keeping its aspect ratio.
+    fn draw_banner(&mut self, banner: &Sprite, x: f32, y: f32, width: f32, height: f32) -> Result<(), CacaoError> {
+        let scale = fit_scale(banner.width, banner.height, width, height);
+        self.renderer.draw_sprite(banner, x + width / 2.0, y + height / 2.0, 0.0, scale)
+    }
+
+    fn render_game_details(&mut self, game: &GameEntry, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+        let info = &game.info;
         let accent = theme.accent_color();
         let text = theme.text_color();
         let card = theme.card_color();
@@ -833,14 +1886,17 @@ impl CacaoEngine {
             [card[0], card[1], card[2], card[3] * alpha * 0.8]
         )?;
         self.renderer.draw_rect_outline(140.0, banner_y, 1000.0, 300.0, 3.0, accent)?;
-        
-        self.renderer.draw_text(
-            &info.title,
-            300.0,
-            230.0,
-            48.0,
-            [text[0], text[1], text[2], text[3] * alpha]
-        )?;
+
+        match &game.banner {
+            Some(banner) if game.banner_loaded => self.draw_banner(banner, 140.0, banner_y, 1000.0, 300.0)?,
+            _ => self.renderer.draw_text(
+                &info.title,
+                300.0,
+                230.0,
+                48.0,
+                [text[0], text[1], text[2], text[3] * alpha]
+            )?,
+        }
 
         let details_y = 450.0;
         self.renderer.draw_text("GAME INFORMATION", 140.0, details_y, 28.0, accent)?;
@@ -858,12 +1914,17 @@ impl CacaoEngine {
         
         self.renderer.draw_text("Engine:", 140.0, info_y, 20.0, secondary_text)?;
         self.renderer.draw_text(&info.engine_version, 300.0, info_y, 20.0, text)?;
+        info_y += 35.0;
+
+        let playtime = self.library.playtime(&info.id).map_or_else(|| "Never".to_string(), format_playtime);
+        self.renderer.draw_text("Played:", 140.0, info_y, 20.0, secondary_text)?;
+        self.renderer.draw_text(&playtime, 300.0, info_y, 20.0, text)?;
 
         let desc_y = details_y;
         self.renderer.draw_rect(600.0, desc_y, 540.0, 200.0, [card[0], card[1], card[2], card[3] * alpha * 0.8])?;
         self.renderer.draw_rect_outline(600.0, desc_y, 540.0, 200.0, 2.0, accent)?;
         self.renderer.draw_text("Description", 620.0, desc_y + 20.0, 20.0, accent)?;
-        self.renderer.draw_text(&info.description, 620.0, desc_y + 60.0, 16.0, text)?;
+        self.renderer.draw_text_wrapped(&info.description, 620.0, desc_y + 60.0, 16.0, text, 500.0)?;
 
         let button_y = 640.0;
         let button_pulse = (self.menu_animation_time * 4.0).sin() * 10.0;
@@ -890,12 +1951,14 @@ impl CacaoEngine {
             accent
         )?;
 
-        self.renderer.draw_text(
-            "[ESC] Back to Library",
-            530.0,
+        let favorite_hint = if self.library.is_favorite(&info.id) { "[F] Unfavorite" } else { "[F] Favorite" };
+        self.renderer.draw_text_aligned(
+            &format!("[ESC] Back to Library    {}", favorite_hint),
+            640.0,
             710.0,
             16.0,
-            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
+            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7],
+            TextAlign::Center
         )?;
 
         Ok(())
@@ -909,7 +1972,7 @@ impl CacaoEngine {
         self.renderer.draw_text("THEME SELECTOR", 80.0, 80.0, 48.0, accent)?;
         self.renderer.draw_rect(80.0, 140.0, 500.0, 2.0, accent)?;
 
-        let theme_options = Theme::all();
+        let theme_options = Theme::all(&self.settings.custom_themes);
 
         // FIXED: Proper access to theme_selector_index
         if let EngineState::Menu { theme_selector_index, .. } = &self.state {
@@ -921,7 +1984,7 @@ impl CacaoEngine {
                 let size = if is_selected { 32.0 } else { 24.0 };
 
                 let card_color = if is_selected { theme.selected_card_color() } else { theme.card_color() };
-                self.renderer.draw_rect(100.0, y, 500.0, 50.0, [card_color[0], card_color[1], card_color[2], card_color[3] * alpha])?;
+                self.renderer.draw_rounded_rect(100.0, y, 500.0, 50.0, 10.0, [card_color[0], card_color[1], card_color[2], card_color[3] * alpha])?;
                 
                 if is_selected {
                     let indicator_x = 60.0 + (self.menu_animation_time * 4.0).sin() * 3.0;
@@ -940,9 +2003,69 @@ impl CacaoEngine {
             }
         }
 
+        self.renderer.draw_text("Transition", 680.0, 220.0, 18.0, [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha])?;
+        self.renderer.draw_text(self.settings.menu_transition.name(), 680.0, 250.0, 28.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
+        self.renderer.draw_text("Overlay", 680.0, 310.0, 18.0, [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha])?;
+        let overlay = if self.settings.theme_overlay { "On" } else { "Off" };
+        self.renderer.draw_text(overlay, 680.0, 340.0, 28.0, [text_color[0], text_color[1], text_color[2], text_color[3] * alpha])?;
+
         self.renderer.draw_text(
-            "[ENTER] Apply Theme • [ESC] Back",
-            300.0,
+            "[ENTER] Apply Theme • [E] Edit Colors • [T] Transition • [O] Overlay • [ESC] Back",
+            190.0,
+            680.0,
+            16.0,
+            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
+        )?;
+
+        Ok(())
+    }
+
+    fn render_theme_editor(&mut self, colors: &ThemeColors, selected_row: usize, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+        let accent = theme.accent_color();
+        let text = theme.text_color();
+        let secondary_text = theme.secondary_text_color();
+
+        self.renderer.draw_text("THEME EDITOR", 80.0, 80.0, 48.0, accent)?;
+        self.renderer.draw_rect(80.0, 140.0, 500.0, 2.0, accent)?;
+        self.renderer.draw_text(&colors.name, 600.0, 100.0, 24.0, text)?;
+
+        let palette = [colors.background, colors.accent, colors.text, colors.card];
+        for (i, (label, color)) in THEME_EDITOR_COLORS.iter().zip(palette).enumerate() {
+            let top = theme_editor_row_y(i * 3);
+            self.renderer.draw_text(label, 100.0, top, 16.0, text)?;
+            self.renderer.draw_rect(100.0, top + 30.0, 80.0, 40.0, color)?;
+            self.renderer.draw_rect_outline(100.0, top + 30.0, 80.0, 40.0, 1.0, secondary_text)?;
+
+            for (channel, name) in ["Red", "Green", "Blue"].iter().enumerate() {
+                let row = i * 3 + channel;
+                let y = theme_editor_row_y(row);
+                let is_selected = row == selected_row;
+                if is_selected {
+                    self.renderer.draw_rect(270.0, y - 6.0, 510.0, 30.0, [accent[0], accent[1], accent[2], 0.15 * alpha])?;
+                }
+                let label_color = if is_selected { accent } else { secondary_text };
+                self.renderer.draw_text(name, 290.0, y, 18.0, label_color)?;
+                self.renderer.draw_rect(380.0, y + 6.0, 280.0, 6.0, [secondary_text[0], secondary_text[1], secondary_text[2], 0.3 * alpha])?;
+                self.renderer.draw_rect(380.0, y + 6.0, 280.0 * color[channel], 6.0, [accent[0], accent[1], accent[2], alpha])?;
+                self.renderer.draw_text(&format!("{}", (color[channel] * 255.0).round()), 690.0, y, 18.0, label_color)?;
+            }
+        }
+
+        // Live preview of the palette being edited
+        let preview = Theme::Custom(colors.clone());
+        self.renderer.draw_rect(820.0, 200.0, 360.0, 400.0, preview.background_color())?;
+        self.renderer.draw_rect_outline(820.0, 200.0, 360.0, 400.0, 2.0, secondary_text)?;
+        self.renderer.draw_text("Preview", 850.0, 230.0, 32.0, preview.accent_color())?;
+        self.renderer.draw_rect(850.0, 300.0, 300.0, 80.0, preview.selected_card_color())?;
+        self.renderer.draw_text("Selected game", 870.0, 315.0, 22.0, preview.text_color())?;
+        self.renderer.draw_text("by Someone", 870.0, 345.0, 16.0, preview.secondary_text_color())?;
+        self.renderer.draw_rect(850.0, 400.0, 300.0, 80.0, preview.card_color())?;
+        self.renderer.draw_text("Another game", 870.0, 415.0, 22.0, preview.text_color())?;
+        self.renderer.draw_text("by Someone Else", 870.0, 445.0, 16.0, preview.secondary_text_color())?;
+
+        self.renderer.draw_text(
+            "[UP/DOWN] Channel • [LEFT/RIGHT] Adjust • [ENTER] Save • [ESC] Cancel",
+            240.0,
             680.0,
             16.0,
             [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
@@ -959,35 +2082,71 @@ impl CacaoEngine {
         self.renderer.draw_text("SETTINGS", 80.0, 80.0, 48.0, accent)?;
         self.renderer.draw_rect(80.0, 140.0, 300.0, 2.0, accent)?;
 
-        let mut y = 200.0;
-        self.renderer.draw_text("Audio", 100.0, y, 28.0, text)?;
-        y += 50.0;
-        self.renderer.draw_text("Master Volume: 100%", 120.0, y, 20.0, secondary_text)?;
-        y += 35.0;
-        self.renderer.draw_text("Music Volume: 80%", 120.0, y, 20.0, secondary_text)?;
-        y += 35.0;
-        self.renderer.draw_text("SFX Volume: 100%", 120.0, y, 20.0, secondary_text)?;
-        
-        y += 80.0;
-        self.renderer.draw_text("Graphics", 100.0, y, 28.0, text)?;
-        y += 50.0;
-        self.renderer.draw_text("Resolution: 1280x720", 120.0, y, 20.0, secondary_text)?;
-        y += 35.0;
-        self.renderer.draw_text("Fullscreen: Off", 120.0, y, 20.0, secondary_text)?;
-        y += 35.0;
-        self.renderer.draw_text("VSync: On", 120.0, y, 20.0, secondary_text)?;
+        let selected = match &self.state {
+            EngineState::Menu { selected_setting_index, .. } => *selected_setting_index,
+            EngineState::Paused { settings: Some(row), .. } => *row,
+            _ => 0,
+        };
+        let settings = self.settings.clone();
+        let volumes = [settings.master_volume, settings.music_volume, settings.sfx_volume];
+
+        self.renderer.draw_text("Audio", 100.0, 200.0, 28.0, text)?;
+        for (i, (label, volume)) in SETTINGS_ROWS.iter().zip(volumes.iter()).enumerate() {
+            let y = settings_row_y(i);
+            let is_selected = i == selected;
+            if is_selected {
+                self.renderer.draw_rect(100.0, y - 8.0, 640.0, 36.0, [accent[0], accent[1], accent[2], 0.15 * alpha])?;
+            }
+            let label_color = if is_selected { accent } else { secondary_text };
+            self.renderer.draw_text(label, 120.0, y, 20.0, label_color)?;
+
+            // Slider track and fill
+            self.renderer.draw_rect(360.0, y + 6.0, 280.0, 6.0, [secondary_text[0], secondary_text[1], secondary_text[2], 0.3 * alpha])?;
+            self.renderer.draw_rect(360.0, y + 6.0, 280.0 * volume, 6.0, [accent[0], accent[1], accent[2], alpha])?;
+            let value = if i == 0 && self.audio.is_muted() { "Muted".to_string() } else { format!("{}%", (volume * 100.0).round()) };
+            self.renderer.draw_text(&value, 660.0, y, 20.0, label_color)?;
+        }
+
+        self.renderer.draw_text("Graphics", 100.0, 410.0, 28.0, text)?;
+        let on_off = |on: bool| if on { "On".to_string() } else { "Off".to_string() };
+        let graphics_values = [
+            format!("{}x{}", settings.resolution[0], settings.resolution[1]),
+            on_off(settings.fullscreen),
+            on_off(settings.vsync),
+            fps_label(settings.target_fps),
+        ];
+        for (i, value) in graphics_values.iter().enumerate() {
+            let row = RESOLUTION_ROW + i;
+            let y = settings_row_y(row);
+            let is_selected = row == selected;
+            if is_selected {
+                self.renderer.draw_rect(100.0, y - 8.0, 640.0, 36.0, [accent[0], accent[1], accent[2], 0.15 * alpha])?;
+            }
+            let label_color = if is_selected { accent } else { secondary_text };
+            self.renderer.draw_text(SETTINGS_ROWS[row], 120.0, y, 20.0, label_color)?;
+            self.renderer.draw_text(value, 360.0, y, 20.0, label_color)?;
+        }
+
+        let y = settings_row_y(CONTROLS_ROW);
+        let is_selected = selected == CONTROLS_ROW;
+        if is_selected {
+            self.renderer.draw_rect(100.0, y - 8.0, 640.0, 36.0, [accent[0], accent[1], accent[2], 0.15 * alpha])?;
+        }
+        let label_color = if is_selected { accent } else { secondary_text };
+        self.renderer.draw_text(SETTINGS_ROWS[CONTROLS_ROW], 120.0, y, 20.0, label_color)?;
+        self.renderer.draw_text("[ENTER] Rebind keys", 360.0, y, 20.0, label_color)?;
 
         self.renderer.draw_text(
-            "(Settings coming soon!)",
-            480.0,
-            350.0,
-            24.0,
-            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.6]
+            "[UP/DOWN] Select  [LEFT/RIGHT] Adjust",
+            440.0,
+            650.0,
+            16.0,
+            [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
         )?;
 
         self.renderer.draw_text(
-            "[ESC] Back to Main Menu",
-            490.0,
+            "[M] Mute • [ESC] Back to Main Menu",
+            440.0,
             680.0,
             16.0,
             [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7]
@@ -996,6 +2155,70 @@ impl CacaoEngine {
         Ok(())
     }
 
+    fn render_controls(&mut self, selected: usize, waiting: bool, warning: Option<&str>, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+        let accent = theme.accent_color();
+        let secondary_text = theme.secondary_text_color();
+        let hint_color = [secondary_text[0], secondary_text[1], secondary_text[2], secondary_text[3] * alpha * 0.7];
+
+        self.renderer.draw_text("CONTROLS", 80.0, 80.0, 48.0, accent)?;
+        self.renderer.draw_rect(80.0, 140.0, 300.0, 2.0, accent)?;
+
+        for (i, action) in self.input.get_action_names().iter().enumerate() {
+            let y = controls_row_y(i);
+            let is_selected = i == selected;
+            if is_selected {
+                self.renderer.draw_rect(100.0, y - 8.0, 1080.0, 36.0, [accent[0], accent[1], accent[2], 0.15 * alpha])?;
+            }
+            let label_color = if is_selected { accent } else { secondary_text };
+            self.renderer.draw_text(action, 120.0, y, 20.0, label_color)?;
+
+            let bindings = if is_selected && waiting {
+                "Press a key...".to_string()
+            } else {
+                self.input.get_action_bindings(action).iter()
+                    .map(|button| button.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            self.renderer.draw_text(&bindings, 400.0, y, 20.0, label_color)?;
+        }
+
+        if let Some(warning) = warning {
+            self.renderer.draw_text(&format!("Warning: {}", warning), 120.0, 610.0, 18.0, [1.0, 0.7, 0.3, alpha])?;
+        }
+
+        let hint = if waiting { "[ESC] Cancel" } else { "[ENTER] Rebind  [ESC] Back to Settings" };
+        self.renderer.draw_text(hint, 440.0, 680.0, 16.0, hint_color)?;
+
+        Ok(())
+    }
+
+    fn render_library_issues(&mut self, issues: &[LibraryIssue], alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
+        let accent = theme.accent_color();
+        let text_color = theme.text_color();
+        let secondary_text = theme.secondary_text_color();
+        let fade = |color: [f32; 4]| [color[0], color[1], color[2], color[3] * alpha];
+
+        self.renderer.draw_text("LIBRARY ISSUES", 80.0, 50.0, 48.0, fade(accent))?;
+        self.renderer.draw_rect(80.0, 110.0, 1120.0, 2.0, fade(accent))?;
+        self.renderer.draw_text("These game files couldn't be read, so they're missing from the library.", 80.0, 130.0, 18.0, fade(secondary_text))?;
+
+        for (i, issue) in issues.iter().take(ISSUES_PER_PAGE).enumerate() {
+            let y = 180.0 + i as f32 * 90.0;
+            let file_name = issue.path.file_name().map_or_else(|| issue.path.display().to_string(), |name| name.to_string_lossy().into_owned());
+            self.renderer.draw_rect(80.0, y, 1120.0, 80.0, fade(theme.card_color()))?;
+            self.renderer.draw_text(&file_name, 100.0, y + 12.0, 22.0, fade(text_color))?;
+            self.renderer.draw_text_wrapped(&issue.error, 100.0, y + 42.0, 16.0, fade(secondary_text), 1080.0)?;
+        }
+        if issues.len() > ISSUES_PER_PAGE {
+            let more = format!("...and {} more, see the log for the full list", issues.len() - ISSUES_PER_PAGE);
+            self.renderer.draw_text(&more, 80.0, 180.0 + ISSUES_PER_PAGE as f32 * 90.0, 18.0, fade(secondary_text))?;
+        }
+
+        self.renderer.draw_text("[ESC] Back", 80.0, 660.0, 20.0, fade(accent))?;
+        Ok(())
+    }
+
     fn render_about(&mut self, alpha: f32, theme: &Theme) -> Result<(), CacaoError> {
         let accent = theme.accent_color();
         let text = theme.text_color();
@@ -1020,8 +2243,8 @@ impl CacaoEngine {
         
         self.renderer.draw_text("🍫", 605.0, 170.0, 64.0, [accent[0], accent[1], accent[2], accent[3] * alpha])?;
 
-        self.renderer.draw_text("CACAO ENGINE", 490.0, 320.0, 36.0, accent)?;
-        self.renderer.draw_text("Version 1.0.0", 545.0, 365.0, 20.0, text)?;
+        self.renderer.draw_text_aligned("CACAO ENGINE", LAYOUT_WIDTH / 2.0, 320.0, 36.0, accent, TextAlign::Center)?;
+        self.renderer.draw_text_aligned("Version 1.0.0", LAYOUT_WIDTH / 2.0, 365.0, 20.0, text, TextAlign::Center)?;
 
         let mut info_y = 420.0;
         self.renderer.draw_text(
@@ -1113,4 +2336,757 @@ impl CacaoEngine {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn render_error_screen(&mut self, message: &str, source: &str) -> Result<(), CacaoError> {
+        self.renderer.clear_screen([0.08, 0.02, 0.04, 1.0]);
+
+        let panel_x = 240.0;
+        let panel_y = 180.0;
+        let panel_width = 800.0;
+        let panel_height = 360.0;
+
+        self.renderer.draw_rect(panel_x, panel_y, panel_width, panel_height, [0.18, 0.05, 0.08, 0.95])?;
+        self.renderer.draw_rect_outline(panel_x, panel_y, panel_width, panel_height, 3.0, [1.0, 0.3, 0.3, 1.0])?;
+        self.renderer.draw_rect(panel_x, panel_y, panel_width, 60.0, [0.5, 0.08, 0.12, 1.0])?;
+
+        self.renderer.draw_text("Something went wrong", panel_x + 30.0, panel_y + 18.0, 28.0, [1.0, 0.9, 0.9, 1.0])?;
+        self.renderer.draw_text(&format!("While: {}", source), panel_x + 30.0, panel_y + 85.0, 18.0, [1.0, 0.6, 0.6, 1.0])?;
+        self.renderer.draw_text_wrapped(message, panel_x + 30.0, panel_y + 125.0, 18.0, [0.95, 0.95, 0.95, 1.0], panel_width - 60.0)?;
+
+        self.renderer.draw_text("[ESC] Back to menu", panel_x + 30.0, panel_y + panel_height - 45.0, 18.0, [1.0, 0.8, 0.4, 1.0])?;
+
+        Ok(())
+    }
+
+    fn render_script_error(&mut self, error: &str) -> Result<(), CacaoError> {
+        // Drawn above anything the game queued, wherever its camera is
+        self.renderer.set_layer(i32::MAX);
+        self.renderer.begin_screen_space();
+        self.renderer.draw_rect(0.0, 0.0, LAYOUT_WIDTH, 110.0, [0.35, 0.02, 0.05, 0.92])?;
+        self.renderer.draw_rect(0.0, 108.0, LAYOUT_WIDTH, 2.0, [1.0, 0.3, 0.3, 1.0])?;
+        self.renderer.draw_text("Script error - still running the last working version", 20.0, 16.0, 20.0, [1.0, 0.8, 0.8, 1.0])?;
+        self.renderer.draw_text_wrapped(error, 20.0, 46.0, 16.0, [1.0, 1.0, 1.0, 0.95], LAYOUT_WIDTH - 40.0)?;
+        self.renderer.begin_world_space();
+        self.renderer.set_layer(0);
+        Ok(())
+    }
+
+    fn render_quit_confirmation(&mut self) -> Result<(), CacaoError> {
+        let size = self.renderer.get_camera().viewport_size();
+        let center_x = size.x / 2.0;
+        let panel_y = size.y / 2.0 - 80.0;
+
+        self.renderer.set_layer(i32::MAX);
+        self.renderer.begin_screen_space();
+        self.use_theme_font();
+        self.renderer.draw_rect(0.0, 0.0, size.x, size.y, [0.0, 0.0, 0.0, 0.6])?;
+        self.renderer.draw_rect(center_x - 260.0, panel_y, 520.0, 160.0, [0.12, 0.08, 0.06, 0.95])?;
+        // From the menus there's no game to save, just the engine to close
+        let (title, detail, keys) = if matches!(self.state, EngineState::Menu { .. }) {
+            ("Exit to desktop?", "Cacao Engine will close.", "[Y] Exit    [N] Stay")
+        } else {
+            ("Quit the game?", "Your progress will be saved.", "[Y] Quit    [N] Keep playing")
+        };
+        self.renderer.draw_text_aligned(title, center_x, panel_y + 24.0, 28.0, [1.0, 1.0, 1.0, 1.0], TextAlign::Center)?;
+        self.renderer.draw_text_aligned(detail, center_x, panel_y + 70.0, 18.0, [0.8, 0.8, 0.8, 1.0], TextAlign::Center)?;
+        self.renderer.draw_text_aligned(keys, center_x, panel_y + 110.0, 18.0, [1.0, 0.8, 0.4, 1.0], TextAlign::Center)?;
+        self.renderer.begin_world_space();
+        self.renderer.set_layer(0);
+        Ok(())
+    }
+
+    fn render_pause_menu(&mut self, selected: usize) -> Result<(), CacaoError> {
+        let size = self.renderer.get_camera().viewport_size();
+        let center_x = size.x / 2.0;
+        let panel_y = size.y / 2.0 - 110.0;
+
+        self.renderer.set_layer(i32::MAX);
+        self.renderer.begin_screen_space();
+        self.renderer.draw_rect(0.0, 0.0, size.x, size.y, [0.0, 0.0, 0.0, 0.6])?;
+        self.renderer.draw_rect(center_x - 180.0, panel_y, 360.0, 220.0, [0.12, 0.08, 0.06, 0.95])?;
+        self.renderer.draw_text_aligned("PAUSED", center_x, panel_y + 24.0, 32.0, [1.0, 1.0, 1.0, 1.0], TextAlign::Center)?;
+        for (i, label) in PAUSE_ROWS.iter().enumerate() {
+            let y = panel_y + 84.0 + i as f32 * 40.0;
+            let color = if i == selected {
+                self.renderer.draw_rect(center_x - 150.0, y - 6.0, 300.0, 32.0, [1.0, 0.8, 0.4, 0.15])?;
+                [1.0, 0.8, 0.4, 1.0]
+            } else {
+                [0.8, 0.8, 0.8, 1.0]
+            };
+            self.renderer.draw_text_aligned(label, center_x, y, 20.0, color, TextAlign::Center)?;
+        }
+        self.renderer.begin_world_space();
+        self.renderer.set_layer(0);
+        Ok(())
+    }
+
+    fn render_debug_overlay(&mut self) -> Result<(), CacaoError> {
+        let lines = [
+            format!("FPS: {:.1}", self.frame_stats.fps()),
+            format!("Frame: {:.2} ms", self.frame_stats.average_frame_time().as_secs_f32() * 1000.0),
+            format!("Sounds: {}", self.audio.get_active_sound_count()),
+            format!("Frames: {}", self.frame_count),
+            format!("Bind groups: {}", self.renderer.sprite_bind_groups_created()),
+            format!("Post targets: {}", self.renderer.post_target_count()),
+        ];
+
+        let memory = self.assets.get_memory_usage();
+        let memory_lines = [
+            format!("Sprites: {}", format_bytes(memory.sprite_memory)),
+            format!("Textures: {}", format_bytes(memory.texture_memory)),
+            format!("Audio: {}", format_bytes(memory.audio_memory)),
+            format!("Scripts: {}", format_bytes(memory.script_memory)),
+            format!("Fonts: {}", format_bytes(memory.font_memory)),
+            format!("Data: {}", format_bytes(memory.data_memory)),
+            format!("Total: {}", format_bytes(memory.total_memory)),
+        ];
+
+        self.renderer.set_layer(i32::MAX);
+        self.renderer.begin_screen_space();
+        let mut y = 8.0;
+        for panel in [&lines[..], &memory_lines[..]] {
+            let height = 24.0 + panel.len() as f32 * 20.0;
+            self.renderer.draw_rect(8.0, y, 220.0, height, [0.0, 0.0, 0.0, 0.6])?;
+            for (i, line) in panel.iter().enumerate() {
+                self.renderer.draw_text(line, 18.0, y + 10.0 + i as f32 * 20.0, 16.0, [0.6, 1.0, 0.6, 1.0])?;
+            }
+            y += height + 8.0;
+        }
+        self.renderer.begin_world_space();
+        self.renderer.set_layer(0);
+        Ok(())
+    }
+}
+
+/// Shortest time between frames for a frame limit; zero when uncapped.
+fn frame_time(target_fps: u32) -> Duration {
+    if target_fps == UNCAPPED_FPS {
+        Duration::ZERO
+    } else {
+        Duration::from_secs_f64(1.0 / target_fps as f64)
+    }
+}
+
+fn fps_label(target_fps: u32) -> String {
+    if target_fps == UNCAPPED_FPS {
+        "Uncapped".to_string()
+    } else {
+        format!("{} FPS", target_fps)
+    }
+}
+
+/// `screenshot-<unix seconds>-<millis>.png`, so names sort by when they were taken.
+fn screenshot_file_name(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("screenshot-{}-{:03}.png", since_epoch.as_secs(), since_epoch.subsec_millis())
+}
+
+/// Whole hours and minutes, e.g. "3h 12m"; under an hour it's just minutes.
+fn format_playtime(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Baseline of a theme editor channel row; each color's three rows sit together.
+fn theme_editor_row_y(row: usize) -> f32 {
+    180.0 + (row / 3) as f32 * 120.0 + (row % 3) as f32 * 32.0
+}
+
+/// Linear blend from `from` towards `to`; `amount` 0 is `from`, 1 is `to`.
+fn mix_color(from: [f32; 4], to: [f32; 4], amount: f32) -> [f32; 4] {
+    std::array::from_fn(|i| from[i] + (to[i] - from[i]) * amount)
+}
+
+/// Human-readable size, e.g. "512 B", "12.5 KB" or "3.20 MB".
+fn format_bytes(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+
+    let value = bytes as f64;
+    if value >= MB {
+        format!("{:.2} MB", value / MB)
+    } else if value >= KB {
+        format!("{:.1} KB", value / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// "<game> - Cacao Engine" while a game is playing; untitled games just get the engine name.
+fn window_title(info: &GameInfo) -> String {
+    let title = info.title.trim();
+    if title.is_empty() {
+        ENGINE_TITLE.to_string()
+    } else {
+        format!("{} - {}", title, ENGINE_TITLE)
+    }
+}
+
+fn load_window_icon() -> Option<Icon> {
+    let image = match image::load_from_memory(WINDOW_ICON_PNG) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => {
+            log::warn!("⚠️ Failed to decode window icon: {}", e);
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height)
+        .map_err(|e| log::warn!("⚠️ Invalid window icon: {}", e))
+        .ok()
+}
+
+/// Case-insensitive title/author match; an empty query matches everything.
+fn game_matches(info: &GameInfo, query: &str) -> bool {
+    let query = query.to_lowercase();
+    info.title.to_lowercase().contains(&query) || info.author.to_lowercase().contains(&query)
+}
+
+/// Indices of the games matching `query`, in library order.
+fn filter_games(games: &[GameEntry], query: &str) -> Vec<usize> {
+    games.iter()
+        .enumerate()
+        .filter(|(_, game)| game_matches(&game.info, query))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Swaps in a rescanned library and returns where the selection should be: on the same
+/// game if it's still visible, otherwise as close to the old position as the list allows.
+fn replace_games(games: &mut Vec<GameEntry>, found: Vec<GameEntry>, query: &str, selected: usize) -> usize {
+    let selected_path = filter_games(games, query).get(selected).map(|&i| games[i].file_path.clone());
+    *games = found;
+
+    let visible = filter_games(games, query);
+    selected_path
+        .and_then(|path| visible.iter().position(|&i| games[i].file_path == path))
+        .unwrap_or_else(|| selected.min(visible.len().saturating_sub(1)))
+}
+
+/// Picks the main menu screen for a key press or a click on one of its rows. Library
+/// Issues is only offered when something failed to load, and Escape asks before exiting.
+fn main_menu_action(input: &InputManager, clicked_row: Option<usize>, has_issues: bool) -> Option<MainMenuAction> {
+    let chose = |key, row| input.is_key_just_pressed(key) || clicked_row == Some(row);
+    let screen = if chose(VirtualKeyCode::Return, 0) {
+        MenuState::GameList
+    } else if chose(VirtualKeyCode::S, 1) {
+        MenuState::Settings
+    } else if chose(VirtualKeyCode::T, 2) {
+        MenuState::ThemeSelector
+    } else if chose(VirtualKeyCode::A, 3) {
+        MenuState::About
+    } else if chose(VirtualKeyCode::L, 4) && has_issues {
+        MenuState::LibraryIssues
+    } else if input.is_key_just_pressed(VirtualKeyCode::Escape) {
+        return Some(MainMenuAction::ConfirmExit);
+    } else {
+        return None;
+    };
+    Some(MainMenuAction::Open(screen))
+}
+
+/// Moves the game list selection with the arrow keys, a page at a time with Page Up and
+/// Page Down, or to either end with Home and End.
+fn game_list_selection(input: &InputManager, selected: usize, len: usize) -> usize {
+    let mut selected = selected;
+    if input.is_key_repeated(VirtualKeyCode::Up) {
+        selected = page_jump(selected, len, -1);
+    }
+    if input.is_key_repeated(VirtualKeyCode::Down) {
+        selected = page_jump(selected, len, 1);
+    }
+    if input.is_key_repeated(VirtualKeyCode::PageUp) {
+        selected = page_jump(selected, len, -(GAMES_PER_PAGE as isize));
+    }
+    if input.is_key_repeated(VirtualKeyCode::PageDown) {
+        selected = page_jump(selected, len, GAMES_PER_PAGE as isize);
+    }
+    if input.is_key_just_pressed(VirtualKeyCode::Home) {
+        selected = 0;
+    }
+    if input.is_key_just_pressed(VirtualKeyCode::End) {
+        selected = len.saturating_sub(1);
+    }
+    selected.min(len.saturating_sub(1))
+}
+
+/// Where the scrollbar thumb starts within a `track_height` track, and how tall it is.
+/// `None` while every game fits on screen.
+fn scrollbar_thumb(visible: usize, scroll_offset: f32, track_height: f32) -> Option<(f32, f32)> {
+    if visible <= GAMES_PER_PAGE {
+        return None;
+    }
+    let thumb_height = track_height * GAMES_PER_PAGE as f32 / visible as f32;
+    let max_scroll = (visible - 1) as f32 * GAME_CARD_SPACING;
+    let progress = (scroll_offset / max_scroll).clamp(0.0, 1.0);
+    Some(((track_height - thumb_height) * progress, thumb_height))
+}
+
+/// Moves the pause menu selection and picks the highlighted row on Enter. Escape resumes.
+fn pause_menu_action(input: &InputManager, selected: &mut usize) -> Option<PauseAction> {
+    if input.is_key_just_pressed(VirtualKeyCode::Escape) {
+        return Some(PauseAction::Resume);
+    }
+    if input.is_key_repeated(VirtualKeyCode::Up) && *selected > 0 {
+        *selected -= 1;
+    }
+    if input.is_key_repeated(VirtualKeyCode::Down) && *selected + 1 < PAUSE_ROWS.len() {
+        *selected += 1;
+    }
+    if !input.is_key_just_pressed(VirtualKeyCode::Return) {
+        return None;
+    }
+    Some(match *selected {
+        0 => PauseAction::Resume,
+        1 => PauseAction::OpenSettings,
+        _ => PauseAction::QuitToMenu,
+    })
+}
+
+/// The player's answer to the quit prompt: `Some(true)` to quit, `Some(false)` to carry on.
+fn quit_prompt_answer(input: &InputManager) -> Option<bool> {
+    if input.is_key_just_pressed(VirtualKeyCode::Y) || input.is_key_just_pressed(VirtualKeyCode::Return) {
+        Some(true)
+    } else if input.is_key_just_pressed(VirtualKeyCode::N) || input.is_key_just_pressed(VirtualKeyCode::Escape) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Closing the window mid-game asks first. From the menus, or a second close while the
+/// prompt is up, it quits without asking.
+fn close_needs_confirmation(state: &EngineState, already_asking: bool) -> bool {
+    matches!(state, EngineState::Playing | EngineState::Paused { .. }) && !already_asking
+}
+
+/// The option after `current`, or before it when `backwards`, wrapping at either end.
+/// A value that isn't one of the options starts over at the first.
+fn cycle_option<T: Copy + PartialEq>(options: &[T], current: T, backwards: bool) -> T {
+    let next = match options.iter().position(|&option| option == current) {
+        Some(i) if backwards => (i + options.len() - 1) % options.len(),
+        Some(i) => (i + 1) % options.len(),
+        None => 0,
+    };
+    options[next]
+}
+
+/// Rounds a volume to the nearest tenth within 0..=1.
+fn snap_volume(volume: f32) -> f32 {
+    (volume * 10.0).round().clamp(0.0, 10.0) / 10.0
+}
+
+/// Moves a theme editor color channel by `step`, snapped to `COLOR_STEP` within 0..=1.
+fn step_channel(value: f32, step: f32) -> f32 {
+    ((value + step) / COLOR_STEP).round().clamp(0.0, 1.0 / COLOR_STEP) * COLOR_STEP
+}
+
+/// The palette and saved slot the theme editor opens with. A saved custom theme is edited
+/// in place; anything else starts a new one, if there's room for it.
+fn theme_to_edit(highlighted: &Theme, custom: &[ThemeColors]) -> Option<(ThemeColors, Option<usize>)> {
+    let slot = match highlighted {
+        Theme::Custom(colors) => custom.iter().position(|c| c == colors),
+        _ => None,
+    };
+    if slot.is_none() && custom.len() >= MAX_CUSTOM_THEMES {
+        return None;
+    }
+
+    let mut colors = highlighted.colors();
+    if slot.is_none() {
+        colors.name = format!("Custom {}", custom.len() + 1);
+    }
+    Some((colors, slot))
+}
+
+fn save_custom_theme(custom: &mut Vec<ThemeColors>, slot: Option<usize>, colors: ThemeColors) {
+    match slot {
+        Some(i) if i < custom.len() => custom[i] = colors,
+        _ => custom.push(colors),
+    }
+}
+
+/// Swaps a rescanned library into the menu and returns the new selection. An open details
+/// page follows its game, or goes back to the list if the game is gone, and the Library
+/// Issues screen closes once there are none left.
+fn apply_rescan(state: &mut MenuState, games: &mut Vec<GameEntry>, found: Vec<GameEntry>, has_issues: bool, query: &str, selected: usize) -> usize {
+    let details_path = match state {
+        MenuState::GameDetails(idx) => games.get(*idx).map(|game| game.file_path.clone()),
+        _ => None,
+    };
+
+    let selected = replace_games(games, found, query, selected);
+
+    if let Some(path) = details_path {
+        match games.iter().position(|game| game.file_path == path) {
+            Some(idx) => *state = MenuState::GameDetails(idx),
+            None => *state = MenuState::GameList,
+        }
+    }
+    if !has_issues && matches!(state, MenuState::LibraryIssues) {
+        *state = MenuState::MainMenu;
+    }
+    selected
+}
+
+/// Scale that fits a `width` x `height` image inside a box, keeping its aspect ratio.
+fn fit_scale(width: f32, height: f32, box_width: f32, box_height: f32) -> f32 {
+    (box_width / width).min(box_height / height)
+}
+
+/// Moves a list selection by `delta` rows, stopping at the first and last entry.
+fn page_jump(selected: usize, len: usize, delta: isize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    selected.saturating_add_signed(delta).min(len - 1)
+}
+
+fn point_in_rect(point: Vec2, x: f32, y: f32, width: f32, height: f32) -> bool {
+    point.x >= x && point.x < x + width && point.y >= y && point.y < y + height
+}
+
+/// Baseline of a selectable settings row: the volume sliders under "Audio", then the "Graphics" rows.
+fn settings_row_y(index: usize) -> f32 {
+    if index < VOLUME_ROWS {
+        250.0 + index as f32 * 40.0
+    } else {
+        460.0 + (index - VOLUME_ROWS) as f32 * 40.0
+    }
+}
+
+fn controls_row_y(index: usize) -> f32 {
+    200.0 + index as f32 * 44.0
+}
+
+/// Maps a point in window pixels onto the fixed 1280x720 menu layout.
+fn window_to_layout(point: Vec2, window_size: winit::dpi::PhysicalSize<u32>) -> Vec2 {
+    Vec2::new(
+        point.x * LAYOUT_WIDTH / window_size.width.max(1) as f32,
+        point.y * LAYOUT_HEIGHT / window_size.height.max(1) as f32,
+    )
+}
+
+fn main_menu_row_at(point: Vec2) -> Option<usize> {
+    MAIN_MENU_ROWS
+        .iter()
+        .position(|&row_y| point_in_rect(point, 440.0, row_y - 6.0, 440.0, 40.0))
+}
+
+/// Mutes `audio` when the window loses focus and `pause_on_focus_loss` is on, and unmutes it
+/// on the way back unless the player had muted it themselves. Returns whether to pause.
+fn mute_for_focus(audio: &mut AudioSystem, muted_for_focus: &mut bool, focused: bool, pause_on_focus_loss: bool) -> bool {
+    if focused {
+        if std::mem::take(muted_for_focus) {
+            audio.set_muted(false);
+        }
+        return false;
+    }
+    if !pause_on_focus_loss {
+        return false;
+    }
+    if !audio.is_muted() {
+        audio.set_muted(true);
+        *muted_for_focus = true;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // winit still requires the deprecated modifiers field in these events
+    #[allow(deprecated)]
+    fn pressed(keys: &[VirtualKeyCode]) -> InputManager {
+        let mut input = InputManager::new();
+        for &key in keys {
+            input.handle_window_event(&WindowEvent::KeyboardInput {
+                device_id: unsafe { winit::event::DeviceId::dummy() },
+                input: KeyboardInput { scancode: 0, state: ElementState::Pressed, virtual_keycode: Some(key), modifiers: Default::default() },
+                is_synthetic: false,
+            });
+        }
+        input
+    }
+
+    fn game(title: &str, author: &str) -> GameEntry {
+        GameEntry {
+            info: GameInfo::new(title.to_string(), author.to_string()),
+            file_path: PathBuf::from(format!("{}.gaem", title)),
+            banner_loaded: false,
+            banner: None,
+        }
+    }
+
+    fn titles(games: &[GameEntry], indices: &[usize]) -> Vec<String> {
+        indices.iter().map(|&i| games[i].info.title.clone()).collect()
+    }
+
+    #[test]
+    fn rects_include_their_top_left_edge_but_not_the_far_edges() {
+        assert!(point_in_rect(Vec2::new(10.0, 20.0), 10.0, 20.0, 5.0, 5.0));
+        assert!(point_in_rect(Vec2::new(14.9, 24.9), 10.0, 20.0, 5.0, 5.0));
+        assert!(!point_in_rect(Vec2::new(15.0, 22.0), 10.0, 20.0, 5.0, 5.0));
+        assert!(!point_in_rect(Vec2::new(12.0, 25.0), 10.0, 20.0, 5.0, 5.0));
+        assert!(!point_in_rect(Vec2::new(9.9, 22.0), 10.0, 20.0, 5.0, 5.0));
+    }
+
+    #[test]
+    fn main_menu_rows_are_hit_by_their_highlight() {
+        for (row, &row_y) in MAIN_MENU_ROWS.iter().enumerate() {
+            assert_eq!(main_menu_row_at(Vec2::new(640.0, row_y)), Some(row));
+        }
+        // Left of the highlight, above the first row and below the last
+        assert_eq!(main_menu_row_at(Vec2::new(400.0, MAIN_MENU_ROWS[0])), None);
+        assert_eq!(main_menu_row_at(Vec2::new(640.0, MAIN_MENU_ROWS[0] - 20.0)), None);
+        assert_eq!(main_menu_row_at(Vec2::new(640.0, MAIN_MENU_ROWS[4] + 40.0)), None);
+    }
+
+    #[test]
+    fn losing_focus_mutes_and_pauses_until_focus_returns() {
+        let mut audio = AudioSystem::new_silent();
+        audio.set_master_volume(0.7);
+        let mut muted_for_focus = false;
+
+        assert!(mute_for_focus(&mut audio, &mut muted_for_focus, false, true));
+        assert!(audio.is_muted() && muted_for_focus);
+
+        assert!(!mute_for_focus(&mut audio, &mut muted_for_focus, true, true));
+        assert!(!audio.is_muted() && !muted_for_focus);
+        assert_eq!(audio.get_master_volume(), 0.7);
+    }
+
+    #[test]
+    fn focus_leaves_the_players_own_mute_alone() {
+        let mut audio = AudioSystem::new_silent();
+        audio.set_muted(true);
+        let mut muted_for_focus = false;
+
+        assert!(mute_for_focus(&mut audio, &mut muted_for_focus, false, true));
+        assert!(!muted_for_focus);
+        mute_for_focus(&mut audio, &mut muted_for_focus, true, true);
+        assert!(audio.is_muted());
+    }
+
+    #[test]
+    fn focus_loss_does_nothing_when_turned_off() {
+        let mut audio = AudioSystem::new_silent();
+        let mut muted_for_focus = false;
+
+        assert!(!mute_for_focus(&mut audio, &mut muted_for_focus, false, false));
+        assert!(!audio.is_muted());
+    }
+
+
+    #[test]
+    fn themes_pick_their_overlays() {
+        assert_eq!(Theme::Wii.overlay_effects(), [
+            PostEffect::Scanlines { strength: 0.12, spacing: 3.0 },
+            PostEffect::Vignette { strength: 0.25 },
+        ]);
+        assert_eq!(Theme::Animated.overlay_effects(), [PostEffect::Vignette { strength: 0.6 }]);
+        assert!(Theme::Dark.overlay_effects().is_empty());
+    }
+
+    #[test]
+    fn failed_loads_show_the_error_screen() {
+        let games_dir = std::env::temp_dir().join(format!("cacao_engine_{}", Uuid::new_v4()));
+        let loader = GameLoader::new(games_dir.clone(), GAME_SECRET_KEY.to_string());
+        let Err(error) = loader.queue_game(&games_dir.join("missing.gaem"), &mut AssetManager::new()) else {
+            panic!("loaded a missing game");
+        };
+
+        let EngineState::Error { message, source } = EngineState::error("Loading game", &error) else {
+            panic!("not the error screen");
+        };
+        assert_eq!(source, "Loading game");
+        assert_eq!(message, error.to_string());
+    }
+
+    #[test]
+    fn main_menu_keys_and_rows_open_their_screens() {
+        let action = |keys: &[VirtualKeyCode], row, has_issues| main_menu_action(&pressed(keys), row, has_issues);
+        assert!(matches!(action(&[VirtualKeyCode::Return], None, false), Some(MainMenuAction::Open(MenuState::GameList))));
+        assert!(matches!(action(&[], Some(1), false), Some(MainMenuAction::Open(MenuState::Settings))));
+        assert!(matches!(action(&[VirtualKeyCode::T], None, false), Some(MainMenuAction::Open(MenuState::ThemeSelector))));
+        assert!(matches!(action(&[], Some(3), false), Some(MainMenuAction::Open(MenuState::About))));
+        assert!(action(&[], None, false).is_none());
+
+        // Library Issues only opens when something failed to load
+        assert!(action(&[VirtualKeyCode::L], None, false).is_none());
+        assert!(matches!(action(&[VirtualKeyCode::L], None, true), Some(MainMenuAction::Open(MenuState::LibraryIssues))));
+
+        // Escape asks before exiting
+        assert!(matches!(action(&[VirtualKeyCode::Escape], None, false), Some(MainMenuAction::ConfirmExit)));
+    }
+
+    #[test]
+    fn closing_asks_first_only_during_a_game() {
+        assert!(close_needs_confirmation(&EngineState::Playing, false));
+        assert!(close_needs_confirmation(&EngineState::Paused { selected: 0, settings: None }, false));
+        assert!(!close_needs_confirmation(&EngineState::Playing, true));
+        assert!(!close_needs_confirmation(&EngineState::Loading { progress: 0.5, status: String::new() }, false));
+
+        assert_eq!(quit_prompt_answer(&pressed(&[VirtualKeyCode::Y])), Some(true));
+        assert_eq!(quit_prompt_answer(&pressed(&[VirtualKeyCode::Return])), Some(true));
+        assert_eq!(quit_prompt_answer(&pressed(&[VirtualKeyCode::N])), Some(false));
+        assert_eq!(quit_prompt_answer(&pressed(&[VirtualKeyCode::Escape])), Some(false));
+        assert_eq!(quit_prompt_answer(&pressed(&[VirtualKeyCode::Space])), None);
+    }
+
+    #[test]
+    fn pause_menu_rows_resume_open_settings_or_quit() {
+        let mut selected = 0;
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Return]), &mut selected), Some(PauseAction::Resume));
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Down]), &mut selected), None);
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Return]), &mut selected), Some(PauseAction::OpenSettings));
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Down, VirtualKeyCode::Return]), &mut selected), Some(PauseAction::QuitToMenu));
+
+        // The selection stops at the last row, and Escape resumes from anywhere
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Down]), &mut selected), None);
+        assert_eq!(selected, PAUSE_ROWS.len() - 1);
+        assert_eq!(pause_menu_action(&pressed(&[VirtualKeyCode::Escape]), &mut selected), Some(PauseAction::Resume));
+    }
+
+    #[test]
+    fn game_list_keys_move_by_row_page_and_end() {
+        let step = |keys: &[VirtualKeyCode], selected| game_list_selection(&pressed(keys), selected, 12);
+        assert_eq!(step(&[VirtualKeyCode::Down], 0), 1);
+        assert_eq!(step(&[VirtualKeyCode::Up], 0), 0);
+        assert_eq!(step(&[VirtualKeyCode::PageDown], 1), 1 + GAMES_PER_PAGE);
+        assert_eq!(step(&[VirtualKeyCode::PageDown], 9), 11);
+        assert_eq!(step(&[VirtualKeyCode::PageUp], 3), 0);
+        assert_eq!(step(&[VirtualKeyCode::End], 3), 11);
+        assert_eq!(step(&[VirtualKeyCode::Home], 7), 0);
+
+        // A selection left past the end by a shorter list is pulled back in
+        assert_eq!(game_list_selection(&pressed(&[]), 20, 4), 3);
+        assert_eq!(page_jump(3, 0, 5), 0);
+    }
+
+    #[test]
+    fn the_scrollbar_thumb_tracks_the_scroll_position() {
+        assert_eq!(scrollbar_thumb(GAMES_PER_PAGE, 0.0, 500.0), None);
+
+        let games = GAMES_PER_PAGE * 2;
+        let (top, height) = scrollbar_thumb(games, 0.0, 500.0).unwrap();
+        assert_eq!((top, height), (0.0, 250.0));
+        let end = (games - 1) as f32 * GAME_CARD_SPACING;
+        assert_eq!(scrollbar_thumb(games, end, 500.0), Some((250.0, 250.0)));
+        assert_eq!(scrollbar_thumb(games, end * 2.0, 500.0), Some((250.0, 250.0)));
+    }
+
+    #[test]
+    fn library_search_matches_title_or_author_in_any_case() {
+        let games = vec![game("Space Race", "Ana"), game("Puzzle Box", "Ben"), game("Star Miner", "Spacey Studio")];
+        assert_eq!(titles(&games, &filter_games(&games, "")), ["Space Race", "Puzzle Box", "Star Miner"]);
+        assert_eq!(titles(&games, &filter_games(&games, "SPACE")), ["Space Race", "Star Miner"]);
+        assert_eq!(titles(&games, &filter_games(&games, "ben")), ["Puzzle Box"]);
+        assert!(filter_games(&games, "kart").is_empty());
+    }
+
+    #[test]
+    fn rescans_keep_the_selected_game_selected() {
+        let mut games = vec![game("A", "x"), game("B", "x"), game("C", "x")];
+        let selected = replace_games(&mut games, vec![game("New", "x"), game("A", "x"), game("B", "x"), game("C", "x")], "", 1);
+        assert_eq!(games[selected].info.title, "B");
+
+        // The selected game is gone, so the selection stays put within the shorter list
+        let selected = replace_games(&mut games, vec![game("A", "x")], "", selected);
+        assert_eq!(selected, 0);
+    }
+
+    #[test]
+    fn rescans_move_open_screens_along() {
+        let mut games = vec![game("A", "x"), game("B", "x")];
+        let mut state = MenuState::GameDetails(1);
+        apply_rescan(&mut state, &mut games, vec![game("Z", "x"), game("A", "x"), game("B", "x")], false, "", 1);
+        assert!(matches!(state, MenuState::GameDetails(2)));
+
+        // Its game was removed, so the details page goes back to the list
+        apply_rescan(&mut state, &mut games, vec![game("A", "x")], false, "", 0);
+        assert!(matches!(state, MenuState::GameList));
+
+        // Library Issues stays open while there's something to show
+        let mut state = MenuState::LibraryIssues;
+        apply_rescan(&mut state, &mut games, vec![game("A", "x")], true, "", 0);
+        assert!(matches!(state, MenuState::LibraryIssues));
+        apply_rescan(&mut state, &mut games, vec![game("A", "x")], false, "", 0);
+        assert!(matches!(state, MenuState::MainMenu));
+    }
+
+    #[test]
+    fn resolutions_and_frame_limits_cycle_both_ways() {
+        assert_eq!(cycle_option(&RESOLUTIONS, [1280, 720], false), [1600, 900]);
+        assert_eq!(cycle_option(&RESOLUTIONS, [1280, 720], true), [2560, 1440]);
+        assert_eq!(cycle_option(&RESOLUTIONS, [2560, 1440], false), [1280, 720]);
+        // A size set by hand in settings.toml starts the cycle over
+        assert_eq!(cycle_option(&RESOLUTIONS, [1024, 768], false), [1280, 720]);
+
+        assert_eq!(cycle_option(&FPS_OPTIONS, 144, false), UNCAPPED_FPS);
+        assert_eq!(cycle_option(&FPS_OPTIONS, UNCAPPED_FPS, false), 30);
+    }
+
+    #[test]
+    fn frame_limits_turn_into_frame_times() {
+        assert_eq!(frame_time(60), Duration::from_secs_f64(1.0 / 60.0));
+        assert_eq!(frame_time(UNCAPPED_FPS), Duration::ZERO);
+        assert_eq!(fps_label(144), "144 FPS");
+        assert_eq!(fps_label(UNCAPPED_FPS), "Uncapped");
+    }
+
+    #[test]
+    fn volumes_snap_to_tenths() {
+        assert_eq!(snap_volume(0.7 + VOLUME_STEP), 0.8);
+        assert_eq!(snap_volume(0.04), 0.0);
+        assert_eq!(snap_volume(-0.1), 0.0);
+        assert_eq!(snap_volume(1.2), 1.0);
+    }
+
+    #[test]
+    fn memory_sizes_read_in_the_largest_unit() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(12_800), "12.5 KB");
+        assert_eq!(format_bytes(3_355_443), "3.20 MB");
+    }
+
+    #[test]
+    fn banners_fit_inside_their_box() {
+        assert_eq!(fit_scale(288.0, 80.0, 144.0, 80.0), 0.5);
+        assert_eq!(fit_scale(72.0, 160.0, 144.0, 80.0), 0.5);
+        assert_eq!(fit_scale(72.0, 40.0, 144.0, 80.0), 2.0);
+    }
+
+    #[test]
+    fn the_theme_editor_edits_saved_themes_and_starts_new_ones() {
+        let (colors, slot) = theme_to_edit(&Theme::Dark, &[]).unwrap();
+        assert_eq!((colors.name.as_str(), slot), ("Custom 1", None));
+        assert_eq!(colors.background, Theme::Dark.background_color());
+
+        let mut custom = Vec::new();
+        save_custom_theme(&mut custom, None, colors);
+        let (mut colors, slot) = theme_to_edit(&Theme::Custom(custom[0].clone()), &custom).unwrap();
+        assert_eq!(slot, Some(0));
+        *colors.channel_mut(4) = 0.0;
+        save_custom_theme(&mut custom, slot, colors);
+        assert_eq!((custom.len(), custom[0].accent[1]), (1, 0.0));
+
+        // No room for another, but saved ones can still be edited
+        custom.resize(MAX_CUSTOM_THEMES, custom[0].clone());
+        assert!(theme_to_edit(&Theme::Wii, &custom).is_none());
+        assert!(theme_to_edit(&Theme::Custom(custom[0].clone()), &custom).is_some());
+    }
+
+    #[test]
+    fn color_channels_step_within_range() {
+        assert!((step_channel(0.5, COLOR_STEP) - 0.55).abs() < 1e-6);
+        assert_eq!(step_channel(1.0, COLOR_STEP), 1.0);
+        assert_eq!(step_channel(0.02, -COLOR_STEP), 0.0);
+    }
+}
@@ -0,0 +1,195 @@
+// src/engine/settings.rs
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::{errors::CacaoError, game::ScriptSandbox, renderer::PostEffect};
+use super::{Theme, ThemeColors, TransitionStyle};
+
+// Hand-edited frame limits are kept in a range the frame pacing can actually hit; 0 stays uncapped
+const MIN_TARGET_FPS: u32 = 10;
+const MAX_TARGET_FPS: u32 = 1000;
+
+/// User preferences persisted to `settings.toml` between launches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub theme: Theme,
+    /// Palettes made in the theme editor, listed after the built-in themes
+    pub custom_themes: Vec<ThemeColors>,
+    /// How menu screens animate when switching between them
+    pub menu_transition: TransitionStyle,
+    /// Most frames drawn per second; 0 means uncapped.
+    pub target_fps: u32,
+    /// Game logic updates per second, independent of the frame rate.
+    pub logic_rate: u32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub resolution: [u32; 2],
+    /// Fullscreen effects applied to every frame, in order. None by default.
+    pub post_effects: Vec<PostEffect>,
+    /// Scanlines, vignettes and the like some menu themes draw over themselves
+    pub theme_overlay: bool,
+    /// Pause the game and silence audio while the window is in the background
+    pub pause_on_focus_loss: bool,
+    pub gamepad_deadzone: f32,
+    /// Lua standard library access for game scripts. Only loosen it for games you trust.
+    pub script_sandbox: ScriptSandbox,
+    /// Developer override to load games made for an incompatible engine version.
+    pub ignore_engine_version: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 1.0,
+            theme: Theme::Animated,
+            custom_themes: Vec::new(),
+            menu_transition: TransitionStyle::default(),
+            target_fps: 60,
+            logic_rate: 60,
+            fullscreen: false,
+            vsync: true,
+            resolution: [1280, 720],
+            post_effects: Vec::new(),
+            theme_overlay: true,
+            pause_on_focus_loss: true,
+            gamepad_deadzone: 0.15,
+            script_sandbox: ScriptSandbox::Strict,
+            ignore_engine_version: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads settings from `path`, falling back to defaults if the file is missing or invalid.
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                log::info!("No settings file found, using defaults");
+                return Self::default();
+            }
+        };
+
+        match toml::from_str::<Settings>(&contents) {
+            Ok(mut settings) => {
+                settings.clamp();
+                settings
+            }
+            Err(e) => {
+                log::warn!("⚠️ Failed to parse {}: {}, using defaults", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    // The settings screen can't produce out-of-range values, but a hand-edited file can
+    fn clamp(&mut self) {
+        let defaults = Self::default();
+        self.master_volume = unit_or(self.master_volume, defaults.master_volume);
+        self.music_volume = unit_or(self.music_volume, defaults.music_volume);
+        self.sfx_volume = unit_or(self.sfx_volume, defaults.sfx_volume);
+        if self.target_fps != 0 {
+            self.target_fps = self.target_fps.clamp(MIN_TARGET_FPS, MAX_TARGET_FPS);
+        }
+    }
+
+    /// Present mode to request from the renderer; Mailbox falls back to Immediate where unsupported.
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        if self.vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::Mailbox
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CacaoError> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+fn unit_or(value: f32, fallback: f32) -> f32 {
+    if value.is_nan() {
+        fallback
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("cacao_settings_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("settings.toml");
+        let settings = Settings {
+            master_volume: 0.3,
+            theme: Theme::Dark,
+            target_fps: 144,
+            fullscreen: true,
+            resolution: [1920, 1080],
+            post_effects: vec![PostEffect::Vignette { strength: 0.5 }],
+            ..Settings::default()
+        };
+
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load(&path), settings);
+    }
+
+    #[test]
+    fn missing_or_invalid_file_gives_defaults() {
+        let path = temp_path("settings.toml");
+        assert_eq!(Settings::load(&path), Settings::default());
+
+        std::fs::write(&path, "master_volume = \"loud\"").unwrap();
+        assert_eq!(Settings::load(&path), Settings::default());
+    }
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let path = temp_path("settings.toml");
+        std::fs::write(&path, "vsync = false").unwrap();
+        let settings = Settings::load(&path);
+        assert!(!settings.vsync);
+        assert_eq!(settings.music_volume, Settings::default().music_volume);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped_on_load() {
+        let path = temp_path("settings.toml");
+        std::fs::write(&path, "master_volume = 7.5\nmusic_volume = -1.0\nsfx_volume = nan\ntarget_fps = 100000").unwrap();
+        let settings = Settings::load(&path);
+        assert_eq!(settings.master_volume, 1.0);
+        assert_eq!(settings.music_volume, 0.0);
+        assert_eq!(settings.sfx_volume, Settings::default().sfx_volume);
+        assert_eq!(settings.target_fps, MAX_TARGET_FPS);
+
+        std::fs::write(&path, "target_fps = 0").unwrap();
+        assert_eq!(Settings::load(&path).target_fps, 0);
+        std::fs::write(&path, "target_fps = 1").unwrap();
+        assert_eq!(Settings::load(&path).target_fps, MIN_TARGET_FPS);
+    }
+
+
+    #[test]
+    fn vsync_picks_the_present_mode() {
+        let mut settings = Settings { vsync: true, ..Settings::default() };
+        assert_eq!(settings.present_mode(), wgpu::PresentMode::Fifo);
+        settings.vsync = false;
+        assert_eq!(settings.present_mode(), wgpu::PresentMode::Mailbox);
+    }
+}
@@ -0,0 +1,73 @@
+// src/engine/timestep.rs
+use std::time::Duration;
+
+// After a long stall only this many steps are caught up; the rest of the backlog is dropped
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Turns variable frame times into a whole number of constant-length logic steps.
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(rate_hz: u32) -> Self {
+        Self {
+            step: Duration::from_secs_f64(1.0 / rate_hz.max(1) as f64),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Adds a frame's wall-clock time and returns how many logic steps to run now.
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator = (self.accumulator + frame_time).min(self.step * MAX_STEPS_PER_FRAME);
+
+        let steps = (self.accumulator.as_nanos() / self.step.as_nanos()) as u32;
+        self.accumulator -= self.step * steps;
+        steps
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// How far the leftover time is into the next step (0..1), for interpolating rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_one_step_per_step_of_time() {
+        let mut timestep = FixedTimestep::new(50);
+        assert_eq!(timestep.advance(Duration::from_millis(10)), 0);
+        assert_eq!(timestep.advance(Duration::from_millis(10)), 1);
+        assert_eq!(timestep.advance(Duration::from_millis(45)), 2);
+        assert!((timestep.alpha() - 0.25).abs() < 1e-6, "{}", timestep.alpha());
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut timestep = FixedTimestep::new(60);
+        assert_eq!(timestep.advance(Duration::from_secs(10)), MAX_STEPS_PER_FRAME);
+        // The time that was dropped doesn't come back later
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn reset_drops_leftover_time() {
+        let mut timestep = FixedTimestep::new(50);
+        timestep.advance(Duration::from_millis(15));
+        timestep.reset();
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(Duration::from_millis(15)), 0);
+    }
+}
@@ -0,0 +1,81 @@
+// src/engine/transition.rs
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+// How far a sliding screen starts from its resting place, in layout pixels
+const SLIDE_DISTANCE: f32 = 120.0;
+
+/// How menu screens move in when they change. Every style also fades in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionStyle {
+    Fade,
+    #[default]
+    SlideLeft,
+    SlideUp,
+}
+
+impl TransitionStyle {
+    pub const ALL: [TransitionStyle; 3] = [TransitionStyle::Fade, TransitionStyle::SlideLeft, TransitionStyle::SlideUp];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransitionStyle::Fade => "Fade",
+            TransitionStyle::SlideLeft => "Slide Left",
+            TransitionStyle::SlideUp => "Slide Up",
+        }
+    }
+
+    pub fn next(&self) -> TransitionStyle {
+        let index = Self::ALL.iter().position(|style| style == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Where the incoming screen is drawn relative to its final position at `progress`
+    /// (0 to 1). Going back comes in from the opposite side.
+    pub fn offset(&self, progress: f32, back: bool) -> Vec2 {
+        let remaining = SLIDE_DISTANCE * (1.0 - ease_out_cubic(progress));
+        let distance = if back { -remaining } else { remaining };
+        match self {
+            TransitionStyle::Fade => Vec2::ZERO,
+            TransitionStyle::SlideLeft => Vec2::new(distance, 0.0),
+            TransitionStyle::SlideUp => Vec2::new(0.0, distance),
+        }
+    }
+}
+
+/// Fast at first and settling gently at 1.
+fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slides_start_away_and_settle_in_place() {
+        let slide = TransitionStyle::SlideLeft;
+        assert_eq!(slide.offset(0.0, false), Vec2::new(SLIDE_DISTANCE, 0.0));
+        assert_eq!(slide.offset(0.0, true), Vec2::new(-SLIDE_DISTANCE, 0.0));
+        assert_eq!(slide.offset(1.0, false), Vec2::ZERO);
+        assert_eq!(TransitionStyle::SlideUp.offset(0.0, false), Vec2::new(0.0, SLIDE_DISTANCE));
+        assert_eq!(TransitionStyle::Fade.offset(0.0, false), Vec2::ZERO);
+
+        // Eases out: most of the way there by the halfway point, and never overshoots
+        let halfway = slide.offset(0.5, false).x;
+        assert!(halfway < SLIDE_DISTANCE / 4.0, "{}", halfway);
+        assert_eq!(slide.offset(2.0, false), Vec2::ZERO);
+    }
+
+    #[test]
+    fn styles_cycle_through_all_of_them() {
+        let mut style = TransitionStyle::default();
+        let mut seen = Vec::new();
+        for _ in 0..TransitionStyle::ALL.len() {
+            seen.push(style);
+            style = style.next();
+        }
+        assert_eq!(style, TransitionStyle::default());
+        assert_eq!(seen, [TransitionStyle::SlideLeft, TransitionStyle::SlideUp, TransitionStyle::Fade]);
+    }
+}
@@ -13,6 +13,12 @@ pub enum CacaoError {
     AudioError(String),
     ScriptError(String),
     LuaError(LuaError),
+    /// A save couldn't be decrypted, usually because the secret key changed
+    SaveDecryptError(String),
+    /// A save decrypted but its contents are damaged
+    SaveCorruptError(String),
+    /// A save is at a version the game has no migration path from
+    SaveVersionError(String),
 }
 
 impl fmt::Display for CacaoError {
@@ -25,6 +31,9 @@ impl fmt::Display for CacaoError {
             CacaoError::AudioError(msg) => write!(f, "Audio Error: {}", msg),
             CacaoError::ScriptError(msg) => write!(f, "Script Error: {}", msg),
             CacaoError::LuaError(err) => write!(f, "Lua Error: {}", err),
+            CacaoError::SaveDecryptError(msg) => write!(f, "Save Decrypt Error: {}", msg),
+            CacaoError::SaveCorruptError(msg) => write!(f, "Save Corrupt Error: {}", msg),
+            CacaoError::SaveVersionError(msg) => write!(f, "Save Version Error: {}", msg),
         }
     }
 }
@@ -0,0 +1,98 @@
+// src/game/api/animation.rs
+use std::cell::RefCell;
+use std::collections::HashMap;
+use mlua::{Lua, Scope, Table};
+use serde::Deserialize;
+use crate::{assets::AssetManager, renderer::AnimatedSprite};
+use super::EngineContext;
+
+/// Every animation a game has defined, by name. Stored as Lua app data so both
+/// `cacao.animation` and `cacao.renderer.draw_animation` can reach it.
+#[derive(Default)]
+pub struct Animations(pub HashMap<String, AnimatedSprite>);
+
+/// One entry of an animation data file: `{"walk": {"sprite": "hero.png", "frames": [[0, 0, 32, 32], ...], "frame_duration": 0.1}}`
+#[derive(Deserialize)]
+struct AnimationData {
+    sprite: String,
+    frames: Vec<[u32; 4]>,
+    frame_duration: f32,
+    #[serde(default = "default_looping")]
+    looping: bool,
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<EngineContext<'_>>,
+) -> mlua::Result<()> {
+    let animation = lua.create_table()?;
+
+    animation.set("define", scope.create_function_mut(move |lua, (name, sprite, frames, frame_duration, looping): (String, String, Vec<[u32; 4]>, f32, Option<bool>)| {
+        let data = AnimationData { sprite, frames, frame_duration, looping: looping.unwrap_or(true) };
+        let animation = build_animation(ctx.borrow().assets, &data)?;
+        insert_animation(lua, name, animation);
+        Ok(())
+    })?)?;
+
+    animation.set("load", scope.create_function_mut(move |lua, file: String| {
+        let ctx = ctx.borrow();
+        let bytes = ctx.assets.get_data_file(&file)
+            .ok_or_else(|| mlua::Error::RuntimeError(format!("Data file not found: {}", file)))?;
+        let definitions: HashMap<String, AnimationData> = serde_json::from_slice(bytes)
+            .map_err(|e| mlua::Error::RuntimeError(format!("Invalid animation file {}: {}", file, e)))?;
+
+        let count = definitions.len();
+        for (name, data) in definitions {
+            let animation = build_animation(ctx.assets, &data)?;
+            insert_animation(lua, name, animation);
+        }
+        Ok(count)
+    })?)?;
+
+    animation.set("reset", scope.create_function_mut(move |lua, name: String| {
+        let found = lua.app_data_mut::<Animations>()
+            .and_then(|mut animations| animations.0.get_mut(&name).map(AnimatedSprite::reset))
+            .is_some();
+        if !found {
+            log::warn!("⚠️ Animation not found: {}", name);
+        }
+        Ok(())
+    })?)?;
+
+    animation.set("is_finished", scope.create_function(move |lua, name: String| {
+        Ok(lua.app_data_ref::<Animations>()
+            .and_then(|animations| animations.0.get(&name).map(|animation| animation.is_finished()))
+            .unwrap_or(false))
+    })?)?;
+
+    cacao.set("animation", animation)?;
+    Ok(())
+}
+
+/// Advances every defined animation by `dt` seconds.
+pub fn update_all(lua: &Lua, dt: f32) {
+    if let Some(mut animations) = lua.app_data_mut::<Animations>() {
+        for animation in animations.0.values_mut() {
+            animation.update(dt);
+        }
+    }
+}
+
+fn build_animation(assets: &AssetManager, data: &AnimationData) -> mlua::Result<AnimatedSprite> {
+    let sprite = assets.get_sprite(&data.sprite)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("Sprite not found: {}", data.sprite)))?;
+    AnimatedSprite::new(&sprite.texture, &data.frames, data.frame_duration, data.looping)
+        .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+}
+
+fn insert_animation(lua: &Lua, name: String, animation: AnimatedSprite) {
+    if let Some(mut animations) = lua.app_data_mut::<Animations>() {
+        animations.0.insert(name, animation);
+    }
+}
@@ -0,0 +1,243 @@
+// src/game/api/audio.rs
+use std::cell::RefCell;
+use std::time::Duration;
+use mlua::{Lua, Scope, Table};
+use crate::audio::{Tone, Waveform, DEFAULT_ATTACK, DEFAULT_RELEASE};
+use super::EngineContext;
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<EngineContext<'_>>,
+) -> mlua::Result<()> {
+    let audio = lua.create_table()?;
+
+    audio.set("play_sound", scope.create_function_mut(move |_, (name, looped): (String, Option<bool>)| {
+        let mut ctx = ctx.borrow_mut();
+        let clip = match ctx.assets.get_audio_clip(&name) {
+            Some(clip) => clip,
+            None => {
+                log::warn!("⚠️ Sound not found: {}", name);
+                return Ok(None);
+            }
+        };
+        match ctx.audio.play_sound(&clip, looped.unwrap_or(false)) {
+            Ok(id) => Ok(Some(id)),
+            Err(e) => {
+                log::warn!("⚠️ Failed to play sound {}: {}", name, e);
+                Ok(None)
+            }
+        }
+    })?)?;
+
+    // Generated beeps, no audio file needed; the waveform defaults to square. Attack and
+    // release are the fade in and out in seconds and default to a few milliseconds.
+    audio.set("play_tone", scope.create_function_mut(move |_, (frequency, duration, waveform, attack, release): (f32, f32, Option<String>, Option<f32>, Option<f32>)| {
+        let waveform = parse_waveform(waveform.as_deref().unwrap_or("square"))?;
+        let duration = parse_seconds("tone duration", duration)?;
+        let attack = attack.map(|secs| parse_seconds("attack", secs)).transpose()?.unwrap_or(DEFAULT_ATTACK);
+        let release = release.map(|secs| parse_seconds("release", secs)).transpose()?.unwrap_or(DEFAULT_RELEASE);
+        let tone = Tone::new(frequency, duration, waveform).with_envelope(attack, release);
+        match ctx.borrow_mut().audio.play_tone(tone) {
+            Ok(id) => Ok(Some(id)),
+            Err(e) => {
+                log::warn!("⚠️ Failed to play tone: {}", e);
+                Ok(None)
+            }
+        }
+    })?)?;
+
+    // With a fade_in in seconds the new track rises from silence while the old one fades out
+    audio.set("play_music", scope.create_function_mut(move |_, (name, looped, fade_in): (String, Option<bool>, Option<f32>)| {
+        let fade_in = fade_in.map(|secs| parse_seconds("fade", secs)).transpose()?;
+        let mut ctx = ctx.borrow_mut();
+        let clip = match ctx.assets.get_audio_clip(&name) {
+            Some(clip) => clip,
+            None => {
+                log::warn!("⚠️ Music not found: {}", name);
+                return Ok(false);
+            }
+        };
+        let looped = looped.unwrap_or(true);
+        let played = match fade_in {
+            Some(fade_in) => ctx.audio.play_music_faded(&clip, looped, fade_in),
+            None => ctx.audio.play_music(&clip, looped),
+        };
+        match played {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                log::warn!("⚠️ Failed to play music {}: {}", name, e);
+                Ok(false)
+            }
+        }
+    })?)?;
+
+    // The intro plays once, then the loop repeats with no gap
+    audio.set("play_music_with_intro", scope.create_function_mut(move |_, (intro, looped): (String, String)| {
+        let mut ctx = ctx.borrow_mut();
+        let (Some(intro_clip), Some(loop_clip)) = (ctx.assets.get_audio_clip(&intro), ctx.assets.get_audio_clip(&looped)) else {
+            log::warn!("⚠️ Music not found: {} / {}", intro, looped);
+            return Ok(false);
+        };
+        match ctx.audio.play_music_with_intro(&intro_clip, &loop_clip) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                log::warn!("⚠️ Failed to play music {}: {}", looped, e);
+                Ok(false)
+            }
+        }
+    })?)?;
+
+    // What a clip is without playing it, e.g. to time a cutscene to its length
+    audio.set("get_info", scope.create_function(move |lua, name: String| {
+        let Some(clip) = ctx.borrow().assets.get_audio_clip(&name) else {
+            return Ok(None);
+        };
+        let info = lua.create_table()?;
+        info.set("duration", clip.duration_secs())?;
+        info.set("sample_rate", clip.sample_rate)?;
+        info.set("channels", clip.channels)?;
+        info.set("format", clip.format.name())?;
+        info.set("streamed", clip.is_streamed())?;
+        Ok(Some(info))
+    })?)?;
+
+    audio.set("stop", scope.create_function_mut(move |_, id: String| {
+        ctx.borrow_mut().audio.stop_sound(&id);
+        Ok(())
+    })?)?;
+
+    // Volumes are 0..1 and apply straight away to whatever is playing
+    audio.set("set_master_volume", scope.create_function_mut(move |_, volume: f32| {
+        ctx.borrow_mut().audio.set_master_volume(volume);
+        Ok(())
+    })?)?;
+    audio.set("get_master_volume", scope.create_function(move |_, ()| Ok(ctx.borrow().audio.get_master_volume()))?)?;
+
+    audio.set("set_music_volume", scope.create_function_mut(move |_, volume: f32| {
+        ctx.borrow_mut().audio.set_music_volume(volume);
+        Ok(())
+    })?)?;
+    audio.set("get_music_volume", scope.create_function(move |_, ()| Ok(ctx.borrow().audio.get_music_volume()))?)?;
+
+    audio.set("set_sfx_volume", scope.create_function_mut(move |_, volume: f32| {
+        ctx.borrow_mut().audio.set_sound_volume(volume);
+        Ok(())
+    })?)?;
+    audio.set("get_sfx_volume", scope.create_function(move |_, ()| Ok(ctx.borrow().audio.get_sound_volume()))?)?;
+
+    // Extra sounds past the cap cut off the oldest ones
+    audio.set("set_max_sounds", scope.create_function_mut(move |_, max_sounds: usize| {
+        ctx.borrow_mut().audio.set_max_sounds(max_sounds);
+        Ok(())
+    })?)?;
+
+    // Optionally fades out over the given seconds before stopping
+    audio.set("stop_music", scope.create_function_mut(move |_, fade_out: Option<f32>| {
+        let fade_out = fade_out.map(|secs| parse_seconds("fade", secs)).transpose()?;
+        let mut ctx = ctx.borrow_mut();
+        match fade_out {
+            Some(fade_out) => ctx.audio.fade_out_music(fade_out),
+            None => ctx.audio.stop_music(),
+        }
+        Ok(())
+    })?)?;
+
+    cacao.set("audio", audio)?;
+    Ok(())
+}
+
+fn parse_seconds(what: &str, secs: f32) -> mlua::Result<Duration> {
+    Duration::try_from_secs_f32(secs)
+        .map_err(|_| mlua::Error::RuntimeError(format!("Invalid {}: {}", what, secs)))
+}
+
+fn parse_waveform(name: &str) -> mlua::Result<Waveform> {
+    match name {
+        "square" => Ok(Waveform::Square),
+        "sine" => Ok(Waveform::Sine),
+        "triangle" => Ok(Waveform::Triangle),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "Unknown waveform: {} (expected square, sine or triangle)", other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assets::AssetManager, audio::AudioSystem, game::AssetType, renderer::Camera, saves::SaveManager};
+    use super::super::{with_engine, EngineContext};
+
+    // Half a second of 16-bit mono silence at 8 kHz
+    fn wav() -> Vec<u8> {
+        let data_len: u32 = 8_000;
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        for field in [16u32.to_le_bytes().as_slice(), &1u16.to_le_bytes(), &1u16.to_le_bytes(), &8_000u32.to_le_bytes(), &16_000u32.to_le_bytes(), &2u16.to_le_bytes(), &16u16.to_le_bytes()] {
+            bytes.extend_from_slice(field);
+        }
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(44 + data_len as usize, 0);
+        bytes
+    }
+
+    async fn assets_with_hit_sound() -> AssetManager {
+        let mut assets = AssetManager::new();
+        assets.queue_asset_with("hit.wav".into(), AssetType::Audio, None, || Ok(wav()));
+        while assets.is_loading() {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assets.process_loaded_assets_headless().unwrap();
+        assets
+    }
+
+    // Runs `script` with `cacao.audio` bound to `audio` and returns what it returns
+    fn run<R: for<'lua> mlua::FromLuaMulti<'lua>>(audio: &mut AudioSystem, assets: &AssetManager, script: &str) -> R {
+        let lua = Lua::new();
+        lua.globals().set("cacao", lua.create_table().unwrap()).unwrap();
+        let mut camera = Camera::new(1280.0, 720.0);
+        let mut saves = SaveManager::new(std::env::temp_dir().join(format!("cacao_audio_{}", uuid::Uuid::new_v4())));
+        let ctx = EngineContext { audio, assets, camera: &mut camera, saves: &mut saves };
+        with_engine(&lua, ctx, || lua.load(script).eval()).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn scripts_play_and_stop_sounds() {
+        let assets = assets_with_hit_sound().await;
+        let mut audio = AudioSystem::new_silent();
+
+        let id: String = run(&mut audio, &assets, "return cacao.audio.play_sound('hit.wav')");
+        assert!(audio.is_sound_playing(&id));
+
+        run::<()>(&mut audio, &assets, &format!("cacao.audio.stop('{}')", id));
+        assert!(!audio.is_sound_playing(&id));
+
+        let missing: Option<String> = run(&mut audio, &assets, "return cacao.audio.play_sound('missing.wav')");
+        assert_eq!(missing, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn scripts_read_clip_info_and_volumes() {
+        let assets = assets_with_hit_sound().await;
+        let mut audio = AudioSystem::new_silent();
+
+        let info: (f32, u32, u16, String, bool, bool) = run(&mut audio, &assets, r#"
+            local info = cacao.audio.get_info('hit.wav')
+            return info.duration, info.sample_rate, info.channels, info.format, info.streamed, cacao.audio.get_info('missing.wav') == nil
+        "#);
+        assert_eq!(info, (0.5, 8_000, 1, "wav".to_string(), false, true));
+
+        let volumes: (f32, f32, f32) = run(&mut audio, &assets, r#"
+            cacao.audio.set_master_volume(0.5)
+            cacao.audio.set_music_volume(2)
+            cacao.audio.set_sfx_volume(0.25)
+            return cacao.audio.get_master_volume(), cacao.audio.get_music_volume(), cacao.audio.get_sfx_volume()
+        "#);
+        assert_eq!(volumes, (0.5, 1.0, 0.25));
+    }
+}
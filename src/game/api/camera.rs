@@ -0,0 +1,53 @@
+// src/game/api/camera.rs
+use std::cell::RefCell;
+use glam::Vec2;
+use mlua::{Lua, Scope, Table};
+use super::EngineContext;
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<EngineContext<'_>>,
+) -> mlua::Result<()> {
+    let camera = lua.create_table()?;
+
+    camera.set("shake", scope.create_function_mut(move |_, (intensity, duration): (f32, f32)| {
+        ctx.borrow_mut().camera.add_shake(intensity, duration);
+        Ok(())
+    })?)?;
+
+    // Same seed, same shake: handy for replays and recorded demos
+    camera.set("set_shake_seed", scope.create_function_mut(move |_, seed: i64| {
+        ctx.borrow_mut().camera.set_shake_seed(seed as u64);
+        Ok(())
+    })?)?;
+
+    camera.set("set_position", scope.create_function_mut(move |_, (x, y): (f32, f32)| {
+        ctx.borrow_mut().camera.set_position(Vec2::new(x, y));
+        Ok(())
+    })?)?;
+
+    camera.set("get_position", scope.create_function(move |_, ()| {
+        let position = ctx.borrow().camera.position;
+        Ok((position.x, position.y))
+    })?)?;
+
+    camera.set("follow", scope.create_function_mut(move |_, (x, y, smoothing, dt): (f32, f32, f32, f32)| {
+        ctx.borrow_mut().camera.follow_target(Vec2::new(x, y), smoothing, dt);
+        Ok(())
+    })?)?;
+
+    camera.set("set_bounds", scope.create_function_mut(move |_, (min_x, min_y, max_x, max_y): (f32, f32, f32, f32)| {
+        ctx.borrow_mut().camera.set_bounds(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+        Ok(())
+    })?)?;
+
+    camera.set("clear_bounds", scope.create_function_mut(move |_, ()| {
+        ctx.borrow_mut().camera.clear_bounds();
+        Ok(())
+    })?)?;
+
+    cacao.set("camera", camera)?;
+    Ok(())
+}
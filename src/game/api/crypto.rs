@@ -0,0 +1,66 @@
+// src/game/api/crypto.rs
+use mlua::{Lua, Table};
+use crate::crypto;
+
+/// `asset_key` is the game's asset key, so data encrypted here can also be packed
+/// with `GamePacker::add_encrypted_asset` and the other way round.
+pub fn register(lua: &Lua, cacao: &Table, asset_key: [u8; 32]) -> mlua::Result<()> {
+    let table = lua.create_table()?;
+
+    table.set("hash", lua.create_function(|_, data: mlua::String| {
+        Ok(crypto::hash_data(data.as_bytes()))
+    })?)?;
+
+    table.set("encrypt", lua.create_function(move |lua, data: mlua::String| {
+        let encrypted = crypto::encrypt_data(data.as_bytes(), &asset_key)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+        lua.create_string(&encrypted)
+    })?)?;
+
+    table.set("decrypt", lua.create_function(move |lua, data: mlua::String| {
+        let decrypted = crypto::decrypt_data(data.as_bytes(), &asset_key)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+        lua.create_string(&decrypted)
+    })?)?;
+
+    cacao.set("crypto", table)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lua_with_key(asset_key: [u8; 32]) -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao, asset_key).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua
+    }
+
+    #[test]
+    fn lua_encryption_round_trips_with_the_asset_key() {
+        let asset_key = crypto::derive_asset_key("key");
+        let lua = lua_with_key(asset_key);
+
+        let round_trip: String = lua.load(r#"return cacao.crypto.decrypt(cacao.crypto.encrypt("high score: 9001"))"#).eval().unwrap();
+        assert_eq!(round_trip, "high score: 9001");
+
+        // What Lua encrypts, the packer's key decrypts
+        let encrypted: mlua::String = lua.load(r#"return cacao.crypto.encrypt("packed")"#).eval().unwrap();
+        assert_eq!(crypto::decrypt_data(encrypted.as_bytes(), &asset_key).unwrap(), b"packed");
+
+        let hash: String = lua.load(r#"return cacao.crypto.hash("packed")"#).eval().unwrap();
+        assert_eq!(hash, crypto::hash_data(b"packed"));
+    }
+
+    #[test]
+    fn decrypting_with_another_key_errors() {
+        let encrypted = crypto::encrypt_data(b"packed", &crypto::derive_asset_key("other key")).unwrap();
+        let lua = lua_with_key(crypto::derive_asset_key("key"));
+        let decrypt: mlua::Function = lua.load("return cacao.crypto.decrypt").eval().unwrap();
+
+        assert!(decrypt.call::<_, mlua::String>(lua.create_string(&encrypted).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// src/game/api/data.rs
+use std::cell::RefCell;
+use std::collections::HashMap;
+use mlua::{Lua, Scope, Table, Value};
+use serde_json::Value as Data;
+use super::EngineContext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DataFormat {
+    Json,
+    Toml,
+    Csv { header: bool },
+}
+
+/// Data files a script has already parsed, so loading one again skips the parse.
+/// Stored as Lua app data; every load still hands the script a fresh table.
+#[derive(Default)]
+pub struct DataCache(HashMap<(DataFormat, String), Data>);
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<EngineContext<'_>>,
+) -> mlua::Result<()> {
+    let data = lua.create_table()?;
+
+    data.set("load_json", scope.create_function_mut(move |lua, name: String| {
+        let parsed = load(lua, &ctx.borrow(), &name, DataFormat::Json)?;
+        to_lua(lua, &parsed)
+    })?)?;
+
+    data.set("load_toml", scope.create_function_mut(move |lua, name: String| {
+        let parsed = load(lua, &ctx.borrow(), &name, DataFormat::Toml)?;
+        to_lua(lua, &parsed)
+    })?)?;
+
+    // With a header row (the default) each row is keyed by column name,
+    // otherwise rows are plain lists. Cells always come back as strings.
+    data.set("load_csv", scope.create_function_mut(move |lua, (name, header): (String, Option<bool>)| {
+        let format = DataFormat::Csv { header: header.unwrap_or(true) };
+        let parsed = load(lua, &ctx.borrow(), &name, format)?;
+        to_lua(lua, &parsed)
+    })?)?;
+
+    cacao.set("data", data)?;
+    Ok(())
+}
+
+fn load(lua: &Lua, ctx: &EngineContext, name: &str, format: DataFormat) -> mlua::Result<Data> {
+    let key = (format, name.to_string());
+    if let Some(cached) = lua.app_data_ref::<DataCache>().and_then(|cache| cache.0.get(&key).cloned()) {
+        return Ok(cached);
+    }
+
+    let bytes = ctx.assets.get_data_file(name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("Data file not found: {}", name)))?;
+    let text = std::str::from_utf8(bytes)
+        .map_err(|e| mlua::Error::RuntimeError(format!("Data file {} is not valid UTF-8: {}", name, e)))?;
+
+    let parsed = match format {
+        DataFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+        DataFormat::Toml => toml::from_str::<toml::Value>(text).map(toml_to_data).map_err(|e| e.to_string()),
+        DataFormat::Csv { header } => parse_csv(text).map(|rows| csv_to_data(rows, header)),
+    }
+    .map_err(|e| mlua::Error::RuntimeError(format!("Invalid data file {}: {}", name, e)))?;
+
+    if let Some(mut cache) = lua.app_data_mut::<DataCache>() {
+        cache.0.insert(key, parsed.clone());
+    }
+    Ok(parsed)
+}
+
+fn to_lua<'lua>(lua: &'lua Lua, data: &Data) -> mlua::Result<Value<'lua>> {
+    Ok(match data {
+        Data::Null => Value::Nil,
+        Data::Bool(b) => Value::Boolean(*b),
+        Data::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Number(n.as_f64().unwrap_or_default()),
+        },
+        Data::String(s) => Value::String(lua.create_string(s)?),
+        Data::Array(items) => {
+            let table = lua.create_table_with_capacity(items.len(), 0)?;
+            for (i, item) in items.iter().enumerate() {
+                table.set(i + 1, to_lua(lua, item)?)?;
+            }
+            Value::Table(table)
+        }
+        Data::Object(fields) => {
+            let table = lua.create_table_with_capacity(0, fields.len())?;
+            for (key, value) in fields {
+                table.set(key.as_str(), to_lua(lua, value)?)?;
+            }
+            Value::Table(table)
+        }
+    })
+}
+
+// TOML dates have no Lua equivalent, so they come through as their text form
+fn toml_to_data(value: toml::Value) -> Data {
+    match value {
+        toml::Value::String(s) => Data::String(s),
+        toml::Value::Integer(i) => Data::from(i),
+        toml::Value::Float(f) => Data::from(f),
+        toml::Value::Boolean(b) => Data::Bool(b),
+        toml::Value::Datetime(d) => Data::String(d.to_string()),
+        toml::Value::Array(items) => Data::Array(items.into_iter().map(toml_to_data).collect()),
+        toml::Value::Table(fields) => Data::Object(fields.into_iter().map(|(k, v)| (k, toml_to_data(v))).collect()),
+    }
+}
+
+fn csv_to_data(mut rows: Vec<Vec<String>>, header: bool) -> Data {
+    if !header || rows.is_empty() {
+        return Data::Array(rows.into_iter().map(|row| row.into_iter().map(Data::String).collect()).collect());
+    }
+
+    let columns = rows.remove(0);
+    Data::Array(rows.into_iter().map(|row| {
+        Data::Object(columns.iter().cloned().zip(row.into_iter().map(Data::String)).collect())
+    }).collect())
+}
+
+/// Splits CSV text into rows of cells. Handles quoted cells, doubled quotes inside
+/// them and both line ending styles; blank lines are skipped.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => quoted = false,
+                _ => cell.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if cell.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if !row.is_empty() || !cell.is_empty() {
+                    row.push(std::mem::take(&mut cell));
+                    rows.push(std::mem::take(&mut row));
+                }
+            }
+            _ => cell.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("unclosed quote".to_string());
+    }
+    if !row.is_empty() || !cell.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn csv_handles_quotes_and_line_endings() {
+        let rows = parse_csv("name,line\r\nAda,\"Hello, \"\"world\"\"\"\n\nBob,\"two\nlines\"").unwrap();
+        assert_eq!(rows, vec![
+            vec!["name".to_string(), "line".to_string()],
+            vec!["Ada".to_string(), "Hello, \"world\"".to_string()],
+            vec!["Bob".to_string(), "two\nlines".to_string()],
+        ]);
+        assert!(parse_csv("a,\"unclosed").is_err());
+    }
+
+    #[test]
+    fn csv_rows_are_keyed_by_the_header() {
+        let rows = parse_csv("id,hp\n1,10\n2,20\n").unwrap();
+        assert_eq!(csv_to_data(rows.clone(), true), json!([{"id": "1", "hp": "10"}, {"id": "2", "hp": "20"}]));
+        assert_eq!(csv_to_data(rows, false), json!([["id", "hp"], ["1", "10"], ["2", "20"]]));
+    }
+
+    #[test]
+    fn toml_converts_to_data() {
+        let value: toml::Value = toml::from_str("title = 'Cave'\nsize = 3\nscale = 1.5\ncreated = 2024-01-02\n[[enemies]]\nname = 'bat'").unwrap();
+        assert_eq!(toml_to_data(value), json!({
+            "title": "Cave",
+            "size": 3,
+            "scale": 1.5,
+            "created": "2024-01-02",
+            "enemies": [{"name": "bat"}],
+        }));
+    }
+
+    #[test]
+    fn json_becomes_lua_tables() {
+        let lua = Lua::new();
+        let data: Data = serde_json::from_str(r#"{"name": "Ada", "level": 3, "speed": 2.5, "items": ["key", null, true]}"#).unwrap();
+        lua.globals().set("data", to_lua(&lua, &data).unwrap()).unwrap();
+
+        let check = r#"
+            assert(data.name == "Ada")
+            assert(math.type(data.level) == "integer" and data.level == 3)
+            assert(math.type(data.speed) == "float")
+            assert(data.items[1] == "key" and data.items[2] == nil and data.items[3] == true)
+        "#;
+        lua.load(check).exec().unwrap();
+    }
+}
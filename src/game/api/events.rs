@@ -0,0 +1,109 @@
+// src/game/api/events.rs
+use std::collections::VecDeque;
+use mlua::{Lua, Table};
+
+// A game that never polls shouldn't grow the queue forever
+const MAX_QUEUED_EVENTS: usize = 64;
+
+/// Something the engine tells the running game about, read with `cacao.events.poll()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    FocusGained,
+    FocusLost,
+    GamepadConnected { name: String },
+    GamepadDisconnected { name: String },
+}
+
+impl GameEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            GameEvent::FocusGained => "focus_gained",
+            GameEvent::FocusLost => "focus_lost",
+            GameEvent::GamepadConnected { .. } => "gamepad_connected",
+            GameEvent::GamepadDisconnected { .. } => "gamepad_disconnected",
+        }
+    }
+
+    fn to_table<'lua>(&self, lua: &'lua Lua) -> mlua::Result<Table<'lua>> {
+        let table = lua.create_table()?;
+        table.set("type", self.name())?;
+        if let GameEvent::GamepadConnected { name } | GameEvent::GamepadDisconnected { name } = self {
+            table.set("name", name.as_str())?;
+        }
+        Ok(table)
+    }
+}
+
+/// Events waiting for the script, oldest first. Stored as Lua app data.
+#[derive(Debug, Default)]
+pub struct EventQueue(VecDeque<GameEvent>);
+
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    lua.set_app_data(EventQueue::default());
+    let events = lua.create_table()?;
+
+    // Each event comes back once, as {type = "focus_lost"} and the like
+    events.set("poll", lua.create_function(|lua, ()| {
+        let pending: Vec<GameEvent> = match lua.app_data_mut::<EventQueue>() {
+            Some(mut queue) => queue.0.drain(..).collect(),
+            None => Vec::new(),
+        };
+        let list = lua.create_table()?;
+        for (i, event) in pending.iter().enumerate() {
+            list.set(i + 1, event.to_table(lua)?)?;
+        }
+        Ok(list)
+    })?)?;
+
+    cacao.set("events", events)?;
+    Ok(())
+}
+
+/// Queues `event` for the next `cacao.events.poll()`, dropping the oldest past the limit.
+pub fn push(lua: &Lua, event: GameEvent) {
+    if let Some(mut queue) = lua.app_data_mut::<EventQueue>() {
+        if queue.0.len() >= MAX_QUEUED_EVENTS {
+            queue.0.pop_front();
+        }
+        queue.0.push_back(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lua_with_events() -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua
+    }
+
+    #[test]
+    fn polling_drains_events_oldest_first() {
+        let lua = lua_with_events();
+        push(&lua, GameEvent::FocusLost);
+        push(&lua, GameEvent::GamepadConnected { name: "Pad".to_string() });
+
+        let polled: (usize, String, String, Option<String>, usize) = lua.load(r#"
+            local events = cacao.events.poll()
+            return #events, events[1].type, events[2].type, events[2].name, #cacao.events.poll()
+        "#).eval().unwrap();
+        assert_eq!(polled, (2, "focus_lost".to_string(), "gamepad_connected".to_string(), Some("Pad".to_string()), 0));
+    }
+
+    #[test]
+    fn unpolled_events_drop_the_oldest_past_the_limit() {
+        let lua = lua_with_events();
+        push(&lua, GameEvent::FocusLost);
+        for _ in 0..MAX_QUEUED_EVENTS {
+            push(&lua, GameEvent::FocusGained);
+        }
+
+        let queue = lua.app_data_ref::<EventQueue>().unwrap();
+        assert_eq!(queue.0.len(), MAX_QUEUED_EVENTS);
+        assert!(queue.0.iter().all(|event| *event == GameEvent::FocusGained));
+    }
+}
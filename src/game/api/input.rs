@@ -0,0 +1,177 @@
+// src/game/api/input.rs
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use glam::Vec2;
+use mlua::{Lua, Table};
+use winit::event::VirtualKeyCode;
+use crate::input::{key_from_name, InputManager, DOUBLE_TAP_WINDOW};
+
+/// Copy of the input state taken at the start of a game update. Stored as Lua
+/// app data so the `cacao.input` functions can answer without borrowing the engine.
+#[derive(Debug, Clone, Default)]
+pub struct InputSnapshot {
+    pub keys_pressed: HashSet<VirtualKeyCode>,
+    pub keys_just_pressed: HashSet<VirtualKeyCode>,
+    pub keys_held_for: HashMap<VirtualKeyCode, Duration>,
+    // Keys that went down within DOUBLE_TAP_WINDOW of their last press
+    pub keys_double_tapped: HashSet<VirtualKeyCode>,
+    pub actions_pressed: HashSet<String>,
+    pub mouse_position: Vec2,
+}
+
+impl InputSnapshot {
+    /// Takes `input` mutably because reading a double tap uses it up.
+    pub fn capture(input: &mut InputManager) -> Self {
+        let keys_just_pressed = input.get_just_pressed_keys();
+        Self {
+            keys_pressed: input.get_pressed_keys().into_iter().collect(),
+            keys_held_for: input.get_pressed_keys().into_iter().map(|key| (key, input.key_held_duration(key))).collect(),
+            keys_double_tapped: keys_just_pressed.iter().copied().filter(|&key| input.is_key_double_tapped(key, DOUBLE_TAP_WINDOW)).collect(),
+            keys_just_pressed: keys_just_pressed.into_iter().collect(),
+            actions_pressed: input.get_pressed_actions().into_iter().collect(),
+            mouse_position: input.get_mouse_position(),
+        }
+    }
+}
+
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    let input = lua.create_table()?;
+
+    input.set("is_key_pressed", lua.create_function(|lua, name: String| {
+        Ok(with_snapshot(lua, |snapshot| {
+            key_from_name(&name).is_some_and(|key| snapshot.keys_pressed.contains(&key))
+        }))
+    })?)?;
+
+    input.set("is_key_just_pressed", lua.create_function(|lua, name: String| {
+        Ok(with_snapshot(lua, |snapshot| {
+            key_from_name(&name).is_some_and(|key| snapshot.keys_just_pressed.contains(&key))
+        }))
+    })?)?;
+
+    // Seconds the key has been held, 0 when it's up
+    input.set("key_held_duration", lua.create_function(|lua, name: String| {
+        Ok(with_snapshot(lua, |snapshot| {
+            key_from_name(&name)
+                .and_then(|key| snapshot.keys_held_for.get(&key))
+                .map_or(0.0, Duration::as_secs_f32)
+        }))
+    })?)?;
+
+    // True on the frame the key goes down a second time in quick succession
+    input.set("is_key_double_tapped", lua.create_function(|lua, name: String| {
+        Ok(with_snapshot(lua, |snapshot| {
+            key_from_name(&name).is_some_and(|key| snapshot.keys_double_tapped.contains(&key))
+        }))
+    })?)?;
+
+    input.set("is_action_pressed", lua.create_function(|lua, action: String| {
+        Ok(with_snapshot(lua, |snapshot| snapshot.actions_pressed.contains(&action)))
+    })?)?;
+
+    input.set("get_mouse_position", lua.create_function(|lua, ()| {
+        let position = with_snapshot(lua, |snapshot| snapshot.mouse_position);
+        Ok((position.x, position.y))
+    })?)?;
+
+    cacao.set("input", input)?;
+    Ok(())
+}
+
+fn with_snapshot<T: Default>(lua: &Lua, f: impl FnOnce(&InputSnapshot) -> T) -> T {
+    lua.app_data_ref::<InputSnapshot>()
+        .map(|snapshot| f(&snapshot))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::{ElementState, KeyboardInput, WindowEvent};
+
+    // winit still requires the deprecated modifiers field in these events
+    #[allow(deprecated)]
+    fn press(input: &mut InputManager, key: VirtualKeyCode) {
+        input.handle_window_event(&WindowEvent::KeyboardInput {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            input: KeyboardInput { scancode: 0, state: ElementState::Pressed, virtual_keycode: Some(key), modifiers: Default::default() },
+            is_synthetic: false,
+        });
+    }
+
+    #[allow(deprecated)]
+    fn release(input: &mut InputManager, key: VirtualKeyCode) {
+        input.handle_window_event(&WindowEvent::KeyboardInput {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            input: KeyboardInput { scancode: 0, state: ElementState::Released, virtual_keycode: Some(key), modifiers: Default::default() },
+            is_synthetic: false,
+        });
+    }
+
+    fn lua_with(input: &mut InputManager) -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua.set_app_data(InputSnapshot::capture(input));
+        lua
+    }
+
+    #[test]
+    fn scripts_see_keys_held_when_the_snapshot_was_taken() {
+        let mut input = InputManager::new();
+        press(&mut input, VirtualKeyCode::Space);
+        let lua = lua_with(&mut input);
+
+        let held: (bool, bool, bool) = lua.load(r#"
+            return cacao.input.is_key_pressed("space"), cacao.input.is_key_just_pressed("Space"), cacao.input.is_key_pressed("a")
+        "#).eval().unwrap();
+        assert_eq!(held, (true, true, false));
+
+        // Still held on the next frame, but no longer just pressed
+        input.update();
+        lua.set_app_data(InputSnapshot::capture(&mut input));
+        let held: (bool, bool) = lua.load(r#"return cacao.input.is_key_pressed("space"), cacao.input.is_key_just_pressed("space")"#).eval().unwrap();
+        assert_eq!(held, (true, false));
+    }
+
+    #[test]
+    fn unknown_keys_and_missing_snapshots_read_as_up() {
+        let lua = lua_with(&mut InputManager::new());
+        let unknown: bool = lua.load(r#"return cacao.input.is_key_pressed("hyper")"#).eval().unwrap();
+        assert!(!unknown);
+
+        lua.remove_app_data::<InputSnapshot>();
+        let position: (f32, f32) = lua.load("return cacao.input.get_mouse_position()").eval().unwrap();
+        assert_eq!(position, (0.0, 0.0));
+    }
+
+    #[test]
+    fn scripts_see_how_long_keys_are_held() {
+        let mut input = InputManager::new();
+        press(&mut input, VirtualKeyCode::Z);
+        let lua = lua_with(&mut input);
+
+        let (held, up): (f32, f32) = lua.load(r#"return cacao.input.key_held_duration("z"), cacao.input.key_held_duration("x")"#).eval().unwrap();
+        assert!((0.0..1.0).contains(&held), "{}", held);
+        assert_eq!(up, 0.0);
+    }
+
+    #[test]
+    fn scripts_see_double_taps_once() {
+        let mut input = InputManager::new();
+        let lua = lua_with(&mut input);
+        let tap = |input: &mut InputManager| {
+            press(input, VirtualKeyCode::Space);
+            lua.set_app_data(InputSnapshot::capture(input));
+            release(input, VirtualKeyCode::Space);
+            input.update();
+            lua.load(r#"return cacao.input.is_key_double_tapped("space")"#).eval::<bool>().unwrap()
+        };
+
+        assert!(!tap(&mut input));
+        assert!(tap(&mut input));
+        // The pair was used up, so a third quick tap starts over
+        assert!(!tap(&mut input));
+    }
+}
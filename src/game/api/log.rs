@@ -0,0 +1,99 @@
+// src/game/api/log.rs
+use mlua::{Function, Lua, Table, Variadic, Value};
+
+/// Sends the script's `print` and `cacao.log` output to the engine log, tagged `[game]`.
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    lua.globals().set("print", lua.create_function(|lua, args: Variadic<Value>| {
+        log::info!("[game] {}", join_args(lua, args)?);
+        Ok(())
+    })?)?;
+
+    let log_table = lua.create_table()?;
+
+    log_table.set("info", lua.create_function(|lua, args: Variadic<Value>| {
+        log::info!("[game] {}", join_args(lua, args)?);
+        Ok(())
+    })?)?;
+
+    log_table.set("warn", lua.create_function(|lua, args: Variadic<Value>| {
+        log::warn!("[game] {}", join_args(lua, args)?);
+        Ok(())
+    })?)?;
+
+    log_table.set("error", lua.create_function(|lua, args: Variadic<Value>| {
+        log::error!("[game] {}", join_args(lua, args)?);
+        Ok(())
+    })?)?;
+
+    cacao.set("log", log_table)?;
+    Ok(())
+}
+
+// Converts each argument with `tostring` and joins them with spaces, like stock `print`
+fn join_args(lua: &Lua, args: Variadic<Value>) -> mlua::Result<String> {
+    let tostring: Function = lua.globals().get("tostring")?;
+    let parts = args
+        .into_iter()
+        .map(|value| tostring.call::<_, String>(value))
+        .collect::<mlua::Result<Vec<_>>>()?;
+    Ok(parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, OnceLock};
+
+    // Keeps every record logged during the test run, whichever test logged it
+    struct Capture(Mutex<Vec<(log::Level, String)>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn captured() -> &'static Capture {
+        static CAPTURE: OnceLock<&'static Capture> = OnceLock::new();
+        CAPTURE.get_or_init(|| {
+            let capture: &'static Capture = Box::leak(Box::new(Capture(Mutex::new(Vec::new()))));
+            log::set_logger(capture).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+            capture
+        })
+    }
+
+    #[test]
+    fn print_and_cacao_log_go_to_the_engine_log() {
+        let capture = captured();
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+
+        lua.load(r#"
+            print("log test", 12, nil, true)
+            cacao.log.info("log test", 2)
+            cacao.log.warn("log test: low health")
+            cacao.log.error("log test: missing sprite")
+        "#).exec().unwrap();
+
+        // Other tests log too, so only this one's lines are looked at
+        let game_lines: Vec<_> = capture.0.lock().unwrap().iter()
+            .filter(|(_, line)| line.starts_with("[game] log test"))
+            .cloned()
+            .collect();
+        assert_eq!(game_lines, [
+            (log::Level::Info, "[game] log test 12 nil true".to_string()),
+            (log::Level::Info, "[game] log test 2".to_string()),
+            (log::Level::Warn, "[game] log test: low health".to_string()),
+            (log::Level::Error, "[game] log test: missing sprite".to_string()),
+        ]);
+    }
+}
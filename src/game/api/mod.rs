@@ -0,0 +1,101 @@
+// ============================================================================
+// FILE: src/game/api/mod.rs - Lua `cacao` API
+// ============================================================================
+pub mod animation;
+pub mod audio;
+pub mod camera;
+pub mod crypto;
+pub mod data;
+pub mod events;
+pub mod renderer;
+pub mod input;
+pub mod log;
+pub mod particles;
+pub mod physics;
+pub mod random;
+pub mod saves;
+pub mod schedule;
+pub mod time;
+pub mod window;
+
+use std::cell::RefCell;
+use mlua::{Lua, Table, Value};
+use crate::{assets::AssetManager, audio::AudioSystem, renderer::{Camera, DrawTarget}, saves::SaveManager};
+
+/// Engine systems a script may touch while one of its callbacks is running.
+pub struct EngineContext<'a> {
+    pub audio: &'a mut AudioSystem,
+    pub assets: &'a AssetManager,
+    pub camera: &'a mut Camera,
+    pub saves: &'a mut SaveManager,
+}
+
+/// What a script's `render` callback draws into.
+pub struct DrawContext<'a> {
+    pub target: &'a mut dyn DrawTarget,
+    pub assets: &'a AssetManager,
+}
+
+// Tables bound by `with_engine`, and so only usable outside of `render`
+const ENGINE_TABLES: [&str; 5] = ["animation", "audio", "camera", "data", "saves"];
+
+/// Registers every engine-backed table on the global `cacao` table.
+pub fn register(lua: &Lua, cacao: &Table, secret_key: &str) -> mlua::Result<()> {
+    lua.set_app_data(animation::Animations::default());
+    lua.set_app_data(data::DataCache::default());
+    crypto::register(lua, cacao, crate::crypto::derive_asset_key(secret_key))?;
+    events::register(lua, cacao)?;
+    input::register(lua, cacao)?;
+    log::register(lua, cacao)?;
+    particles::register(lua, cacao)?;
+    physics::register(lua, cacao)?;
+    random::register(lua, cacao)?;
+    schedule::register(lua, cacao)?;
+    time::register(lua, cacao)?;
+    window::register(lua, cacao)?;
+    Ok(())
+}
+
+/// Binds the tables that need mutable engine access, runs `f`, then drops them
+/// again so scripts can never hold on to a stale borrow.
+pub fn with_engine<R>(lua: &Lua, ctx: EngineContext, f: impl FnOnce() -> mlua::Result<R>) -> mlua::Result<R> {
+    let ctx = RefCell::new(ctx);
+    lua.scope(|scope| {
+        let cacao: Table = lua.globals().get("cacao")?;
+        cacao.set("renderer", unavailable_table(lua, "renderer", "the render callback")?)?;
+        animation::bind(lua, scope, &cacao, &ctx)?;
+        audio::bind(lua, scope, &cacao, &ctx)?;
+        camera::bind(lua, scope, &cacao, &ctx)?;
+        data::bind(lua, scope, &cacao, &ctx)?;
+        saves::bind(lua, scope, &cacao, &ctx)?;
+        f()
+    })
+}
+
+/// Like `with_engine`, but binds `cacao.renderer` to `ctx` for the length of a render call.
+pub fn with_draw_target<R>(lua: &Lua, ctx: DrawContext, f: impl FnOnce() -> mlua::Result<R>) -> mlua::Result<R> {
+    let ctx = RefCell::new(ctx);
+    lua.scope(|scope| {
+        let cacao: Table = lua.globals().get("cacao")?;
+        for name in ENGINE_TABLES {
+            cacao.set(name, unavailable_table(lua, name, "init, update and the other non-render callbacks")?)?;
+        }
+        renderer::bind(lua, scope, &cacao, &ctx)?;
+        f()
+    })
+}
+
+/// Stands in for a scoped table outside the callbacks it's bound for, so calling
+/// into it says where it can be used instead of failing with a destructed callback.
+fn unavailable_table<'lua>(lua: &'lua Lua, name: &'static str, usable_in: &'static str) -> mlua::Result<Table<'lua>> {
+    let index = lua.create_function(move |lua, (_, key): (Value, String)| {
+        lua.create_function(move |_, _: mlua::MultiValue| -> mlua::Result<()> {
+            Err(mlua::Error::RuntimeError(format!("cacao.{}.{} can only be used from {}", name, key, usable_in)))
+        })
+    })?;
+    let metatable = lua.create_table()?;
+    metatable.set("__index", index)?;
+    let table = lua.create_table()?;
+    table.set_metatable(Some(metatable));
+    Ok(table)
+}
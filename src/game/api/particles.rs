@@ -0,0 +1,146 @@
+// src/game/api/particles.rs
+use mlua::{Lua, Table, Value};
+use crate::renderer::{ParticleConfig, ParticleSystem};
+use super::renderer::parse_color;
+
+const DEFAULT_EMIT_COUNT: usize = 20;
+// One call can't stall the game by asking for millions of particles
+const MAX_EMIT_COUNT: usize = 1000;
+
+/// The game's particles live in Lua app data; they're drawn with `cacao.renderer.draw_particles()`.
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    lua.set_app_data(ParticleSystem::new());
+    let particles = lua.create_table()?;
+
+    particles.set("emit", lua.create_function(|lua, (x, y, config): (f32, f32, Option<Table>)| {
+        let count = match &config {
+            Some(table) => table.get::<_, Option<usize>>("count")?.unwrap_or(DEFAULT_EMIT_COUNT),
+            None => DEFAULT_EMIT_COUNT,
+        };
+        let config = parse_config(config)?;
+        if let Some(mut system) = lua.app_data_mut::<ParticleSystem>() {
+            system.emit(x, y, &config, count.min(MAX_EMIT_COUNT));
+        }
+        Ok(())
+    })?)?;
+
+    // With a `count`, keeps that many alive; otherwise spawns `spawn_rate` a second
+    particles.set("start", lua.create_function(|lua, (x, y, config): (f32, f32, Option<Table>)| {
+        let pool_size = match &config {
+            Some(table) => table.get::<_, Option<usize>>("count")?,
+            None => None,
+        };
+        let config = parse_config(config)?;
+        Ok(lua.app_data_mut::<ParticleSystem>().map(|mut system| match pool_size {
+            Some(size) => system.start_pool(x, y, config, size),
+            None => system.start_emitter(x, y, config),
+        }))
+    })?)?;
+
+    particles.set("stop", lua.create_function(|lua, id: u32| {
+        Ok(lua.app_data_mut::<ParticleSystem>().is_some_and(|mut system| system.stop_emitter(id)))
+    })?)?;
+
+    particles.set("clear", lua.create_function(|lua, ()| {
+        if let Some(mut system) = lua.app_data_mut::<ParticleSystem>() {
+            system.clear();
+        }
+        Ok(())
+    })?)?;
+
+    particles.set("count", lua.create_function(|lua, ()| {
+        Ok(lua.app_data_ref::<ParticleSystem>().map_or(0, |system| system.len()))
+    })?)?;
+
+    cacao.set("particles", particles)?;
+    Ok(())
+}
+
+/// Moves the game's particles on by `dt` seconds.
+pub fn update_all(lua: &Lua, dt: f32) {
+    if let Some(mut system) = lua.app_data_mut::<ParticleSystem>() {
+        system.update(dt);
+    }
+}
+
+/// Missing fields keep `ParticleConfig::default()`. Ranges are a number or `{min, max}`,
+/// and `color` sets both ends of the color range.
+fn parse_config(table: Option<Table>) -> mlua::Result<ParticleConfig> {
+    let mut config = ParticleConfig::default();
+    let Some(table) = table else {
+        return Ok(config);
+    };
+
+    if let Some(rate) = table.get::<_, Option<f32>>("spawn_rate")? {
+        config.spawn_rate = rate;
+    }
+    if let Some(gravity) = table.get::<_, Option<f32>>("gravity")? {
+        config.gravity = gravity;
+    }
+    for (key, range) in [
+        ("lifetime", &mut config.lifetime),
+        ("velocity_x", &mut config.velocity_x),
+        ("velocity_y", &mut config.velocity_y),
+        ("size", &mut config.size),
+        ("spread", &mut config.spread),
+    ] {
+        if let Some(value) = parse_range(key, table.get(key)?)? {
+            *range = value;
+        }
+    }
+
+    if let Some(color) = table.get::<_, Option<Vec<f32>>>("color")? {
+        config.color_min = parse_color(Some(color))?;
+        config.color_max = config.color_min;
+    }
+    if let Some(color) = table.get::<_, Option<Vec<f32>>>("color_min")? {
+        config.color_min = parse_color(Some(color))?;
+    }
+    if let Some(color) = table.get::<_, Option<Vec<f32>>>("color_max")? {
+        config.color_max = parse_color(Some(color))?;
+    }
+
+    Ok(config)
+}
+
+fn parse_range(key: &str, value: Value) -> mlua::Result<Option<(f32, f32)>> {
+    match value {
+        Value::Nil => Ok(None),
+        Value::Integer(n) => Ok(Some((n as f32, n as f32))),
+        Value::Number(n) => Ok(Some((n as f32, n as f32))),
+        Value::Table(range) => Ok(Some((range.get(1)?, range.get(2)?))),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "{} must be a number or {{min, max}}, got {}", key, other.type_name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particles_lua() -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua
+    }
+
+    #[test]
+    fn emit_counts_are_capped() {
+        let lua = particles_lua();
+        let count: usize = lua.load(r#"
+            cacao.particles.emit(0, 0, { count = 1000000 })
+            return cacao.particles.count()
+        "#).eval().unwrap();
+        assert_eq!(count, MAX_EMIT_COUNT);
+    }
+
+    #[test]
+    fn emit_defaults_to_a_small_burst() {
+        let lua = particles_lua();
+        let count: usize = lua.load("cacao.particles.emit(0, 0) return cacao.particles.count()").eval().unwrap();
+        assert_eq!(count, DEFAULT_EMIT_COUNT);
+    }
+}
@@ -0,0 +1,28 @@
+// src/game/api/physics.rs
+use glam::Vec2;
+use mlua::{Lua, Table};
+use crate::game::physics::Aabb;
+
+/// Boxes are passed as `x, y, width, height` with (x, y) the top-left corner.
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    let physics = lua.create_table()?;
+
+    physics.set("aabb_overlap", lua.create_function(|_, (x1, y1, w1, h1, x2, y2, w2, h2): (f32, f32, f32, f32, f32, f32, f32, f32)| {
+        Ok(Aabb::from_rect(x1, y1, w1, h1).intersects(&Aabb::from_rect(x2, y2, w2, h2)))
+    })?)?;
+
+    // Returns how far to move the first box so it no longer overlaps the second (0, 0 if it doesn't)
+    physics.set("aabb_resolve", lua.create_function(|_, (x1, y1, w1, h1, x2, y2, w2, h2): (f32, f32, f32, f32, f32, f32, f32, f32)| {
+        let push = Aabb::from_rect(x1, y1, w1, h1)
+            .resolve(&Aabb::from_rect(x2, y2, w2, h2))
+            .unwrap_or(Vec2::ZERO);
+        Ok((push.x, push.y))
+    })?)?;
+
+    physics.set("point_in_aabb", lua.create_function(|_, (px, py, x, y, width, height): (f32, f32, f32, f32, f32, f32)| {
+        Ok(Aabb::from_rect(x, y, width, height).contains_point(Vec2::new(px, py)))
+    })?)?;
+
+    cacao.set("physics", physics)?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// src/game/api/random.rs
+use mlua::{Lua, Table};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The game's random number generator, stored as Lua app data for `cacao.random`.
+/// Starts from entropy; seeding it makes every run draw the same numbers.
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    lua.set_app_data(GameRng::default());
+    let random = lua.create_table()?;
+
+    random.set("seed", lua.create_function(|lua, seed: i64| {
+        lua.set_app_data(GameRng(StdRng::seed_from_u64(seed as u64)));
+        Ok(())
+    })?)?;
+
+    // In [0, 1)
+    random.set("float", lua.create_function(|lua, ()| {
+        Ok(with_rng(lua, |rng| rng.gen::<f64>()))
+    })?)?;
+
+    // Both ends included, like math.random
+    random.set("int", lua.create_function(|lua, (min, max): (i64, i64)| {
+        if min > max {
+            return Err(mlua::Error::RuntimeError(format!("random.int: min {} is greater than max {}", min, max)));
+        }
+        Ok(with_rng(lua, |rng| rng.gen_range(min..=max)))
+    })?)?;
+
+    cacao.set("random", random)?;
+    Ok(())
+}
+
+fn with_rng<T>(lua: &Lua, f: impl FnOnce(&mut StdRng) -> T) -> T {
+    match lua.app_data_mut::<GameRng>() {
+        Some(mut rng) => f(&mut rng.0),
+        None => f(&mut StdRng::from_entropy()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lua_with_random() -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua
+    }
+
+    fn draws(lua: &Lua, seed: i64) -> Vec<f64> {
+        let chunk = format!(
+            "cacao.random.seed({}) local t = {{}} for i = 1, 5 do t[i] = cacao.random.float() end t[6] = cacao.random.int(1, 1000000) return t",
+            seed
+        );
+        lua.load(&chunk).eval().unwrap()
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_numbers() {
+        let lua = lua_with_random();
+        let first = draws(&lua, 42);
+        assert_eq!(draws(&lua, 42), first);
+        assert_eq!(draws(&lua_with_random(), 42), first);
+        assert_ne!(draws(&lua, 43), first);
+        assert!(first[..5].iter().all(|value| (0.0..1.0).contains(value)));
+    }
+
+    #[test]
+    fn int_includes_both_ends_and_checks_its_range() {
+        let lua = lua_with_random();
+        let values: Vec<i64> = lua.load("local t = {} for i = 1, 200 do t[i] = cacao.random.int(1, 2) end return t").eval().unwrap();
+        assert!(values.contains(&1) && values.contains(&2));
+        assert!(values.iter().all(|value| (1..=2).contains(value)));
+        assert!(lua.load("cacao.random.int(3, 1)").exec().is_err());
+    }
+}
@@ -0,0 +1,182 @@
+// src/game/api/renderer.rs
+use std::cell::RefCell;
+use mlua::{Lua, Scope, Table, Value};
+use crate::renderer::{FilterMode, NinePatchInsets, ParticleSystem, Sprite, TextAlign};
+use super::{animation::Animations, DrawContext};
+
+const DEFAULT_CIRCLE_SEGMENTS: u32 = 32;
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<DrawContext<'_>>,
+) -> mlua::Result<()> {
+    let renderer = lua.create_table()?;
+
+    renderer.set("set_layer", scope.create_function_mut(move |_, layer: i32| {
+        ctx.borrow_mut().target.set_layer(layer);
+        Ok(())
+    })?)?;
+
+    // For HUDs: draws between these two calls ignore the camera
+    renderer.set("begin_screen_space", scope.create_function_mut(move |_, ()| {
+        ctx.borrow_mut().target.begin_screen_space();
+        Ok(())
+    })?)?;
+
+    renderer.set("begin_world_space", scope.create_function_mut(move |_, ()| {
+        ctx.borrow_mut().target.begin_world_space();
+        Ok(())
+    })?)?;
+
+    // `filter` ("nearest" or "linear") overrides the game's sprite filter for this draw
+    renderer.set("draw_sprite", scope.create_function_mut(move |_, (name, x, y, rotation, scale, filter): (String, f32, f32, Option<f32>, Option<f32>, Option<String>)| {
+        let filter = filter.as_deref().map(parse_filter).transpose()?;
+        let mut ctx = ctx.borrow_mut();
+        let Some(sprite) = ctx.assets.get_sprite(&name) else {
+            log::warn!("⚠️ Sprite not found: {}", name);
+            return Ok(false);
+        };
+        let filtered;
+        let sprite: &Sprite = match filter {
+            Some(filter) => {
+                filtered = Sprite::clone(&sprite).with_filter(filter);
+                &filtered
+            }
+            None => &sprite,
+        };
+        ctx.target.draw_sprite(sprite, x, y, rotation.unwrap_or(0.0), scale.unwrap_or(1.0))
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+        Ok(true)
+    })?)?;
+
+    // For dialog boxes and panels: the sprite's borders keep their size however big the box is
+    renderer.set("draw_nine_patch", scope.create_function_mut(move |_, (name, x, y, width, height, insets): (String, f32, f32, f32, f32, Value)| {
+        let insets = parse_insets(insets)?;
+        let mut ctx = ctx.borrow_mut();
+        let Some(sprite) = ctx.assets.get_sprite(&name) else {
+            log::warn!("⚠️ Sprite not found: {}", name);
+            return Ok(false);
+        };
+        ctx.target.draw_nine_patch(&sprite, x, y, width, height, insets)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+        Ok(true)
+    })?)?;
+
+    renderer.set("draw_animation", scope.create_function_mut(move |lua, (name, x, y, rotation, scale): (String, f32, f32, Option<f32>, Option<f32>)| {
+        let Some(animations) = lua.app_data_ref::<Animations>() else {
+            return Ok(false);
+        };
+        let Some(animation) = animations.0.get(&name) else {
+            log::warn!("⚠️ Animation not found: {}", name);
+            return Ok(false);
+        };
+        ctx.borrow_mut().target.draw_sprite(animation.current_sprite(), x, y, rotation.unwrap_or(0.0), scale.unwrap_or(1.0))
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+        Ok(true)
+    })?)?;
+
+    renderer.set("draw_particles", scope.create_function_mut(move |lua, ()| {
+        let Some(particles) = lua.app_data_ref::<ParticleSystem>().filter(|particles| !particles.is_empty()) else {
+            return Ok(());
+        };
+        particles.render(&mut *ctx.borrow_mut().target)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    renderer.set("draw_text", scope.create_function_mut(move |_, (text, x, y, size, color): (String, f32, f32, Option<f32>, Option<Vec<f32>>)| {
+        ctx.borrow_mut().target.draw_text(&text, x, y, size.unwrap_or(24.0), parse_color(color)?)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    renderer.set("draw_text_aligned", scope.create_function_mut(move |_, (text, x, y, align, size, color): (String, f32, f32, String, Option<f32>, Option<Vec<f32>>)| {
+        ctx.borrow_mut().target.draw_text_aligned(&text, x, y, size.unwrap_or(24.0), parse_color(color)?, parse_align(&align)?)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    renderer.set("measure_text", scope.create_function_mut(move |_, (text, size): (String, Option<f32>)| {
+        Ok(ctx.borrow_mut().target.measure_text(&text, size.unwrap_or(24.0)))
+    })?)?;
+
+    renderer.set("draw_rect", scope.create_function_mut(move |_, (x, y, width, height, color): (f32, f32, f32, f32, Option<Vec<f32>>)| {
+        ctx.borrow_mut().target.draw_rect(x, y, width, height, parse_color(color)?)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    renderer.set("draw_line", scope.create_function_mut(move |_, (x1, y1, x2, y2, thickness, color): (f32, f32, f32, f32, Option<f32>, Option<Vec<f32>>)| {
+        ctx.borrow_mut().target.draw_line(x1, y1, x2, y2, thickness.unwrap_or(1.0), parse_color(color)?)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    renderer.set("draw_circle", scope.create_function_mut(move |_, (x, y, radius, color, segments): (f32, f32, f32, Option<Vec<f32>>, Option<u32>)| {
+        let segments = segments.unwrap_or(DEFAULT_CIRCLE_SEGMENTS);
+        ctx.borrow_mut().target.draw_circle(x, y, radius, segments, parse_color(color)?)
+            .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    cacao.set("renderer", renderer)?;
+    Ok(())
+}
+
+/// Colors are `{r, g, b}` or `{r, g, b, a}` in 0..1; nil means white.
+pub(super) fn parse_color(color: Option<Vec<f32>>) -> mlua::Result<[f32; 4]> {
+    match color.as_deref() {
+        None => Ok([1.0, 1.0, 1.0, 1.0]),
+        Some(&[r, g, b]) => Ok([r, g, b, 1.0]),
+        Some(&[r, g, b, a]) => Ok([r, g, b, a]),
+        Some(other) => Err(mlua::Error::RuntimeError(format!(
+            "Color needs 3 or 4 components, got {}", other.len()
+        ))),
+    }
+}
+
+/// Insets are one number for every side or `{left = .., right = .., top = .., bottom = ..}`.
+fn parse_insets(insets: Value) -> mlua::Result<NinePatchInsets> {
+    match insets {
+        Value::Integer(inset) => Ok(NinePatchInsets::uniform(inset as f32)),
+        Value::Number(inset) => Ok(NinePatchInsets::uniform(inset as f32)),
+        Value::Table(sides) => Ok(NinePatchInsets {
+            left: sides.get::<_, Option<f32>>("left")?.unwrap_or(0.0),
+            right: sides.get::<_, Option<f32>>("right")?.unwrap_or(0.0),
+            top: sides.get::<_, Option<f32>>("top")?.unwrap_or(0.0),
+            bottom: sides.get::<_, Option<f32>>("bottom")?.unwrap_or(0.0),
+        }),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "Nine-patch insets must be a number or a table, got {}", other.type_name()
+        ))),
+    }
+}
+
+fn parse_filter(filter: &str) -> mlua::Result<FilterMode> {
+    match filter {
+        "nearest" => Ok(FilterMode::Nearest),
+        "linear" => Ok(FilterMode::Linear),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "Unknown sprite filter: {} (expected nearest or linear)", other
+        ))),
+    }
+}
+
+fn parse_align(align: &str) -> mlua::Result<TextAlign> {
+    match align {
+        "left" => Ok(TextAlign::Left),
+        "center" => Ok(TextAlign::Center),
+        "right" => Ok(TextAlign::Right),
+        other => Err(mlua::Error::RuntimeError(format!(
+            "Unknown text alignment: {} (expected left, center or right)", other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filter_names() {
+        assert_eq!(parse_filter("nearest").unwrap(), FilterMode::Nearest);
+        assert_eq!(parse_filter("linear").unwrap(), FilterMode::Linear);
+        assert!(parse_filter("blurry").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// src/game/api/saves.rs
+use std::cell::RefCell;
+use std::collections::HashMap;
+use mlua::{Function, Lua, RegistryKey, Scope, Table, Value};
+use crate::saves::{SaveMigrations, SaveValue};
+use super::EngineContext;
+
+/// Migrations the main script registered with `cacao.saves.register_migration`, stored
+/// as Lua app data until the game's save is loaded.
+#[derive(Default)]
+pub struct MigrationList(Vec<(u32, u32, RegistryKey)>);
+
+pub fn bind<'lua, 'scope>(
+    lua: &'lua Lua,
+    scope: &Scope<'lua, 'scope>,
+    cacao: &Table<'lua>,
+    ctx: &'scope RefCell<EngineContext<'_>>,
+) -> mlua::Result<()> {
+    let saves = lua.create_table()?;
+
+    saves.set("write", scope.create_function_mut(move |_, (key, value): (String, Value)| {
+        let mut ctx = ctx.borrow_mut();
+        if let Value::Nil = value {
+            ctx.saves.remove(&key);
+            return Ok(());
+        }
+        let value = lua_to_save_value(value)?;
+        ctx.saves.write(key, value).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    saves.set("read", scope.create_function(move |lua, key: String| {
+        match ctx.borrow().saves.read(&key) {
+            Some(value) => save_value_to_lua(lua, value),
+            None => Ok(Value::Nil),
+        }
+    })?)?;
+
+    saves.set("write_path", scope.create_function_mut(move |_, (path, value): (String, Value)| {
+        let value = lua_to_save_value(value)?;
+        ctx.borrow_mut().saves.write_path(&path, value).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+    })?)?;
+
+    saves.set("read_path", scope.create_function(move |lua, path: String| {
+        match ctx.borrow().saves.read_path(&path) {
+            Some(value) => save_value_to_lua(lua, value),
+            None => Ok(Value::Nil),
+        }
+    })?)?;
+
+    // Saves are loaded after the main script's top level runs and before init, so
+    // migrations have to be registered at the top level to apply
+    saves.set("register_migration", lua.create_function(|lua, (from, to, migration): (u32, u32, Function)| {
+        if to <= from {
+            return Err(mlua::Error::RuntimeError(format!(
+                "Save migration from {} to {} has to move to a higher version", from, to
+            )));
+        }
+        let key = lua.create_registry_value(migration)?;
+        match lua.app_data_mut::<MigrationList>() {
+            Some(mut list) => list.0.push((from, to, key)),
+            None => {
+                lua.set_app_data(MigrationList(vec![(from, to, key)]));
+            }
+        }
+        Ok(())
+    })?)?;
+
+    saves.set("save", scope.create_function(move |_, ()| {
+        match ctx.borrow().saves.save_to_disk() {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                log::error!("❌ Failed to save game data: {}", e);
+                Ok(false)
+            }
+        }
+    })?)?;
+
+    cacao.set("saves", saves)?;
+    Ok(())
+}
+
+/// The registered migrations, each run as a call to its Lua function. The function gets
+/// the save as a table and can change it in place or return a replacement.
+pub fn migrations(lua: &Lua) -> mlua::Result<SaveMigrations<'_>> {
+    let mut migrations = SaveMigrations::new();
+    let Some(list) = lua.app_data_ref::<MigrationList>() else {
+        return Ok(migrations);
+    };
+
+    for &(from, to, ref key) in &list.0 {
+        let migration: Function = lua.registry_value(key)?;
+        migrations.register_migration(from, to, move |data| {
+            let table = save_value_to_lua(lua, &SaveValue::Object(std::mem::take(data)))?;
+            let result = match migration.call::<_, Value>(table.clone())? {
+                Value::Nil => table,
+                returned => returned,
+            };
+            match lua_to_save_value(result)? {
+                SaveValue::Object(fields) => *data = fields,
+                other => return Err(mlua::Error::RuntimeError(format!(
+                    "Save migration from {} must leave a table of fields, not {:?}", from, other
+                )).into()),
+            }
+            Ok(())
+        });
+    }
+    Ok(migrations)
+}
+
+pub fn lua_to_save_value(value: Value) -> mlua::Result<SaveValue> {
+    match value {
+        Value::String(s) => Ok(SaveValue::String(s.to_str()?.to_string())),
+        Value::Integer(i) => Ok(SaveValue::Integer(i)),
+        Value::Number(n) => Ok(SaveValue::Float(n)),
+        Value::Boolean(b) => Ok(SaveValue::Boolean(b)),
+        Value::Table(table) => {
+            let len = table.raw_len();
+            let pair_count = table.clone().pairs::<Value, Value>().count();
+
+            if len > 0 && len == pair_count {
+                let mut array = Vec::with_capacity(len);
+                for value in table.sequence_values::<Value>() {
+                    array.push(lua_to_save_value(value?)?);
+                }
+                Ok(SaveValue::Array(array))
+            } else {
+                let mut object = HashMap::new();
+                for pair in table.pairs::<Value, Value>() {
+                    let (key, value) = pair?;
+                    let key = match key {
+                        Value::String(s) => s.to_str()?.to_string(),
+                        Value::Integer(i) => i.to_string(),
+                        other => {
+                            return Err(mlua::Error::RuntimeError(format!(
+                                "Cannot save table key of type {}", other.type_name()
+                            )))
+                        }
+                    };
+                    object.insert(key, lua_to_save_value(value)?);
+                }
+                Ok(SaveValue::Object(object))
+            }
+        }
+        other => Err(mlua::Error::RuntimeError(format!(
+            "Cannot save value of type {}", other.type_name()
+        ))),
+    }
+}
+
+pub fn save_value_to_lua<'lua>(lua: &'lua Lua, value: &SaveValue) -> mlua::Result<Value<'lua>> {
+    match value {
+        SaveValue::String(s) => Ok(Value::String(lua.create_string(s)?)),
+        SaveValue::Integer(i) => Ok(Value::Integer(*i)),
+        SaveValue::Float(f) => Ok(Value::Number(*f)),
+        SaveValue::Boolean(b) => Ok(Value::Boolean(*b)),
+        SaveValue::Array(items) => {
+            let table = lua.create_table()?;
+            for item in items {
+                table.raw_push(save_value_to_lua(lua, item)?)?;
+            }
+            Ok(Value::Table(table))
+        }
+        SaveValue::Object(fields) => {
+            let table = lua.create_table()?;
+            for (key, item) in fields {
+                table.raw_set(key.as_str(), save_value_to_lua(lua, item)?)?;
+            }
+            Ok(Value::Table(table))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(lua: &Lua, chunk: &str) -> mlua::Result<SaveValue> {
+        lua_to_save_value(lua.load(chunk).eval()?)
+    }
+
+    #[test]
+    fn lua_values_convert_to_save_values() {
+        let lua = Lua::new();
+        assert_eq!(convert(&lua, "3").unwrap(), SaveValue::Integer(3));
+        assert_eq!(convert(&lua, "3.5").unwrap(), SaveValue::Float(3.5));
+        assert_eq!(convert(&lua, "'hi'").unwrap(), SaveValue::String("hi".to_string()));
+        assert_eq!(convert(&lua, "{1, 2}").unwrap(), SaveValue::Array(vec![SaveValue::Integer(1), SaveValue::Integer(2)]));
+
+        // A table with holes or string keys is saved as an object
+        let object = convert(&lua, "{[1] = true, [3] = false, name = 'x'}").unwrap();
+        assert_eq!(object, SaveValue::Object(HashMap::from([
+            ("1".to_string(), SaveValue::Boolean(true)),
+            ("3".to_string(), SaveValue::Boolean(false)),
+            ("name".to_string(), SaveValue::String("x".to_string())),
+        ])));
+    }
+
+    #[test]
+    fn unsaveable_values_are_rejected() {
+        let lua = Lua::new();
+        assert!(convert(&lua, "function() end").is_err());
+        assert!(convert(&lua, "{[true] = 1}").is_err());
+        assert!(convert(&lua, "{inner = {print}}").is_err());
+    }
+
+    #[test]
+    fn save_values_round_trip_through_lua() {
+        let lua = Lua::new();
+        let value = SaveValue::Object(HashMap::from([
+            ("items".to_string(), SaveValue::Array(vec![SaveValue::String("key".to_string()), SaveValue::Float(0.5)])),
+            ("level".to_string(), SaveValue::Integer(4)),
+        ]));
+        let back = lua_to_save_value(save_value_to_lua(&lua, &value).unwrap()).unwrap();
+        assert_eq!(back, value);
+    }
+}
@@ -0,0 +1,252 @@
+// src/game/api/schedule.rs
+use std::collections::HashSet;
+use mlua::{Function, Lua, RegistryKey, Table, Thread, ThreadStatus, Value};
+
+struct Timer {
+    handle: u64,
+    remaining: f64,
+    // Repeat period for `cacao.every`; one-shot timers have none
+    interval: Option<f64>,
+    // The function to start, or a coroutine paused in `cacao.wait`
+    callback: RegistryKey,
+}
+
+/// Timers a game has scheduled, advanced once per update. Stored as Lua app data.
+#[derive(Default)]
+pub struct Scheduler {
+    next_handle: u64,
+    timers: Vec<Timer>,
+    // Handles due this tick that haven't run yet, so cancelling one still stops it
+    firing: Vec<u64>,
+}
+
+impl Scheduler {
+    fn add(&mut self, handle: u64, delay: f64, interval: Option<f64>, callback: RegistryKey) {
+        self.timers.push(Timer { handle, remaining: delay, interval, callback });
+    }
+
+    fn next_handle(&mut self) -> u64 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    fn cancel(&mut self, handle: u64) -> bool {
+        let before = self.timers.len() + self.firing.len();
+        self.timers.retain(|timer| timer.handle != handle);
+        self.firing.retain(|&firing| firing != handle);
+        self.timers.len() + self.firing.len() != before
+    }
+}
+
+/// Callbacks run as coroutines, so they can pause themselves with `cacao.wait(seconds)`.
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    lua.set_app_data(Scheduler::default());
+
+    cacao.set("schedule", lua.create_function(|lua, (delay, callback): (f64, Function)| {
+        add_timer(lua, delay, None, callback)
+    })?)?;
+
+    cacao.set("every", lua.create_function(|lua, (interval, callback): (f64, Function)| {
+        if interval <= 0.0 {
+            return Err(mlua::Error::RuntimeError(format!("Interval must be positive, got {}", interval)));
+        }
+        add_timer(lua, interval, Some(interval), callback)
+    })?)?;
+
+    cacao.set("cancel", lua.create_function(|lua, handle: u64| {
+        Ok(lua.app_data_mut::<Scheduler>().is_some_and(|mut scheduler| scheduler.cancel(handle)))
+    })?)?;
+
+    // Rust functions can't yield, so this one is Lua
+    let wait: Function = lua.load("return function(seconds) return coroutine.yield(seconds or 0) end")
+        .set_name("=cacao.wait")
+        .eval()?;
+    cacao.set("wait", wait)?;
+
+    Ok(())
+}
+
+fn add_timer(lua: &Lua, delay: f64, interval: Option<f64>, callback: Function) -> mlua::Result<u64> {
+    let key = lua.create_registry_value(callback)?;
+    let mut scheduler = lua.app_data_mut::<Scheduler>()
+        .ok_or_else(|| mlua::Error::RuntimeError("Scheduler is not available".to_string()))?;
+    let handle = scheduler.next_handle();
+    scheduler.add(handle, delay.max(0.0), interval, key);
+    Ok(handle)
+}
+
+/// Advances every timer by `dt` and runs the ones that came due, each at most once.
+pub fn update_all(lua: &Lua, dt: f32) -> mlua::Result<()> {
+    let due: Vec<(u64, Value)> = {
+        let Some(mut scheduler) = lua.app_data_mut::<Scheduler>() else {
+            return Ok(());
+        };
+
+        // A repeating timer's paused run is queued under the same handle, so cancelling
+        // stops both. The timer skips its turns until that run finishes instead of stacking
+        // up more copies.
+        let paused: HashSet<u64> = scheduler.timers.iter()
+            .filter(|timer| timer.interval.is_none())
+            .map(|timer| timer.handle)
+            .collect();
+
+        let mut due = Vec::new();
+        let mut pending = Vec::new();
+        for mut timer in std::mem::take(&mut scheduler.timers) {
+            timer.remaining -= dt as f64;
+            if timer.remaining > 0.0 {
+                pending.push(timer);
+                continue;
+            }
+
+            let Some(interval) = timer.interval else {
+                due.push((timer.handle, lua.registry_value(&timer.callback)?));
+                continue;
+            };
+            if !paused.contains(&timer.handle) {
+                due.push((timer.handle, lua.registry_value(&timer.callback)?));
+            }
+            timer.remaining += interval;
+            pending.push(timer);
+        }
+        scheduler.timers = pending;
+        scheduler.firing = due.iter().map(|(handle, _)| *handle).collect();
+        due
+    };
+
+    for (handle, callback) in due {
+        let still_due = lua.app_data_mut::<Scheduler>().is_some_and(|mut scheduler| {
+            let before = scheduler.firing.len();
+            scheduler.firing.retain(|&firing| firing != handle);
+            scheduler.firing.len() != before
+        });
+        if !still_due {
+            continue;
+        }
+
+        let thread: Thread = match callback {
+            Value::Function(function) => lua.create_thread(function)?,
+            Value::Thread(thread) => thread,
+            _ => continue,
+        };
+
+        // Whatever a finished callback returns is ignored; a paused one yields its wait time
+        let yielded: Value = thread.resume(())?;
+        if thread.status() == ThreadStatus::Resumable {
+            let wait = match yielded {
+                Value::Number(seconds) => seconds,
+                Value::Integer(seconds) => seconds as f64,
+                _ => 0.0,
+            };
+            let key = lua.create_registry_value(thread)?;
+            if let Some(mut scheduler) = lua.app_data_mut::<Scheduler>() {
+                scheduler.add(handle, wait.max(0.0), None, key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler_lua() -> Lua {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        lua.load("log = {}").exec().unwrap();
+        lua
+    }
+
+    fn run(lua: &Lua, code: &str) -> u64 {
+        lua.load(code).eval().unwrap()
+    }
+
+    fn log(lua: &Lua) -> Vec<String> {
+        lua.load("return log").eval().unwrap()
+    }
+
+    #[test]
+    fn scheduled_callbacks_wait_for_their_delay() {
+        let lua = scheduler_lua();
+        run(&lua, "return cacao.schedule(0.5, function() table.insert(log, 'fired') end)");
+
+        for _ in 0..3 {
+            update_all(&lua, 0.125).unwrap();
+        }
+        assert!(log(&lua).is_empty());
+
+        update_all(&lua, 0.125).unwrap();
+        assert_eq!(log(&lua), ["fired"]);
+
+        update_all(&lua, 1.0).unwrap();
+        assert_eq!(log(&lua), ["fired"]);
+    }
+
+    #[test]
+    fn every_repeats_at_most_once_per_update() {
+        let lua = scheduler_lua();
+        run(&lua, "return cacao.every(0.25, function() table.insert(log, 'tick') end)");
+
+        for _ in 0..8 {
+            update_all(&lua, 0.125).unwrap();
+        }
+        assert_eq!(log(&lua).len(), 4);
+
+        // A long frame still only runs it once
+        update_all(&lua, 1.0).unwrap();
+        assert_eq!(log(&lua).len(), 5);
+    }
+
+    #[test]
+    fn cancelling_stops_a_callback_paused_in_wait() {
+        let lua = scheduler_lua();
+        let handle = run(&lua, r#"
+            return cacao.schedule(0, function()
+                table.insert(log, "before")
+                cacao.wait(1)
+                table.insert(log, "after")
+            end)
+        "#);
+
+        update_all(&lua, 0.0).unwrap();
+        assert_eq!(log(&lua), ["before"]);
+
+        assert!(lua.load(format!("return cacao.cancel({})", handle)).eval::<bool>().unwrap());
+        update_all(&lua, 2.0).unwrap();
+        assert_eq!(log(&lua), ["before"]);
+    }
+
+    #[test]
+    fn repeating_callbacks_that_wait_do_not_pile_up() {
+        let lua = scheduler_lua();
+        let handle = run(&lua, r#"
+            return cacao.every(0.5, function()
+                table.insert(log, "start")
+                cacao.wait(1.25)
+                table.insert(log, "end")
+            end)
+        "#);
+
+        // Starts at 0.5 and is still waiting through the turns at 1.0 and 1.5
+        for _ in 0..3 {
+            update_all(&lua, 0.5).unwrap();
+        }
+        assert_eq!(log(&lua), ["start"]);
+
+        // Finishes at 1.75, then the next turn at 2.0 starts it again
+        update_all(&lua, 0.25).unwrap();
+        assert_eq!(log(&lua), ["start", "end"]);
+        update_all(&lua, 0.25).unwrap();
+        assert_eq!(log(&lua), ["start", "end", "start"]);
+
+        assert!(lua.load(format!("return cacao.cancel({})", handle)).eval::<bool>().unwrap());
+        for _ in 0..4 {
+            update_all(&lua, 0.5).unwrap();
+        }
+        assert_eq!(log(&lua), ["start", "end", "start"]);
+    }
+}
@@ -0,0 +1,67 @@
+// src/game/api/time.rs
+use mlua::{Lua, Table};
+
+/// A game's own clock, started when it loads and advanced once per update.
+/// Stored as Lua app data for `cacao.time`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GameClock {
+    pub elapsed: f64,
+    pub delta: f32,
+    pub frame_count: u64,
+}
+
+impl GameClock {
+    pub fn tick(&mut self, dt: f32) {
+        self.elapsed += dt as f64;
+        self.delta = dt;
+        self.frame_count += 1;
+    }
+}
+
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    let time = lua.create_table()?;
+
+    time.set("elapsed", lua.create_function(|lua, ()| {
+        Ok(with_clock(lua, |clock| clock.elapsed))
+    })?)?;
+
+    time.set("delta", lua.create_function(|lua, ()| {
+        Ok(with_clock(lua, |clock| clock.delta))
+    })?)?;
+
+    time.set("frame_count", lua.create_function(|lua, ()| {
+        Ok(with_clock(lua, |clock| clock.frame_count))
+    })?)?;
+
+    cacao.set("time", time)?;
+    Ok(())
+}
+
+fn with_clock<T: Default>(lua: &Lua, f: impl FnOnce(&GameClock) -> T) -> T {
+    lua.app_data_ref::<GameClock>()
+        .map(|clock| f(&clock))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_read_the_clock_as_of_the_last_tick() {
+        let lua = Lua::new();
+        let cacao = lua.create_table().unwrap();
+        register(&lua, &cacao).unwrap();
+        lua.globals().set("cacao", cacao).unwrap();
+        let read = || lua.load("return cacao.time.elapsed(), cacao.time.delta(), cacao.time.frame_count()").eval::<(f64, f32, u64)>().unwrap();
+
+        // No clock yet reads as zero
+        assert_eq!(read(), (0.0, 0.0, 0));
+
+        let mut clock = GameClock::default();
+        clock.tick(0.25);
+        clock.tick(0.5);
+        lua.set_app_data(clock);
+        assert_eq!(read(), (0.75, 0.5, 2));
+    }
+}
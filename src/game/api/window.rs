@@ -0,0 +1,24 @@
+// src/game/api/window.rs
+use mlua::{Lua, Table};
+
+/// The window's size in physical pixels, kept up to date by the engine.
+/// Stored as Lua app data for `cacao.window`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindowSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn register(lua: &Lua, cacao: &Table) -> mlua::Result<()> {
+    let window = lua.create_table()?;
+
+    window.set("size", lua.create_function(|lua, ()| {
+        let size = lua.app_data_ref::<WindowSize>()
+            .map(|size| *size)
+            .unwrap_or_default();
+        Ok((size.width, size.height))
+    })?)?;
+
+    cacao.set("window", window)?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+// src/game/headless.rs
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::{
+    assets::AssetManager,
+    audio::AudioSystem,
+    errors::CacaoError,
+    input::InputManager,
+    renderer::{Camera, NullRenderer},
+    saves::{SaveManager, SaveValue},
+};
+use super::GameLoader;
+
+/// Fixed step the headless runner feeds to `update`, matching the default logic rate.
+const HEADLESS_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Loads `game_file` without a window or audio device, runs `init` and then `frames`
+/// update/render ticks with no input, and returns the game's save data at the end.
+/// Sprites aren't uploaded, so scripts see them as missing.
+pub async fn run_headless(game_file: &Path, secret_key: &str, saves_dir: PathBuf, frames: u32) -> Result<HashMap<String, SaveValue>, CacaoError> {
+    let games_dir = game_file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let loader = GameLoader::new(games_dir, secret_key.to_string());
+
+    let mut assets = AssetManager::new();
+    let mut game = loader.queue_game(game_file, &mut assets)?;
+    while assets.is_loading() {
+        if let Err(e) = assets.process_loaded_assets_headless() {
+            assets.cancel_loading();
+            return Err(e);
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    assets.process_loaded_assets_headless()?;
+
+    let mut audio = AudioSystem::new_silent();
+    let mut input = InputManager::new();
+    let mut camera = Camera::new(1280.0, 720.0);
+    let mut saves = SaveManager::new(saves_dir);
+    let mut renderer = NullRenderer;
+
+    game.initialize(secret_key.to_string(), &mut audio, &assets, &mut camera, &mut saves)?;
+    for _ in 0..frames {
+        game.update(HEADLESS_STEP, &mut input, &mut audio, &assets, &mut camera, &mut saves)?;
+        input.update();
+        game.render(&mut renderer, &assets, 1.0)?;
+    }
+
+    log::info!("🧪 Ran {} headlessly for {} frames", game.get_info().title, frames);
+    Ok(saves.data().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{packer::GamePacker, AssetType, GameInfo};
+
+    const KEY: &str = "headless key";
+
+    // A tiny game that counts its ticks and stores what it saw in its save data
+    const FIXTURE: &str = r#"
+local ticks = 0
+local elapsed = 0
+
+function init()
+    cacao.saves.write("initialized", true)
+end
+
+function update(dt)
+    ticks = ticks + 1
+    elapsed = elapsed + dt
+    cacao.saves.write("ticks", ticks)
+    cacao.saves.write("elapsed", elapsed)
+end
+
+function render(alpha)
+    cacao.renderer.draw_rect(0, 0, 10, 10, {1, 1, 1, 1})
+end
+"#;
+
+    fn pack(script: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("cacao_headless_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.lua"), script).unwrap();
+
+        let package = dir.join("fixture.gaem");
+        GamePacker::new(&package)
+            .set_info(GameInfo::new("Fixture".to_string(), "Tester".to_string()))
+            .set_secret_key(KEY)
+            .add_asset(dir.join("main.lua"), AssetType::Script)
+            .build()
+            .unwrap();
+        (package, dir.join("saves"))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_a_packed_game_for_the_requested_frames() {
+        let (package, saves_dir) = pack(FIXTURE);
+        let saves = run_headless(&package, KEY, saves_dir, 10).await.unwrap();
+
+        assert_eq!(saves.get("initialized"), Some(&SaveValue::Boolean(true)));
+        assert_eq!(saves.get("ticks"), Some(&SaveValue::Integer(10)));
+        let Some(SaveValue::Float(elapsed)) = saves.get("elapsed") else {
+            panic!("elapsed missing: {:?}", saves);
+        };
+        assert!((elapsed - 10.0 * HEADLESS_STEP.as_secs_f64()).abs() < 1e-6);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn script_errors_are_reported() {
+        let (package, saves_dir) = pack("function update(dt) error('boom') end");
+        let error = run_headless(&package, KEY, saves_dir, 1).await.unwrap_err();
+        assert!(error.to_string().contains("boom"), "unexpected error: {}", error);
+    }
+}
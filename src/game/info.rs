@@ -1,10 +1,25 @@
 // src/game/info.rs
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 use uuid::Uuid;
+use crate::{crypto, errors::CacaoError, renderer::FilterMode};
 
 /// Magic bytes for .gaem files: "GAEM" in ASCII
 pub const GAEM_MAGIC: [u8; 4] = [0x47, 0x41, 0x45, 0x4D];
-pub const GAEM_VERSION: u16 = 1;
+/// Version 2 gzips the JSON header, appends a packed asset section and ends with a signature
+pub const GAEM_VERSION: u16 = 2;
+/// Original layout with a plain JSON header and no bundled assets, still accepted by the loader
+pub const GAEM_VERSION_UNCOMPRESSED: u16 = 1;
+
+/// How a game's `engine_version` compares with the running engine, by semver rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineCompatibility {
+    Compatible,
+    /// Same major version, but built with a newer release than this one
+    Newer,
+    /// A different major version (or minor, before 1.0), or not a version at all
+    Incompatible,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameInfo {
@@ -17,6 +32,9 @@ pub struct GameInfo {
     pub entry_point: String,      // Main script file
     pub required_assets: Vec<AssetInfo>,
     pub engine_version: String,
+    /// How the game's sprites are filtered when scaled
+    #[serde(default)]
+    pub sprite_filter: FilterMode,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,12 +43,17 @@ pub struct AssetInfo {
     pub checksum: String,  // SHA-256 checksum
     pub size: u64,
     pub asset_type: AssetType,
+    /// Packed encrypted with the game's asset key; checksum and size are of the decrypted bytes
+    #[serde(default)]
+    pub encrypted: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AssetType {
     Sprite,
     Audio,
+    /// Long audio that is streamed from disk rather than preloaded
+    Music,
     Script,
     Data,
     Font,
@@ -48,6 +71,7 @@ impl GameInfo {
             entry_point: "main.lua".to_string(),
             required_assets: Vec::new(),
             engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            sprite_filter: FilterMode::default(),
         }
     }
 
@@ -65,4 +89,83 @@ impl GameInfo {
         let computed_hash = format!("{:x}", hasher.finalize());
         computed_hash == self.secret_key_hash
     }
-}
\ No newline at end of file
+
+    /// Signs the bytes of a packaged game with an HMAC keyed by its secret key. Anyone holding
+    /// the key can re-sign an edited package, and the engine's built-in key ships in its source,
+    /// so a signature catches damaged or hand-edited packages, not deliberate tampering.
+    pub fn sign(&self, key: &str, signed: &mut impl Read) -> Result<[u8; crypto::SIGNATURE_LEN], CacaoError> {
+        if !self.verify_secret_key(key) {
+            return Err(CacaoError::CryptoError("Secret key does not match this game".to_string()));
+        }
+        crypto::sign_reader(signed, key.as_bytes())
+    }
+
+    /// Checks `engine_version` against the engine this was compiled into.
+    pub fn engine_compatibility(&self) -> EngineCompatibility {
+        engine_compatibility(&self.engine_version, env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Checks a signature made by `sign`. Needs no parsed header, so it can run before the header is trusted.
+    pub fn verify(key: &str, signed: &mut impl Read, signature: &[u8]) -> Result<bool, CacaoError> {
+        crypto::verify_reader(signed, key.as_bytes(), signature)
+    }
+}
+
+pub fn engine_compatibility(game_version: &str, engine_version: &str) -> EngineCompatibility {
+    let (Some(game), Some(engine)) = (parse_version(game_version), parse_version(engine_version)) else {
+        return EngineCompatibility::Incompatible;
+    };
+
+    // Before 1.0 the minor version is the breaking one
+    if game.0 != engine.0 || (engine.0 == 0 && game.1 != engine.1) {
+        EngineCompatibility::Incompatible
+    } else if game > engine {
+        EngineCompatibility::Newer
+    } else {
+        EngineCompatibility::Compatible
+    }
+}
+
+/// `major.minor.patch`, where missing parts count as 0 and pre-release or build
+/// suffixes are ignored.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EngineCompatibility::*;
+
+    #[test]
+    fn engine_compatibility_matrix() {
+        let cases = [
+            ("1.2.3", "1.2.3", Compatible),
+            ("1.1.0", "1.2.3", Compatible),
+            ("1.2.4", "1.2.3", Newer),
+            ("1.3.0", "1.2.3", Newer),
+            ("2.0.0", "1.2.3", Incompatible),
+            ("0.9.0", "1.2.3", Incompatible),
+            // Before 1.0 the minor version breaks compatibility
+            ("0.1.5", "0.1.2", Newer),
+            ("0.1.0", "0.2.0", Incompatible),
+            ("0.3.0", "0.2.0", Incompatible),
+            // Missing parts count as 0 and suffixes are ignored
+            ("1", "1.0.0", Compatible),
+            ("1.2.3-beta+build", "1.2.3", Compatible),
+            ("banana", "1.2.3", Incompatible),
+            ("1.x", "1.2.3", Incompatible),
+        ];
+        for (game, engine, expected) in cases {
+            assert_eq!(engine_compatibility(game, engine), expected, "game {} on engine {}", game, engine);
+        }
+    }
+}
@@ -1,19 +1,98 @@
 // ============================================================================
 // FILE: src/game/loader.rs - Fixed Compiler Warnings
 // ============================================================================
-use super::{Game, GameInfo, GAEM_MAGIC, GAEM_VERSION};
-use crate::{assets::AssetManager, errors::CacaoError};
+use super::{EngineCompatibility, Game, GameInfo, GAEM_MAGIC, GAEM_VERSION, GAEM_VERSION_UNCOMPRESSED};
+use crate::{assets::AssetManager, crypto::{self, SIGNATURE_LEN}, errors::CacaoError};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
+/// Library artwork, looked up in the container first and then the game folder.
+const BANNER_PATH: &str = "banner.png";
+
+/// What `GameLoader::validate` found. The package passes when there are no problems;
+/// warnings don't stop a game from loading.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub info: Option<GameInfo>,
+    /// Every checked asset with the error it failed on, if any
+    pub assets: Vec<(String, Option<String>)>,
+    pub problems: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty() && self.assets.iter().all(|(_, error)| error.is_none())
+    }
+}
+
 pub struct GameLoader {
     games_dir: PathBuf,
+    secret_key: String,
+    ignore_engine_version: bool,
 }
 
 impl GameLoader {
-    pub fn new(games_dir: PathBuf) -> Self {
-        Self { games_dir }
+    /// `secret_key` checks the signature of every .gaem file this loader opens.
+    pub fn new(games_dir: PathBuf, secret_key: String) -> Self {
+        Self { games_dir, secret_key, ignore_engine_version: false }
+    }
+
+    /// Lets games built for an incompatible engine load anyway, with a warning. For
+    /// developers testing games across engine releases.
+    pub fn set_ignore_engine_version(&mut self, ignore: bool) {
+        self.ignore_engine_version = ignore;
+    }
+
+    /// Reads a game's header and queues its assets for background loading on `assets`.
+    /// The game is ready to initialize once `assets.is_loading()` turns false.
+    pub fn queue_game(&self, game_file: &Path, assets: &mut AssetManager) -> Result<Game, CacaoError> {
+        let (game_info, reader, version) = self.open_gaem_file(game_file)?;
+        self.check_engine_version(&game_info)?;
+        assets.set_sprite_filter(game_info.sprite_filter);
+
+        let game_folder = match AssetArchive::read_index(reader, version)? {
+            Some(archive) => {
+                let asset_key = crypto::derive_asset_key(&self.secret_key);
+                for asset_info in &game_info.required_assets {
+                    let (start, size) = archive.locate(&asset_info.path)?;
+                    let archive_path = game_file.to_path_buf();
+                    let encrypted = asset_info.encrypted;
+                    assets.queue_asset_with(
+                        PathBuf::from(&asset_info.path),
+                        asset_info.asset_type.clone(),
+                        Some(asset_info.checksum.clone()),
+                        move || {
+                            let mut file = BufReader::new(File::open(archive_path)?);
+                            file.seek(SeekFrom::Start(start))?;
+                            let data = read_packed_asset(&mut file, size)?;
+                            if encrypted {
+                                return decrypt_packed_asset(&data, &asset_key);
+                            }
+                            Ok(data)
+                        },
+                    );
+                }
+                self.find_game_folder(&game_info)
+                    .unwrap_or_else(|_| game_file.parent().unwrap_or(&self.games_dir).to_path_buf())
+            }
+            None => {
+                let game_folder = self.find_game_folder(&game_info)?;
+                for asset_info in &game_info.required_assets {
+                    assets.queue_asset(
+                        game_folder.join(&asset_info.path),
+                        asset_info.asset_type.clone(),
+                        Some(asset_info.checksum.clone()),
+                    );
+                }
+                game_folder
+            }
+        };
+
+        Ok(Game::new(game_info, game_folder))
     }
 
     pub async fn load_game(
@@ -23,21 +102,49 @@ impl GameLoader {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> Result<Game, CacaoError> {
-        let game_info = self.parse_gaem_file(game_file)?;
-        let game_folder = self.find_game_folder(&game_info)?;
+        let (game_info, reader, version) = self.open_gaem_file(game_file)?;
+        self.check_engine_version(&game_info)?;
+        assets.set_sprite_filter(game_info.sprite_filter);
 
-        for asset_info in &game_info.required_assets {
-            let asset_path = game_folder.join(&asset_info.path);
-            self.verify_asset(&asset_path, asset_info)?;
-            assets.load_asset(&asset_path, asset_info.asset_type.clone(), device, queue).await?;
-        }
+        // Packed assets are read straight out of the container; older games use the loose folder
+        let game_folder = match AssetArchive::read_index(reader, version)? {
+            Some(mut archive) => {
+                let asset_key = crypto::derive_asset_key(&self.secret_key);
+                for asset_info in &game_info.required_assets {
+                    let mut data = archive.read(&asset_info.path)?;
+                    if asset_info.encrypted {
+                        data = decrypt_packed_asset(&data, &asset_key)?;
+                    }
+                    self.verify_asset_bytes(&data, asset_info)?;
+                    assets.load_asset_bytes(Path::new(&asset_info.path), data, asset_info.asset_type.clone(), device, queue)?;
+                }
+                // A loose copy of the game, if there is one, is still where scripts hot-reload from
+                self.find_game_folder(&game_info)
+                    .unwrap_or_else(|_| game_file.parent().unwrap_or(&self.games_dir).to_path_buf())
+            }
+            None => {
+                let game_folder = self.find_game_folder(&game_info)?;
+                for asset_info in &game_info.required_assets {
+                    let asset_path = game_folder.join(&asset_info.path);
+                    self.verify_asset(&asset_path, asset_info)?;
+                    assets.load_asset(&asset_path, asset_info.asset_type.clone(), device, queue).await?;
+                }
+                game_folder
+            }
+        };
 
         let game = Game::new(game_info, game_folder);
         Ok(game)
     }
 
     fn parse_gaem_file(&self, file_path: &Path) -> Result<GameInfo, CacaoError> {
-        let mut file = File::open(file_path)?;
+        let (game_info, _, _) = self.open_gaem_file(file_path)?;
+        Ok(game_info)
+    }
+
+    /// Reads the header and leaves the reader positioned at the packed asset section.
+    fn open_gaem_file(&self, file_path: &Path) -> Result<(GameInfo, BufReader<File>, u16), CacaoError> {
+        let mut file = BufReader::new(File::open(file_path)?);
 
         let mut magic = [0u8; 4];
         file.read_exact(&mut magic)?;
@@ -48,7 +155,7 @@ impl GameLoader {
         let mut version_bytes = [0u8; 2];
         file.read_exact(&mut version_bytes)?;
         let version = u16::from_le_bytes(version_bytes);
-        if version != GAEM_VERSION {
+        if version != GAEM_VERSION && version != GAEM_VERSION_UNCOMPRESSED {
             return Err(CacaoError::GameLoadError(format!("Unsupported .gaem version: {}", version)));
         }
 
@@ -58,10 +165,127 @@ impl GameLoader {
 
         let mut info_buffer = vec![0u8; header_size];
         file.read_exact(&mut info_buffer)?;
+
+        if version == GAEM_VERSION {
+            // Check the signature before trusting anything in the header
+            self.verify_signature(&mut file, file_path)?;
+
+            let mut decompressed = Vec::new();
+            GzDecoder::new(info_buffer.as_slice())
+                .read_to_end(&mut decompressed)
+                .map_err(|e| CacaoError::GameLoadError(format!("Failed to decompress game info: {}", e)))?;
+            info_buffer = decompressed;
+        } else {
+            log::warn!("⚠️ {} uses the unsigned v1 format", file_path.display());
+        }
+
         let game_info: GameInfo = serde_json::from_slice(&info_buffer)
             .map_err(|e| CacaoError::GameLoadError(format!("Failed to parse game info: {}", e)))?;
 
-        Ok(game_info)
+        Ok((game_info, file, version))
+    }
+
+    /// Checks the trailing signature over everything before it, then rewinds to the asset section.
+    fn verify_signature(&self, file: &mut BufReader<File>, file_path: &Path) -> Result<(), CacaoError> {
+        let asset_section = file.stream_position()?;
+        let file_len = file.get_ref().metadata()?.len();
+        let signed_len = file_len.checked_sub(SIGNATURE_LEN as u64)
+            .filter(|&len| len >= asset_section)
+            .ok_or_else(|| CacaoError::GameLoadError(format!("Missing signature: {}", file_path.display())))?;
+
+        let mut signature = [0u8; SIGNATURE_LEN];
+        file.seek(SeekFrom::Start(signed_len))?;
+        file.read_exact(&mut signature)?;
+
+        file.seek(SeekFrom::Start(0))?;
+        let verified = GameInfo::verify(&self.secret_key, &mut file.by_ref().take(signed_len), &signature)?;
+        if !verified {
+            return Err(CacaoError::GameLoadError(format!(
+                "Signature verification failed: {}",
+                file_path.display()
+            )));
+        }
+
+        file.seek(SeekFrom::Start(asset_section))?;
+        Ok(())
+    }
+
+    /// Checks a package the way loading it would, without loading anything: header, magic,
+    /// version, signature, engine version and every asset checksum. Unlike loading, it keeps
+    /// going after a bad asset so the report lists all of them.
+    pub fn validate(&self, file_path: &Path) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let (game_info, reader, version) = match self.open_gaem_file(file_path) {
+            Ok(opened) => opened,
+            Err(e) => {
+                report.problems.push(e.to_string());
+                return report;
+            }
+        };
+
+        let engine_version = env!("CARGO_PKG_VERSION");
+        match game_info.engine_compatibility() {
+            EngineCompatibility::Compatible => {}
+            EngineCompatibility::Newer => report.warnings.push(format!(
+                "Built with Cacao {}, newer than this engine ({})", game_info.engine_version, engine_version
+            )),
+            EngineCompatibility::Incompatible => report.problems.push(format!(
+                "Needs Cacao engine version {}, but this is {}", game_info.engine_version, engine_version
+            )),
+        }
+        if version == GAEM_VERSION_UNCOMPRESSED {
+            report.warnings.push("Uses the unsigned v1 format".to_string());
+        }
+
+        match AssetArchive::read_index(reader, version) {
+            Ok(Some(mut archive)) => {
+                let asset_key = crypto::derive_asset_key(&self.secret_key);
+                for asset_info in &game_info.required_assets {
+                    let result = archive.read(&asset_info.path).and_then(|data| {
+                        let data = if asset_info.encrypted { decrypt_packed_asset(&data, &asset_key)? } else { data };
+                        self.verify_asset_bytes(&data, asset_info)
+                    });
+                    report.assets.push((asset_info.path.clone(), result.err().map(|e| e.to_string())));
+                }
+            }
+            Ok(None) => match self.find_game_folder(&game_info) {
+                Ok(game_folder) => {
+                    for asset_info in &game_info.required_assets {
+                        let result = self.verify_asset(&game_folder.join(&asset_info.path), asset_info);
+                        report.assets.push((asset_info.path.clone(), result.err().map(|e| e.to_string())));
+                    }
+                }
+                Err(e) if !game_info.required_assets.is_empty() => report.problems.push(e.to_string()),
+                Err(_) => {}
+            },
+            Err(e) => report.problems.push(e.to_string()),
+        }
+
+        report.info = Some(game_info);
+        report
+    }
+
+    fn check_engine_version(&self, game_info: &GameInfo) -> Result<(), CacaoError> {
+        let engine_version = env!("CARGO_PKG_VERSION");
+        match game_info.engine_compatibility() {
+            EngineCompatibility::Compatible => {}
+            EngineCompatibility::Newer => log::warn!(
+                "⚠️ {} was built with Cacao {}, newer than this engine ({}); some features may be missing",
+                game_info.title, game_info.engine_version, engine_version
+            ),
+            EngineCompatibility::Incompatible if self.ignore_engine_version => log::warn!(
+                "⚠️ {} needs Cacao {}, not {}; loading anyway",
+                game_info.title, game_info.engine_version, engine_version
+            ),
+            EngineCompatibility::Incompatible => {
+                return Err(CacaoError::GameLoadError(format!(
+                    "{} needs Cacao engine version {}, but this is {}",
+                    game_info.title, game_info.engine_version, engine_version
+                )));
+            }
+        }
+        Ok(())
     }
 
     fn find_game_folder(&self, game_info: &GameInfo) -> Result<PathBuf, CacaoError> {
@@ -96,6 +320,20 @@ impl GameLoader {
         Ok(())
     }
 
+    fn verify_asset_bytes(&self, data: &[u8], asset_info: &crate::game::AssetInfo) -> Result<(), CacaoError> {
+        use sha2::{Digest, Sha256};
+
+        let computed_checksum = format!("{:x}", Sha256::digest(data));
+        if computed_checksum != asset_info.checksum {
+            return Err(CacaoError::GameLoadError(format!(
+                "Asset checksum mismatch: {}",
+                asset_info.path
+            )));
+        }
+
+        Ok(())
+    }
+
     pub fn discover_games(&self) -> Result<Vec<PathBuf>, CacaoError> {
         let mut games = Vec::new();
 
@@ -118,6 +356,143 @@ impl GameLoader {
     pub fn parse_gaem_file_engine(&self, file_path: &Path) -> Result<GameInfo, CacaoError> {
         self.parse_gaem_file(file_path)
     }
+
+    /// Header plus the raw bytes of the game's banner image, if it has one. Only opens
+    /// the file once, which matters for the library scan.
+    pub fn read_library_info(&self, file_path: &Path) -> Result<(GameInfo, Option<Vec<u8>>), CacaoError> {
+        let (game_info, reader, version) = self.open_gaem_file(file_path)?;
+
+        if let Some(mut archive) = AssetArchive::read_index(reader, version)? {
+            if archive.entries.contains_key(BANNER_PATH) {
+                let mut data = archive.read(BANNER_PATH)?;
+                let encrypted = game_info.required_assets.iter().any(|asset| asset.path == BANNER_PATH && asset.encrypted);
+                if encrypted {
+                    data = decrypt_packed_asset(&data, &crypto::derive_asset_key(&self.secret_key))?;
+                }
+                return Ok((game_info, Some(data)));
+            }
+        }
+
+        let banner = match self.find_game_folder(&game_info) {
+            Ok(folder) if folder.join(BANNER_PATH).is_file() => Some(std::fs::read(folder.join(BANNER_PATH))?),
+            _ => None,
+        };
+        Ok((game_info, banner))
+    }
+}
+
+/// Writes a version 2 .gaem file: gzipped JSON header, the packed assets, then an
+/// HMAC-SHA256 signature of all of it keyed by the game's secret key.
+/// Asset paths are relative to the game folder, matching `AssetInfo::path`.
+pub fn write_gaem_file(file_path: &Path, game_info: &GameInfo, assets: &[(String, Vec<u8>)], secret_key: &str) -> Result<(), CacaoError> {
+    let info_json = serde_json::to_vec(game_info)
+        .map_err(|e| CacaoError::GameLoadError(format!("Failed to serialize game info: {}", e)))?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&info_json)?;
+    let header = encoder.finish()?;
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&GAEM_MAGIC);
+    bytes.extend_from_slice(&GAEM_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&header);
+
+    // Offset table: count, then (path length, path, offset, size) per asset.
+    // Offsets are relative to the first byte after the table.
+    bytes.extend_from_slice(&(assets.len() as u32).to_le_bytes());
+    let mut offset = 0u64;
+    for (path, data) in assets {
+        bytes.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        offset += data.len() as u64;
+    }
+
+    for (_, data) in assets {
+        bytes.extend_from_slice(data);
+    }
+
+    let signature = game_info.sign(secret_key, &mut bytes.as_slice())?;
+    bytes.extend_from_slice(&signature);
+
+    std::fs::write(file_path, bytes)?;
+    Ok(())
+}
+
+/// Offset table of the assets packed after a v2 header. Bytes are only read when asked for.
+struct AssetArchive {
+    reader: BufReader<File>,
+    data_start: u64,
+    entries: HashMap<String, (u64, u64)>,
+}
+
+impl AssetArchive {
+    /// Returns `None` for v1 files and for v2 files that don't pack any assets.
+    fn read_index(mut reader: BufReader<File>, version: u16) -> Result<Option<Self>, CacaoError> {
+        if version == GAEM_VERSION_UNCOMPRESSED {
+            return Ok(None);
+        }
+
+        let mut count_bytes = [0u8; 4];
+        reader.read_exact(&mut count_bytes)?;
+        let count = u32::from_le_bytes(count_bytes);
+        if count == 0 {
+            return Ok(None);
+        }
+
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let mut path_len_bytes = [0u8; 4];
+            reader.read_exact(&mut path_len_bytes)?;
+            let path_len = u32::from_le_bytes(path_len_bytes) as usize;
+
+            let mut path_bytes = vec![0u8; path_len];
+            reader.read_exact(&mut path_bytes)?;
+            let path = String::from_utf8(path_bytes)
+                .map_err(|_| CacaoError::GameLoadError("Packed asset path is not valid UTF-8".to_string()))?;
+
+            let mut offset_bytes = [0u8; 8];
+            reader.read_exact(&mut offset_bytes)?;
+            let mut size_bytes = [0u8; 8];
+            reader.read_exact(&mut size_bytes)?;
+
+            entries.insert(path, (u64::from_le_bytes(offset_bytes), u64::from_le_bytes(size_bytes)));
+        }
+
+        let data_start = reader.stream_position()?;
+        Ok(Some(Self { reader, data_start, entries }))
+    }
+
+    fn read(&mut self, path: &str) -> Result<Vec<u8>, CacaoError> {
+        let (start, size) = self.locate(path)?;
+        self.reader.seek(SeekFrom::Start(start))?;
+        read_packed_asset(&mut self.reader, size)
+            .map_err(|_| CacaoError::GameLoadError(format!("Packed asset is truncated: {}", path)))
+    }
+
+    /// Absolute file offset and size of a packed asset.
+    fn locate(&self, path: &str) -> Result<(u64, u64), CacaoError> {
+        let &(offset, size) = self.entries.get(path)
+            .ok_or_else(|| CacaoError::GameLoadError(format!("Asset not found in archive: {}", path)))?;
+        Ok((self.data_start + offset, size))
+    }
+}
+
+fn read_packed_asset(reader: &mut impl Read, size: u64) -> std::io::Result<Vec<u8>> {
+    // Read through `take` so a corrupt size can't force a huge allocation up front
+    let mut data = Vec::new();
+    reader.take(size).read_to_end(&mut data)?;
+    if data.len() as u64 != size {
+        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "packed asset is truncated"));
+    }
+    Ok(data)
+}
+
+fn decrypt_packed_asset(data: &[u8], key: &[u8; 32]) -> std::io::Result<Vec<u8>> {
+    crypto::decrypt_data(data, key)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
 }
 
 fn sanitize_filename(filename: &str) -> String {
@@ -130,3 +505,261 @@ fn sanitize_filename(filename: &str) -> String {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{packer::GamePacker, AssetType};
+
+    // magic (4) + version (2) + header size (4)
+    const HEADER_START: usize = 10;
+
+    /// Packs a one-script game into a fresh temp directory and returns the package path.
+    fn pack_game(key: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cacao_loader_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.lua"), "function update(dt) end").unwrap();
+
+        let package = dir.join("game.gaem");
+        GamePacker::new(&package)
+            .set_info(GameInfo::new("Test".to_string(), "Tester".to_string()))
+            .set_secret_key(key)
+            .add_asset(dir.join("main.lua"), AssetType::Script)
+            .build()
+            .unwrap();
+        package
+    }
+
+    fn loader_for(package: &Path, key: &str) -> GameLoader {
+        GameLoader::new(package.parent().unwrap().to_path_buf(), key.to_string())
+    }
+
+    fn flip_byte(path: &Path, offset: usize) {
+        let mut bytes = std::fs::read(path).unwrap();
+        bytes[offset] ^= 0x01;
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn signed_package_opens() {
+        let package = pack_game("key");
+        let info = loader_for(&package, "key").parse_gaem_file(&package).unwrap();
+        assert_eq!(info.title, "Test");
+    }
+
+    #[test]
+    fn tampered_header_byte_fails_verification() {
+        let package = pack_game("key");
+        flip_byte(&package, HEADER_START + 4);
+
+        let error = loader_for(&package, "key").parse_gaem_file(&package).unwrap_err();
+        assert!(
+            matches!(&error, CacaoError::GameLoadError(message) if message.starts_with("Signature verification failed")),
+            "unexpected error: {:?}", error
+        );
+    }
+
+    #[test]
+    fn package_signed_with_another_key_fails_verification() {
+        let package = pack_game("other key");
+        assert!(loader_for(&package, "key").parse_gaem_file(&package).is_err());
+    }
+
+    #[test]
+    fn validating_a_good_package_passes() {
+        let package = pack_game("key");
+        let report = loader_for(&package, "key").validate(&package);
+
+        assert!(report.passed(), "unexpected problems: {:?} {:?}", report.problems, report.assets);
+        assert_eq!(report.assets, vec![("main.lua".to_string(), None)]);
+        assert_eq!(report.info.map(|info| info.title), Some("Test".to_string()));
+    }
+
+    #[test]
+    fn validating_a_tampered_package_fails() {
+        let package = pack_game("key");
+        flip_byte(&package, HEADER_START + 4);
+        let report = loader_for(&package, "key").validate(&package);
+
+        assert!(!report.passed());
+        assert!(report.problems.iter().any(|problem| problem.contains("Signature verification failed")), "{:?}", report.problems);
+    }
+
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn encrypted_assets_load_decrypted() {
+        const SECRET: &str = r#"{"answer": 42}"#;
+        let dir = std::env::temp_dir().join(format!("cacao_loader_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.lua"), "function update(dt) end").unwrap();
+        std::fs::write(dir.join("secret.json"), SECRET).unwrap();
+
+        let package = dir.join("game.gaem");
+        GamePacker::new(&package)
+            .set_info(GameInfo::new("Test".to_string(), "Tester".to_string()))
+            .set_secret_key("key")
+            .add_asset(dir.join("main.lua"), AssetType::Script)
+            .add_encrypted_asset(dir.join("secret.json"), AssetType::Data)
+            .build()
+            .unwrap();
+        std::fs::remove_file(dir.join("secret.json")).unwrap();
+        let packed = std::fs::read(&package).unwrap();
+        assert!(!packed.windows(SECRET.len()).any(|window| window == SECRET.as_bytes()));
+
+        let mut assets = AssetManager::new();
+        loader_for(&package, "key").queue_game(&package, &mut assets).unwrap();
+        while assets.is_loading() {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        assets.process_loaded_assets_headless().unwrap();
+
+        assert_eq!(assets.get_data_file("secret.json").map(Vec::as_slice), Some(SECRET.as_bytes()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+
+    fn read_u32(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn read_u64(bytes: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn v2_packages_gzip_the_header_and_pack_assets_after_it() {
+        let package = pack_game("key");
+        let bytes = std::fs::read(&package).unwrap();
+
+        assert_eq!(bytes[..4], GAEM_MAGIC);
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), GAEM_VERSION);
+        let header_end = HEADER_START + read_u32(&bytes, 6) as usize;
+        let mut header = Vec::new();
+        GzDecoder::new(&bytes[HEADER_START..header_end]).read_to_end(&mut header).unwrap();
+        let info: GameInfo = serde_json::from_slice(&header).unwrap();
+        assert_eq!(info.title, "Test");
+
+        // One entry: path length, path, offset, size; then the data and the signature
+        let script = b"function update(dt) end";
+        assert_eq!(read_u32(&bytes, header_end), 1);
+        assert_eq!(read_u32(&bytes, header_end + 4), 8);
+        assert_eq!(&bytes[header_end + 8..header_end + 16], b"main.lua");
+        assert_eq!((read_u64(&bytes, header_end + 16), read_u64(&bytes, header_end + 24)), (0, script.len() as u64));
+        let data_start = header_end + 32;
+        assert_eq!(&bytes[data_start..data_start + script.len()], script);
+        assert_eq!(bytes.len(), data_start + script.len() + SIGNATURE_LEN);
+    }
+
+    #[test]
+    fn packed_assets_are_read_by_name() {
+        let dir = std::env::temp_dir().join(format!("cacao_loader_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.lua"), "function update(dt) end").unwrap();
+        std::fs::write(dir.join("level.json"), r#"{"width": 3}"#).unwrap();
+        let package = dir.join("game.gaem");
+        GamePacker::new(&package)
+            .set_info(GameInfo::new("Test".to_string(), "Tester".to_string()))
+            .set_secret_key("key")
+            .add_asset(dir.join("main.lua"), AssetType::Script)
+            .add_asset(dir.join("level.json"), AssetType::Data)
+            .build()
+            .unwrap();
+
+        let (_, reader, version) = loader_for(&package, "key").open_gaem_file(&package).unwrap();
+        let mut archive = AssetArchive::read_index(reader, version).unwrap().unwrap();
+        assert_eq!(archive.read("level.json").unwrap(), br#"{"width": 3}"#);
+        assert_eq!(archive.read("main.lua").unwrap(), b"function update(dt) end");
+        assert!(archive.read("missing.png").is_err());
+
+        // A size running past the end of the data is an error, not a short read
+        let error = read_packed_asset(&mut b"abc".as_slice(), 4).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // An unsigned v1 package whose assets live in a folder named after the game
+    fn v1_game(games_dir: &Path) -> PathBuf {
+        let script = b"function update(dt) end";
+        let game_folder = games_dir.join("Old_Game");
+        std::fs::create_dir_all(&game_folder).unwrap();
+        std::fs::write(game_folder.join("main.lua"), script).unwrap();
+
+        let mut info = GameInfo::new("Old Game".to_string(), "Tester".to_string());
+        info.required_assets.push(crate::game::AssetInfo {
+            path: "main.lua".to_string(),
+            checksum: crypto::hash_data(script),
+            size: script.len() as u64,
+            asset_type: AssetType::Script,
+            encrypted: false,
+        });
+        let header = serde_json::to_vec(&info).unwrap();
+
+        let mut bytes = GAEM_MAGIC.to_vec();
+        bytes.extend_from_slice(&GAEM_VERSION_UNCOMPRESSED.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        let package = games_dir.join("old.gaem");
+        std::fs::write(&package, bytes).unwrap();
+        package
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn v1_packages_still_load_from_their_folder() {
+        let games_dir = std::env::temp_dir().join(format!("cacao_loader_{}", uuid::Uuid::new_v4()));
+        let package = v1_game(&games_dir);
+        let loader = GameLoader::new(games_dir.clone(), "key".to_string());
+
+        let report = loader.validate(&package);
+        assert!(report.passed(), "{:?}", report);
+        assert_eq!(report.warnings, vec!["Uses the unsigned v1 format".to_string()]);
+
+        let mut assets = AssetManager::new();
+        loader.queue_game(&package, &mut assets).unwrap();
+        while assets.is_loading() {
+            assets.process_loaded_assets_headless().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        assets.process_loaded_assets_headless().unwrap();
+        assert_eq!(assets.get_script("main.lua").map(String::as_str), Some("function update(dt) end"));
+
+        std::fs::remove_dir_all(&games_dir).unwrap();
+    }
+
+
+    #[test]
+    fn library_scans_read_the_banner_without_loading_the_game() {
+        let dir = std::env::temp_dir().join(format!("cacao_loader_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.lua"), "function update(dt) end").unwrap();
+        std::fs::write(dir.join(BANNER_PATH), b"banner bytes").unwrap();
+
+        let pack = |name: &str, banner: Option<bool>| {
+            let package = dir.join(name);
+            let mut packer = GamePacker::new(&package)
+                .set_info(GameInfo::new(name.to_string(), "Tester".to_string()))
+                .set_secret_key("key")
+                .add_asset(dir.join("main.lua"), AssetType::Script);
+            packer = match banner {
+                Some(true) => packer.add_encrypted_asset(dir.join(BANNER_PATH), AssetType::Sprite),
+                Some(false) => packer.add_asset(dir.join(BANNER_PATH), AssetType::Sprite),
+                None => packer,
+            };
+            packer.build().unwrap();
+            package
+        };
+        let loader = GameLoader::new(dir.clone(), "key".to_string());
+
+        for encrypted in [false, true] {
+            let (info, banner) = loader.read_library_info(&pack(&format!("banner_{}.gaem", encrypted), Some(encrypted))).unwrap();
+            assert_eq!(info.title, format!("banner_{}.gaem", encrypted));
+            assert_eq!(banner.as_deref(), Some(b"banner bytes".as_slice()));
+        }
+        assert_eq!(loader.read_library_info(&pack("plain.gaem", None)).unwrap().1, None);
+
+        // Broken files are reported rather than skipped, for the Library Issues screen
+        std::fs::write(dir.join("broken.gaem"), b"not a game").unwrap();
+        assert!(loader.read_library_info(&dir.join("broken.gaem")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,9 +2,17 @@
 // FILE: src/game/mod.rs - Module Exports
 // ============================================================================
 pub mod loader;
+pub mod packer;
 pub mod info;
 pub mod runtime;
+pub mod api;
+pub mod headless;
+pub mod physics;
+pub mod sandbox;
 
-pub use loader::GameLoader;
-pub use info::{GameInfo, AssetInfo, AssetType, GAEM_MAGIC, GAEM_VERSION};
-pub use runtime::Game;
\ No newline at end of file
+pub use loader::{GameLoader, ValidationReport};
+pub use info::{GameInfo, AssetInfo, AssetType, EngineCompatibility, GAEM_MAGIC, GAEM_VERSION, GAEM_VERSION_UNCOMPRESSED};
+pub use runtime::Game;
+pub use api::events::GameEvent;
+pub use headless::run_headless;
+pub use sandbox::ScriptSandbox;
\ No newline at end of file
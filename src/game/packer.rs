@@ -0,0 +1,204 @@
+// src/game/packer.rs
+use super::{loader::write_gaem_file, AssetInfo, AssetType, GameInfo};
+use crate::{assets::determine_asset_type, crypto, errors::CacaoError};
+use std::path::{Path, PathBuf};
+
+/// Builds a signed .gaem file from a `GameInfo` and the asset files on disk.
+pub struct GamePacker {
+    output: PathBuf,
+    info: Option<GameInfo>,
+    secret_key: String,
+    // (path, type, encrypted)
+    assets: Vec<(PathBuf, AssetType, bool)>,
+}
+
+impl GamePacker {
+    pub fn new(output: impl Into<PathBuf>) -> Self {
+        Self {
+            output: output.into(),
+            info: None,
+            secret_key: String::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    /// Assets are packed under their file name, which is also the name scripts load them by.
+    pub fn add_asset(mut self, path: impl Into<PathBuf>, asset_type: AssetType) -> Self {
+        self.assets.push((path.into(), asset_type, false));
+        self
+    }
+
+    /// Adds every file under `dir` whose extension is a known asset type, in name order.
+    /// Other files are left out.
+    pub fn add_directory(mut self, dir: &Path) -> Result<Self, CacaoError> {
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry.map_err(|e| CacaoError::GameLoadError(format!("Failed to read {}: {}", dir.display(), e)))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if let Some(asset_type) = determine_asset_type(entry.path()) {
+                files.push((entry.into_path(), asset_type));
+            }
+        }
+
+        for (path, asset_type) in files {
+            self = self.add_asset(path, asset_type);
+        }
+        Ok(self)
+    }
+
+    /// Like `add_asset`, but the asset is stored encrypted and decrypted again on load.
+    pub fn add_encrypted_asset(mut self, path: impl Into<PathBuf>, asset_type: AssetType) -> Self {
+        self.assets.push((path.into(), asset_type, true));
+        self
+    }
+
+    pub fn set_info(mut self, info: GameInfo) -> Self {
+        self.info = Some(info);
+        self
+    }
+
+    /// Key the game is unlocked and signed with.
+    pub fn set_secret_key(mut self, key: &str) -> Self {
+        self.secret_key = key.to_string();
+        self
+    }
+
+    /// Reads every asset, fills in checksums and sizes, and writes the .gaem file.
+    pub fn build(self) -> Result<GameInfo, CacaoError> {
+        let mut info = self.info
+            .ok_or_else(|| CacaoError::GameLoadError("No game info set on packer".to_string()))?;
+        if self.secret_key.is_empty() {
+            return Err(CacaoError::GameLoadError("No secret key set on packer".to_string()));
+        }
+        info.set_secret_key(&self.secret_key);
+
+        let asset_key = crypto::derive_asset_key(&self.secret_key);
+        let mut packed = Vec::with_capacity(self.assets.len());
+        info.required_assets.clear();
+        for (path, asset_type, encrypted) in self.assets {
+            let name = asset_name(&path)?;
+            if packed.iter().any(|(packed_name, _)| *packed_name == name) {
+                return Err(CacaoError::GameLoadError(format!("Duplicate asset name: {}", name)));
+            }
+
+            let data = std::fs::read(&path)?;
+            info.required_assets.push(AssetInfo {
+                path: name.clone(),
+                checksum: crypto::hash_data(&data),
+                size: data.len() as u64,
+                asset_type,
+                encrypted,
+            });
+            let data = if encrypted { crypto::encrypt_data(&data, &asset_key)? } else { data };
+            packed.push((name, data));
+        }
+
+        if !info.required_assets.iter().any(|asset| asset.path == info.entry_point) {
+            return Err(CacaoError::GameLoadError(format!("Entry point {} is not packed", info.entry_point)));
+        }
+
+        if let Some(parent) = self.output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_gaem_file(&self.output, &info, &packed, &self.secret_key)?;
+
+        log::info!("📦 Packed {} ({} assets)", self.output.display(), packed.len());
+        Ok(info)
+    }
+}
+
+fn asset_name(path: &Path) -> Result<String, CacaoError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| CacaoError::GameLoadError(format!("Invalid asset path: {}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameLoader;
+
+    const KEY: &str = "packer key";
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cacao_packer_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn info() -> GameInfo {
+        GameInfo::new("Packed".to_string(), "Packer".to_string())
+    }
+
+    #[test]
+    fn packed_games_read_back_with_their_assets() {
+        let dir = temp_dir();
+        std::fs::write(dir.join("main.lua"), "function update(dt) end").unwrap();
+        std::fs::write(dir.join("level.json"), r#"{"width": 3}"#).unwrap();
+
+        let package = dir.join("packed.gaem");
+        let built = GamePacker::new(&package)
+            .set_info(info())
+            .set_secret_key(KEY)
+            .add_asset(dir.join("main.lua"), AssetType::Script)
+            .add_asset(dir.join("level.json"), AssetType::Data)
+            .build()
+            .unwrap();
+        assert!(built.verify_secret_key(KEY));
+
+        let report = GameLoader::new(dir.clone(), KEY.to_string()).validate(&package);
+        assert!(report.passed(), "{:?}", report);
+        let read = report.info.unwrap();
+        assert_eq!((read.title.as_str(), read.id), ("Packed", built.id));
+        let assets: Vec<(&str, &str)> = read.required_assets.iter().map(|asset| (asset.path.as_str(), asset.checksum.as_str())).collect();
+        assert_eq!(assets, [
+            ("main.lua", crypto::hash_data(b"function update(dt) end").as_str()),
+            ("level.json", crypto::hash_data(br#"{"width": 3}"#).as_str()),
+        ]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directories_add_every_known_asset() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("main.lua"), "").unwrap();
+        std::fs::write(dir.join("data/enemies.csv"), "name,hp").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an asset").unwrap();
+
+        let built = GamePacker::new(dir.join("out/game.gaem"))
+            .set_info(info())
+            .set_secret_key(KEY)
+            .add_directory(&dir)
+            .unwrap()
+            .build()
+            .unwrap();
+        let names: Vec<&str> = built.required_assets.iter().map(|asset| asset.path.as_str()).collect();
+        assert_eq!(names, ["enemies.csv", "main.lua"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn packing_needs_the_entry_point_and_unique_names() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join("a/main.lua"), "").unwrap();
+        std::fs::write(dir.join("b/main.lua"), "").unwrap();
+        std::fs::write(dir.join("b/level.json"), "{}").unwrap();
+
+        let packer = || GamePacker::new(dir.join("game.gaem")).set_info(info()).set_secret_key(KEY);
+        let missing_entry = packer().add_asset(dir.join("b/level.json"), AssetType::Data).build();
+        assert!(matches!(missing_entry, Err(CacaoError::GameLoadError(message)) if message.contains("Entry point main.lua")));
+
+        let duplicate = packer().add_directory(&dir).unwrap().build();
+        assert!(matches!(duplicate, Err(CacaoError::GameLoadError(message)) if message.contains("Duplicate asset name: main.lua")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+// src/game/physics.rs
+use glam::Vec2;
+
+/// Axis-aligned box. Boxes that only share an edge don't count as overlapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min: min.min(max), max: min.max(max) }
+    }
+
+    /// A box from its top-left corner and size.
+    pub fn from_rect(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self::new(Vec2::new(x, y), Vec2::new(x + width, y + height))
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x && self.max.x > other.min.x
+            && self.min.y < other.max.y && self.max.y > other.min.y
+    }
+
+    /// Points on the edge count as inside.
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// The shortest move that pushes this box out of `other`, or `None` if they don't overlap.
+    pub fn resolve(&self, other: &Aabb) -> Option<Vec2> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let push_left = other.min.x - self.max.x;
+        let push_right = other.max.x - self.min.x;
+        let push_up = other.min.y - self.max.y;
+        let push_down = other.max.y - self.min.y;
+
+        let dx = if push_right < -push_left { push_right } else { push_left };
+        let dy = if push_down < -push_up { push_down } else { push_up };
+
+        if dx.abs() < dy.abs() {
+            Some(Vec2::new(dx, 0.0))
+        } else {
+            Some(Vec2::new(0.0, dy))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_boxes_intersect_but_touching_ones_do_not() {
+        let a = Aabb::from_rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.intersects(&Aabb::from_rect(5.0, 5.0, 10.0, 10.0)));
+        assert!(a.intersects(&Aabb::from_rect(2.0, 2.0, 2.0, 2.0)));
+        assert!(!a.intersects(&Aabb::from_rect(10.0, 0.0, 5.0, 5.0)));
+        assert!(!a.intersects(&Aabb::from_rect(0.0, 20.0, 5.0, 5.0)));
+    }
+
+    #[test]
+    fn negative_sizes_are_normalised() {
+        assert_eq!(Aabb::from_rect(10.0, 10.0, -10.0, -5.0), Aabb::from_rect(0.0, 5.0, 10.0, 5.0));
+    }
+
+    #[test]
+    fn edge_points_are_inside() {
+        let a = Aabb::from_rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.contains_point(Vec2::new(0.0, 10.0)));
+        assert!(a.contains_point(Vec2::new(5.0, 5.0)));
+        assert!(!a.contains_point(Vec2::new(10.1, 5.0)));
+    }
+
+    #[test]
+    fn resolve_pushes_out_along_the_shallowest_axis() {
+        let wall = Aabb::from_rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(Aabb::from_rect(8.0, 2.0, 4.0, 4.0).resolve(&wall), Some(Vec2::new(2.0, 0.0)));
+        assert_eq!(Aabb::from_rect(-3.0, 2.0, 4.0, 4.0).resolve(&wall), Some(Vec2::new(-1.0, 0.0)));
+        assert_eq!(Aabb::from_rect(2.0, -3.0, 4.0, 4.0).resolve(&wall), Some(Vec2::new(0.0, -1.0)));
+        assert_eq!(Aabb::from_rect(20.0, 0.0, 4.0, 4.0).resolve(&wall), None);
+
+        let pushed = Aabb::from_rect(8.0 + 2.0, 2.0, 4.0, 4.0);
+        assert!(!pushed.intersects(&wall));
+    }
+}
@@ -5,13 +5,14 @@ use std::path::PathBuf;
 use std::time::Duration;
 use mlua::{Lua, Function};
 use crate::{
+    assets::AssetManager,
     input::InputManager,
     audio::AudioSystem,
     saves::SaveManager,
-    renderer::Renderer,
+    renderer::{Camera, DrawTarget},
     errors::CacaoError,
 };
-use super::GameInfo;
+use super::{api, GameInfo, ScriptSandbox};
 
 pub struct Game {
     info: GameInfo,
@@ -19,6 +20,8 @@ pub struct Game {
     lua: Lua,
     _secret_key: String,
     initialized: bool,
+    clock: api::time::GameClock,
+    sandbox: ScriptSandbox,
 }
 
 impl Game {
@@ -31,67 +34,437 @@ impl Game {
             lua,
             _secret_key: String::new(),
             initialized: false,
+            clock: api::time::GameClock::default(),
+            sandbox: ScriptSandbox::default(),
         }
     }
 
-    pub fn initialize(&mut self, secret_key: String) -> Result<(), CacaoError> {
+    /// Takes effect when the game is initialized.
+    pub fn set_sandbox(&mut self, sandbox: ScriptSandbox) {
+        self.sandbox = sandbox;
+    }
+
+    pub fn initialize(&mut self, secret_key: String, audio: &mut AudioSystem, assets: &AssetManager, camera: &mut Camera, saves: &mut SaveManager) -> Result<(), CacaoError> {
         if !self.info.verify_secret_key(&secret_key) {
             return Err(CacaoError::GameLoadError("Invalid secret key".to_string()));
         }
         
+        saves.set_game_context(self.info.id.to_string(), &secret_key)?;
+
         self._secret_key = secret_key;
+        self.clock = api::time::GameClock::default();
         self.setup_lua_api()?;
+        self.lua.set_app_data(self.clock);
         
+        // Packed games have no script on disk, so prefer the copy the asset manager loaded
         let main_script_path = self._game_folder.join(&self.info.entry_point);
-        let script_content = std::fs::read_to_string(&main_script_path)?;
+        let script_name = main_script_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let script_content = match assets.get_script(&script_name) {
+            Some(script) => script.clone(),
+            None => std::fs::read_to_string(&main_script_path)?,
+        };
         
-        self.lua.load(&script_content).exec()
-            .map_err(|e| CacaoError::ScriptError(format!("Failed to load main script: {}", e)))?;
-        
-        if let Ok(init_fn) = self.lua.globals().get::<_, Function>("init") {
-            init_fn.call::<_, ()>(())
-                .map_err(|e| CacaoError::ScriptError(format!("Init function failed: {}", e)))?;
-        }
+        self.run_main_chunk(&script_content, api::EngineContext { audio, assets, camera, saves })?;
+        // After the top level, so the script's save migrations are registered
+        let migrations = api::saves::migrations(&self.lua)?;
+        saves.load(&migrations)?;
+        self.call_init(api::EngineContext { audio, assets, camera, saves })?;
         
         self.initialized = true;
         Ok(())
     }
 
-    pub fn update(&mut self, delta_time: Duration, _input: &mut InputManager, _audio: &mut AudioSystem, _saves: &mut SaveManager) {
+    /// Re-runs an edited main script and calls `init` again. Save data is left alone.
+    /// A script that doesn't compile is rejected before anything runs, so the old one keeps going.
+    pub fn reload_script(&mut self, script_content: &str, audio: &mut AudioSystem, assets: &AssetManager, camera: &mut Camera, saves: &mut SaveManager) -> Result<(), CacaoError> {
         if !self.initialized {
-            return;
+            return Err(CacaoError::ScriptError("Game is not initialized".to_string()));
         }
 
-        if let Ok(update_fn) = self.lua.globals().get::<_, Function>("update") {
-            let dt = delta_time.as_secs_f32();
-            if let Err(e) = update_fn.call::<_, ()>(dt) {
-                log::error!("Update function error: {}", e);
+        self.lua.load(script_content)
+            .set_name(self.chunk_name())
+            .into_function()
+            .map_err(|e| script_error("Failed to compile main script", &e))?;
+
+        self.run_main_chunk(script_content, api::EngineContext { audio, assets, camera, saves })?;
+        self.call_init(api::EngineContext { audio, assets, camera, saves })
+    }
+
+    /// Path of the main script in the game folder; packed games may not have one on disk.
+    pub fn script_path(&self) -> PathBuf {
+        self._game_folder.join(&self.info.entry_point)
+    }
+
+    fn run_main_chunk(&self, script_content: &str, ctx: api::EngineContext) -> Result<(), CacaoError> {
+        // A reloaded script registers its migrations again
+        self.lua.set_app_data(api::saves::MigrationList::default());
+        api::with_engine(&self.lua, ctx, || {
+            if let Err(e) = self.lua.load(script_content).set_name(self.chunk_name()).exec() {
+                return Ok(Err(script_error("Failed to load main script", &e)));
             }
+            Ok(Ok(()))
+        }).map_err(|e| script_error("Failed to load main script", &e))?
+    }
+
+    fn call_init(&self, ctx: api::EngineContext) -> Result<(), CacaoError> {
+        api::with_engine(&self.lua, ctx, || {
+            if let Ok(init_fn) = self.lua.globals().get::<_, Function>("init") {
+                if let Err(e) = init_fn.call::<_, ()>(()) {
+                    return Ok(Err(script_error("Init function failed", &e)));
+                }
+            }
+            Ok(Ok(()))
+        }).map_err(|e| script_error("Failed to load main script", &e))?
+    }
+
+    // "@" makes Lua report locations as `main.lua:3` rather than `[string "main.lua"]:3`
+    fn chunk_name(&self) -> String {
+        format!("@{}", self.info.entry_point)
+    }
+
+    pub fn update(&mut self, delta_time: Duration, input: &mut InputManager, audio: &mut AudioSystem, assets: &AssetManager, camera: &mut Camera, saves: &mut SaveManager) -> Result<(), CacaoError> {
+        if !self.initialized {
+            return Ok(());
         }
+
+        self.lua.set_app_data(api::input::InputSnapshot::capture(input));
+
+        let dt = delta_time.as_secs_f32();
+        self.clock.tick(dt);
+        self.lua.set_app_data(self.clock);
+        api::animation::update_all(&self.lua, dt);
+        api::particles::update_all(&self.lua, dt);
+
+        let ctx = api::EngineContext { audio, assets, camera, saves };
+        api::with_engine(&self.lua, ctx, || {
+            if let Err(e) = api::schedule::update_all(&self.lua, dt) {
+                return Ok(Err(script_error("Scheduled callback failed", &e)));
+            }
+
+            if let Ok(update_fn) = self.lua.globals().get::<_, Function>("update") {
+                if let Err(e) = update_fn.call::<_, ()>(dt) {
+                    return Ok(Err(script_error("Update function failed", &e)));
+                }
+            }
+            Ok(Ok(()))
+        }).map_err(|e| script_error("Update function failed", &e))?
+    }
+
+    /// Sets what `cacao.window.size()` reports without telling the script. Call before
+    /// `initialize` so `init` already sees the right size.
+    pub fn set_window_size(&self, width: u32, height: u32) {
+        self.lua.set_app_data(api::window::WindowSize { width, height });
     }
 
-    pub fn render(&self, _renderer: &mut Renderer) -> Result<(), CacaoError> {
+    /// Hands `event` to the script, which picks it up from `cacao.events.poll()`.
+    pub fn push_event(&self, event: api::events::GameEvent) {
+        api::events::push(&self.lua, event);
+    }
+
+    /// Updates `cacao.window.size()` and calls the script's `on_resize(w, h)`, if it has one.
+    pub fn resize(&self, width: u32, height: u32, audio: &mut AudioSystem, assets: &AssetManager, camera: &mut Camera, saves: &mut SaveManager) -> Result<(), CacaoError> {
+        self.set_window_size(width, height);
+        if !self.initialized {
+            return Ok(());
+        }
+
+        if let Ok(resize_fn) = self.lua.globals().get::<_, Function>("on_resize") {
+            let ctx = api::EngineContext { audio, assets, camera, saves };
+            api::with_engine(&self.lua, ctx, || resize_fn.call::<_, ()>((width, height)))
+                .map_err(|e| script_error("Resize function failed", &e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Calls the script's `on_quit`, if it has one, then writes the save to disk. The save
+    /// is written even when `on_quit` fails.
+    pub fn quit(&self, audio: &mut AudioSystem, assets: &AssetManager, camera: &mut Camera, saves: &mut SaveManager) -> Result<(), CacaoError> {
+        if !self.initialized {
+            return Ok(());
+        }
+
+        let hook_result = match self.lua.globals().get::<_, Function>("on_quit") {
+            Ok(on_quit_fn) => {
+                let ctx = api::EngineContext { audio, assets, camera, saves: &mut *saves };
+                api::with_engine(&self.lua, ctx, || on_quit_fn.call::<_, ()>(()))
+                    .map_err(|e| script_error("Quit function failed", &e))
+            }
+            Err(_) => Ok(()),
+        };
+
+        saves.save_to_disk()?;
+        hook_result
+    }
+
+    /// `alpha` is how far between the last two logic steps this frame falls (0..1).
+    /// The script draws through `cacao.renderer` into `target`.
+    pub fn render(&self, target: &mut dyn DrawTarget, assets: &AssetManager, alpha: f32) -> Result<(), CacaoError> {
         if !self.initialized {
             return Ok(());
         }
 
         if let Ok(render_fn) = self.lua.globals().get::<_, Function>("render") {
-            render_fn.call::<_, ()>(())
-                .map_err(|e| CacaoError::ScriptError(format!("Render function failed: {}", e)))?;
+            let ctx = api::DrawContext { target, assets };
+            api::with_draw_target(&self.lua, ctx, || render_fn.call::<_, ()>(alpha))
+                .map_err(|e| script_error("Render function failed", &e))?;
         }
         
         Ok(())
     }
 
     fn setup_lua_api(&self) -> Result<(), CacaoError> {
+        self.sandbox.apply(&self.lua)?;
+
         let globals = self.lua.globals();
         let cacao_table = self.lua.create_table()?;
+        api::register(&self.lua, &cacao_table, &self._secret_key)?;
         globals.set("cacao", cacao_table)?;
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn get_info(&self) -> &GameInfo {
         &self.info
     }
 }
+
+// Lua frames kept from a traceback; deeper ones rarely help and push the message off screen
+const MAX_TRACEBACK_FRAMES: usize = 8;
+
+/// A `ScriptError` that starts with the script file and line the error came from, followed
+/// by the Lua part of the stack traceback.
+fn script_error(context: &str, error: &mlua::Error) -> CacaoError {
+    let (mut message, traceback) = split_lua_error(error);
+
+    // Lua frames look like "main.lua:3: in function 'update'"; engine frames start with "[C]"
+    let frames: Vec<&str> = traceback.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("[C]") && !line.starts_with("stack traceback"))
+        .take(MAX_TRACEBACK_FRAMES)
+        .collect();
+
+    // Errors raised from engine functions carry no location of their own
+    if let Some(location) = frames.first().and_then(|frame| frame.split(": in ").next()) {
+        if !message.starts_with(location) {
+            message = format!("{}: {}", location, message);
+        }
+    }
+
+    let mut text = format!("{}: {}", context, message);
+    if !frames.is_empty() {
+        text.push_str("\nstack traceback:");
+        for frame in frames {
+            text.push_str("\n  ");
+            text.push_str(frame);
+        }
+    }
+    CacaoError::ScriptError(text)
+}
+
+// (message, traceback) of the innermost error
+fn split_lua_error(error: &mlua::Error) -> (String, String) {
+    match error {
+        mlua::Error::CallbackError { traceback, cause } => {
+            let (message, inner_traceback) = split_lua_error(cause);
+            // The innermost traceback is closest to where the error happened
+            if inner_traceback.is_empty() {
+                (message, traceback.clone())
+            } else {
+                (message, inner_traceback)
+            }
+        }
+        mlua::Error::RuntimeError(message) => match message.split_once("\nstack traceback:") {
+            Some((message, traceback)) => (message.to_string(), traceback.to_string()),
+            None => (message.clone(), String::new()),
+        },
+        other => (other.to_string(), String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{CoordinateSpace, DrawCommand, RecordingTarget};
+    use std::path::Path;
+    use crate::saves::{SaveMigrations, SaveValue};
+
+    struct Harness {
+        game: Game,
+        audio: AudioSystem,
+        assets: AssetManager,
+        camera: Camera,
+        saves: SaveManager,
+    }
+
+    fn temp_game_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cacao_runtime_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_info() -> GameInfo {
+        let mut info = GameInfo::new("Test".to_string(), "Tester".to_string());
+        info.set_secret_key("key");
+        info
+    }
+
+    fn start(script: &str, sandbox: ScriptSandbox) -> (Harness, Result<(), CacaoError>) {
+        start_in(&temp_game_dir(), test_info(), script, sandbox)
+    }
+
+    /// Starts `script` in `dir`, so a second start with the same info sees the first one's saves.
+    fn start_in(dir: &Path, info: GameInfo, script: &str, sandbox: ScriptSandbox) -> (Harness, Result<(), CacaoError>) {
+        std::fs::write(dir.join("main.lua"), script).unwrap();
+        let mut game = Game::new(info, dir.to_path_buf());
+        game.set_sandbox(sandbox);
+
+        let mut harness = Harness {
+            game,
+            audio: AudioSystem::new_silent(),
+            assets: AssetManager::new(),
+            camera: Camera::new(1280.0, 720.0),
+            saves: SaveManager::new(dir.join("saves")),
+        };
+        let h = &mut harness;
+        let result = h.game.initialize("key".to_string(), &mut h.audio, &h.assets, &mut h.camera, &mut h.saves);
+        (harness, result)
+    }
+
+    #[test]
+    fn strict_sandbox_hides_os_execute_from_games() {
+        let (h, result) = start("cacao.saves.write('exec_nil', os.execute == nil)", ScriptSandbox::Strict);
+        result.unwrap();
+        assert_eq!(h.saves.data().get("exec_nil"), Some(&SaveValue::Boolean(true)));
+    }
+
+    #[test]
+    fn trusted_sandbox_keeps_os_execute() {
+        let (h, result) = start("cacao.saves.write('exec_nil', os.execute == nil)", ScriptSandbox::Trusted);
+        result.unwrap();
+        assert_eq!(h.saves.data().get("exec_nil"), Some(&SaveValue::Boolean(false)));
+    }
+
+    #[test]
+    fn render_script_draws_into_the_target() {
+        let script = r#"
+            function render(alpha)
+                cacao.renderer.set_layer(2)
+                cacao.renderer.begin_screen_space()
+                cacao.renderer.draw_rect(10, 20, 30, 40, {1, 0, 0})
+                cacao.renderer.draw_text("Score", 5, 6, 16, {0, 1, 0, 0.5})
+            end
+        "#;
+        let (h, result) = start(script, ScriptSandbox::Strict);
+        result.unwrap();
+
+        let mut target = RecordingTarget::new();
+        h.game.render(&mut target, &h.assets, 1.0).unwrap();
+        assert_eq!(target.commands, vec![
+            DrawCommand::SetLayer(2),
+            DrawCommand::SetSpace(CoordinateSpace::Screen),
+            DrawCommand::Rect { x: 10.0, y: 20.0, width: 30.0, height: 40.0, color: [1.0, 0.0, 0.0, 1.0] },
+            DrawCommand::Text { text: "Score".to_string(), x: 5.0, y: 6.0, size: 16.0, color: [0.0, 1.0, 0.0, 0.5] },
+        ]);
+    }
+
+    #[test]
+    fn render_errors_name_the_script_line() {
+        let (h, result) = start("function render()\n    cacao.renderer.draw_rect(0, 0, 1, 1, {2})\nend", ScriptSandbox::Strict);
+        result.unwrap();
+
+        let error = h.game.render(&mut RecordingTarget::new(), &h.assets, 1.0).unwrap_err();
+        assert!(error.to_string().contains("main.lua:2"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn engine_tables_explain_they_are_not_available_in_render() {
+        let script = "function render()\n    cacao.log.info('drawing')\n    cacao.audio.play_sound('hit.wav')\nend";
+        let (h, result) = start(script, ScriptSandbox::Strict);
+        result.unwrap();
+
+        let error = h.game.render(&mut RecordingTarget::new(), &h.assets, 1.0).unwrap_err().to_string();
+        assert!(error.contains("cacao.audio.play_sound can only be used from init, update"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn renderer_explains_it_is_only_available_in_render() {
+        let (mut h, result) = start("function update(dt) cacao.renderer.draw_rect(0, 0, 1, 1) end", ScriptSandbox::Strict);
+        result.unwrap();
+
+        let h = &mut h;
+        let mut input = InputManager::new();
+        let error = h.game.update(Duration::from_millis(16), &mut input, &mut h.audio, &h.assets, &mut h.camera, &mut h.saves)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("cacao.renderer.draw_rect can only be used from the render callback"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn scripts_migrate_older_saves_before_init() {
+        let dir = temp_game_dir();
+        let info = test_info();
+        let (_, result) = start_in(&dir, info.clone(), "function init() cacao.saves.write('coins', 7) cacao.saves.save() end", ScriptSandbox::Strict);
+        result.unwrap();
+
+        let script = r#"
+            cacao.saves.register_migration(1, 2, function(save)
+                save.gold = save.coins
+                save.coins = nil
+            end)
+            function init() cacao.saves.write('gold_in_init', cacao.saves.read('gold')) end
+        "#;
+        let (h, result) = start_in(&dir, info.clone(), script, ScriptSandbox::Strict);
+        result.unwrap();
+        assert_eq!(h.saves.read("gold_in_init"), Some(&SaveValue::Integer(7)));
+        assert_eq!(h.saves.read("coins"), None);
+
+        // Rewritten at version 2, so a script without the migration can't open it any more
+        let mut saves = SaveManager::new(dir.join("saves"));
+        saves.set_game_context(info.id.to_string(), "key").unwrap();
+        let error = saves.load(&SaveMigrations::new()).unwrap_err();
+        assert!(matches!(error, CacaoError::SaveVersionError(_)), "unexpected error: {:?}", error);
+    }
+
+    #[test]
+    fn migrations_must_move_forward() {
+        let (_, result) = start("cacao.saves.register_migration(2, 1, function() end)", ScriptSandbox::Strict);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("has to move to a higher version"), "unexpected error: {}", error);
+    }
+
+
+    #[test]
+    fn resizing_tells_the_script_the_new_size() {
+        let script = r#"
+            function on_resize(w, h)
+                local sw, sh = cacao.window.size()
+                cacao.saves.write('resized', w .. 'x' .. h .. ' ' .. sw .. 'x' .. sh)
+            end
+        "#;
+        let (mut h, result) = start(script, ScriptSandbox::Strict);
+        result.unwrap();
+
+        let h = &mut h;
+        h.game.resize(800, 600, &mut h.audio, &h.assets, &mut h.camera, &mut h.saves).unwrap();
+        assert_eq!(h.saves.read("resized"), Some(&SaveValue::String("800x600 800x600".to_string())));
+    }
+
+    #[test]
+    fn quitting_runs_on_quit_and_saves_even_when_it_fails() {
+        let dir = temp_game_dir();
+        let info = test_info();
+        let (mut h, result) = start_in(&dir, info.clone(), "function on_quit() cacao.saves.write('quit', true) error('boom') end", ScriptSandbox::Strict);
+        result.unwrap();
+
+        let h = &mut h;
+        let error = h.game.quit(&mut h.audio, &h.assets, &mut h.camera, &mut h.saves).unwrap_err();
+        assert!(error.to_string().contains("Quit function failed"), "unexpected error: {}", error);
+
+        let mut saves = SaveManager::new(dir.join("saves"));
+        saves.set_game_context(info.id.to_string(), "key").unwrap();
+        saves.load(&SaveMigrations::new()).unwrap();
+        assert_eq!(saves.read("quit"), Some(&SaveValue::Boolean(true)));
+    }
+}
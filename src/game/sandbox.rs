@@ -0,0 +1,105 @@
+// src/game/sandbox.rs
+use mlua::{Function, Lua, MultiValue, Table, Value};
+use serde::{Deserialize, Serialize};
+
+// Globals that reach the file system or load code from disk
+const BLOCKED_GLOBALS: [&str; 5] = ["io", "package", "require", "loadfile", "dofile"];
+// The only parts of `os` that can't touch files, processes or the environment
+const SAFE_OS_FUNCTIONS: [&str; 4] = ["clock", "date", "difftime", "time"];
+
+/// How much of Lua's standard library a game script gets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptSandbox {
+    /// No file, process or module access; `os` keeps only its clock and date functions
+    #[default]
+    Strict,
+    /// The whole standard library, for local scripts you trust while developing them
+    Trusted,
+}
+
+impl ScriptSandbox {
+    /// Strips whatever this level doesn't allow from `lua`'s globals.
+    pub fn apply(self, lua: &Lua) -> mlua::Result<()> {
+        if self == ScriptSandbox::Trusted {
+            return Ok(());
+        }
+
+        let globals = lua.globals();
+        for name in BLOCKED_GLOBALS {
+            globals.set(name, Value::Nil)?;
+        }
+
+        // Hand-made bytecode can break out of the VM, so `load` only takes source text
+        // and scripts can't produce bytecode with `string.dump` either
+        if let Ok(load) = globals.get::<_, Function>("load") {
+            let load = lua.create_registry_value(load)?;
+            globals.set("load", lua.create_function(move |lua, (chunk, name, mode, env): (Value, Value, Option<String>, Value)| {
+                if mode.as_deref().is_some_and(|mode| mode != "t") {
+                    return Err(mlua::Error::RuntimeError("load: binary chunks are not allowed in the sandbox".to_string()));
+                }
+                lua.registry_value::<Function>(&load)?.call::<_, MultiValue>((chunk, name, "t", env))
+            })?)?;
+        }
+        if let Ok(string) = globals.get::<_, Table>("string") {
+            string.set("dump", Value::Nil)?;
+        }
+
+        if let Ok(os) = globals.get::<_, Table>("os") {
+            let safe_os = lua.create_table()?;
+            for name in SAFE_OS_FUNCTIONS {
+                safe_os.set(name, os.get::<_, Value>(name)?)?;
+            }
+            globals.set("os", safe_os)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_lua() -> Lua {
+        let lua = Lua::new();
+        ScriptSandbox::Strict.apply(&lua).unwrap();
+        lua
+    }
+
+    #[test]
+    fn strict_blocks_files_modules_and_processes() {
+        let lua = strict_lua();
+        assert!(lua.load("return io.open('x')").exec().is_err());
+        assert!(lua.load("require('os')").exec().is_err());
+        assert!(lua.load("os.execute('true')").exec().is_err());
+        assert!(lua.load("return string.dump(function() end)").exec().is_err());
+        assert!(lua.load("return os.clock()").eval::<f64>().is_ok());
+    }
+
+    #[test]
+    fn strict_load_rejects_binary_chunks() {
+        let bytecode = {
+            let trusted = Lua::new();
+            let dumped: mlua::String = trusted.load("return string.dump(function() return 1 end)").eval().unwrap();
+            dumped.as_bytes().to_vec()
+        };
+        let lua = strict_lua();
+        lua.globals().set("bytecode", lua.create_string(&bytecode).unwrap()).unwrap();
+
+        // Default mode loads as text, so the bytecode is refused rather than run
+        let (chunk, err): (Value, Option<String>) = lua.load("return load(bytecode)").eval().unwrap();
+        assert!(chunk.is_nil());
+        assert!(err.unwrap().contains("binary"));
+        assert!(lua.load("return load(bytecode, 'x', 'b')").exec().is_err());
+        assert!(lua.load("return load(bytecode, 'x', 'bt')").exec().is_err());
+
+        assert_eq!(lua.load("return load('return 1 + 1')()").eval::<i64>().unwrap(), 2);
+    }
+
+    #[test]
+    fn trusted_keeps_the_standard_library() {
+        let lua = Lua::new();
+        ScriptSandbox::Trusted.apply(&lua).unwrap();
+        assert!(lua.load("return io ~= nil and os.execute ~= nil and string.dump ~= nil").eval::<bool>().unwrap());
+    }
+}
@@ -1,9 +1,22 @@
 // src/input/mod.rs
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use gilrs::{Axis, Button, EventType, Gilrs};
+use serde::{Deserialize, Serialize};
 use winit::event::{WindowEvent, KeyboardInput, VirtualKeyCode, ElementState, MouseButton};
 use glam::Vec2;
+use crate::errors::CacaoError;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Held keys start repeating after KEY_REPEAT_DELAY, then fire every KEY_REPEAT_INTERVAL
+const KEY_REPEAT_DELAY: Duration = Duration::from_millis(400);
+const KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(80);
+
+/// How close together two presses must be to count as a double tap in scripts.
+pub const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GamepadButton {
     A, B, X, Y,
     DPadUp, DPadDown, DPadLeft, DPadRight,
@@ -13,33 +26,64 @@ pub enum GamepadButton {
     Start, Select,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InputButton {
     Key(VirtualKeyCode),
     Mouse(MouseButton),
     Gamepad(GamepadButton),
 }
 
+impl fmt::Display for InputButton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputButton::Key(key) => write!(f, "{:?}", key),
+            InputButton::Mouse(button) => write!(f, "Mouse {:?}", button),
+            InputButton::Gamepad(button) => write!(f, "Pad {:?}", button),
+        }
+    }
+}
+
+/// A controller being plugged in or removed, with its name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadChange {
+    Connected(String),
+    Disconnected(String),
+}
+
 pub struct InputManager {
     // Keyboard state
     keys_pressed: HashSet<VirtualKeyCode>,
     keys_just_pressed: HashSet<VirtualKeyCode>,
     keys_just_released: HashSet<VirtualKeyCode>,
+    // When each held key went down, and (previous, latest) press times for double taps
+    key_press_started: HashMap<VirtualKeyCode, Instant>,
+    key_taps: HashMap<VirtualKeyCode, (Option<Instant>, Instant)>,
+    // Held keys whose auto-repeat fired at the last update, and when that update ran
+    keys_repeated: HashSet<VirtualKeyCode>,
+    last_update: Instant,
     
     // Mouse state
     mouse_buttons_pressed: HashSet<MouseButton>,
     mouse_buttons_just_pressed: HashSet<MouseButton>,
     mouse_buttons_just_released: HashSet<MouseButton>,
+    // Cursor position each held button went down at
+    mouse_drag_starts: HashMap<MouseButton, Vec2>,
     mouse_position: Vec2,
     mouse_delta: Vec2,
     scroll_delta: Vec2,
+    // Characters typed since the buffer was last drained, for text fields
+    text_input: String,
     
-    // Gamepad state (simplified for now)
+    // Gamepad state
+    gilrs: Option<Gilrs>,
     gamepad_buttons_pressed: HashSet<GamepadButton>,
     gamepad_buttons_just_pressed: HashSet<GamepadButton>,
     gamepad_buttons_just_released: HashSet<GamepadButton>,
     left_stick: Vec2,
     right_stick: Vec2,
+    gamepad_deadzone: f32,
+    // Connections seen since the engine last took them
+    gamepad_changes: Vec<GamepadChange>,
     
     // Input mapping
     input_map: std::collections::HashMap<String, Vec<InputButton>>,
@@ -50,21 +94,43 @@ pub struct InputManager {
 
 impl InputManager {
     pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => {
+                for (_, gamepad) in gilrs.gamepads() {
+                    log::info!("🎮 Gamepad found: {}", gamepad.name());
+                }
+                Some(gilrs)
+            }
+            Err(e) => {
+                log::warn!("Gamepad support unavailable: {}", e);
+                None
+            }
+        };
+
         Self {
             keys_pressed: HashSet::new(),
             keys_just_pressed: HashSet::new(),
             keys_just_released: HashSet::new(),
+            key_press_started: HashMap::new(),
+            key_taps: HashMap::new(),
+            keys_repeated: HashSet::new(),
+            last_update: Instant::now(),
             mouse_buttons_pressed: HashSet::new(),
             mouse_buttons_just_pressed: HashSet::new(),
             mouse_buttons_just_released: HashSet::new(),
+            mouse_drag_starts: HashMap::new(),
             mouse_position: Vec2::ZERO,
             mouse_delta: Vec2::ZERO,
             scroll_delta: Vec2::ZERO,
+            text_input: String::new(),
+            gilrs,
             gamepad_buttons_pressed: HashSet::new(),
             gamepad_buttons_just_pressed: HashSet::new(),
             gamepad_buttons_just_released: HashSet::new(),
             left_stick: Vec2::ZERO,
             right_stick: Vec2::ZERO,
+            gamepad_deadzone: 0.15,
+            gamepad_changes: Vec::new(),
             input_map: std::collections::HashMap::new(),
             previous_mouse_position: Vec2::ZERO,
         }
@@ -81,16 +147,8 @@ impl InputManager {
                 ..
             } => {
                 match state {
-                    ElementState::Pressed => {
-                        if !self.keys_pressed.contains(keycode) {
-                            self.keys_just_pressed.insert(*keycode);
-                        }
-                        self.keys_pressed.insert(*keycode);
-                    }
-                    ElementState::Released => {
-                        self.keys_pressed.remove(keycode);
-                        self.keys_just_released.insert(*keycode);
-                    }
+                    ElementState::Pressed => self.press_key(*keycode, Instant::now()),
+                    ElementState::Released => self.release_key(*keycode),
                 }
             }
             WindowEvent::MouseInput { state, button, .. } => {
@@ -98,6 +156,7 @@ impl InputManager {
                     ElementState::Pressed => {
                         if !self.mouse_buttons_pressed.contains(button) {
                             self.mouse_buttons_just_pressed.insert(*button);
+                            self.mouse_drag_starts.insert(*button, self.mouse_position);
                         }
                         self.mouse_buttons_pressed.insert(*button);
                     }
@@ -108,7 +167,13 @@ impl InputManager {
                 }
             }
             WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_position = Vec2::new(position.x as f32, position.y as f32);
+                let new_position = Vec2::new(position.x as f32, position.y as f32);
+                self.mouse_delta += new_position - self.mouse_position;
+                self.mouse_position = new_position;
+            }
+            // Enter, Backspace, Escape etc. arrive as control characters; they're handled as keys
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                self.text_input.push(*c);
             }
             WindowEvent::MouseWheel { delta, .. } => {
                 match delta {
@@ -124,9 +189,25 @@ impl InputManager {
         }
     }
 
+    fn press_key(&mut self, key: VirtualKeyCode, now: Instant) {
+        if !self.keys_pressed.contains(&key) {
+            self.keys_just_pressed.insert(key);
+            self.key_press_started.insert(key, now);
+            let previous = self.key_taps.get(&key).map(|&(_, latest)| latest);
+            self.key_taps.insert(key, (previous, now));
+        }
+        self.keys_pressed.insert(key);
+    }
+
+    fn release_key(&mut self, key: VirtualKeyCode) {
+        self.keys_pressed.remove(&key);
+        self.key_press_started.remove(&key);
+        self.keys_just_released.insert(key);
+    }
+
     pub fn update(&mut self) {
-        // Calculate mouse delta
-        self.mouse_delta = self.mouse_position - self.previous_mouse_position;
+        // Mouse delta accumulates from cursor events until the end of the frame
+        self.mouse_delta = Vec2::ZERO;
         self.previous_mouse_position = self.mouse_position;
         
         // Clear "just pressed/released" states
@@ -136,9 +217,91 @@ impl InputManager {
         self.mouse_buttons_just_released.clear();
         self.gamepad_buttons_just_pressed.clear();
         self.gamepad_buttons_just_released.clear();
+        self.collect_key_repeats(Instant::now());
+
+        // Released drags stay readable until the end of the frame they ended on
+        let pressed = &self.mouse_buttons_pressed;
+        self.mouse_drag_starts.retain(|button, _| pressed.contains(button));
         
         // Reset scroll delta
         self.scroll_delta = Vec2::ZERO;
+
+        self.poll_gamepads();
+    }
+
+    // Marks the held keys that passed one of their repeat points since the last update
+    fn collect_key_repeats(&mut self, now: Instant) {
+        self.keys_repeated.clear();
+        for (key, started) in &self.key_press_started {
+            let before = self.last_update.saturating_duration_since(*started);
+            let after = now.saturating_duration_since(*started);
+            if repeats_between(before, after) {
+                self.keys_repeated.insert(*key);
+            }
+        }
+        self.last_update = now;
+    }
+
+    fn poll_gamepads(&mut self) {
+        while let Some(event) = self.gilrs.as_mut().and_then(|gilrs| gilrs.next_event()) {
+            if let Some(gilrs) = self.gilrs.as_ref() {
+                let name = gilrs.gamepad(event.id).name().to_string();
+                match event.event {
+                    EventType::Connected => {
+                        log::info!("🎮 Gamepad connected: {}", name);
+                        self.gamepad_changes.push(GamepadChange::Connected(name));
+                    }
+                    EventType::Disconnected => {
+                        log::info!("🎮 Gamepad disconnected: {}", name);
+                        self.gamepad_changes.push(GamepadChange::Disconnected(name));
+                    }
+                    _ => {}
+                }
+            }
+            self.handle_gamepad_event(&event.event);
+        }
+    }
+
+    /// Applies a single gamepad event to the button and stick state.
+    pub fn handle_gamepad_event(&mut self, event: &EventType) {
+        match event {
+            EventType::ButtonPressed(button, _) => self.press_gamepad_button(*button),
+            EventType::ButtonReleased(button, _) => self.release_gamepad_button(*button),
+            EventType::AxisChanged(axis, value, _) => self.move_gamepad_axis(*axis, *value),
+            EventType::Disconnected => {
+                self.gamepad_buttons_pressed.clear();
+                self.left_stick = Vec2::ZERO;
+                self.right_stick = Vec2::ZERO;
+            }
+            _ => {}
+        }
+    }
+
+    fn press_gamepad_button(&mut self, button: Button) {
+        if let Some(button) = map_gamepad_button(button) {
+            if !self.gamepad_buttons_pressed.contains(&button) {
+                self.gamepad_buttons_just_pressed.insert(button);
+            }
+            self.gamepad_buttons_pressed.insert(button);
+        }
+    }
+
+    fn release_gamepad_button(&mut self, button: Button) {
+        if let Some(button) = map_gamepad_button(button) {
+            self.gamepad_buttons_pressed.remove(&button);
+            self.gamepad_buttons_just_released.insert(button);
+        }
+    }
+
+    // Raw values are kept; the deadzone is applied to the whole stick when read
+    fn move_gamepad_axis(&mut self, axis: Axis, value: f32) {
+        match axis {
+            Axis::LeftStickX => self.left_stick.x = value,
+            Axis::LeftStickY => self.left_stick.y = value,
+            Axis::RightStickX => self.right_stick.x = value,
+            Axis::RightStickY => self.right_stick.y = value,
+            _ => {}
+        }
     }
 
     // Keyboard input methods
@@ -154,6 +317,34 @@ impl InputManager {
         self.keys_just_released.contains(&key)
     }
 
+    /// True when `key` goes down, and again every so often while it's held, like
+    /// typing repeat. Meant for moving through lists.
+    pub fn is_key_repeated(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_pressed.contains(&key) || self.keys_repeated.contains(&key)
+    }
+
+    /// How long `key` has been held, or zero if it's up.
+    pub fn key_held_duration(&self, key: VirtualKeyCode) -> Duration {
+        self.key_press_started.get(&key).map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    /// True on the frame `key` goes down for the second time within `window`. Firing
+    /// forgets both taps, so a third quick press starts a new double tap instead of retriggering.
+    pub fn is_key_double_tapped(&mut self, key: VirtualKeyCode, window: Duration) -> bool {
+        if !self.is_key_just_pressed(key) {
+            return false;
+        }
+
+        let double_tapped = matches!(
+            self.key_taps.get(&key),
+            Some(&(Some(previous), latest)) if latest.duration_since(previous) <= window
+        );
+        if double_tapped {
+            self.key_taps.remove(&key);
+        }
+        double_tapped
+    }
+
     // Mouse input methods
     pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
         self.mouse_buttons_pressed.contains(&button)
@@ -167,6 +358,16 @@ impl InputManager {
         self.mouse_buttons_just_released.contains(&button)
     }
 
+    /// Where the current drag with `button` started, while it's held (and on the frame it's released).
+    pub fn get_drag_start(&self, button: MouseButton) -> Option<Vec2> {
+        self.mouse_drag_starts.get(&button).copied()
+    }
+
+    /// Cursor movement since `button` went down, or zero when not dragging.
+    pub fn get_drag_delta(&self, button: MouseButton) -> Vec2 {
+        self.get_drag_start(button).map_or(Vec2::ZERO, |start| self.mouse_position - start)
+    }
+
     pub fn get_mouse_position(&self) -> Vec2 {
         self.mouse_position
     }
@@ -179,6 +380,12 @@ impl InputManager {
         self.scroll_delta
     }
 
+    /// Drains the printable characters typed since the last call. Unlike key state this
+    /// isn't reset by `update`, so text typed between two reads is never lost.
+    pub fn take_text_input(&mut self) -> String {
+        std::mem::take(&mut self.text_input)
+    }
+
     // Gamepad input methods
     pub fn is_gamepad_button_pressed(&self, button: GamepadButton) -> bool {
         self.gamepad_buttons_pressed.contains(&button)
@@ -193,11 +400,24 @@ impl InputManager {
     }
 
     pub fn get_left_stick(&self) -> Vec2 {
-        self.left_stick
+        apply_radial_deadzone(self.left_stick, self.gamepad_deadzone)
     }
 
     pub fn get_right_stick(&self) -> Vec2 {
-        self.right_stick
+        apply_radial_deadzone(self.right_stick, self.gamepad_deadzone)
+    }
+
+    /// Gamepads connected or disconnected since the last call, oldest first.
+    pub fn take_gamepad_changes(&mut self) -> Vec<GamepadChange> {
+        std::mem::take(&mut self.gamepad_changes)
+    }
+
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) {
+        self.gamepad_deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    pub fn get_gamepad_deadzone(&self) -> f32 {
+        self.gamepad_deadzone
     }
 
     // Input mapping system
@@ -262,6 +482,13 @@ impl InputManager {
         self.keys_just_pressed.iter().cloned().collect()
     }
 
+    pub fn get_pressed_actions(&self) -> Vec<String> {
+        self.input_map.keys()
+            .filter(|action| self.is_action_pressed(action))
+            .cloned()
+            .collect()
+    }
+
     pub fn clear_input_map(&mut self) {
         self.input_map.clear();
     }
@@ -270,6 +497,79 @@ impl InputManager {
         self.input_map.remove(action_name);
     }
 
+    /// Mapped action names in alphabetical order.
+    pub fn get_action_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.input_map.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn get_action_bindings(&self, action_name: &str) -> &[InputButton] {
+        self.input_map.get(action_name).map_or(&[], |buttons| buttons.as_slice())
+    }
+
+    /// Makes `key` the action's only keyboard binding; mouse and gamepad bindings are kept.
+    /// Returns the other actions that are also bound to `key`.
+    pub fn rebind_key(&mut self, action_name: &str, key: VirtualKeyCode) -> Vec<String> {
+        let buttons = self.input_map.entry(action_name.to_string()).or_default();
+        buttons.retain(|button| !matches!(button, InputButton::Key(_)));
+        buttons.insert(0, InputButton::Key(key));
+
+        let conflicts = self.conflicting_actions(action_name, InputButton::Key(key));
+        if !conflicts.is_empty() {
+            log::warn!("⚠️ {:?} is bound to {} and also to {}", key, action_name, conflicts.join(", "));
+        }
+        conflicts
+    }
+
+    /// Other actions that share `button` with `action_name`.
+    pub fn conflicting_actions(&self, action_name: &str, button: InputButton) -> Vec<String> {
+        let mut conflicts: Vec<String> = self.input_map.iter()
+            .filter(|(name, buttons)| name.as_str() != action_name && buttons.contains(&button))
+            .map(|(name, _)| name.clone())
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    pub fn save_mappings(&self, path: &Path) -> Result<(), CacaoError> {
+        let sorted: BTreeMap<&String, &Vec<InputButton>> = self.input_map.iter().collect();
+        let contents = toml::to_string_pretty(&sorted)
+            .map_err(|e| CacaoError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Replaces the bindings of every action listed in `path`. Actions missing from the
+    /// file keep their current bindings, and a missing or invalid file changes nothing.
+    pub fn load_mappings(&mut self, path: &Path) {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+
+        match toml::from_str::<BTreeMap<String, Vec<InputButton>>>(&contents) {
+            Ok(mappings) => {
+                for (action, buttons) in mappings {
+                    self.input_map.insert(action, buttons);
+                }
+                let mut actions_by_button: HashMap<InputButton, Vec<&str>> = HashMap::new();
+                for (action, buttons) in &self.input_map {
+                    for button in buttons {
+                        actions_by_button.entry(*button).or_default().push(action);
+                    }
+                }
+                for (button, mut actions) in actions_by_button {
+                    if actions.len() > 1 {
+                        actions.sort();
+                        log::warn!("⚠️ {} is bound to several actions: {}", button, actions.join(", "));
+                    }
+                }
+            }
+            Err(e) => log::warn!("⚠️ Failed to parse {}: {}, keeping default controls", path.display(), e),
+        }
+    }
+
     // Common input mappings setup
     pub fn setup_default_mappings(&mut self) {
         // Movement
@@ -334,14 +634,329 @@ impl InputManager {
             movement.x += 1.0;
         }
 
-        // Add gamepad stick input
-        movement += self.left_stick;
-        
         // Normalize to prevent faster diagonal movement
         if movement.length() > 1.0 {
             movement = movement.normalize();
         }
-        
-        movement
+
+        // Keys and stick together still can't go faster than full tilt
+        (movement + self.get_left_stick()).clamp_length_max(1.0)
+    }
+}
+
+/// Zeroes a stick inside `deadzone` (measured on its length, not per axis) and rescales
+/// the rest so output rises smoothly from 0 at the deadzone edge to 1 at full tilt.
+pub fn apply_radial_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    let scaled = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    stick / length * scaled
+}
+
+// Whether a key held from `before` to `after` crossed a repeat point on the way
+fn repeats_between(before: Duration, after: Duration) -> bool {
+    let repeats_by = |held: Duration| match held.checked_sub(KEY_REPEAT_DELAY) {
+        Some(past_delay) => past_delay.as_nanos() / KEY_REPEAT_INTERVAL.as_nanos() + 1,
+        None => 0,
+    };
+    repeats_by(after) > repeats_by(before)
+}
+
+fn map_gamepad_button(button: Button) -> Option<GamepadButton> {
+    let mapped = match button {
+        Button::South => GamepadButton::A,
+        Button::East => GamepadButton::B,
+        Button::West => GamepadButton::X,
+        Button::North => GamepadButton::Y,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        Button::LeftTrigger => GamepadButton::LeftShoulder,
+        Button::RightTrigger => GamepadButton::RightShoulder,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::Start => GamepadButton::Start,
+        Button::Select => GamepadButton::Select,
+        _ => return None,
+    };
+
+    Some(mapped)
+}
+
+/// Maps a human-readable key name (as used by game scripts) to a `VirtualKeyCode`.
+/// Matching is case-insensitive; unknown names return `None`.
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    let key = match name.to_ascii_lowercase().as_str() {
+        "a" => A, "b" => B, "c" => C, "d" => D, "e" => E, "f" => F, "g" => G,
+        "h" => H, "i" => I, "j" => J, "k" => K, "l" => L, "m" => M, "n" => N,
+        "o" => O, "p" => P, "q" => Q, "r" => R, "s" => S, "t" => T, "u" => U,
+        "v" => V, "w" => W, "x" => X, "y" => Y, "z" => Z,
+        "0" => Key0, "1" => Key1, "2" => Key2, "3" => Key3, "4" => Key4,
+        "5" => Key5, "6" => Key6, "7" => Key7, "8" => Key8, "9" => Key9,
+        "f1" => F1, "f2" => F2, "f3" => F3, "f4" => F4, "f5" => F5, "f6" => F6,
+        "f7" => F7, "f8" => F8, "f9" => F9, "f10" => F10, "f11" => F11, "f12" => F12,
+        "up" => Up,
+        "down" => Down,
+        "left" => Left,
+        "right" => Right,
+        "space" => Space,
+        "return" | "enter" => Return,
+        "escape" | "esc" => Escape,
+        "tab" => Tab,
+        "backspace" | "back" => Back,
+        "delete" => Delete,
+        "insert" => Insert,
+        "home" => Home,
+        "end" => End,
+        "pageup" => PageUp,
+        "pagedown" => PageDown,
+        "lshift" | "shift" => LShift,
+        "rshift" => RShift,
+        "lcontrol" | "lctrl" | "ctrl" => LControl,
+        "rcontrol" | "rctrl" => RControl,
+        "lalt" | "alt" => LAlt,
+        "ralt" => RAlt,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Presses and releases `key`, then ends the frame, as if it were tapped at `at`
+    fn tap(input: &mut InputManager, key: VirtualKeyCode, at: Instant) -> bool {
+        input.press_key(key, at);
+        let double_tapped = input.is_key_double_tapped(key, DOUBLE_TAP_WINDOW);
+        input.release_key(key);
+        input.update();
+        double_tapped
+    }
+
+    #[test]
+    fn key_names_are_case_insensitive_and_have_aliases() {
+        assert_eq!(key_from_name("A"), Some(VirtualKeyCode::A));
+        assert_eq!(key_from_name("f12"), Some(VirtualKeyCode::F12));
+        assert_eq!(key_from_name("7"), Some(VirtualKeyCode::Key7));
+        assert_eq!(key_from_name("Enter"), key_from_name("return"));
+        assert_eq!(key_from_name("esc"), Some(VirtualKeyCode::Escape));
+        assert_eq!(key_from_name("ctrl"), Some(VirtualKeyCode::LControl));
+        assert_eq!(key_from_name("hyper"), None);
+    }
+
+    #[test]
+    fn deadzone_is_radial_and_rescaled() {
+        assert_eq!(apply_radial_deadzone(Vec2::new(0.1, 0.1), 0.15), Vec2::ZERO);
+        // Each axis alone is inside the deadzone, but the stick as a whole is not
+        assert_ne!(apply_radial_deadzone(Vec2::new(0.12, 0.12), 0.15), Vec2::ZERO);
+
+        let half = apply_radial_deadzone(Vec2::new(0.0, 0.575), 0.15);
+        assert!((half.y - 0.5).abs() < 1e-5, "{:?}", half);
+        assert_eq!(half.x, 0.0);
+
+        let full = apply_radial_deadzone(Vec2::new(1.0, 1.0), 0.15);
+        assert!((full.length() - 1.0).abs() < 1e-5, "{:?}", full);
+        assert!((full.x - full.y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn two_quick_taps_are_a_double_tap() {
+        let mut input = InputManager::new();
+        let start = Instant::now();
+
+        assert!(!tap(&mut input, VirtualKeyCode::Space, start));
+        assert!(tap(&mut input, VirtualKeyCode::Space, start + Duration::from_millis(200)));
+        // The pair was used up, so a third quick tap starts over
+        assert!(!tap(&mut input, VirtualKeyCode::Space, start + Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn slow_taps_are_not_a_double_tap() {
+        let mut input = InputManager::new();
+        let start = Instant::now();
+
+        assert!(!tap(&mut input, VirtualKeyCode::Space, start));
+        assert!(!tap(&mut input, VirtualKeyCode::Space, start + Duration::from_millis(400)));
+    }
+
+    #[test]
+    fn held_keys_report_how_long_they_have_been_down() {
+        let mut input = InputManager::new();
+        assert_eq!(input.key_held_duration(VirtualKeyCode::Z), Duration::ZERO);
+
+        input.press_key(VirtualKeyCode::Z, Instant::now() - Duration::from_secs(1));
+        assert!(input.key_held_duration(VirtualKeyCode::Z) >= Duration::from_secs(1));
+
+        input.release_key(VirtualKeyCode::Z);
+        assert_eq!(input.key_held_duration(VirtualKeyCode::Z), Duration::ZERO);
+    }
+
+    #[test]
+    fn held_keys_repeat_after_the_delay_then_every_interval() {
+        let ms = Duration::from_millis;
+        assert!(!repeats_between(ms(0), ms(399)));
+        assert!(repeats_between(ms(399), ms(400)));
+        assert!(!repeats_between(ms(400), ms(479)));
+        assert!(repeats_between(ms(479), ms(480)));
+        assert!(repeats_between(ms(0), ms(1000)));
+    }
+
+
+    // winit still requires the deprecated modifiers field in these events
+    #[allow(deprecated)]
+    fn click(input: &mut InputManager, state: ElementState) {
+        input.handle_window_event(&WindowEvent::MouseInput {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            state,
+            button: MouseButton::Left,
+            modifiers: Default::default(),
+        });
+    }
+
+    #[allow(deprecated)]
+    fn move_cursor(input: &mut InputManager, x: f64, y: f64) {
+        input.handle_window_event(&WindowEvent::CursorMoved {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            position: winit::dpi::PhysicalPosition::new(x, y),
+            modifiers: Default::default(),
+        });
+    }
+
+    #[test]
+    fn drags_measure_from_where_the_button_went_down() {
+        let mut input = InputManager::new();
+        move_cursor(&mut input, 10.0, 10.0);
+        assert_eq!(input.get_drag_start(MouseButton::Left), None);
+
+        click(&mut input, ElementState::Pressed);
+        input.update();
+        move_cursor(&mut input, 40.0, 30.0);
+
+        assert_eq!(input.get_drag_start(MouseButton::Left), Some(Vec2::new(10.0, 10.0)));
+        assert_eq!(input.get_drag_delta(MouseButton::Left), Vec2::new(30.0, 20.0));
+    }
+
+    #[test]
+    fn drags_reset_after_release() {
+        let mut input = InputManager::new();
+        move_cursor(&mut input, 10.0, 10.0);
+        click(&mut input, ElementState::Pressed);
+        move_cursor(&mut input, 40.0, 30.0);
+        click(&mut input, ElementState::Released);
+
+        // Still readable on the frame the button came up
+        assert_eq!(input.get_drag_delta(MouseButton::Left), Vec2::new(30.0, 20.0));
+
+        input.update();
+        assert_eq!(input.get_drag_start(MouseButton::Left), None);
+        assert_eq!(input.get_drag_delta(MouseButton::Left), Vec2::ZERO);
+
+        // A new press starts a new drag from the current position
+        click(&mut input, ElementState::Pressed);
+        move_cursor(&mut input, 45.0, 30.0);
+        assert_eq!(input.get_drag_delta(MouseButton::Left), Vec2::new(5.0, 0.0));
+    }
+
+
+    #[test]
+    fn gamepad_buttons_map_to_engine_buttons_and_actions() {
+        let mut input = InputManager::new();
+        input.setup_default_mappings();
+
+        input.press_gamepad_button(Button::South);
+        assert!(input.is_gamepad_button_just_pressed(GamepadButton::A));
+        assert!(input.is_action_just_pressed("jump"));
+        input.press_gamepad_button(Button::LeftTrigger2);
+        assert!(input.is_gamepad_button_pressed(GamepadButton::LeftTrigger));
+        // Buttons the engine has no name for are ignored
+        input.press_gamepad_button(Button::Mode);
+
+        input.update();
+        assert!(input.is_gamepad_button_pressed(GamepadButton::A));
+        assert!(!input.is_gamepad_button_just_pressed(GamepadButton::A));
+
+        input.release_gamepad_button(Button::South);
+        assert!(input.is_gamepad_button_just_released(GamepadButton::A));
+        assert!(!input.is_action_pressed("jump"));
+    }
+
+    #[test]
+    fn sticks_follow_axes_and_reset_on_disconnect() {
+        let mut input = InputManager::new();
+        input.move_gamepad_axis(Axis::LeftStickX, 1.0);
+        input.move_gamepad_axis(Axis::RightStickY, -1.0);
+        // Triggers aren't sticks
+        input.move_gamepad_axis(Axis::LeftZ, 1.0);
+        assert_eq!(input.get_left_stick(), Vec2::new(1.0, 0.0));
+        assert_eq!(input.get_right_stick(), Vec2::new(0.0, -1.0));
+        assert_eq!(input.get_movement_vector(), Vec2::new(1.0, 0.0));
+
+        input.press_gamepad_button(Button::North);
+        input.handle_gamepad_event(&EventType::Disconnected);
+        assert_eq!((input.get_left_stick(), input.get_right_stick()), (Vec2::ZERO, Vec2::ZERO));
+        assert!(!input.is_gamepad_button_pressed(GamepadButton::Y));
+    }
+
+
+    #[allow(deprecated)]
+    fn press(input: &mut InputManager, key: VirtualKeyCode) {
+        input.handle_window_event(&WindowEvent::KeyboardInput {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            input: KeyboardInput { scancode: 0, state: ElementState::Pressed, virtual_keycode: Some(key), modifiers: Default::default() },
+            is_synthetic: false,
+        });
+    }
+
+    fn typed(input: &mut InputManager, c: char) {
+        input.handle_window_event(&WindowEvent::ReceivedCharacter(c));
+    }
+
+    #[test]
+    fn typed_text_is_kept_until_taken() {
+        let mut input = InputManager::new();
+        for c in ['h', 'i', '\u{8}', '\r', ' ', 'é'] {
+            typed(&mut input, c);
+        }
+        // Frames passing don't lose it, and control characters are left to the key events
+        input.update();
+        assert_eq!(input.take_text_input(), "hi é");
+        assert_eq!(input.take_text_input(), "");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rebound_keys_replace_only_the_keyboard_binding_and_persist() {
+        let path = std::env::temp_dir().join(format!("cacao_controls_{}.toml", uuid::Uuid::new_v4()));
+        let mut input = InputManager::new();
+        input.setup_default_mappings();
+
+        // S already moves down, so the rebind warns about it
+        assert_eq!(input.rebind_key("jump", VirtualKeyCode::S), vec!["move_down".to_string()]);
+        assert!(input.get_action_bindings("jump").contains(&InputButton::Key(VirtualKeyCode::S)));
+        assert_eq!(input.get_action_bindings("jump").iter().filter(|button| matches!(button, InputButton::Key(_))).count(), 1);
+        assert!(input.get_action_bindings("jump").iter().any(|button| matches!(button, InputButton::Gamepad(_))));
+        input.save_mappings(&path).unwrap();
+
+        let mut restarted = InputManager::new();
+        restarted.setup_default_mappings();
+        restarted.load_mappings(&path);
+        assert_eq!(restarted.get_action_bindings("jump"), input.get_action_bindings("jump"));
+        press(&mut restarted, VirtualKeyCode::S);
+        assert!(restarted.is_action_just_pressed("jump"));
+
+        // A broken file keeps what was there
+        std::fs::write(&path, "jump = 5").unwrap();
+        restarted.load_mappings(&path);
+        assert_eq!(restarted.get_action_bindings("jump"), input.get_action_bindings("jump"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 // ============================================================================
 // FILE: src/main.rs
 // ============================================================================
+use std::path::{Path, PathBuf};
 use log::info;
 
 mod engine;
@@ -13,13 +14,242 @@ mod crypto;
 mod saves;
 mod errors;
 
-use engine::CacaoEngine;
+use engine::{CacaoEngine, GAME_SECRET_KEY};
+use game::{packer::GamePacker, GameInfo, GameLoader, ValidationReport, run_headless};
+
+// Ticks `cacao run --headless` runs when no --frames is given: one second at the default logic rate
+const DEFAULT_HEADLESS_FRAMES: u32 = 60;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("validate") {
+        std::process::exit(validate_command(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("pack") {
+        std::process::exit(pack_command(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("run") {
+        std::process::exit(run_command(&args[1..]).await);
+    }
+
     info!("🍫 Starting Cacao Engine v1.0.0...");
+    let (data_dir, games_dir, saves_dir) = engine_dirs(&args, std::env::current_dir()?);
 
-    let engine = CacaoEngine::new().await?;
+    let engine = CacaoEngine::with_dirs(games_dir, saves_dir, data_dir).await?;
     engine.run().await;
 }
+
+/// Value given as `--flag <value>` or `--flag=<value>`.
+fn option_value(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(str::to_string)
+        }
+    })
+}
+
+/// Arguments that aren't flags, skipping the values of the `--flag <value>` options in `valued`.
+fn positional_args<'a>(args: &'a [String], valued: &[&str]) -> Vec<&'a String> {
+    let mut positional = Vec::new();
+    let mut options = args.iter();
+    while let Some(arg) = options.next() {
+        if valued.contains(&arg.as_str()) {
+            options.next();
+        } else if !arg.starts_with("--") {
+            positional.push(arg);
+        }
+    }
+    positional
+}
+
+/// Directory given as `--flag <path>` or `--flag=<path>`, falling back to the `env_var` environment variable.
+fn dir_option(args: &[String], flag: &str, env_var: &str) -> Option<PathBuf> {
+    option_value(args, flag)
+        .or_else(|| std::env::var(env_var).ok())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The data, games and saves directories, from flags or the environment. Settings, controls
+/// and screenshots live in the data dir, which also holds games/ and saves/ by default.
+fn engine_dirs(args: &[String], current_dir: PathBuf) -> (PathBuf, PathBuf, PathBuf) {
+    let data_dir = dir_option(args, "--data-dir", "CACAO_DATA_DIR").unwrap_or(current_dir);
+    let games_dir = dir_option(args, "--games-dir", "CACAO_GAMES_DIR").unwrap_or_else(|| data_dir.join("games"));
+    let saves_dir = dir_option(args, "--saves-dir", "CACAO_SAVES_DIR").unwrap_or_else(|| data_dir.join("saves"));
+    (data_dir, games_dir, saves_dir)
+}
+
+/// `cacao run --headless <file.gaem> [--frames N] [--saves-dir DIR]`: runs a game with no window,
+/// audio device or input and prints its save data at the end. Exits with 0 when the game ran
+/// cleanly, 1 when it failed and 2 on bad usage. Saves go to a fresh temp directory unless
+/// a saves dir is given, so a test run never touches real save files.
+async fn run_command(args: &[String]) -> i32 {
+    const USAGE: &str = "Usage: cacao run --headless <file.gaem> [--frames N] [--saves-dir DIR]";
+
+    let positional = positional_args(args, &["--frames", "--saves-dir"]);
+    let ([file], true) = (positional.as_slice(), args.iter().any(|arg| arg == "--headless")) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let frames = match option_value(args, "--frames") {
+        None => DEFAULT_HEADLESS_FRAMES,
+        Some(frames) => match frames.parse() {
+            Ok(frames) => frames,
+            Err(_) => {
+                eprintln!("--frames takes a whole number\n{}", USAGE);
+                return 2;
+            }
+        },
+    };
+    let saves_dir = dir_option(args, "--saves-dir", "CACAO_SAVES_DIR")
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("cacao_headless_{}", std::process::id())));
+
+    println!("🧪 Running {} headlessly for {} frames", file, frames);
+    match run_headless(Path::new(file), GAME_SECRET_KEY, saves_dir, frames).await {
+        Ok(saves) => {
+            let mut keys: Vec<&String> = saves.keys().collect();
+            keys.sort();
+            for key in keys {
+                println!("   {} = {:?}", key, saves[key]);
+            }
+            println!("PASS: {} frames ran without errors", frames);
+            0
+        }
+        Err(e) => {
+            println!("FAIL: {}", e);
+            1
+        }
+    }
+}
+
+/// `cacao validate <file.gaem>`: checks a package without running it. Exits with 0 when it
+/// passes, 1 when it doesn't and 2 on bad usage.
+fn validate_command(args: &[String]) -> i32 {
+    let [file] = args else {
+        eprintln!("Usage: cacao validate <file.gaem>");
+        return 2;
+    };
+    let file = Path::new(file);
+
+    // Loose assets are looked up next to the package, like the headless runner does
+    let games_dir = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let report = GameLoader::new(games_dir, GAME_SECRET_KEY.to_string()).validate(file);
+
+    println!("🔍 Validating {}", file.display());
+    print_report(&report)
+}
+
+/// `cacao pack <game folder> [--out FILE] [--title TITLE] [--author AUTHOR]`: packs every
+/// asset in a folder into a signed .gaem, by default next to the folder. The folder needs a
+/// main.lua. Exits with 0 when the package was written, 1 when packing failed and 2 on bad usage.
+fn pack_command(args: &[String]) -> i32 {
+    const USAGE: &str = "Usage: cacao pack <game folder> [--out FILE] [--title TITLE] [--author AUTHOR]";
+
+    let [folder] = positional_args(args, &["--out", "--title", "--author"])[..] else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let folder = Path::new(folder);
+    let Some(folder_name) = folder.file_name().map(|name| name.to_string_lossy().to_string()) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let title = option_value(args, "--title").unwrap_or(folder_name);
+    let author = option_value(args, "--author").unwrap_or_else(|| "Unknown".to_string());
+    let output = option_value(args, "--out").map(PathBuf::from).unwrap_or_else(|| folder.with_extension("gaem"));
+
+    println!("📦 Packing {}", folder.display());
+    let packed = GamePacker::new(&output)
+        .set_info(GameInfo::new(title, author))
+        .set_secret_key(GAME_SECRET_KEY)
+        .add_directory(folder)
+        .and_then(GamePacker::build);
+    match packed {
+        Ok(info) => {
+            for asset in &info.required_assets {
+                println!("   {} ({} bytes)", asset.path, asset.size);
+            }
+            println!("PASS: {} v{} written to {}", info.title, info.version, output.display());
+            0
+        }
+        Err(e) => {
+            println!("FAIL: {}", e);
+            1
+        }
+    }
+}
+
+/// Prints a validation report and returns the exit code for it: 0 if it passed, 1 if not.
+fn print_report(report: &ValidationReport) -> i32 {
+    if let Some(info) = &report.info {
+        println!("   {} v{} by {} (engine {})", info.title, info.version, info.author, info.engine_version);
+    }
+    for (path, error) in &report.assets {
+        match error {
+            None => println!("✅ {}", path),
+            Some(error) => println!("❌ {}: {}", path, error),
+        }
+    }
+    for warning in &report.warnings {
+        println!("⚠️ {}", warning);
+    }
+    for problem in &report.problems {
+        println!("❌ {}", problem);
+    }
+
+    if report.passed() {
+        println!("PASS: {} assets verified", report.assets.len());
+        0
+    } else {
+        let failed = report.problems.len() + report.assets.iter().filter(|(_, error)| error.is_some()).count();
+        println!("FAIL: {} problem(s) found", failed);
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn games_and_saves_default_to_the_data_dir() {
+        let (data, games, saves) = engine_dirs(&args(&["--data-dir", "/srv/cacao"]), PathBuf::from("/home/player"));
+        assert_eq!(data, PathBuf::from("/srv/cacao"));
+        assert_eq!(games, PathBuf::from("/srv/cacao/games"));
+        assert_eq!(saves, PathBuf::from("/srv/cacao/saves"));
+    }
+
+    #[test]
+    fn each_dir_can_be_given_on_its_own() {
+        let given = args(&["--data-dir=/data", "--games-dir", "/library", "--saves-dir=/saves"]);
+        let (data, games, saves) = engine_dirs(&given, PathBuf::from("/home/player"));
+        assert_eq!((data, games, saves), (PathBuf::from("/data"), PathBuf::from("/library"), PathBuf::from("/saves")));
+    }
+
+    #[test]
+    fn flags_win_over_the_environment() {
+        let var = format!("CACAO_TEST_DIR_{}", std::process::id());
+        assert_eq!(dir_option(&[], "--games-dir", &var), None);
+
+        std::env::set_var(&var, "/from/env");
+        assert_eq!(dir_option(&[], "--games-dir", &var), Some(PathBuf::from("/from/env")));
+        assert_eq!(dir_option(&args(&["--games-dir", "/from/flag"]), "--games-dir", &var), Some(PathBuf::from("/from/flag")));
+
+        // An empty value is the same as not setting it
+        std::env::set_var(&var, "");
+        assert_eq!(dir_option(&[], "--games-dir", &var), None);
+        std::env::remove_var(&var);
+    }
+}
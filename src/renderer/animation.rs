@@ -0,0 +1,107 @@
+// src/renderer/animation.rs
+use crate::errors::CacaoError;
+use super::{Sprite, Texture};
+
+/// Frames cut from one sprite sheet, shown one after another at a fixed rate.
+/// Non-looping animations stop on their last frame.
+pub struct AnimatedSprite {
+    frames: Vec<Sprite>,
+    frame_duration: f32,
+    looping: bool,
+    elapsed: f32,
+}
+
+impl AnimatedSprite {
+    /// `regions` are (x, y, width, height) pixel rects of `texture`, in play order.
+    pub fn new(texture: &Texture, regions: &[[u32; 4]], frame_duration: f32, looping: bool) -> Result<Self, CacaoError> {
+        if regions.is_empty() {
+            return Err(CacaoError::RenderError("Animation needs at least one frame".to_string()));
+        }
+        if frame_duration <= 0.0 {
+            return Err(CacaoError::RenderError(format!("Invalid frame duration: {}", frame_duration)));
+        }
+
+        let frames = regions
+            .iter()
+            .map(|&[x, y, width, height]| Sprite::with_region(texture.clone(), x, y, width, height))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { frames, frame_duration, looping, elapsed: 0.0 })
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let total = self.total_duration();
+        self.elapsed += dt;
+        if self.looping {
+            self.elapsed %= total;
+        } else {
+            self.elapsed = self.elapsed.min(total);
+        }
+    }
+
+    pub fn current_frame(&self) -> usize {
+        ((self.elapsed / self.frame_duration) as usize).min(self.frames.len() - 1)
+    }
+
+    pub fn current_sprite(&self) -> &Sprite {
+        &self.frames[self.current_frame()]
+    }
+
+    pub fn total_duration(&self) -> f32 {
+        self.frame_duration * self.frames.len() as f32
+    }
+
+    /// Only a non-looping animation ever finishes.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.elapsed >= self.total_duration()
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three 10x10 frames side by side
+    fn animation(looping: bool) -> AnimatedSprite {
+        let (device, queue) = crate::renderer::test_gpu();
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(30, 10));
+        let texture = Texture::from_image(&device, &queue, &image, None).unwrap();
+        AnimatedSprite::new(&texture, &[[0, 0, 10, 10], [10, 0, 10, 10], [20, 0, 10, 10]], 0.1, looping).unwrap()
+    }
+
+    #[test]
+    fn looping_animations_wrap_around() {
+        let mut walk = animation(true);
+        assert_eq!(walk.current_frame(), 0);
+        walk.update(0.15);
+        assert_eq!(walk.current_frame(), 1);
+        walk.update(0.2);
+        assert_eq!(walk.current_frame(), 0);
+        assert!(!walk.is_finished());
+    }
+
+    #[test]
+    fn one_shot_animations_hold_their_last_frame() {
+        let mut attack = animation(false);
+        attack.update(1.0);
+        assert_eq!(attack.current_frame(), 2);
+        assert!(attack.is_finished());
+
+        attack.reset();
+        assert_eq!(attack.current_frame(), 0);
+        assert!(!attack.is_finished());
+    }
+
+    #[test]
+    fn animations_need_frames_and_a_positive_rate() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(10, 10));
+        let texture = Texture::from_image(&device, &queue, &image, None).unwrap();
+        assert!(AnimatedSprite::new(&texture, &[], 0.1, true).is_err());
+        assert!(AnimatedSprite::new(&texture, &[[0, 0, 10, 10]], 0.0, true).is_err());
+    }
+}
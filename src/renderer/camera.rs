@@ -1,5 +1,17 @@
 // src/renderer/camera.rs
 use glam::{Mat4, Vec2, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Which matrix a draw goes through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoordinateSpace {
+    /// Centred on the camera position with y up; moves, zooms and rotates with the camera
+    #[default]
+    World,
+    /// Pixels from the top-left corner with y down, whatever the camera does. The engine's
+    /// menus and overlays draw in this space, and games use it for HUDs
+    Screen,
+}
 
 #[derive(Debug, Clone)]
 pub struct Camera {
@@ -11,7 +23,14 @@ pub struct Camera {
     view_matrix: Mat4,
     projection_matrix: Mat4,
     view_projection_matrix: Mat4,
+    screen_matrix: Mat4,
     dirty: bool,
+    bounds: Option<(Vec2, Vec2)>,
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_elapsed: f32,
+    shake_offset: Vec2,
+    shake_rng: StdRng,
 }
 
 impl Camera {
@@ -25,17 +44,61 @@ impl Camera {
             view_matrix: Mat4::IDENTITY,
             projection_matrix: Mat4::IDENTITY,
             view_projection_matrix: Mat4::IDENTITY,
+            screen_matrix: Mat4::IDENTITY,
             dirty: true,
+            bounds: None,
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_elapsed: 0.0,
+            shake_offset: Vec2::ZERO,
+            shake_rng: StdRng::from_entropy(),
         };
         camera.update_matrices();
         camera
     }
 
     pub fn set_position(&mut self, position: Vec2) {
-        self.position = position;
+        self.position = self.clamp_to_bounds(position);
         self.dirty = true;
     }
 
+    /// Moves a fraction (`smoothing * dt`) of the way towards `target` each call.
+    pub fn follow_target(&mut self, target: Vec2, smoothing: f32, dt: f32) {
+        let t = (smoothing * dt).clamp(0.0, 1.0);
+        self.set_position(self.position.lerp(target, t));
+    }
+
+    /// Keeps the visible area inside the `min`..`max` world rectangle.
+    pub fn set_bounds(&mut self, min: Vec2, max: Vec2) {
+        self.bounds = Some((min.min(max), min.max(max)));
+        self.set_position(self.position);
+    }
+
+    pub fn clear_bounds(&mut self) {
+        self.bounds = None;
+    }
+
+    fn clamp_to_bounds(&self, position: Vec2) -> Vec2 {
+        let Some((min, max)) = self.bounds else {
+            return position;
+        };
+
+        let half_view = Vec2::new(self.viewport_width, self.viewport_height) / (2.0 * self.zoom);
+        let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+            // A level smaller than the view (or a zero-size rect) pins the camera to its centre
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                value.clamp(min + half, max - half)
+            }
+        };
+
+        Vec2::new(
+            clamp_axis(position.x, min.x, max.x, half_view.x),
+            clamp_axis(position.y, min.y, max.y, half_view.y),
+        )
+    }
+
     pub fn set_zoom(&mut self, zoom: f32) {
         self.zoom = zoom.max(0.1); // Prevent negative/zero zoom
         self.dirty = true;
@@ -52,11 +115,59 @@ impl Camera {
         self.dirty = true;
     }
 
+    pub fn viewport_size(&self) -> Vec2 {
+        Vec2::new(self.viewport_width, self.viewport_height)
+    }
+
     pub fn translate(&mut self, delta: Vec2) {
-        self.position += delta;
+        self.set_position(self.position + delta);
+    }
+
+    /// Starts a shake of up to `intensity` pixels that decays to nothing over `duration` seconds.
+    /// A weaker shake never cuts a stronger one short.
+    pub fn add_shake(&mut self, intensity: f32, duration: f32) {
+        let remaining = (self.shake_duration - self.shake_elapsed).max(0.0);
+        self.shake_intensity = self.current_shake_strength().max(intensity.max(0.0));
+        self.shake_duration = remaining.max(duration.max(0.0));
+        self.shake_elapsed = 0.0;
+    }
+
+    /// Makes the shake directions repeatable from here on.
+    pub fn set_shake_seed(&mut self, seed: u64) {
+        self.shake_rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn get_shake_offset(&self) -> Vec2 {
+        self.shake_offset
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.shake_duration <= 0.0 {
+            return;
+        }
+
+        self.shake_elapsed += dt;
+        if self.shake_elapsed >= self.shake_duration {
+            self.shake_intensity = 0.0;
+            self.shake_duration = 0.0;
+            self.shake_elapsed = 0.0;
+            self.shake_offset = Vec2::ZERO;
+        } else {
+            let angle = self.shake_rng.gen_range(0.0..std::f32::consts::TAU);
+            self.shake_offset = Vec2::from_angle(angle) * self.current_shake_strength();
+        }
         self.dirty = true;
     }
 
+    fn current_shake_strength(&self) -> f32 {
+        if self.shake_duration <= 0.0 {
+            return 0.0;
+        }
+        // Quadratic falloff so the shake eases out instead of stopping abruptly
+        let remaining = 1.0 - (self.shake_elapsed / self.shake_duration).min(1.0);
+        self.shake_intensity * remaining * remaining
+    }
+
     pub fn get_view_projection_matrix(&mut self) -> Mat4 {
         if self.dirty {
             self.update_matrices();
@@ -64,6 +175,22 @@ impl Camera {
         self.view_projection_matrix
     }
 
+    /// Pixel ortho with (0, 0) at the top-left and (width, height) at the bottom-right of
+    /// the viewport. Ignores position, zoom, rotation and shake.
+    pub fn get_screen_projection_matrix(&mut self) -> Mat4 {
+        if self.dirty {
+            self.update_matrices();
+        }
+        self.screen_matrix
+    }
+
+    pub fn matrix_for(&mut self, space: CoordinateSpace) -> Mat4 {
+        match space {
+            CoordinateSpace::World => self.get_view_projection_matrix(),
+            CoordinateSpace::Screen => self.get_screen_projection_matrix(),
+        }
+    }
+
     fn update_matrices(&mut self) {
         // Create orthographic projection matrix
         let left = -self.viewport_width / 2.0;
@@ -74,12 +201,14 @@ impl Camera {
         self.projection_matrix = Mat4::orthographic_rh(left, right, bottom, top, -1000.0, 1000.0);
 
         // Create view matrix
-        let translation = Mat4::from_translation(Vec3::new(-self.position.x, -self.position.y, 0.0));
+        let eye = self.position + self.shake_offset;
+        let translation = Mat4::from_translation(Vec3::new(-eye.x, -eye.y, 0.0));
         let rotation = Mat4::from_rotation_z(-self.rotation);
         let scale = Mat4::from_scale(Vec3::new(self.zoom, self.zoom, 1.0));
         
         self.view_matrix = scale * rotation * translation;
         self.view_projection_matrix = self.projection_matrix * self.view_matrix;
+        self.screen_matrix = Mat4::orthographic_rh(0.0, self.viewport_width, self.viewport_height, 0.0, -1000.0, 1000.0);
         self.dirty = false;
     }
 
@@ -95,4 +224,109 @@ impl Camera {
         
         world_pos
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    fn shake_offsets(seed: u64, frames: usize) -> Vec<Vec2> {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.set_shake_seed(seed);
+        camera.add_shake(10.0, 1.0);
+        (0..frames)
+            .map(|_| {
+                camera.update(FRAME);
+                camera.get_shake_offset()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn seeded_shake_is_repeatable() {
+        assert_eq!(shake_offsets(7, 30), shake_offsets(7, 30));
+        assert_ne!(shake_offsets(7, 30), shake_offsets(8, 30));
+    }
+
+    #[test]
+    fn shake_decays_and_stops() {
+        let offsets = shake_offsets(1, 70);
+        assert!(offsets[0].length() > 9.0 && offsets[0].length() <= 10.0);
+        assert!(offsets[50].length() < offsets[0].length());
+        assert_eq!(*offsets.last().unwrap(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn weaker_shake_does_not_cut_a_stronger_one_short() {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.add_shake(10.0, 1.0);
+        camera.add_shake(1.0, 0.1);
+        camera.update(0.5);
+        assert!(camera.get_shake_offset().length() > 1.0);
+    }
+
+    #[test]
+    fn follow_moves_part_of_the_way_each_step() {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.follow_target(Vec2::new(100.0, 0.0), 5.0, 0.1);
+        assert_eq!(camera.position, Vec2::new(50.0, 0.0));
+        camera.follow_target(Vec2::new(100.0, 0.0), 5.0, 0.1);
+        assert_eq!(camera.position, Vec2::new(75.0, 0.0));
+
+        // A long frame snaps to the target instead of overshooting
+        camera.follow_target(Vec2::new(100.0, 0.0), 5.0, 1.0);
+        assert_eq!(camera.position, Vec2::new(100.0, 0.0));
+    }
+
+    #[test]
+    fn bounds_keep_the_view_inside_the_level() {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.set_bounds(Vec2::ZERO, Vec2::new(2000.0, 1000.0));
+        assert_eq!(camera.position, Vec2::new(400.0, 300.0));
+
+        camera.set_position(Vec2::new(5000.0, -50.0));
+        assert_eq!(camera.position, Vec2::new(1600.0, 300.0));
+
+        // Zoomed in, the view is smaller and can get closer to the edge
+        camera.set_zoom(2.0);
+        camera.set_position(Vec2::new(5000.0, -50.0));
+        assert_eq!(camera.position, Vec2::new(1800.0, 150.0));
+    }
+
+    #[test]
+    fn a_level_smaller_than_the_view_is_centred() {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.set_bounds(Vec2::new(100.0, 100.0), Vec2::new(300.0, 1100.0));
+        camera.set_position(Vec2::ZERO);
+        assert_eq!(camera.position, Vec2::new(200.0, 400.0));
+
+        camera.clear_bounds();
+        camera.set_position(Vec2::ZERO);
+        assert_eq!(camera.position, Vec2::ZERO);
+    }
+
+
+    #[test]
+    fn screen_space_is_top_left_pixels_whatever_the_camera_does() {
+        let mut camera = Camera::new(800.0, 600.0);
+        camera.set_position(Vec2::new(300.0, -200.0));
+        camera.set_zoom(3.0);
+        camera.set_rotation(1.0);
+        camera.add_shake(10.0, 1.0);
+        camera.update(FRAME);
+
+        let screen = camera.matrix_for(CoordinateSpace::Screen);
+        let project = |matrix: Mat4, x: f32, y: f32| matrix.project_point3(Vec3::new(x, y, 0.0)).truncate();
+        assert!(project(screen, 0.0, 0.0).abs_diff_eq(Vec2::new(-1.0, 1.0), 1e-6));
+        assert!(project(screen, 800.0, 600.0).abs_diff_eq(Vec2::new(1.0, -1.0), 1e-6));
+        assert!(project(screen, 400.0, 300.0).abs_diff_eq(Vec2::ZERO, 1e-6));
+
+        // The world view is centred on the camera
+        let mut still = Camera::new(800.0, 600.0);
+        still.set_position(Vec2::new(300.0, -200.0));
+        let world = still.matrix_for(CoordinateSpace::World);
+        assert!(project(world, 300.0, -200.0).abs_diff_eq(Vec2::ZERO, 1e-6));
+    }
+}
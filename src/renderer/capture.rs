@@ -0,0 +1,125 @@
+// src/renderer/capture.rs
+use image::RgbaImage;
+use crate::errors::CacaoError;
+
+/// Copies a 4-byte-per-pixel texture back to the CPU. The texture needs `COPY_SRC` usage;
+/// BGRA formats are swizzled so the image always comes out RGBA.
+pub fn read_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<RgbaImage, CacaoError> {
+    let width = texture.width();
+    let height = texture.height();
+    let format = texture.format();
+    if format.block_size(None) != Some(4) {
+        return Err(CacaoError::RenderError(format!("Can't capture {:?} textures", format)));
+    }
+
+    // Buffer rows have to be a multiple of 256 bytes, wider than the image itself
+    let row_bytes = width * 4;
+    let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: (padded_row_bytes * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Capture Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .map_err(|_| CacaoError::RenderError("Capture buffer was never mapped".to_string()))?
+        .map_err(|e| CacaoError::RenderError(format!("Failed to read captured frame: {}", e)))?;
+
+    let swap_red_blue = matches!(format, wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb);
+    let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_row_bytes as usize) {
+            for pixel in row[..row_bytes as usize].chunks_exact(4) {
+                if swap_red_blue {
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                } else {
+                    pixels.extend_from_slice(pixel);
+                }
+            }
+        }
+    }
+    buffer.unmap();
+
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| CacaoError::RenderError("Captured frame has the wrong size".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three pixels wide, so rows need padding up to the copy alignment
+    fn captured(format: wgpu::TextureFormat, texels: &[u8]) -> Result<RgbaImage, CacaoError> {
+        let (device, queue) = crate::renderer::test_gpu();
+        let size = wgpu::Extent3d { width: 3, height: 2, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let bytes_per_row = texels.len() as u32 / 2;
+        queue.write_texture(
+            texture.as_image_copy(),
+            texels,
+            wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(bytes_per_row), rows_per_image: Some(2) },
+            size,
+        );
+        read_texture(&device, &queue, &texture)
+    }
+
+    fn texels() -> Vec<u8> {
+        (0..24).collect()
+    }
+
+    #[test]
+    fn rgba_textures_come_back_unpadded() {
+        let image = captured(wgpu::TextureFormat::Rgba8Unorm, &texels()).unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.into_raw(), texels());
+    }
+
+    #[test]
+    fn bgra_textures_are_swizzled_to_rgba() {
+        let image = captured(wgpu::TextureFormat::Bgra8Unorm, &texels()).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [2, 1, 0, 3]);
+        assert_eq!(image.get_pixel(2, 1).0, [22, 21, 20, 23]);
+    }
+
+    #[test]
+    fn other_formats_are_refused() {
+        let error = captured(wgpu::TextureFormat::R8Unorm, &[0; 6]).unwrap_err();
+        assert!(error.to_string().contains("R8Unorm"), "{}", error);
+    }
+}
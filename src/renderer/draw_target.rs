@@ -0,0 +1,238 @@
+// src/renderer/draw_target.rs
+use crate::errors::CacaoError;
+use super::{NinePatchInsets, Renderer, Sprite, TextAlign};
+
+/// The drawing calls a game makes, so it can run against the window's `Renderer`,
+/// against `NullRenderer` when there is no window or GPU, or against `RecordingTarget` in tests.
+pub trait DrawTarget {
+    fn set_layer(&mut self, layer: i32);
+    /// Draws after this are in screen pixels from the top-left, unaffected by the camera.
+    fn begin_screen_space(&mut self);
+    /// Draws after this go through the camera again.
+    fn begin_world_space(&mut self);
+    fn draw_sprite(&mut self, sprite: &Sprite, x: f32, y: f32, rotation: f32, scale: f32) -> Result<(), CacaoError>;
+    /// Stretches `sprite` over a box without stretching its corners; see `NinePatchInsets`.
+    fn draw_nine_patch(&mut self, sprite: &Sprite, x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets) -> Result<(), CacaoError>;
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) -> Result<(), CacaoError>;
+    fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Result<(), CacaoError>;
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError>;
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, segments: u32, color: [f32; 4]) -> Result<(), CacaoError>;
+    /// Width and height `text` would take up if drawn at `size`. Targets without a font
+    /// atlas fall back to the built-in bitmap font's fixed advance.
+    fn measure_text(&mut self, text: &str, size: f32) -> (f32, f32) {
+        estimate_text_size(text, size)
+    }
+
+    /// Each line of `text` is aligned on its own.
+    fn draw_text_aligned(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], align: TextAlign) -> Result<(), CacaoError> {
+        let mut line_y = y;
+        for line in text.split('\n') {
+            let (width, height) = self.measure_text(line, size);
+            self.draw_text(line, x + align.offset(width), line_y, size, color)?;
+            line_y += height;
+        }
+        Ok(())
+    }
+}
+
+impl DrawTarget for Renderer {
+    fn set_layer(&mut self, layer: i32) {
+        Renderer::set_layer(self, layer);
+    }
+
+    fn begin_screen_space(&mut self) {
+        Renderer::begin_screen_space(self);
+    }
+
+    fn begin_world_space(&mut self) {
+        Renderer::begin_world_space(self);
+    }
+
+    fn draw_sprite(&mut self, sprite: &Sprite, x: f32, y: f32, rotation: f32, scale: f32) -> Result<(), CacaoError> {
+        Renderer::draw_sprite(self, sprite, x, y, rotation, scale)
+    }
+
+    fn draw_nine_patch(&mut self, sprite: &Sprite, x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets) -> Result<(), CacaoError> {
+        Renderer::draw_nine_patch(self, sprite, x, y, width, height, insets)
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        Renderer::draw_text(self, text, x, y, size, color)
+    }
+
+    fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        Renderer::draw_rect(self, x, y, width, height, color)
+    }
+
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        Renderer::draw_line(self, x1, y1, x2, y2, thickness, color)
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, segments: u32, color: [f32; 4]) -> Result<(), CacaoError> {
+        Renderer::draw_circle(self, x, y, radius, segments, color)
+    }
+
+    fn measure_text(&mut self, text: &str, size: f32) -> (f32, f32) {
+        Renderer::measure_text(self, text, size)
+    }
+
+    fn draw_text_aligned(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], align: TextAlign) -> Result<(), CacaoError> {
+        Renderer::draw_text_aligned(self, text, x, y, size, color, align)
+    }
+}
+
+/// Accepts every draw and does nothing with it.
+pub struct NullRenderer;
+
+impl DrawTarget for NullRenderer {
+    fn set_layer(&mut self, _layer: i32) {}
+
+    fn begin_screen_space(&mut self) {}
+
+    fn begin_world_space(&mut self) {}
+
+    fn draw_sprite(&mut self, _sprite: &Sprite, _x: f32, _y: f32, _rotation: f32, _scale: f32) -> Result<(), CacaoError> {
+        Ok(())
+    }
+
+    fn draw_nine_patch(&mut self, _sprite: &Sprite, _x: f32, _y: f32, _width: f32, _height: f32, _insets: NinePatchInsets) -> Result<(), CacaoError> {
+        Ok(())
+    }
+
+    fn draw_text(&mut self, _text: &str, _x: f32, _y: f32, _size: f32, _color: [f32; 4]) -> Result<(), CacaoError> {
+        Ok(())
+    }
+
+    fn draw_rect(&mut self, _x: f32, _y: f32, _width: f32, _height: f32, _color: [f32; 4]) -> Result<(), CacaoError> {
+        Ok(())
+    }
+
+    fn draw_line(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, _thickness: f32, _color: [f32; 4]) -> Result<(), CacaoError> {
+        Ok(())
+    }
+
+    fn draw_circle(&mut self, _x: f32, _y: f32, _radius: f32, _segments: u32, _color: [f32; 4]) -> Result<(), CacaoError> {
+        Ok(())
+    }
+}
+
+/// One call captured by `RecordingTarget`.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    SetLayer(i32),
+    SetSpace(super::CoordinateSpace),
+    Sprite { x: f32, y: f32, width: f32, height: f32, rotation: f32, scale: f32 },
+    NinePatch { x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets },
+    Text { text: String, x: f32, y: f32, size: f32, color: [f32; 4] },
+    Rect { x: f32, y: f32, width: f32, height: f32, color: [f32; 4] },
+    Line { x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 4] },
+    Circle { x: f32, y: f32, radius: f32, segments: u32, color: [f32; 4] },
+}
+
+/// Keeps every draw in order, so tests can check exactly what a script drew.
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingTarget {
+    pub commands: Vec<DrawCommand>,
+}
+
+#[cfg(test)]
+impl RecordingTarget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+impl DrawTarget for RecordingTarget {
+    fn set_layer(&mut self, layer: i32) {
+        self.commands.push(DrawCommand::SetLayer(layer));
+    }
+
+    fn begin_screen_space(&mut self) {
+        self.commands.push(DrawCommand::SetSpace(super::CoordinateSpace::Screen));
+    }
+
+    fn begin_world_space(&mut self) {
+        self.commands.push(DrawCommand::SetSpace(super::CoordinateSpace::World));
+    }
+
+    fn draw_sprite(&mut self, sprite: &Sprite, x: f32, y: f32, rotation: f32, scale: f32) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::Sprite { x, y, width: sprite.width, height: sprite.height, rotation, scale });
+        Ok(())
+    }
+
+    fn draw_nine_patch(&mut self, _sprite: &Sprite, x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::NinePatch { x, y, width, height, insets });
+        Ok(())
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::Text { text: text.to_string(), x, y, size, color });
+        Ok(())
+    }
+
+    fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::Rect { x, y, width, height, color });
+        Ok(())
+    }
+
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::Line { x1, y1, x2, y2, thickness, color });
+        Ok(())
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, segments: u32, color: [f32; 4]) -> Result<(), CacaoError> {
+        self.commands.push(DrawCommand::Circle { x, y, radius, segments, color });
+        Ok(())
+    }
+}
+
+/// Size of `text` in the built-in bitmap font, for targets without a font atlas.
+fn estimate_text_size(text: &str, size: f32) -> (f32, f32) {
+    let mut width: f32 = 0.0;
+    let mut lines = 0;
+    for line in text.split('\n') {
+        let line_width: f32 = line.chars()
+            .map(|ch| match ch {
+                '\t' => size * 4.0,
+                ' ' => size * 0.5,
+                _ => size,
+            })
+            .sum();
+        width = width.max(line_width);
+        lines += 1;
+    }
+    (width, lines as f32 * size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitmap_width_is_char_count_times_advance() {
+        assert_eq!(NullRenderer.measure_text("CACAO", 8.0), (40.0, 8.0));
+        assert_eq!(NullRenderer.measure_text("A B", 10.0), (25.0, 10.0));
+    }
+
+    #[test]
+    fn each_line_adds_height_and_the_widest_line_wins() {
+        assert_eq!(NullRenderer.measure_text("AB\nABCD\n", 8.0), (32.0, 24.0));
+        assert_eq!(NullRenderer.measure_text("", 8.0), (0.0, 8.0));
+    }
+
+    #[test]
+    fn aligned_text_is_offset_by_its_measured_width() {
+        let mut target = RecordingTarget::new();
+        target.draw_text_aligned("AB\nABCD", 100.0, 0.0, 8.0, [1.0; 4], TextAlign::Right).unwrap();
+        let xs: Vec<f32> = target.commands.iter()
+            .map(|command| match command {
+                DrawCommand::Text { x, .. } => *x,
+                other => panic!("unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(xs, vec![84.0, 68.0]);
+    }
+}
@@ -0,0 +1,141 @@
+// src/renderer/layer.rs
+use std::ops::Range;
+use super::camera::CoordinateSpace;
+
+/// A stretch of reordered draws: (layer, space, queue range).
+pub type LayerDraw = (i32, CoordinateSpace, Range<u32>);
+
+/// Remembers which draw layer and coordinate space each stretch of a renderer's queue
+/// was submitted with. Layers are replayed lowest first; draws on the same layer keep
+/// submission order.
+pub struct LayerRuns {
+    // (layer, space, first queue index of the run)
+    runs: Vec<(i32, CoordinateSpace, usize)>,
+}
+
+impl LayerRuns {
+    pub fn new() -> Self {
+        Self { runs: vec![(0, CoordinateSpace::World, 0)] }
+    }
+
+    pub fn set_layer(&mut self, layer: i32, queue_len: usize) {
+        let (_, space, _) = *self.runs.last().unwrap();
+        self.start_run(layer, space, queue_len);
+    }
+
+    pub fn set_space(&mut self, space: CoordinateSpace, queue_len: usize) {
+        let (layer, _, _) = *self.runs.last().unwrap();
+        self.start_run(layer, space, queue_len);
+    }
+
+    /// The space draws queued now will be in.
+    pub fn space(&self) -> CoordinateSpace {
+        self.runs.last().unwrap().1
+    }
+
+    fn start_run(&mut self, layer: i32, space: CoordinateSpace, queue_len: usize) {
+        let (current_layer, current_space, start) = *self.runs.last().unwrap();
+        if current_layer == layer && current_space == space {
+            return;
+        }
+
+        // Nothing was queued in the previous run, so just replace it
+        if start == queue_len {
+            self.runs.pop();
+            if self.runs.last().is_some_and(|&(previous_layer, previous_space, _)| previous_layer == layer && previous_space == space) {
+                return;
+            }
+        }
+        self.runs.push((layer, space, queue_len));
+    }
+
+    /// Copies `items` into layer order and returns the range each layer occupies, split
+    /// wherever the coordinate space changes.
+    pub fn reorder<T: Clone>(&self, items: &[T]) -> (Vec<T>, Vec<LayerDraw>) {
+        let mut ranges: Vec<(i32, CoordinateSpace, Range<usize>)> = self.runs
+            .iter()
+            .enumerate()
+            .map(|(i, &(layer, space, start))| {
+                let end = self.runs.get(i + 1).map_or(items.len(), |&(_, _, next)| next);
+                (layer, space, start..end.min(items.len()))
+            })
+            .filter(|(_, _, range)| !range.is_empty())
+            .collect();
+        // Stable, so equal layers stay in submission order
+        ranges.sort_by_key(|(layer, _, _)| *layer);
+
+        let mut ordered = Vec::with_capacity(items.len());
+        let mut layers: Vec<LayerDraw> = Vec::new();
+        for (layer, space, range) in ranges {
+            let start = ordered.len() as u32;
+            ordered.extend_from_slice(&items[range]);
+            let end = ordered.len() as u32;
+
+            match layers.last_mut() {
+                Some((last_layer, last_space, last_range)) if *last_layer == layer && *last_space == space => last_range.end = end,
+                _ => layers.push((layer, space, start..end)),
+            }
+        }
+
+        (ordered, layers)
+    }
+
+    pub fn reset(&mut self) {
+        self.runs.clear();
+        self.runs.push((0, CoordinateSpace::World, 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CoordinateSpace::*;
+
+    #[test]
+    fn layers_replay_lowest_first_in_submission_order() {
+        let mut runs = LayerRuns::new();
+        let mut queue = vec!["a"];
+        runs.set_layer(2, queue.len());
+        queue.push("b");
+        runs.set_layer(-1, queue.len());
+        queue.push("c");
+        runs.set_layer(0, queue.len());
+        queue.push("d");
+        runs.set_layer(2, queue.len());
+        queue.push("e");
+
+        let (ordered, layers) = runs.reorder(&queue);
+        assert_eq!(ordered, vec!["c", "a", "d", "b", "e"]);
+        assert_eq!(layers, vec![(-1, World, 0..1), (0, World, 1..3), (2, World, 3..5)]);
+    }
+
+    #[test]
+    fn space_changes_split_a_layer() {
+        let mut runs = LayerRuns::new();
+        let mut queue = vec![1];
+        runs.set_space(Screen, queue.len());
+        queue.push(2);
+        runs.set_space(World, queue.len());
+        queue.push(3);
+
+        let (ordered, layers) = runs.reorder(&queue);
+        assert_eq!(ordered, vec![1, 2, 3]);
+        assert_eq!(layers, vec![(0, World, 0..1), (0, Screen, 1..2), (0, World, 2..3)]);
+    }
+
+    #[test]
+    fn empty_runs_are_dropped() {
+        let mut runs = LayerRuns::new();
+        runs.set_layer(5, 0);
+        runs.set_layer(3, 0);
+        runs.set_space(Screen, 0);
+        runs.set_layer(0, 0);
+        runs.set_space(World, 0);
+        assert_eq!(runs.runs, vec![(0, World, 0)]);
+
+        runs.set_layer(1, 0);
+        let (ordered, layers) = runs.reorder(&["x"]);
+        assert_eq!(ordered, vec!["x"]);
+        assert_eq!(layers, vec![(1, World, 0..1)]);
+    }
+}
@@ -5,26 +5,44 @@ pub mod sprite;
 pub mod camera;
 pub mod text;
 pub mod primitive;
+pub mod animation;
+pub mod particles;
+pub mod post;
+mod render_target;
+mod layer;
+mod draw_target;
+mod view_uniforms;
+mod capture;
 
+use image::RgbaImage;
 use winit::window::Window;
 use crate::errors::CacaoError;
 
-pub use texture::Texture;
-pub use sprite::{Sprite, SpriteRenderer};
-pub use camera::Camera;
-pub use text::TextRenderer;
-pub use primitive::PrimitiveRenderer;
+pub use texture::{FilterMode, Texture};
+pub use sprite::{NinePatchInsets, Sprite, SpriteRenderer};
+pub use camera::{Camera, CoordinateSpace};
+pub use text::{TextAlign, TextRenderer};
+pub use primitive::{BlendMode, PrimitiveRenderer};
+pub use animation::AnimatedSprite;
+pub use particles::{ParticleConfig, ParticleSystem};
+pub use post::{PostChain, PostEffect};
+pub use render_target::RenderTarget;
+pub use draw_target::{DrawTarget, NullRenderer};
+#[cfg(test)]
+pub use draw_target::{DrawCommand, RecordingTarget};
 
 pub struct Renderer {
     surface: wgpu::Surface,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>,
     size: winit::dpi::PhysicalSize<u32>,
     
     sprite_renderer: SpriteRenderer,
     text_renderer: TextRenderer,
     primitive_renderer: PrimitiveRenderer,
+    post_chain: PostChain,
     camera: Camera,
     
     clear_color: wgpu::Color,
@@ -32,6 +50,8 @@ pub struct Renderer {
     current_encoder: Option<wgpu::CommandEncoder>,
     current_output: Option<wgpu::SurfaceTexture>,
     current_view: Option<wgpu::TextureView>,
+    // Added to the position of every draw; see set_offset
+    offset: glam::Vec2,
 }
 
 impl Renderer {
@@ -72,7 +92,7 @@ impl Renderer {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
+            present_mode: choose_present_mode(wgpu::PresentMode::Fifo, &surface_caps.present_modes),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
         };
@@ -81,6 +101,7 @@ impl Renderer {
         let sprite_renderer = SpriteRenderer::new(&device, &config)?;
         let text_renderer = TextRenderer::new(&device, &queue, &config)?;
         let primitive_renderer = PrimitiveRenderer::new(&device, &config)?;
+        let post_chain = PostChain::new(&device, config.format);
         let camera = Camera::new(size.width as f32, size.height as f32);
 
         Ok(Self {
@@ -88,15 +109,18 @@ impl Renderer {
             device,
             queue,
             config,
+            present_modes: surface_caps.present_modes,
             size,
             sprite_renderer,
             text_renderer,
             primitive_renderer,
+            post_chain,
             camera,
             clear_color: wgpu::Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
             current_encoder: None,
             current_output: None,
             current_view: None,
+            offset: glam::Vec2::ZERO,
         })
     }
 
@@ -105,14 +129,50 @@ impl Renderer {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-            self.camera.set_viewport(new_size.width as f32, new_size.height as f32);
+            self.reconfigure_surface();
         }
     }
 
-    pub fn begin_frame(&mut self) -> Result<(), CacaoError> {
-        let output = self.surface.get_current_texture()
-            .map_err(|e| CacaoError::RenderError(format!("Failed to get surface texture: {}", e)))?;
+    /// Sets the size of the coordinate space draws are made in; it's stretched to fill the window.
+    pub fn set_logical_size(&mut self, width: f32, height: f32) {
+        if self.camera.viewport_size() != glam::Vec2::new(width, height) {
+            self.camera.set_viewport(width, height);
+        }
+    }
+
+    /// Switches the swapchain's present mode, falling back to a supported one if the
+    /// adapter can't do `mode`. Returns the mode actually in use.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let chosen = choose_present_mode(mode, &self.present_modes);
+        if chosen != mode {
+            log::warn!("⚠️ Present mode {:?} not supported, using {:?}", mode, chosen);
+        }
+        if chosen != self.config.present_mode {
+            self.config.present_mode = chosen;
+            self.reconfigure_surface();
+        }
+        chosen
+    }
+
+    fn reconfigure_surface(&mut self) {
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    /// Starts a frame. Returns `Ok(false)` when the frame should be skipped because the
+    /// surface isn't ready; only running out of GPU memory is an error.
+    pub fn begin_frame(&mut self) -> Result<bool, CacaoError> {
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(e) => {
+                if surface_recovery(e)? == SurfaceRecovery::Reconfigure {
+                    log::warn!("⚠️ Surface lost or outdated, reconfiguring");
+                    self.reconfigure_surface();
+                } else {
+                    log::warn!("⚠️ Surface timed out, skipping frame");
+                }
+                return Ok(false);
+            }
+        };
         
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
         
@@ -124,30 +184,17 @@ impl Renderer {
         self.current_view = Some(view);
         self.current_encoder = Some(encoder);
         
-        Ok(())
+        Ok(true)
     }
 
     pub fn end_frame(&mut self) -> Result<(), CacaoError> {
         if let (Some(mut encoder), Some(view)) = (self.current_encoder.take(), self.current_view.take()) {
-            {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Primary Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(self.clear_color),
-                            store: true,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                });
-
-                // FIXED: Pass device to all flush calls
-                self.primitive_renderer.flush(&mut render_pass, &self.queue, &mut self.camera);
-                self.sprite_renderer.flush(&mut render_pass, &self.device, &self.queue, &mut self.camera);
-                self.text_renderer.flush(&mut render_pass, &self.queue, &mut self.camera);
-            }
+            self.encode_frame(&mut encoder, &view);
+
+            self.primitive_renderer.clear();
+            self.sprite_renderer.clear();
+            self.text_renderer.clear();
+            self.offset = glam::Vec2::ZERO;
 
             self.queue.submit(std::iter::once(encoder.finish()));
         }
@@ -159,6 +206,125 @@ impl Renderer {
         Ok(())
     }
 
+    /// Renders what has been drawn so far this frame into an offscreen texture and reads it
+    /// back. Call it before `end_frame`; the frame itself still goes to the window as usual.
+    pub fn capture_frame(&mut self) -> Result<RgbaImage, CacaoError> {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d { width: self.config.width, height: self.config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Render Encoder"),
+        });
+        self.encode_frame(&mut encoder, &view);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        capture::read_texture(&self.device, &self.queue, &texture)
+    }
+
+    /// Sets the post-processing chain the frame goes through before reaching the window.
+    /// Empty, the default, draws straight to the window.
+    pub fn set_post_effects(&mut self, effects: &[PostEffect]) {
+        if self.post_chain.effects() != effects {
+            self.post_chain.set_effects(&self.device, effects);
+        }
+    }
+
+    /// Draws the queued frame into `view`, going through the post chain if it has any effects.
+    fn encode_frame(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let layers = self.prepare_draws();
+        if self.post_chain.is_enabled() {
+            self.post_chain.prepare_targets(&self.device, self.config.width, self.config.height);
+            self.encode_draws(encoder, self.post_chain.scene_view(), &layers);
+            self.post_chain.apply(&self.device, encoder, view);
+        } else {
+            self.encode_draws(encoder, view, &layers);
+        }
+    }
+
+    /// Uploads the queued draws and returns every layer in use, lowest first.
+    fn prepare_draws(&mut self) -> Vec<i32> {
+        self.primitive_renderer.prepare(&self.queue, &mut self.camera);
+        self.sprite_renderer.prepare(&self.device, &self.queue, &mut self.camera);
+        self.text_renderer.prepare(&self.queue, &mut self.camera);
+
+        let mut layers: Vec<i32> = self.primitive_renderer.layers()
+            .chain(self.sprite_renderer.layers())
+            .chain(self.text_renderer.layers())
+            .collect();
+        layers.sort_unstable();
+        layers.dedup();
+        layers
+    }
+
+    fn encode_draws(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, layers: &[i32]) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Primary Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        // Within a layer primitives go under sprites, and text goes on top
+        for &layer in layers {
+            self.primitive_renderer.draw_layer(&mut render_pass, layer);
+            self.sprite_renderer.draw_layer(&mut render_pass, layer);
+            self.text_renderer.draw_layer(&mut render_pass, layer);
+        }
+    }
+
+    /// Draws queued after this call go on `layer`; higher layers render on top.
+    /// Every frame starts on layer 0.
+    pub fn set_layer(&mut self, layer: i32) {
+        self.primitive_renderer.set_layer(layer);
+        self.sprite_renderer.set_layer(layer);
+        self.text_renderer.set_layer(layer);
+    }
+
+    /// Draws queued after this call are in screen pixels from the top-left corner, y down,
+    /// unaffected by the camera.
+    pub fn begin_screen_space(&mut self) {
+        self.set_space(CoordinateSpace::Screen);
+    }
+
+    /// Draws queued after this call go through the camera again, centred on its position
+    /// with y up. Every frame starts in world space.
+    pub fn begin_world_space(&mut self) {
+        self.set_space(CoordinateSpace::World);
+    }
+
+    fn set_space(&mut self, space: CoordinateSpace) {
+        self.primitive_renderer.set_space(space);
+        self.sprite_renderer.set_space(space);
+        self.text_renderer.set_space(space);
+    }
+
+    /// Blend mode for rects, lines, circles and triangles queued after this call.
+    /// Every frame starts with `BlendMode::Alpha`.
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.primitive_renderer.set_blend_mode(mode);
+    }
+
+    /// Moves everything drawn after this call by (`x`, `y`), e.g. to slide a whole screen
+    /// in. Every frame starts with no offset.
+    pub fn set_offset(&mut self, x: f32, y: f32) {
+        self.offset = glam::Vec2::new(x, y);
+    }
+
     pub fn clear_screen(&mut self, color: [f32; 4]) {
         self.clear_color = wgpu::Color {
             r: color[0] as f64,
@@ -169,42 +335,83 @@ impl Renderer {
     }
 
     pub fn draw_sprite(&mut self, sprite: &Sprite, x: f32, y: f32, rotation: f32, scale: f32) -> Result<(), CacaoError> {
-        self.sprite_renderer.draw_sprite(sprite, x, y, rotation, scale, &self.camera);
+        self.sprite_renderer.draw_sprite(sprite, x + self.offset.x, y + self.offset.y, rotation, scale, &self.camera);
+        Ok(())
+    }
+
+    pub fn draw_nine_patch(&mut self, sprite: &Sprite, x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets) -> Result<(), CacaoError> {
+        self.sprite_renderer.draw_nine_patch(sprite, x + self.offset.x, y + self.offset.y, width, height, insets);
         Ok(())
     }
 
     pub fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.text_renderer.draw_text(text, x, y, size, color);
+        self.text_renderer.draw_text(text, x + self.offset.x, y + self.offset.y, size, color);
+        Ok(())
+    }
+
+    pub fn draw_text_aligned(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], align: TextAlign) -> Result<(), CacaoError> {
+        self.text_renderer.draw_text_aligned(text, x + self.offset.x, y + self.offset.y, size, color, align);
+        Ok(())
+    }
+
+    pub fn measure_text(&mut self, text: &str, size: f32) -> (f32, f32) {
+        self.text_renderer.measure_text(text, size)
+    }
+
+    pub fn draw_text_wrapped(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], max_width: f32) -> Result<(), CacaoError> {
+        self.text_renderer.draw_text_wrapped(text, x + self.offset.x, y + self.offset.y, size, color, max_width);
         Ok(())
     }
 
+    pub fn load_font(&mut self, font_name: &str, data: &[u8]) -> Result<(), CacaoError> {
+        self.text_renderer.load_font(&self.device, font_name, data)
+    }
+
+    /// Text drawn after this uses `font_name`, if it has been loaded. Fonts can be mixed
+    /// freely within a frame. Returns whether the font was found.
+    pub fn set_font(&mut self, font_name: &str) -> bool {
+        self.text_renderer.set_font(font_name)
+    }
+
     pub fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_rect(x, y, width, height, color);
+        self.primitive_renderer.draw_rect(x + self.offset.x, y + self.offset.y, width, height, color);
+        Ok(())
+    }
+
+    pub fn draw_rect_gradient(&mut self, x: f32, y: f32, width: f32, height: f32, top_color: [f32; 4], bottom_color: [f32; 4]) -> Result<(), CacaoError> {
+        self.primitive_renderer.draw_rect_gradient(x + self.offset.x, y + self.offset.y, width, height, top_color, bottom_color);
+        Ok(())
+    }
+
+    pub fn draw_rounded_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32, color: [f32; 4]) -> Result<(), CacaoError> {
+        self.primitive_renderer.draw_rounded_rect(x + self.offset.x, y + self.offset.y, width, height, radius, color);
         Ok(())
     }
 
     pub fn draw_rect_outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_rect_outline(x, y, width, height, thickness, color);
+        self.primitive_renderer.draw_rect_outline(x + self.offset.x, y + self.offset.y, width, height, thickness, color);
         Ok(())
     }
 
     pub fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_line(x1, y1, x2, y2, thickness, color);
+        let (dx, dy) = (self.offset.x, self.offset.y);
+        self.primitive_renderer.draw_line(x1 + dx, y1 + dy, x2 + dx, y2 + dy, thickness, color);
         Ok(())
     }
 
     pub fn draw_circle(&mut self, x: f32, y: f32, radius: f32, segments: u32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_circle(x, y, radius, segments, color);
+        self.primitive_renderer.draw_circle(x + self.offset.x, y + self.offset.y, radius, segments, color);
         Ok(())
     }
 
     pub fn draw_circle_outline(&mut self, x: f32, y: f32, radius: f32, segments: u32, thickness: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_circle_outline(x, y, radius, segments, thickness, color);
+        self.primitive_renderer.draw_circle_outline(x + self.offset.x, y + self.offset.y, radius, segments, thickness, color);
         Ok(())
     }
 
     pub fn draw_triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, color: [f32; 4]) -> Result<(), CacaoError> {
-        self.primitive_renderer.draw_triangle(x1, y1, x2, y2, x3, y3, color);
+        let (dx, dy) = (self.offset.x, self.offset.y);
+        self.primitive_renderer.draw_triangle(x1 + dx, y1 + dy, x2 + dx, y2 + dy, x3 + dx, y3 + dy, color);
         Ok(())
     }
 
@@ -212,6 +419,18 @@ impl Renderer {
         &mut self.camera
     }
 
+    pub fn clear_texture_cache(&mut self) {
+        self.sprite_renderer.clear_texture_cache();
+    }
+
+    pub fn sprite_bind_groups_created(&self) -> usize {
+        self.sprite_renderer.bind_groups_created()
+    }
+
+    pub fn post_target_count(&self) -> usize {
+        self.post_chain.intermediate_count()
+    }
+
     pub fn get_device(&self) -> &wgpu::Device {
         &self.device
     }
@@ -219,4 +438,96 @@ impl Renderer {
     pub fn get_queue(&self) -> &wgpu::Queue {
         &self.queue
     }
-}
\ No newline at end of file
+}
+
+/// Picks `requested` if the surface supports it. Otherwise a non-vsync request tries the
+/// other non-vsync mode, and everything else ends up on Fifo, which every surface supports.
+pub fn choose_present_mode(requested: wgpu::PresentMode, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    use wgpu::PresentMode;
+
+    if supported.contains(&requested) {
+        return requested;
+    }
+
+    let fallbacks: &[PresentMode] = match requested {
+        PresentMode::Immediate | PresentMode::Mailbox | PresentMode::AutoNoVsync => &[PresentMode::Mailbox, PresentMode::Immediate],
+        _ => &[],
+    };
+    fallbacks.iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .or_else(|| supported.contains(&PresentMode::Fifo).then_some(PresentMode::Fifo))
+        .or_else(|| supported.first().copied())
+        .unwrap_or(PresentMode::Fifo)
+}
+
+/// How `begin_frame` gets past a surface that couldn't hand out a texture.
+#[derive(Debug, PartialEq, Eq)]
+enum SurfaceRecovery {
+    Reconfigure,
+    SkipFrame,
+}
+
+// The swapchain goes stale on resize, alt-tab or GPU sleep; it's rebuilt and drawn to next frame
+fn surface_recovery(error: wgpu::SurfaceError) -> Result<SurfaceRecovery, CacaoError> {
+    match error {
+        wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => Ok(SurfaceRecovery::Reconfigure),
+        wgpu::SurfaceError::Timeout => Ok(SurfaceRecovery::SkipFrame),
+        wgpu::SurfaceError::OutOfMemory => Err(CacaoError::RenderError("Failed to get surface texture: out of memory".to_string())),
+    }
+}
+
+/// A device on the software fallback adapter, for tests that need real GPU objects.
+#[cfg(test)]
+pub(crate) fn test_gpu() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        force_fallback_adapter: true,
+        ..Default::default()
+    })).expect("no fallback GPU adapter");
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("Test Device"),
+        features: wgpu::Features::empty(),
+        limits: wgpu::Limits::downlevel_webgl2_defaults(),
+    }, None)).expect("failed to create a test device")
+}
+
+/// What the renderers are built against when there's no window surface.
+#[cfg(test)]
+pub(crate) fn test_surface_config(width: u32, height: u32) -> wgpu::SurfaceConfiguration {
+    wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::PresentMode;
+
+    #[test]
+    fn stale_surfaces_are_rebuilt_and_only_running_out_of_memory_fails() {
+        assert_eq!(surface_recovery(wgpu::SurfaceError::Lost).unwrap(), SurfaceRecovery::Reconfigure);
+        assert_eq!(surface_recovery(wgpu::SurfaceError::Outdated).unwrap(), SurfaceRecovery::Reconfigure);
+        assert_eq!(surface_recovery(wgpu::SurfaceError::Timeout).unwrap(), SurfaceRecovery::SkipFrame);
+        assert!(surface_recovery(wgpu::SurfaceError::OutOfMemory).is_err());
+    }
+
+    #[test]
+    fn unsupported_present_modes_fall_back() {
+        let all = [PresentMode::Fifo, PresentMode::Mailbox, PresentMode::Immediate];
+        assert_eq!(choose_present_mode(PresentMode::Mailbox, &all), PresentMode::Mailbox);
+
+        // Turning vsync off takes whichever non-vsync mode there is, else stays on Fifo
+        assert_eq!(choose_present_mode(PresentMode::Mailbox, &[PresentMode::Fifo, PresentMode::Immediate]), PresentMode::Immediate);
+        assert_eq!(choose_present_mode(PresentMode::Immediate, &[PresentMode::Fifo]), PresentMode::Fifo);
+        assert_eq!(choose_present_mode(PresentMode::FifoRelaxed, &[PresentMode::Mailbox, PresentMode::Fifo]), PresentMode::Fifo);
+        assert_eq!(choose_present_mode(PresentMode::Fifo, &[]), PresentMode::Fifo);
+    }
+}
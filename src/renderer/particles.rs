@@ -0,0 +1,242 @@
+// src/renderer/particles.rs
+use glam::Vec2;
+use rand::Rng;
+use crate::errors::CacaoError;
+use super::DrawTarget;
+
+const PARTICLE_SEGMENTS: u32 = 12;
+
+/// How a batch of particles looks and moves. Ranges are (min, max) and each new
+/// particle picks a random value inside them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleConfig {
+    /// Particles per second for emitters started with `ParticleSystem::start_emitter`
+    pub spawn_rate: f32,
+    /// Seconds each particle lives
+    pub lifetime: (f32, f32),
+    pub velocity_x: (f32, f32),
+    pub velocity_y: (f32, f32),
+    /// Circle radius
+    pub size: (f32, f32),
+    pub color_min: [f32; 4],
+    pub color_max: [f32; 4],
+    /// Added to the vertical velocity every second; positive pulls down the screen
+    pub gravity: f32,
+    /// Width and height of the box new particles appear in, centred on the emit point
+    pub spread: (f32, f32),
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            spawn_rate: 20.0,
+            lifetime: (0.5, 1.0),
+            velocity_x: (-50.0, 50.0),
+            velocity_y: (-50.0, 50.0),
+            size: (2.0, 4.0),
+            color_min: [1.0, 1.0, 1.0, 1.0],
+            color_max: [1.0, 1.0, 1.0, 1.0],
+            gravity: 0.0,
+            spread: (0.0, 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    size: f32,
+    color: [f32; 4],
+    gravity: f32,
+    age: f32,
+    lifetime: f32,
+    // The emitter that spawned it, so pools know how many of theirs are alive
+    emitter: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct Emitter {
+    id: u32,
+    position: Vec2,
+    config: ParticleConfig,
+    // Fractional particles carried over to the next update
+    pending: f32,
+    // Pools keep exactly this many particles alive instead of using spawn_rate
+    pool_size: Option<usize>,
+}
+
+/// A pool of short-lived circles, fed by one-off bursts and continuous emitters.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    emitters: Vec<Emitter>,
+    next_emitter_id: u32,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns `count` particles around (x, y) at once.
+    pub fn emit(&mut self, x: f32, y: f32, config: &ParticleConfig, count: usize) {
+        let mut rng = rand::thread_rng();
+        let origin = Vec2::new(x, y);
+        self.particles.extend((0..count).map(|_| spawn(&mut rng, origin, config, None)));
+    }
+
+    /// Keeps spawning `config.spawn_rate` particles a second around (x, y) until stopped.
+    pub fn start_emitter(&mut self, x: f32, y: f32, config: ParticleConfig) -> u32 {
+        self.add_emitter(x, y, config, None)
+    }
+
+    /// Keeps `size` particles alive around (x, y), spawning a new one whenever one dies.
+    /// The first `size` appear straight away.
+    pub fn start_pool(&mut self, x: f32, y: f32, config: ParticleConfig, size: usize) -> u32 {
+        let id = self.add_emitter(x, y, config, Some(size));
+        self.refill();
+        id
+    }
+
+    fn add_emitter(&mut self, x: f32, y: f32, config: ParticleConfig, pool_size: Option<usize>) -> u32 {
+        let id = self.next_emitter_id;
+        self.next_emitter_id += 1;
+        self.emitters.push(Emitter { id, position: Vec2::new(x, y), config, pending: 0.0, pool_size });
+        id
+    }
+
+    /// Returns false if there was no emitter with that id. Its particles live out their lifetime.
+    pub fn stop_emitter(&mut self, id: u32) -> bool {
+        let before = self.emitters.len();
+        self.emitters.retain(|emitter| emitter.id != id);
+        self.emitters.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.emitters.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Moves and ages every particle, dropping the expired ones, then runs the emitters.
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.velocity.y += particle.gravity * dt;
+            particle.position += particle.velocity * dt;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+
+        let mut rng = rand::thread_rng();
+        for emitter in self.emitters.iter_mut().filter(|emitter| emitter.pool_size.is_none()) {
+            emitter.pending += emitter.config.spawn_rate * dt;
+            while emitter.pending >= 1.0 {
+                emitter.pending -= 1.0;
+                self.particles.push(spawn(&mut rng, emitter.position, &emitter.config, Some(emitter.id)));
+            }
+        }
+        self.refill();
+    }
+
+    // Tops every pool back up to its size
+    fn refill(&mut self) {
+        let mut rng = rand::thread_rng();
+        for emitter in &self.emitters {
+            let Some(size) = emitter.pool_size else { continue };
+            let alive = self.particles.iter().filter(|particle| particle.emitter == Some(emitter.id)).count();
+            for _ in alive..size {
+                self.particles.push(spawn(&mut rng, emitter.position, &emitter.config, Some(emitter.id)));
+            }
+        }
+    }
+
+    /// Draws every particle as a circle that fades in quickly and out over its life.
+    pub fn render(&self, target: &mut dyn DrawTarget) -> Result<(), CacaoError> {
+        for particle in &self.particles {
+            let t = particle.age / particle.lifetime;
+            let fade = (1.0 - t).min(t * 10.0).clamp(0.0, 1.0);
+            let [r, g, b, a] = particle.color;
+            target.draw_circle(particle.position.x, particle.position.y, particle.size, PARTICLE_SEGMENTS, [r, g, b, a * fade])?;
+        }
+        Ok(())
+    }
+}
+
+fn spawn(rng: &mut impl Rng, origin: Vec2, config: &ParticleConfig, emitter: Option<u32>) -> Particle {
+    let offset = Vec2::new(
+        random_in(rng, (-config.spread.0 / 2.0, config.spread.0 / 2.0)),
+        random_in(rng, (-config.spread.1 / 2.0, config.spread.1 / 2.0)),
+    );
+    let mut color = [0.0; 4];
+    for (channel, value) in color.iter_mut().enumerate() {
+        *value = random_in(rng, (config.color_min[channel], config.color_max[channel]));
+    }
+
+    Particle {
+        position: origin + offset,
+        velocity: Vec2::new(random_in(rng, config.velocity_x), random_in(rng, config.velocity_y)),
+        size: random_in(rng, config.size),
+        color,
+        gravity: config.gravity,
+        age: 0.0,
+        lifetime: random_in(rng, config.lifetime).max(f32::EPSILON),
+        emitter,
+    }
+}
+
+// gen_range panics on an empty range, which a min equal to max would be
+fn random_in(rng: &mut impl Rng, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_die_out_after_their_lifetime() {
+        let mut system = ParticleSystem::new();
+        let config = ParticleConfig { lifetime: (0.5, 1.0), ..ParticleConfig::default() };
+        system.emit(10.0, 20.0, &config, 50);
+        assert_eq!(system.len(), 50);
+
+        system.update(0.4);
+        assert_eq!(system.len(), 50);
+
+        system.update(0.7);
+        assert!(system.is_empty());
+    }
+
+
+    #[test]
+    fn pools_refill_to_the_same_count() {
+        let mut system = ParticleSystem::new();
+        let config = ParticleConfig { lifetime: (0.05, 0.2), ..ParticleConfig::default() };
+        let pool = system.start_pool(0.0, 0.0, config.clone(), 10);
+        assert_eq!(system.len(), 10);
+
+        // Bursts from the same spot don't count towards the pool
+        system.emit(0.0, 0.0, &ParticleConfig { lifetime: (10.0, 10.0), ..config }, 5);
+        for _ in 0..30 {
+            system.update(0.05);
+            assert_eq!(system.len(), 15);
+        }
+
+        // Once stopped the pool's particles just die out
+        assert!(system.stop_emitter(pool));
+        system.update(0.25);
+        assert_eq!(system.len(), 5);
+    }
+}
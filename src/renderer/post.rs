@@ -0,0 +1,309 @@
+// src/renderer/post.rs
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+use super::RenderTarget;
+
+/// A fullscreen effect applied to the finished frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PostEffect {
+    /// Blurs everything brighter than `threshold` and adds it back on top, so bright
+    /// colors glow. `radius` is roughly how far the glow spreads, in pixels.
+    Bloom { threshold: f32, intensity: f32, radius: f32 },
+    /// Darkens the corners; 0 leaves the frame alone, 1 takes them to black.
+    Vignette { strength: f32 },
+    /// Dark horizontal lines every `spacing` pixels, like an old CRT.
+    Scanlines { strength: f32, spacing: f32 },
+}
+
+impl PostEffect {
+    fn params(&self) -> [f32; 4] {
+        match *self {
+            PostEffect::Bloom { threshold, intensity, radius } => [threshold, intensity, radius, 0.0],
+            PostEffect::Vignette { strength } => [strength, 0.0, 0.0, 0.0],
+            PostEffect::Scanlines { strength, spacing } => [strength, spacing, 0.0, 0.0],
+        }
+    }
+}
+
+struct PostPass {
+    effect: PostEffect,
+    params: wgpu::Buffer,
+}
+
+/// Runs the frame through a list of `PostEffect`s in order. The scene is drawn into an
+/// offscreen target first, and the last effect writes to the real output.
+pub struct PostChain {
+    format: wgpu::TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bloom_pipeline: wgpu::RenderPipeline,
+    vignette_pipeline: wgpu::RenderPipeline,
+    scanline_pipeline: wgpu::RenderPipeline,
+    passes: Vec<PostPass>,
+    // The scene target, plus a second one to ping-pong between when there are several passes
+    targets: Vec<RenderTarget>,
+}
+
+impl PostChain {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/post.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("Post Bind Group Layout"),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point: &str| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            format,
+            bloom_pipeline: pipeline("fs_bloom"),
+            vignette_pipeline: pipeline("fs_vignette"),
+            scanline_pipeline: pipeline("fs_scanlines"),
+            bind_group_layout,
+            sampler,
+            passes: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Replaces the chain. An empty list turns post-processing off and frees the targets.
+    pub fn set_effects(&mut self, device: &wgpu::Device, effects: &[PostEffect]) {
+        self.passes = effects
+            .iter()
+            .map(|effect| PostPass {
+                effect: *effect,
+                params: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Post Params Buffer"),
+                    contents: bytemuck::cast_slice(&effect.params()),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                }),
+            })
+            .collect();
+        self.targets.clear();
+    }
+
+    pub fn effects(&self) -> Vec<PostEffect> {
+        self.passes.iter().map(|pass| pass.effect).collect()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.passes.is_empty()
+    }
+
+    /// How many offscreen textures the chain is holding on to.
+    pub fn intermediate_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Makes sure the offscreen targets exist at this size.
+    pub fn prepare_targets(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let needed = if self.passes.len() > 1 { 2 } else { 1 };
+        let stale = self.targets.first().is_some_and(|target| target.size() != (width, height));
+        if stale || self.targets.len() != needed {
+            self.targets = (0..needed)
+                .map(|_| RenderTarget::new(device, width, height, self.format, "Post Target"))
+                .collect();
+        }
+    }
+
+    /// Where the scene should be drawn. Only valid after `prepare_targets`.
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        self.targets[0].view()
+    }
+
+    /// Encodes every pass, the last one drawing into `output`. Returns how many passes ran.
+    pub fn apply(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) -> usize {
+        for (i, pass) in self.passes.iter().enumerate() {
+            let source = &self.targets[i % self.targets.len()];
+            let destination = if i + 1 == self.passes.len() {
+                output
+            } else {
+                self.targets[(i + 1) % self.targets.len()].view()
+            };
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(source.view()) },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+                    wgpu::BindGroupEntry { binding: 2, resource: pass.params.as_entire_binding() },
+                ],
+                label: Some("Post Bind Group"),
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: destination,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(match pass.effect {
+                PostEffect::Bloom { .. } => &self.bloom_pipeline,
+                PostEffect::Vignette { .. } => &self.vignette_pipeline,
+                PostEffect::Scanlines { .. } => &self.scanline_pipeline,
+            });
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        self.passes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    fn output_texture(device: &wgpu::Device, size: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Test Output"),
+            size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    fn clear_to_white(encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Test Scene Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::WHITE), store: true },
+            })],
+            depth_stencil_attachment: None,
+        });
+    }
+
+    #[test]
+    fn chain_starts_disabled_without_targets() {
+        let (device, _) = crate::renderer::test_gpu();
+        let chain = PostChain::new(&device, FORMAT);
+        assert!(!chain.is_enabled());
+        assert_eq!(chain.intermediate_count(), 0);
+    }
+
+    #[test]
+    fn enabling_an_effect_adds_a_target_and_a_compositing_draw() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let mut chain = PostChain::new(&device, FORMAT);
+        chain.set_effects(&device, &[PostEffect::Vignette { strength: 1.0 }]);
+        assert!(chain.is_enabled());
+
+        chain.prepare_targets(&device, 32, 32);
+        assert_eq!(chain.intermediate_count(), 1);
+
+        let output = output_texture(&device, 32);
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        clear_to_white(&mut encoder, chain.scene_view());
+        assert_eq!(chain.apply(&device, &mut encoder, &output_view), 1);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        // The white scene reached the output through the vignette: bright middle, dark corners
+        let image = crate::renderer::capture::read_texture(&device, &queue, &output).unwrap();
+        assert!(image.get_pixel(16, 16)[0] > 240, "{:?}", image.get_pixel(16, 16));
+        assert!(image.get_pixel(0, 0)[0] < 128, "{:?}", image.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn several_effects_ping_pong_between_two_targets() {
+        let (device, _) = crate::renderer::test_gpu();
+        let mut chain = PostChain::new(&device, FORMAT);
+        chain.set_effects(&device, &[
+            PostEffect::Bloom { threshold: 0.8, intensity: 1.0, radius: 4.0 },
+            PostEffect::Scanlines { strength: 0.5, spacing: 2.0 },
+            PostEffect::Vignette { strength: 0.5 },
+        ]);
+        chain.prepare_targets(&device, 16, 16);
+        assert_eq!(chain.intermediate_count(), 2);
+
+        let output = output_texture(&device, 16);
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        assert_eq!(chain.apply(&device, &mut encoder, &output_view), 3);
+
+        // Turning it off again lets the targets go
+        chain.set_effects(&device, &[]);
+        assert_eq!(chain.intermediate_count(), 0);
+    }
+}
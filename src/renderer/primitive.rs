@@ -1,6 +1,9 @@
 // src/renderer/primitive.rs - FIXED SIGNATURE
 use crate::errors::CacaoError;
-use super::Camera;
+use super::{Camera, CoordinateSpace, layer::{LayerDraw, LayerRuns}, view_uniforms::ViewUniforms};
+
+// Triangles in each rounded corner
+const CORNER_SEGMENTS: u32 = 8;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -24,21 +27,59 @@ impl PrimitiveVertex {
     }
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct PrimitiveUniform {
-    view_proj: [[f32; 4]; 4],
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Alpha,
+    Additive,
+    Opaque,
 }
 
-pub struct PrimitiveRenderer {
+impl BlendMode {
+    // Within a layer opaque shapes go first and additive glows last
+    const DRAW_ORDER: [BlendMode; 3] = [BlendMode::Opaque, BlendMode::Alpha, BlendMode::Additive];
+
+    fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendMode::Opaque => wgpu::BlendState::REPLACE,
+        }
+    }
+
+    // Batches are kept in draw order
+    fn index(self) -> usize {
+        Self::DRAW_ORDER.iter()
+            .position(|&mode| mode == self)
+            .expect("every blend mode is in DRAW_ORDER")
+    }
+}
+
+/// Geometry queued for one blend mode, with its own GPU buffers.
+struct PrimitiveBatch {
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
-    
     vertices: Vec<PrimitiveVertex>,
     indices: Vec<u16>,
+    layer_runs: LayerRuns,
+    layer_draws: Vec<LayerDraw>,
+}
+
+pub struct PrimitiveRenderer {
+    uniforms: ViewUniforms,
+    batches: Vec<PrimitiveBatch>,
+    blend_mode: BlendMode,
     max_primitives: usize,
 }
 
@@ -52,13 +93,6 @@ impl PrimitiveRenderer {
             source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/primitive.wgsl").into()),
         });
 
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Primitive Uniform Buffer"),
-            size: std::mem::size_of::<PrimitiveUniform>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
@@ -73,14 +107,7 @@ impl PrimitiveRenderer {
             label: Some("Primitive Uniform Bind Group Layout"),
         });
 
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-            label: Some("Primitive Uniform Bind Group"),
-        });
+        let uniforms = ViewUniforms::new(device, &uniform_bind_group_layout, "Primitive");
 
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Primitive Render Pipeline Layout"),
@@ -88,82 +115,158 @@ impl PrimitiveRenderer {
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Primitive Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[PrimitiveVertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
-
         let max_primitives = 2048;
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Primitive Vertex Buffer"),
-            size: (max_primitives * 4 * std::mem::size_of::<PrimitiveVertex>()) as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Primitive Index Buffer"),
-            size: (max_primitives * 6 * std::mem::size_of::<u16>()) as u64,
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let batches = BlendMode::DRAW_ORDER
+            .into_iter()
+            .map(|mode| {
+                let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Primitive Render Pipeline"),
+                    layout: Some(&render_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[PrimitiveVertex::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: "fs_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: config.format,
+                            blend: Some(mode.blend_state()),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: 1,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                });
+
+                let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Primitive Vertex Buffer"),
+                    size: (max_primitives * 4 * std::mem::size_of::<PrimitiveVertex>()) as u64,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+
+                let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Primitive Index Buffer"),
+                    size: (max_primitives * 6 * std::mem::size_of::<u16>()) as u64,
+                    usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+
+                PrimitiveBatch {
+                    render_pipeline,
+                    vertex_buffer,
+                    index_buffer,
+                    vertices: Vec::new(),
+                    indices: Vec::new(),
+                    layer_runs: LayerRuns::new(),
+                    layer_draws: Vec::new(),
+                }
+            })
+            .collect();
 
         Ok(Self {
-            render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            uniform_buffer,
-            uniform_bind_group,
-            vertices: Vec::new(),
-            indices: Vec::new(),
+            uniforms,
+            batches,
+            blend_mode: BlendMode::Alpha,
             max_primitives,
         })
     }
 
+    pub fn set_layer(&mut self, layer: i32) {
+        for batch in &mut self.batches {
+            batch.layer_runs.set_layer(layer, batch.indices.len());
+        }
+    }
+
+    pub fn set_space(&mut self, space: CoordinateSpace) {
+        for batch in &mut self.batches {
+            batch.layer_runs.set_space(space, batch.indices.len());
+        }
+    }
+
+    /// Shapes queued after this call use `mode` until it is changed again.
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    fn batch(&mut self) -> &mut PrimitiveBatch {
+        &mut self.batches[self.blend_mode.index()]
+    }
+
     pub fn draw_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
-        let vert_idx = self.vertices.len() as u16;
+        self.draw_rect_gradient(x, y, width, height, color, color);
+    }
+
+    /// `top_color` is used along the edge at `y` and `bottom_color` along `y + height`,
+    /// blended in between.
+    pub fn draw_rect_gradient(&mut self, x: f32, y: f32, width: f32, height: f32, top_color: [f32; 4], bottom_color: [f32; 4]) {
+        let batch = self.batch();
+        let vert_idx = batch.vertices.len() as u16;
 
-        self.vertices.push(PrimitiveVertex { position: [x, y], color });
-        self.vertices.push(PrimitiveVertex { position: [x + width, y], color });
-        self.vertices.push(PrimitiveVertex { position: [x + width, y + height], color });
-        self.vertices.push(PrimitiveVertex { position: [x, y + height], color });
+        batch.vertices.push(PrimitiveVertex { position: [x, y], color: top_color });
+        batch.vertices.push(PrimitiveVertex { position: [x + width, y], color: top_color });
+        batch.vertices.push(PrimitiveVertex { position: [x + width, y + height], color: bottom_color });
+        batch.vertices.push(PrimitiveVertex { position: [x, y + height], color: bottom_color });
 
-        self.indices.extend_from_slice(&[
+        batch.indices.extend_from_slice(&[
             vert_idx, vert_idx + 1, vert_idx + 2,
             vert_idx + 2, vert_idx + 3, vert_idx,
         ]);
     }
 
+    /// A filled rect whose corners are rounded off with quarter circles. `radius` is
+    /// capped at half the shorter side.
+    pub fn draw_rounded_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32, color: [f32; 4]) {
+        let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+        if radius <= 0.0 {
+            self.draw_rect(x, y, width, height, color);
+            return;
+        }
+
+        // A full-height middle column, then the two sides between the corners
+        self.draw_rect(x + radius, y, width - 2.0 * radius, height, color);
+        self.draw_rect(x, y + radius, radius, height - 2.0 * radius, color);
+        self.draw_rect(x + width - radius, y + radius, radius, height - 2.0 * radius, color);
+
+        let quarter = std::f32::consts::FRAC_PI_2;
+        self.draw_corner(x + width - radius, y + height - radius, radius, 0.0, color);
+        self.draw_corner(x + radius, y + height - radius, radius, quarter, color);
+        self.draw_corner(x + radius, y + radius, radius, 2.0 * quarter, color);
+        self.draw_corner(x + width - radius, y + radius, radius, 3.0 * quarter, color);
+    }
+
+    // A quarter-circle fan around (x, y) starting at `start_angle`
+    fn draw_corner(&mut self, x: f32, y: f32, radius: f32, start_angle: f32, color: [f32; 4]) {
+        let batch = self.batch();
+        let center_idx = batch.vertices.len() as u16;
+        batch.vertices.push(PrimitiveVertex { position: [x, y], color });
+
+        for i in 0..=CORNER_SEGMENTS {
+            let angle = start_angle + std::f32::consts::FRAC_PI_2 * (i as f32) / (CORNER_SEGMENTS as f32);
+            batch.vertices.push(PrimitiveVertex { position: [x + radius * angle.cos(), y + radius * angle.sin()], color });
+
+            if i > 0 {
+                batch.indices.extend_from_slice(&[center_idx, center_idx + i as u16, center_idx + i as u16 + 1]);
+            }
+        }
+    }
+
     pub fn draw_rect_outline(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: [f32; 4]) {
         self.draw_rect(x, y, width, thickness, color);
         self.draw_rect(x, y + height - thickness, width, thickness, color);
@@ -187,14 +290,15 @@ impl PrimitiveRenderer {
         let perpx = -sin_a * half_thickness;
         let perpy = cos_a * half_thickness;
 
-        let vert_idx = self.vertices.len() as u16;
+        let batch = self.batch();
+        let vert_idx = batch.vertices.len() as u16;
 
-        self.vertices.push(PrimitiveVertex { position: [x1 + perpx, y1 + perpy], color });
-        self.vertices.push(PrimitiveVertex { position: [x2 + perpx, y2 + perpy], color });
-        self.vertices.push(PrimitiveVertex { position: [x2 - perpx, y2 - perpy], color });
-        self.vertices.push(PrimitiveVertex { position: [x1 - perpx, y1 - perpy], color });
+        batch.vertices.push(PrimitiveVertex { position: [x1 + perpx, y1 + perpy], color });
+        batch.vertices.push(PrimitiveVertex { position: [x2 + perpx, y2 + perpy], color });
+        batch.vertices.push(PrimitiveVertex { position: [x2 - perpx, y2 - perpy], color });
+        batch.vertices.push(PrimitiveVertex { position: [x1 - perpx, y1 - perpy], color });
 
-        self.indices.extend_from_slice(&[
+        batch.indices.extend_from_slice(&[
             vert_idx, vert_idx + 1, vert_idx + 2,
             vert_idx + 2, vert_idx + 3, vert_idx,
         ]);
@@ -205,17 +309,18 @@ impl PrimitiveRenderer {
             return;
         }
 
-        let center_idx = self.vertices.len() as u16;
-        self.vertices.push(PrimitiveVertex { position: [x, y], color });
+        let batch = self.batch();
+        let center_idx = batch.vertices.len() as u16;
+        batch.vertices.push(PrimitiveVertex { position: [x, y], color });
 
         for i in 0..=segments {
             let angle = 2.0 * std::f32::consts::PI * (i as f32) / (segments as f32);
             let px = x + radius * angle.cos();
             let py = y + radius * angle.sin();
-            self.vertices.push(PrimitiveVertex { position: [px, py], color });
+            batch.vertices.push(PrimitiveVertex { position: [px, py], color });
 
             if i > 0 {
-                self.indices.extend_from_slice(&[
+                batch.indices.extend_from_slice(&[
                     center_idx,
                     center_idx + i as u16,
                     center_idx + i as u16 + 1,
@@ -243,46 +348,183 @@ impl PrimitiveRenderer {
     }
 
     pub fn draw_triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, color: [f32; 4]) {
-        let vert_idx = self.vertices.len() as u16;
+        let batch = self.batch();
+        let vert_idx = batch.vertices.len() as u16;
 
-        self.vertices.push(PrimitiveVertex { position: [x1, y1], color });
-        self.vertices.push(PrimitiveVertex { position: [x2, y2], color });
-        self.vertices.push(PrimitiveVertex { position: [x3, y3], color });
+        batch.vertices.push(PrimitiveVertex { position: [x1, y1], color });
+        batch.vertices.push(PrimitiveVertex { position: [x2, y2], color });
+        batch.vertices.push(PrimitiveVertex { position: [x3, y3], color });
 
-        self.indices.extend_from_slice(&[vert_idx, vert_idx + 1, vert_idx + 2]);
+        batch.indices.extend_from_slice(&[vert_idx, vert_idx + 1, vert_idx + 2]);
     }
 
-    pub fn flush<'a>(
-        &'a mut self,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        queue: &wgpu::Queue,
-        camera: &mut Camera,
-    ) {
-        if self.vertices.is_empty() {
-            return;
+    /// Uploads this frame's geometry, sorted by layer.
+    pub fn prepare(&mut self, queue: &wgpu::Queue, camera: &mut Camera) {
+        self.uniforms.write(queue, camera);
+
+        for batch in &mut self.batches {
+            batch.layer_draws.clear();
+            if batch.vertices.is_empty() {
+                continue;
+            }
+
+            if batch.vertices.len() / 4 > self.max_primitives {
+                batch.vertices.truncate(self.max_primitives * 4);
+                batch.indices.truncate(self.max_primitives * 6);
+            }
+
+            let (mut indices, layer_draws) = batch.layer_runs.reorder(&batch.indices);
+            // Buffer writes must be 4-byte aligned; triangles leave an odd u16 count
+            if indices.len() % 2 == 1 {
+                indices.push(0);
+            }
+
+            queue.write_buffer(&batch.vertex_buffer, 0, bytemuck::cast_slice(&batch.vertices));
+            queue.write_buffer(&batch.index_buffer, 0, bytemuck::cast_slice(&indices));
+            batch.layer_draws = layer_draws;
         }
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = i32> + '_ {
+        self.batches
+            .iter()
+            .flat_map(|batch| batch.layer_draws.iter().map(|(layer, _, _)| *layer))
+    }
+
+    pub fn draw_layer<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, layer: i32) {
+        for batch in &self.batches {
+            let mut draws = batch.layer_draws.iter().filter(|(l, _, _)| *l == layer).peekable();
+            if draws.peek().is_none() {
+                continue;
+            }
 
-        if self.vertices.len() / 4 > self.max_primitives {
-            self.vertices.truncate(self.max_primitives * 4);
-            self.indices.truncate(self.max_primitives * 6);
+            render_pass.set_pipeline(&batch.render_pipeline);
+            render_pass.set_vertex_buffer(0, batch.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(batch.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            for (_, space, indices) in draws {
+                render_pass.set_bind_group(0, self.uniforms.bind_group(*space), &[]);
+                render_pass.draw_indexed(indices.clone(), 0, 0..1);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for batch in &mut self.batches {
+            batch.vertices.clear();
+            batch.indices.clear();
+            batch.layer_runs.reset();
+            batch.layer_draws.clear();
         }
+        self.blend_mode = BlendMode::Alpha;
+    }
+}
 
-        let view_proj = camera.get_view_projection_matrix();
-        let uniform = PrimitiveUniform {
-            view_proj: view_proj.to_cols_array_2d(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{test_gpu, test_surface_config};
+
+    #[test]
+    fn blend_modes_draw_opaque_then_alpha_then_additive() {
+        assert_eq!(BlendMode::DRAW_ORDER, [BlendMode::Opaque, BlendMode::Alpha, BlendMode::Additive]);
+        for (i, mode) in BlendMode::DRAW_ORDER.into_iter().enumerate() {
+            assert_eq!(mode.index(), i);
+        }
+
+        let (device, queue) = test_gpu();
+        let config = test_surface_config(8, 8);
+        let mut primitives = PrimitiveRenderer::new(&device, &config).unwrap();
+        let mut camera = Camera::new(8.0, 8.0);
+
+        // Queued backwards; each covers the whole target
+        let cover = |primitives: &mut PrimitiveRenderer, mode, color| {
+            primitives.set_blend_mode(mode);
+            primitives.draw_rect(-100.0, -100.0, 200.0, 200.0, color);
         };
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        cover(&mut primitives, BlendMode::Additive, [0.5, 0.0, 0.0, 1.0]);
+        cover(&mut primitives, BlendMode::Alpha, [0.0, 0.0, 1.0, 0.5]);
+        cover(&mut primitives, BlendMode::Opaque, [0.0, 1.0, 0.0, 1.0]);
+        primitives.prepare(&queue, &mut camera);
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width: 8, height: 8, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: true },
+                })],
+                depth_stencil_attachment: None,
+            });
+            primitives.draw_layer(&mut pass, 0);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        // Green, half covered by blue, plus half red: 0.5 on every channel, about 188 in sRGB
+        let image = crate::renderer::capture::read_texture(&device, &queue, &target).unwrap();
+        let pixel = image.get_pixel(4, 4);
+        assert!(pixel.0[..3].iter().all(|&channel| channel.abs_diff(188) <= 3), "{:?}", pixel);
+    }
 
-        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
-        queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&self.indices));
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..self.indices.len() as u32, 0, 0..1);
+    fn alpha_batch(primitives: &PrimitiveRenderer) -> &PrimitiveBatch {
+        &primitives.batches[BlendMode::Alpha.index()]
+    }
 
-        self.vertices.clear();
-        self.indices.clear();
+    #[test]
+    fn gradients_colour_the_top_and_bottom_edges() {
+        let (device, _queue) = test_gpu();
+        let mut primitives = PrimitiveRenderer::new(&device, &test_surface_config(8, 8)).unwrap();
+        let (top, bottom) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+        primitives.draw_rect_gradient(10.0, 20.0, 30.0, 40.0, top, bottom);
+
+        let batch = alpha_batch(&primitives);
+        let corners: Vec<_> = batch.vertices.iter().map(|vertex| (vertex.position, vertex.color)).collect();
+        assert_eq!(corners, [([10.0, 20.0], top), ([40.0, 20.0], top), ([40.0, 60.0], bottom), ([10.0, 60.0], bottom)]);
+        assert_eq!(batch.indices, [0, 1, 2, 2, 3, 0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rounded_rects_stay_inside_their_bounds() {
+        let (device, _queue) = test_gpu();
+        let mut primitives = PrimitiveRenderer::new(&device, &test_surface_config(8, 8)).unwrap();
+        // The radius is capped at half the height
+        primitives.draw_rounded_rect(0.0, 0.0, 40.0, 20.0, 100.0, [1.0; 4]);
+
+        let batch = alpha_batch(&primitives);
+        // Three rects, then four fans of a centre plus CORNER_SEGMENTS + 1 rim points
+        let segments = CORNER_SEGMENTS as usize;
+        assert_eq!(batch.vertices.len(), 3 * 4 + 4 * (segments + 2));
+        assert_eq!(batch.indices.len(), 3 * 6 + 4 * segments * 3);
+        assert!(batch.indices.iter().all(|&index| (index as usize) < batch.vertices.len()));
+
+        for vertex in &batch.vertices {
+            let [x, y] = vertex.position;
+            assert!((-1e-4..=40.0001).contains(&x) && (-1e-4..=20.0001).contains(&y), "{:?}", vertex.position);
+        }
+        // The top-left corner is cut away, its rim points a radius from (10, 10)
+        assert!(!batch.vertices.iter().any(|vertex| vertex.position == [0.0, 0.0]));
+        let rim = &batch.vertices[12 + 2 * (segments + 2) + 1..12 + 3 * (segments + 2)];
+        for vertex in rim {
+            let distance = glam::Vec2::from(vertex.position).distance(glam::Vec2::new(10.0, 10.0));
+            assert!((distance - 10.0).abs() < 1e-3, "{}", distance);
+        }
+
+        // No radius is a plain rect
+        primitives.clear();
+        primitives.draw_rounded_rect(0.0, 0.0, 40.0, 20.0, 0.0, [1.0; 4]);
+        assert_eq!(alpha_batch(&primitives).vertices.len(), 4);
+    }
+}
@@ -1,9 +1,11 @@
 // ============================================================================
 // FILE: src/renderer/sprite.rs - PROPERLY FIXED
 // ============================================================================
+use std::collections::HashMap;
 use wgpu::util::DeviceExt;
+use std::ops::Range;
 use crate::{errors::CacaoError, renderer::Camera};
-use super::Texture;
+use super::{CoordinateSpace, FilterMode, Texture, layer::{LayerDraw, LayerRuns}, view_uniforms::ViewUniforms};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -29,16 +31,43 @@ impl SpriteVertex {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct SpriteUniform {
-    view_proj: [[f32; 4]; 4],
+struct SpriteInstance {
     transform: [[f32; 4]; 4],
     color: [f32; 4],
+    uv_rect: [f32; 4],
 }
 
+impl SpriteInstance {
+    const ATTRIBS: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        2 => Float32x4,
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+    ];
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+const INITIAL_INSTANCE_CAPACITY: usize = 256;
+const FULL_UV_RECT: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+#[derive(Clone)]
 pub struct Sprite {
     pub texture: Texture,
     pub width: f32,
     pub height: f32,
+    /// Part of the texture to sample as (u, v, width, height), all 0..1
+    pub uv_rect: [f32; 4],
+    /// Starts as the texture's filter mode
+    pub filter: FilterMode,
 }
 
 impl Sprite {
@@ -46,27 +75,140 @@ impl Sprite {
         Self {
             width: texture.width() as f32,
             height: texture.height() as f32,
+            uv_rect: FULL_UV_RECT,
+            filter: texture.filter_mode(),
             texture,
         }
     }
+
+    /// Draws this sprite with `filter` instead of its texture's filter mode.
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// A sprite showing the `width` x `height` pixel region at (`x`, `y`) of `texture`,
+    /// e.g. one frame of a sprite sheet.
+    pub fn with_region(texture: Texture, x: u32, y: u32, width: u32, height: u32) -> Result<Self, CacaoError> {
+        let (texture_width, texture_height) = (texture.width(), texture.height());
+        if width == 0 || height == 0 || x + width > texture_width || y + height > texture_height {
+            return Err(CacaoError::RenderError(format!(
+                "Sprite region {}x{} at ({}, {}) doesn't fit in a {}x{} texture",
+                width, height, x, y, texture_width, texture_height
+            )));
+        }
+
+        Ok(Self {
+            width: width as f32,
+            height: height as f32,
+            uv_rect: region_uv_rect(x, y, width, height, texture_width, texture_height),
+            filter: texture.filter_mode(),
+            texture,
+        })
+    }
+}
+
+/// Normalizes a pixel region of a `texture_width` x `texture_height` texture.
+pub fn region_uv_rect(x: u32, y: u32, width: u32, height: u32, texture_width: u32, texture_height: u32) -> [f32; 4] {
+    let (texture_width, texture_height) = (texture_width as f32, texture_height as f32);
+    [
+        x as f32 / texture_width,
+        y as f32 / texture_height,
+        width as f32 / texture_width,
+        height as f32 / texture_height,
+    ]
+}
+
+/// Border widths of a nine-patch sprite, in the sprite's own pixels. The corners keep
+/// this size, the edges stretch one way and the middle stretches both.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NinePatchInsets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl NinePatchInsets {
+    pub fn uniform(inset: f32) -> Self {
+        Self { left: inset, right: inset, top: inset, bottom: inset }
+    }
+}
+
+/// Splits a nine-patch into its pieces, row by row from the top-left. Each piece is
+/// where it goes as (x, y, width, height) from the top-left of a `width` x `height` box,
+/// y pointing down, and the `uv_rect` it samples. Borders shrink evenly when the box is
+/// too small to fit them.
+pub fn nine_patch_pieces(sprite: &Sprite, insets: NinePatchInsets, width: f32, height: f32) -> Vec<([f32; 4], [f32; 4])> {
+    let shrink_x = (width / (insets.left + insets.right)).min(1.0);
+    let shrink_y = (height / (insets.top + insets.bottom)).min(1.0);
+
+    let columns = slices(insets.left, insets.right, sprite.width, width, shrink_x);
+    let rows = slices(insets.top, insets.bottom, sprite.height, height, shrink_y);
+    let [u, v, uv_width, uv_height] = sprite.uv_rect;
+
+    let mut pieces = Vec::with_capacity(9);
+    for &(dest_y, dest_height, src_y, src_height) in &rows {
+        for &(dest_x, dest_width, src_x, src_width) in &columns {
+            if dest_width <= 0.0 || dest_height <= 0.0 {
+                continue;
+            }
+            pieces.push((
+                [dest_x, dest_y, dest_width, dest_height],
+                [
+                    u + uv_width * src_x / sprite.width,
+                    v + uv_height * src_y / sprite.height,
+                    uv_width * src_width / sprite.width,
+                    uv_height * src_height / sprite.height,
+                ],
+            ));
+        }
+    }
+    pieces
+}
+
+// (dest start, dest size, source start, source size) for the three bands along one axis
+fn slices(start_inset: f32, end_inset: f32, source: f32, dest: f32, shrink: f32) -> [(f32, f32, f32, f32); 3] {
+    let (start, end) = (start_inset * shrink, end_inset * shrink);
+    [
+        (0.0, start, 0.0, start_inset),
+        (start, dest - start - end, start_inset, source - start_inset - end_inset),
+        (dest - end, end, source - end_inset, end_inset),
+    ]
 }
 
+#[derive(Clone)]
 struct SpriteDrawCall {
     texture: Texture,
     transform: glam::Mat4,
     color: [f32; 4],
+    uv_rect: [f32; 4],
+    filter: FilterMode,
 }
 
+// Texture id plus the filter it's sampled with
+type BindGroupKey = (u64, FilterMode);
+
+// (layer, space, bind group, instances)
+type SpriteBatch = (i32, CoordinateSpace, BindGroupKey, Range<u32>);
+
 pub struct SpriteRenderer {
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    uniforms: ViewUniforms,
     texture_bind_group_layout: wgpu::BindGroupLayout,
+    nearest_sampler: wgpu::Sampler,
+    linear_sampler: wgpu::Sampler,
     sprite_queue: Vec<SpriteDrawCall>,
-    // Store bind groups to satisfy lifetime requirements
-    cached_bind_groups: Vec<(wgpu::BindGroup, wgpu::BindGroup)>,
+    // Texture bind groups, reused across frames
+    texture_bind_groups: HashMap<BindGroupKey, wgpu::BindGroup>,
+    bind_groups_created: usize,
+    layer_runs: LayerRuns,
+    // Instanced draws for the prepared frame
+    batches: Vec<SpriteBatch>,
 }
 
 impl SpriteRenderer {
@@ -97,13 +239,6 @@ impl SpriteRenderer {
             usage: wgpu::BufferUsages::INDEX,
         });
 
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Sprite Uniform Buffer"),
-            size: std::mem::size_of::<SpriteUniform>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
@@ -118,6 +253,10 @@ impl SpriteRenderer {
             label: Some("Sprite Uniform Bind Group Layout"),
         });
 
+        let uniforms = ViewUniforms::new(device, &uniform_bind_group_layout, "Sprite");
+
+        let instance_buffer = create_instance_buffer(device, INITIAL_INSTANCE_CAPACITY);
+
         let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
@@ -152,14 +291,14 @@ impl SpriteRenderer {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[SpriteVertex::desc()],
+                buffers: &[SpriteVertex::desc(), SpriteInstance::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -167,7 +306,8 @@ impl SpriteRenderer {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                // Screen space flips y, which turns quads clockwise
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -185,14 +325,28 @@ impl SpriteRenderer {
             render_pipeline,
             vertex_buffer,
             index_buffer,
-            uniform_buffer,
-            uniform_bind_group_layout,
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            uniforms,
             texture_bind_group_layout,
+            nearest_sampler: device.create_sampler(&FilterMode::Nearest.sampler_descriptor()),
+            linear_sampler: device.create_sampler(&FilterMode::Linear.sampler_descriptor()),
             sprite_queue: Vec::new(),
-            cached_bind_groups: Vec::new(),
+            texture_bind_groups: HashMap::new(),
+            bind_groups_created: 0,
+            layer_runs: LayerRuns::new(),
+            batches: Vec::new(),
         })
     }
 
+    pub fn set_layer(&mut self, layer: i32) {
+        self.layer_runs.set_layer(layer, self.sprite_queue.len());
+    }
+
+    pub fn set_space(&mut self, space: CoordinateSpace) {
+        self.layer_runs.set_space(space, self.sprite_queue.len());
+    }
+
     pub fn draw_sprite(
         &mut self, 
         sprite: &Sprite, 
@@ -204,11 +358,14 @@ impl SpriteRenderer {
     ) {
         use glam::{Mat4, Vec3, Quat};
         
+        // Screen space has y pointing down, so the quad is flipped to keep the image upright
+        let flip = if self.layer_runs.space() == CoordinateSpace::Screen { -1.0 } else { 1.0 };
+
         let translation = Mat4::from_translation(Vec3::new(x, y, 0.0));
         let rotation_mat = Mat4::from_quat(Quat::from_rotation_z(rotation));
         let scale_mat = Mat4::from_scale(Vec3::new(
             sprite.width * scale,
-            sprite.height * scale,
+            sprite.height * scale * flip,
             1.0,
         ));
         
@@ -218,73 +375,282 @@ impl SpriteRenderer {
             texture: sprite.texture.clone(),
             transform,
             color: [1.0, 1.0, 1.0, 1.0],
+            uv_rect: sprite.uv_rect,
+            filter: sprite.filter,
         });
     }
 
-    pub fn flush<'a>(
-        &'a mut self,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        camera: &mut Camera,
-    ) {
+    /// Stretches `sprite` over the `width` x `height` box at (`x`, `y`) without stretching its
+    /// corners. (`x`, `y`) is the box's top-left in screen space and its bottom-left in world space.
+    pub fn draw_nine_patch(&mut self, sprite: &Sprite, x: f32, y: f32, width: f32, height: f32, insets: NinePatchInsets) {
+        use glam::{Mat4, Vec3};
+
+        let screen = self.layer_runs.space() == CoordinateSpace::Screen;
+        for ([piece_x, piece_y, piece_width, piece_height], uv_rect) in nine_patch_pieces(sprite, insets, width, height) {
+            let center_x = x + piece_x + piece_width / 2.0;
+            // World space has y pointing up, so the top row goes at the far edge
+            let (center_y, flip) = if screen {
+                (y + piece_y + piece_height / 2.0, -1.0)
+            } else {
+                (y + height - piece_y - piece_height / 2.0, 1.0)
+            };
+
+            let transform = Mat4::from_translation(Vec3::new(center_x, center_y, 0.0))
+                * Mat4::from_scale(Vec3::new(piece_width, piece_height * flip, 1.0));
+            self.sprite_queue.push(SpriteDrawCall {
+                texture: sprite.texture.clone(),
+                transform,
+                color: [1.0, 1.0, 1.0, 1.0],
+                uv_rect,
+                filter: sprite.filter,
+            });
+        }
+    }
+
+    /// Texture bind groups made since startup. Stays flat while the same textures are drawn.
+    pub fn bind_groups_created(&self) -> usize {
+        self.bind_groups_created
+    }
+
+    /// Drops cached bind groups so unloaded textures can be freed.
+    pub fn clear_texture_cache(&mut self) {
+        self.texture_bind_groups.clear();
+    }
+
+    /// Uploads instance data for this frame and groups it into per-layer texture batches.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, camera: &mut Camera) {
+        self.batches.clear();
         if self.sprite_queue.is_empty() {
             return;
         }
-        
-        // Clear cached bind groups from previous frame
-        self.cached_bind_groups.clear();
-        
-        let view_proj = camera.get_view_projection_matrix();
-        
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        
-        // Pre-create all bind groups and store them so they live long enough
-        for draw_call in &self.sprite_queue {
-            let uniform = SpriteUniform {
-                view_proj: view_proj.to_cols_array_2d(),
+
+        self.uniforms.write(queue, camera);
+
+        if self.sprite_queue.len() > self.instance_capacity {
+            self.instance_capacity = self.sprite_queue.len().next_power_of_two();
+            self.instance_buffer = create_instance_buffer(device, self.instance_capacity);
+        }
+
+        let (draw_calls, layers) = self.layer_runs.reorder(&self.sprite_queue);
+
+        let instances: Vec<SpriteInstance> = draw_calls
+            .iter()
+            .map(|draw_call| SpriteInstance {
                 transform: draw_call.transform.to_cols_array_2d(),
                 color: draw_call.color,
-            };
-            
-            queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
-            
-            let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &self.uniform_bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: self.uniform_buffer.as_entire_binding(),
-                }],
-                label: Some("Sprite Uniform Bind Group"),
-            });
-            
-            let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &self.texture_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(draw_call.texture.view()),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(draw_call.texture.sampler()),
-                    },
-                ],
-                label: Some("Sprite Texture Bind Group"),
-            });
-            
-            self.cached_bind_groups.push((uniform_bind_group, texture_bind_group));
+                uv_rect: draw_call.uv_rect,
+            })
+            .collect();
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+
+        let keys: Vec<BindGroupKey> = draw_calls
+            .iter()
+            .map(|draw_call| (draw_call.texture.id(), draw_call.filter))
+            .collect();
+        self.batches = build_batches(&layers, &keys);
+
+        for (_, _, key, instances) in &self.batches {
+            if !self.texture_bind_groups.contains_key(key) {
+                let draw_call = &draw_calls[instances.start as usize];
+                let sampler = match draw_call.filter {
+                    FilterMode::Nearest => &self.nearest_sampler,
+                    FilterMode::Linear => &self.linear_sampler,
+                };
+                let bind_group = create_texture_bind_group(device, &self.texture_bind_group_layout, &draw_call.texture, sampler);
+                self.texture_bind_groups.insert(*key, bind_group);
+                self.bind_groups_created += 1;
+            }
         }
-        
-        // Now draw all sprites using the cached bind groups
-        for (uniform_bind_group, texture_bind_group) in &self.cached_bind_groups {
-            render_pass.set_bind_group(0, uniform_bind_group, &[]);
-            render_pass.set_bind_group(1, texture_bind_group, &[]);
-            render_pass.draw_indexed(0..6, 0, 0..1);
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = i32> + '_ {
+        self.batches.iter().map(|(layer, _, _, _)| *layer)
+    }
+
+    pub fn draw_layer<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, layer: i32) {
+        let mut batches = self.batches.iter().filter(|(l, _, _, _)| *l == layer).peekable();
+        if batches.peek().is_none() {
+            return;
         }
-        
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        for (_, space, key, instances) in batches {
+            render_pass.set_bind_group(0, self.uniforms.bind_group(*space), &[]);
+            render_pass.set_bind_group(1, &self.texture_bind_groups[key], &[]);
+            render_pass.draw_indexed(0..6, 0, instances.clone());
+        }
+    }
+
+    pub fn clear(&mut self) {
         self.sprite_queue.clear();
+        self.layer_runs.reset();
+        self.batches.clear();
     }
-}
\ No newline at end of file
+}
+
+/// Groups draws, already in layer order, into instanced draws. Consecutive sprites sharing a
+/// texture and filter become one batch, which keeps submission order intact for alpha blending.
+fn build_batches(layers: &[LayerDraw], keys: &[BindGroupKey]) -> Vec<SpriteBatch> {
+    let mut batches: Vec<SpriteBatch> = Vec::new();
+    for (layer, space, range) in layers {
+        for i in range.clone() {
+            let key = keys[i as usize];
+            match batches.last_mut() {
+                Some((l, sp, k, batch)) if l == layer && sp == space && *k == key => batch.end = i + 1,
+                _ => batches.push((*layer, *space, key, i..i + 1)),
+            }
+        }
+    }
+    batches
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sprite Instance Buffer"),
+        size: (capacity * std::mem::size_of::<SpriteInstance>()) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_texture_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, texture: &Texture, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(texture.view()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("Sprite Texture Bind Group"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD: CoordinateSpace = CoordinateSpace::World;
+
+    #[test]
+    fn one_texture_is_one_batch() {
+        let keys = vec![(7, FilterMode::Nearest); 1000];
+        let batches = build_batches(&[(0, WORLD, 0..1000)], &keys);
+        assert_eq!(batches, vec![(0, WORLD, (7, FilterMode::Nearest), 0..1000)]);
+    }
+
+    #[test]
+    fn texture_filter_or_layer_changes_split_batches() {
+        let keys = [
+            (1, FilterMode::Nearest), (1, FilterMode::Nearest),
+            (2, FilterMode::Nearest),
+            (2, FilterMode::Linear),
+            (2, FilterMode::Linear),
+        ];
+        let batches = build_batches(&[(0, WORLD, 0..4), (1, WORLD, 4..5)], &keys);
+        assert_eq!(batches, vec![
+            (0, WORLD, (1, FilterMode::Nearest), 0..2),
+            (0, WORLD, (2, FilterMode::Nearest), 2..3),
+            (0, WORLD, (2, FilterMode::Linear), 3..4),
+            (1, WORLD, (2, FilterMode::Linear), 4..5),
+        ]);
+    }
+
+    #[test]
+    fn textures_are_not_merged_across_other_draws() {
+        // Batching A, B, A as A+A, B would draw B over the second A
+        let keys = [(1, FilterMode::Nearest), (2, FilterMode::Nearest), (1, FilterMode::Nearest)];
+        let batches = build_batches(&[(0, WORLD, 0..3)], &keys);
+        assert_eq!(batches.len(), 3);
+    }
+
+
+    #[test]
+    fn drawing_one_texture_for_ten_frames_makes_one_bind_group() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let mut renderer = SpriteRenderer::new(&device, &crate::renderer::test_surface_config(64, 64)).unwrap();
+        let mut camera = Camera::new(64.0, 64.0);
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(2, 2));
+        let sprite = Sprite::new(Texture::from_image(&device, &queue, &image, None).unwrap());
+
+        for _ in 0..10 {
+            renderer.draw_sprite(&sprite, 0.0, 0.0, 0.0, 1.0, &camera);
+            renderer.draw_sprite(&sprite, 10.0, 0.0, 0.0, 1.0, &camera);
+            renderer.prepare(&device, &queue, &mut camera);
+            renderer.clear();
+        }
+        assert_eq!(renderer.bind_groups_created(), 1);
+
+        // Clearing the cache (as unloading a game does) makes the next draw start over
+        renderer.clear_texture_cache();
+        renderer.draw_sprite(&sprite, 0.0, 0.0, 0.0, 1.0, &camera);
+        renderer.prepare(&device, &queue, &mut camera);
+        assert_eq!(renderer.bind_groups_created(), 2);
+    }
+
+
+    fn assert_rects_near(actual: [f32; 4], expected: [f32; 4]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    // A 30x30 frame on the right half of a 60x30 texture
+    fn frame_sprite() -> Sprite {
+        let (device, queue) = crate::renderer::test_gpu();
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(60, 30));
+        let texture = Texture::from_image(&device, &queue, &image, None).unwrap();
+        Sprite::with_region(texture, 30, 0, 30, 30).unwrap()
+    }
+
+    #[test]
+    fn nine_patch_keeps_corners_and_stretches_the_rest() {
+        let pieces = nine_patch_pieces(&frame_sprite(), NinePatchInsets::uniform(10.0), 100.0, 50.0);
+
+        let rects: Vec<[f32; 4]> = pieces.iter().map(|(rect, _)| *rect).collect();
+        assert_eq!(rects, vec![
+            [0.0, 0.0, 10.0, 10.0], [10.0, 0.0, 80.0, 10.0], [90.0, 0.0, 10.0, 10.0],
+            [0.0, 10.0, 10.0, 30.0], [10.0, 10.0, 80.0, 30.0], [90.0, 10.0, 10.0, 30.0],
+            [0.0, 40.0, 10.0, 10.0], [10.0, 40.0, 80.0, 10.0], [90.0, 40.0, 10.0, 10.0],
+        ]);
+
+        // Each piece samples its third of the frame, inside the frame's region of the texture
+        let third_u = 1.0 / 6.0;
+        let third_v = 1.0 / 3.0;
+        for (i, (_, uv)) in pieces.iter().enumerate() {
+            let (column, row) = ((i % 3) as f32, (i / 3) as f32);
+            assert_rects_near(*uv, [0.5 + column * third_u, row * third_v, third_u, third_v]);
+        }
+    }
+
+    #[test]
+    fn nine_patch_borders_shrink_to_fit_small_boxes() {
+        let pieces = nine_patch_pieces(&frame_sprite(), NinePatchInsets::uniform(10.0), 10.0, 10.0);
+
+        // No room for the edges or middle, so only the four corners are left, at half size
+        let rects: Vec<[f32; 4]> = pieces.iter().map(|(rect, _)| *rect).collect();
+        assert_eq!(rects, vec![
+            [0.0, 0.0, 5.0, 5.0], [5.0, 0.0, 5.0, 5.0],
+            [0.0, 5.0, 5.0, 5.0], [5.0, 5.0, 5.0, 5.0],
+        ]);
+        assert_rects_near(pieces[3].1, [0.5 + 2.0 / 6.0, 2.0 / 3.0, 1.0 / 6.0, 1.0 / 3.0]);
+    }
+
+
+    #[test]
+    fn atlas_regions_normalize_to_the_texture_size() {
+        assert_eq!(region_uv_rect(64, 32, 32, 16, 256, 128), [0.25, 0.25, 0.125, 0.125]);
+        assert_eq!(region_uv_rect(0, 0, 256, 128, 256, 128), FULL_UV_RECT);
+        // The last 16x16 cell of a non-power-of-two sheet ends exactly on the edge
+        let [u, v, width, height] = region_uv_rect(144, 80, 16, 16, 160, 96);
+        assert_eq!((u + width, v + height), (1.0, 1.0));
+    }
+}
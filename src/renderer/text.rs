@@ -1,11 +1,12 @@
 // src/renderer/text.rs - FIXED FONT RENDERING
 use crate::errors::CacaoError;
-use super::Camera;
+use super::{Camera, CoordinateSpace, layer::LayerRuns, view_uniforms::ViewUniforms};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::ops::Range;
 
 const FONT_ATLAS_SIZE: u32 = 512;
-const MAX_GLYPHS: usize = 96; // ASCII printable characters (32-126)
+const TTF_ATLAS_SIZE: u32 = 1024;
+const BITMAP_GLYPH_SIZE: u32 = 8;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -31,12 +32,8 @@ impl GlyphVertex {
     }
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct TextUniform {
-    view_proj: [[f32; 4]; 4],
-}
-
+/// Placement of a glyph inside its atlas, in atlas pixels at the size it was rasterized.
+/// `offset_y` is measured from the top of the line box down to the top of the bitmap.
 #[derive(Debug)]
 struct GlyphMetrics {
     x: u32,
@@ -44,35 +41,278 @@ struct GlyphMetrics {
     width: u32,
     height: u32,
     advance_x: f32,
-    advance_y: f32,
     offset_x: i32,
     offset_y: i32,
 }
 
+/// A glyph atlas for one font. TTF/OTF fonts rasterize glyphs on demand for every
+/// (char, pixel size) pair they're drawn at; the built-in bitmap font has `font: None`
+/// and a fixed set of 8px glyphs.
 struct FontAtlas {
     texture: wgpu::Texture,
-    view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
     bind_group: wgpu::BindGroup,
-    glyph_metrics: HashMap<char, GlyphMetrics>,
+    size: u32,
+    pixels: Vec<u8>,
+    dirty: bool,
+    font: Option<fontdue::Font>,
+    glyph_metrics: HashMap<(char, u32), GlyphMetrics>,
     cursor_x: u32,
     cursor_y: u32,
     max_row_height: u32,
+    // Set when a glyph didn't fit; the atlas is cleared once the frame using it is done
+    full: bool,
+}
+
+impl FontAtlas {
+    fn new(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        size: u32,
+        label: &str,
+        font: Option<fontdue::Font>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(label),
+        });
+
+        Self {
+            texture,
+            bind_group,
+            size,
+            pixels: vec![0u8; (size * size) as usize],
+            dirty: true,
+            font,
+            glyph_metrics: HashMap::new(),
+            cursor_x: 0,
+            cursor_y: 0,
+            max_row_height: 0,
+            full: false,
+        }
+    }
+
+    /// Pixel size glyphs are rasterized at when drawing text at `size`.
+    fn raster_size(&self, size: f32) -> u32 {
+        match self.font {
+            Some(_) => (size.round() as u32).max(1),
+            None => BITMAP_GLYPH_SIZE,
+        }
+    }
+
+    fn line_height(&self, px: u32) -> f32 {
+        self.font.as_ref()
+            .and_then(|font| font.horizontal_line_metrics(px as f32))
+            .map(|metrics| metrics.new_line_size)
+            .unwrap_or(px as f32)
+    }
+
+    fn kerning(&self, left: char, right: char, px: u32) -> f32 {
+        self.font.as_ref()
+            .and_then(|font| font.horizontal_kern(left, right, px as f32))
+            .unwrap_or(0.0)
+    }
+
+    /// Makes sure `ch` at `px` is present in the atlas, rasterizing it if needed.
+    /// Returns false if the font has no such glyph, or the atlas has no room left this frame.
+    fn ensure_glyph(&mut self, ch: char, px: u32) -> bool {
+        if self.glyph_metrics.contains_key(&(ch, px)) {
+            return true;
+        }
+
+        let (metrics, bitmap, ascent) = match &self.font {
+            Some(font) if font.lookup_glyph_index(ch) != 0 => {
+                let (metrics, bitmap) = font.rasterize(ch, px as f32);
+                let ascent = font.horizontal_line_metrics(px as f32)
+                    .map(|line| line.ascent)
+                    .unwrap_or(px as f32);
+                (metrics, bitmap, ascent)
+            }
+            _ => return false,
+        };
+
+        let width = metrics.width as u32;
+        let height = metrics.height as u32;
+
+        let (x, y) = match self.allocate(width, height) {
+            Some(position) => position,
+            None => {
+                // Clearing now would leave quads already queued this frame pointing at the wrong glyphs
+                if !self.full {
+                    log::warn!("Font atlas full, evicting cached glyphs next frame");
+                }
+                self.full = true;
+                return false;
+            }
+        };
+
+        for row in 0..height as usize {
+            let src = &bitmap[row * width as usize..(row + 1) * width as usize];
+            let dst_start = (y as usize + row) * self.size as usize + x as usize;
+            self.pixels[dst_start..dst_start + width as usize].copy_from_slice(src);
+        }
+
+        self.glyph_metrics.insert((ch, px), GlyphMetrics {
+            x,
+            y,
+            width,
+            height,
+            advance_x: metrics.advance_width,
+            offset_x: metrics.xmin,
+            offset_y: (ascent - (metrics.ymin as f32 + metrics.height as f32)).round() as i32,
+        });
+        self.dirty = true;
+        true
+    }
+
+    // Simple shelf packer with a 1px gutter between glyphs to avoid bleeding
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if self.cursor_x + width + 1 > self.size {
+            self.cursor_x = 0;
+            self.cursor_y += self.max_row_height + 1;
+            self.max_row_height = 0;
+        }
+
+        if width + 1 > self.size || self.cursor_y + height + 1 > self.size {
+            return None;
+        }
+
+        let position = (self.cursor_x, self.cursor_y);
+        self.cursor_x += width + 1;
+        self.max_row_height = self.max_row_height.max(height);
+        Some(position)
+    }
+
+    /// Clears the atlas if it ran out of room. Only safe between frames.
+    fn evict_if_full(&mut self) {
+        if self.full {
+            self.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.full = false;
+        self.glyph_metrics.clear();
+        self.pixels.fill(0);
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.max_row_height = 0;
+        self.dirty = true;
+    }
+
+    fn upload(&mut self, queue: &wgpu::Queue) {
+        if !self.dirty {
+            return;
+        }
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.size),
+                rows_per_image: Some(self.size),
+            },
+            wgpu::Extent3d {
+                width: self.size,
+                height: self.size,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.dirty = false;
+    }
+}
+
+/// Where a line of text sits relative to the x it is drawn at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Starts at x
+    #[default]
+    Left,
+    /// Centered on x
+    Center,
+    /// Ends at x
+    Right,
+}
+
+impl TextAlign {
+    /// How far left of x a line `width` wide starts.
+    pub fn offset(self, width: f32) -> f32 {
+        match self {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => -width / 2.0,
+            TextAlign::Right => -width,
+        }
+    }
+}
+
+/// One draw call: a stretch of the reordered indices that shares a layer, space and font.
+struct TextDraw {
+    layer: i32,
+    space: CoordinateSpace,
+    // Index into `frame_fonts`
+    font: u16,
+    indices: Range<u32>,
 }
 
 pub struct TextRenderer {
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
+    uniforms: ViewUniforms,
     
     font_atlases: HashMap<String, FontAtlas>,
     current_font: String,
     
     vertices: Vec<GlyphVertex>,
     indices: Vec<u16>,
+    // The font each entry of `indices` was drawn with, as an index into `frame_fonts`
+    index_fonts: Vec<u16>,
+    // Fonts used so far this frame
+    frame_fonts: Vec<String>,
     max_chars: usize,
+    layer_runs: LayerRuns,
+    draws: Vec<TextDraw>,
     
     texture_bind_group_layout: wgpu::BindGroupLayout,
 }
@@ -88,13 +328,6 @@ impl TextRenderer {
             source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/text.wgsl").into()),
         });
 
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Text Uniform Buffer"),
-            size: std::mem::size_of::<TextUniform>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
@@ -131,14 +364,7 @@ impl TextRenderer {
             label: Some("Text Texture Bind Group Layout"),
         });
 
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-            label: Some("Text Uniform Bind Group"),
-        });
+        let uniforms = ViewUniforms::new(device, &uniform_bind_group_layout, "Text");
 
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Text Render Pipeline Layout"),
@@ -204,13 +430,16 @@ impl TextRenderer {
             render_pipeline,
             vertex_buffer,
             index_buffer,
-            uniform_buffer,
-            uniform_bind_group,
+            uniforms,
             font_atlases,
             current_font: "default".to_string(),
             vertices: Vec::new(),
             indices: Vec::new(),
+            index_fonts: Vec::new(),
+            frame_fonts: Vec::new(),
             max_chars,
+            layer_runs: LayerRuns::new(),
+            draws: Vec::new(),
             texture_bind_group_layout,
         })
     }
@@ -220,25 +449,8 @@ impl TextRenderer {
         queue: &wgpu::Queue,
         bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Result<FontAtlas, CacaoError> {
-        let size = wgpu::Extent3d {
-            width: FONT_ATLAS_SIZE,
-            height: FONT_ATLAS_SIZE,
-            depth_or_array_layers: 1,
-        };
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Default Font Texture"),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        let mut atlas = FontAtlas::new(device, bind_group_layout, FONT_ATLAS_SIZE, "Default Font Atlas", None);
 
-        let mut data = vec![0u8; (FONT_ATLAS_SIZE * FONT_ATLAS_SIZE) as usize];
-        
         // Simple 8x8 font rendering
         for ch in 32u8..127u8 {
             let idx = ch as usize - 32;
@@ -256,177 +468,260 @@ impl TextRenderer {
                         let atlas_y = char_y + y;
                         let atlas_idx = atlas_y * FONT_ATLAS_SIZE as usize + atlas_x;
                         
-                        if atlas_idx < data.len() {
-                            data[atlas_idx] = 255;
+                        if atlas_idx < atlas.pixels.len() {
+                            atlas.pixels[atlas_idx] = 255;
                         }
                     }
                 }
             }
-        }
-        
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(FONT_ATLAS_SIZE),
-                rows_per_image: Some(FONT_ATLAS_SIZE),
-            },
-            size,
-        );
-
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-            label: Some("Default Font Bind Group"),
-        });
 
-        let mut glyph_metrics = HashMap::new();
-        for ch in 32u8..127u8 {  // Changed from 32..127 to 32u8..127u8
-            let idx = ch as usize - 32;
-            let row = idx / 16;
-            let col = idx % 16;
-            
-            glyph_metrics.insert(
-                ch as char,  // This now works because ch is u8
+            // Space keeps its historical half-width advance and draws nothing
+            let (width, advance_x) = if ch == b' ' { (0, 4.0) } else { (8, 8.0) };
+            atlas.glyph_metrics.insert(
+                (ch as char, BITMAP_GLYPH_SIZE),
                 GlyphMetrics {
                     x: (col * 8) as u32,
                     y: (row * 8) as u32,
-                    width: 8,
-                    height: 8,
-                    advance_x: 8.0,
-                    advance_y: 0.0,
+                    width,
+                    height: width,
+                    advance_x,
                     offset_x: 0,
                     offset_y: 0,
                 }
             );
         }
 
-        Ok(FontAtlas {
-            texture,
-            view,
-            sampler,
-            bind_group,
-            glyph_metrics,
-            cursor_x: 0,
-            cursor_y: 0,
-            max_row_height: 0,
-        })
+        atlas.upload(queue);
+        Ok(atlas)
+    }
+
+    /// Parses a TTF/OTF font and registers it under `font_name` for `set_font`.
+    pub fn load_font(&mut self, device: &wgpu::Device, font_name: &str, data: &[u8]) -> Result<(), CacaoError> {
+        let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+            .map_err(|e| CacaoError::RenderError(format!("Failed to parse font {}: {}", font_name, e)))?;
+
+        let label = format!("{} Font Atlas", font_name);
+        let atlas = FontAtlas::new(device, &self.texture_bind_group_layout, TTF_ATLAS_SIZE, &label, Some(font));
+        self.font_atlases.insert(font_name.to_string(), atlas);
+        log::info!("Loaded font: {}", font_name);
+        Ok(())
     }
 
-    pub fn set_font(&mut self, font_name: &str) {
-        if self.font_atlases.contains_key(font_name) {
-            self.current_font = font_name.to_string();
+    /// Returns false, leaving the current font alone, if `font_name` was never loaded.
+    pub fn set_font(&mut self, font_name: &str) -> bool {
+        if !self.font_atlases.contains_key(font_name) {
+            return false;
         }
+        self.current_font = font_name.to_string();
+        true
+    }
+
+    pub fn set_layer(&mut self, layer: i32) {
+        self.layer_runs.set_layer(layer, self.indices.len());
+    }
+
+    pub fn set_space(&mut self, space: CoordinateSpace) {
+        self.layer_runs.set_space(space, self.indices.len());
     }
 
     pub fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) {
+        let first_index = self.indices.len();
+        self.queue_glyphs(text, x, y, size, color);
+
+        let font = match self.frame_fonts.iter().position(|name| *name == self.current_font) {
+            Some(font) => font,
+            None => {
+                self.frame_fonts.push(self.current_font.clone());
+                self.frame_fonts.len() - 1
+            }
+        };
+        self.index_fonts.resize(first_index, 0);
+        self.index_fonts.resize(self.indices.len(), font as u16);
+    }
+
+    fn queue_glyphs(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4]) {
         let font_atlas = self.font_atlases.get_mut(&self.current_font).unwrap();
+        let px = font_atlas.raster_size(size);
+        let scale = size / px as f32;
+        let line_height = font_atlas.line_height(px) * scale;
+        let atlas_size = font_atlas.size as f32;
+
         let mut cursor_x = x;
         let mut cursor_y = y;
+        let mut previous: Option<char> = None;
 
         for ch in text.chars() {
             if ch == '\n' {
                 cursor_x = x;
-                cursor_y += size;
+                cursor_y += line_height;
+                previous = None;
                 continue;
             }
 
             if ch == '\t' {
                 cursor_x += size * 4.0; // 4 spaces
+                previous = None;
+                continue;
+            }
+
+            if !font_atlas.ensure_glyph(ch, px) {
+                // Skip unknown characters
+                cursor_x += size * 0.5;
+                previous = None;
                 continue;
             }
 
-            let metrics = match font_atlas.glyph_metrics.get(&ch) {
-                Some(metrics) => metrics,
-                None => {
-                    // Skip unknown characters
-                    if ch != ' ' {
-                        cursor_x += size * 0.5; // Space for unknown char
+            if let Some(left) = previous {
+                cursor_x += font_atlas.kerning(left, ch, px) * scale;
+            }
+            previous = Some(ch);
+
+            let metrics = &font_atlas.glyph_metrics[&(ch, px)];
+
+            if metrics.width > 0 && metrics.height > 0 {
+                let glyph_width = metrics.width as f32 * scale;
+                let glyph_height = metrics.height as f32 * scale;
+
+                let u0 = metrics.x as f32 / atlas_size;
+                let v0 = metrics.y as f32 / atlas_size;
+                let u1 = (metrics.x + metrics.width) as f32 / atlas_size;
+                let v1 = (metrics.y + metrics.height) as f32 / atlas_size;
+
+                let pos_x = cursor_x + metrics.offset_x as f32 * scale;
+                let pos_y = cursor_y + metrics.offset_y as f32 * scale;
+
+                let vert_idx = self.vertices.len() as u16;
+
+                self.vertices.push(GlyphVertex {
+                    position: [pos_x, pos_y],
+                    tex_coords: [u0, v0],
+                    color,
+                });
+                self.vertices.push(GlyphVertex {
+                    position: [pos_x + glyph_width, pos_y],
+                    tex_coords: [u1, v0],
+                    color,
+                });
+                self.vertices.push(GlyphVertex {
+                    position: [pos_x + glyph_width, pos_y + glyph_height],
+                    tex_coords: [u1, v1],
+                    color,
+                });
+                self.vertices.push(GlyphVertex {
+                    position: [pos_x, pos_y + glyph_height],
+                    tex_coords: [u0, v1],
+                    color,
+                });
+
+                self.indices.extend_from_slice(&[
+                    vert_idx, vert_idx + 1, vert_idx + 2,
+                    vert_idx + 2, vert_idx + 3, vert_idx,
+                ]);
+            }
+
+            cursor_x += metrics.advance_x * scale;
+        }
+    }
+
+    /// Draws `text`, breaking lines on spaces so no line is wider than `max_width`.
+    /// Words longer than `max_width` are kept whole on their own line.
+    pub fn draw_text_wrapped(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], max_width: f32) {
+        let wrapped = self.wrap_text(text, size, max_width);
+        self.draw_text(&wrapped, x, y, size, color);
+    }
+
+    fn wrap_text(&mut self, text: &str, size: f32, max_width: f32) -> String {
+        let space_width = self.line_width(" ", size);
+        let mut wrapped = String::with_capacity(text.len());
+
+        for (i, paragraph) in text.split('\n').enumerate() {
+            if i > 0 {
+                wrapped.push('\n');
+            }
+
+            let mut line_width = 0.0;
+            for (j, word) in paragraph.split(' ').enumerate() {
+                let word_width = self.line_width(word, size);
+                if j > 0 {
+                    if line_width + space_width + word_width > max_width {
+                        wrapped.push('\n');
+                        line_width = 0.0;
+                    } else {
+                        wrapped.push(' ');
+                        line_width += space_width;
                     }
-                    continue;
                 }
-            };
+                wrapped.push_str(word);
+                line_width += word_width;
+            }
+        }
+
+        wrapped
+    }
+
+    /// Each line of `text` is aligned on its own.
+    pub fn draw_text_aligned(&mut self, text: &str, x: f32, y: f32, size: f32, color: [f32; 4], align: TextAlign) {
+        let font_atlas = &self.font_atlases[&self.current_font];
+        let px = font_atlas.raster_size(size);
+        let line_height = font_atlas.line_height(px) * size / px as f32;
+
+        for (i, line) in text.split('\n').enumerate() {
+            let width = self.line_width(line, size);
+            self.draw_text(line, x + align.offset(width), y + i as f32 * line_height, size, color);
+        }
+    }
+
+    /// Width and height of `text` at `size` in the current font. The width is the
+    /// widest line; each `\n` adds a line.
+    pub fn measure_text(&mut self, text: &str, size: f32) -> (f32, f32) {
+        let font_atlas = &self.font_atlases[&self.current_font];
+        let px = font_atlas.raster_size(size);
+        let line_height = font_atlas.line_height(px) * size / px as f32;
+
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            width = width.max(self.line_width(line, size));
+            lines += 1;
+        }
 
-            if ch == ' ' {
-                cursor_x += size * 0.5; // Space width
+        (width, lines as f32 * line_height)
+    }
+
+    // Advance width of a single line of text in the current font
+    fn line_width(&mut self, line: &str, size: f32) -> f32 {
+        let font_atlas = self.font_atlases.get_mut(&self.current_font).unwrap();
+        let px = font_atlas.raster_size(size);
+        let scale = size / px as f32;
+
+        let mut width = 0.0;
+        let mut previous: Option<char> = None;
+        for ch in line.chars() {
+            if ch == '\t' {
+                width += size * 4.0;
+                previous = None;
                 continue;
             }
 
-            let glyph_width = metrics.width as f32 * size / 8.0;
-            let glyph_height = metrics.height as f32 * size / 8.0;
-            
-            let u0 = metrics.x as f32 / FONT_ATLAS_SIZE as f32;
-            let v0 = metrics.y as f32 / FONT_ATLAS_SIZE as f32;
-            let u1 = (metrics.x + metrics.width) as f32 / FONT_ATLAS_SIZE as f32;
-            let v1 = (metrics.y + metrics.height) as f32 / FONT_ATLAS_SIZE as f32;
-
-            let pos_x = cursor_x + (metrics.offset_x as f32) * size / 8.0;
-            let pos_y = cursor_y + (metrics.offset_y as f32) * size / 8.0;
-
-            let vert_idx = self.vertices.len() as u16;
-
-            self.vertices.push(GlyphVertex {
-                position: [pos_x, pos_y],
-                tex_coords: [u0, v0],
-                color,
-            });
-            self.vertices.push(GlyphVertex {
-                position: [pos_x + glyph_width, pos_y],
-                tex_coords: [u1, v0],
-                color,
-            });
-            self.vertices.push(GlyphVertex {
-                position: [pos_x + glyph_width, pos_y + glyph_height],
-                tex_coords: [u1, v1],
-                color,
-            });
-            self.vertices.push(GlyphVertex {
-                position: [pos_x, pos_y + glyph_height],
-                tex_coords: [u0, v1],
-                color,
-            });
-
-            self.indices.extend_from_slice(&[
-                vert_idx, vert_idx + 1, vert_idx + 2,
-                vert_idx + 2, vert_idx + 3, vert_idx,
-            ]);
-
-            cursor_x += metrics.advance_x * size / 8.0;
+            if !font_atlas.ensure_glyph(ch, px) {
+                width += size * 0.5;
+                previous = None;
+                continue;
+            }
+
+            if let Some(left) = previous {
+                width += font_atlas.kerning(left, ch, px) * scale;
+            }
+            previous = Some(ch);
+            width += font_atlas.glyph_metrics[&(ch, px)].advance_x * scale;
         }
+
+        width
     }
 
-    pub fn flush<'a>(
-        &'a mut self,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        queue: &wgpu::Queue,
-        camera: &mut Camera,
-    ) {
+    /// Uploads this frame's glyph quads, sorted by layer and split wherever the font changes.
+    pub fn prepare(&mut self, queue: &wgpu::Queue, camera: &mut Camera) {
+        self.draws.clear();
         if self.vertices.is_empty() {
             return;
         }
@@ -434,27 +729,173 @@ impl TextRenderer {
         if self.vertices.len() / 4 > self.max_chars {
             self.vertices.truncate(self.max_chars * 4);
             self.indices.truncate(self.max_chars * 6);
+            self.index_fonts.truncate(self.max_chars * 6);
         }
 
-        let view_proj = camera.get_view_projection_matrix();
-        let uniform = TextUniform {
-            view_proj: view_proj.to_cols_array_2d(),
-        };
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        self.uniforms.write(queue, camera);
 
+        let (indices, layer_draws) = self.layer_runs.reorder(&self.indices);
+        let (index_fonts, _) = self.layer_runs.reorder(&self.index_fonts);
         queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
-        queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&self.indices));
+        queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&indices));
+
+        for (layer, space, range) in layer_draws {
+            let mut start = range.start;
+            for i in range.start..range.end {
+                let font = index_fonts[i as usize];
+                if i + 1 == range.end || index_fonts[i as usize + 1] != font {
+                    self.draws.push(TextDraw { layer, space, font, indices: start..i + 1 });
+                    start = i + 1;
+                }
+            }
+        }
 
-        let font_atlas = self.font_atlases.get(&self.current_font).unwrap();
+        for name in &self.frame_fonts {
+            if let Some(font_atlas) = self.font_atlases.get_mut(name) {
+                font_atlas.upload(queue);
+            }
+        }
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = i32> + '_ {
+        self.draws.iter().map(|draw| draw.layer)
+    }
+
+    pub fn draw_layer<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, layer: i32) {
+        let mut draws = self.draws.iter().filter(|draw| draw.layer == layer).peekable();
+        if draws.peek().is_none() {
+            return;
+        }
 
         render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-        render_pass.set_bind_group(1, &font_atlas.bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..self.indices.len() as u32, 0, 0..1);
+        for draw in draws {
+            let Some(font_atlas) = self.font_atlases.get(&self.frame_fonts[draw.font as usize]) else {
+                continue;
+            };
+            render_pass.set_bind_group(0, self.uniforms.bind_group(draw.space), &[]);
+            render_pass.set_bind_group(1, &font_atlas.bind_group, &[]);
+            render_pass.draw_indexed(draw.indices.clone(), 0, 0..1);
+        }
+    }
 
+    pub fn clear(&mut self) {
         self.vertices.clear();
         self.indices.clear();
+        self.index_fonts.clear();
+        self.frame_fonts.clear();
+        self.layer_runs.reset();
+        self.draws.clear();
+        for font_atlas in self.font_atlases.values_mut() {
+            font_atlas.evict_if_full();
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{test_gpu, test_surface_config};
+
+    const ROBOTO: &[u8] = include_bytes!("../../assets/fonts/Roboto-Regular.ttf");
+
+    // A Roboto atlas `size` pixels square, built against a real text renderer's layout
+    fn roboto_atlas(size: u32) -> FontAtlas {
+        let (device, queue) = test_gpu();
+        let text = TextRenderer::new(&device, &queue, &test_surface_config(64, 64)).unwrap();
+        let font = fontdue::Font::from_bytes(ROBOTO, fontdue::FontSettings::default()).unwrap();
+        FontAtlas::new(&device, &text.texture_bind_group_layout, size, "Test Font Atlas", Some(font))
+    }
+
+    #[test]
+    fn glyphs_pack_without_overlapping() {
+        let mut atlas = roboto_atlas(128);
+        for ch in 'A'..='Z' {
+            assert!(atlas.ensure_glyph(ch, 16), "no room for {}", ch);
+        }
+        // Already cached glyphs don't take more room
+        let cursor = (atlas.cursor_x, atlas.cursor_y);
+        assert!(atlas.ensure_glyph('A', 16));
+        assert_eq!((atlas.cursor_x, atlas.cursor_y), cursor);
+
+        let rects: Vec<_> = atlas.glyph_metrics.values().map(|m| (m.x, m.y, m.x + m.width, m.y + m.height)).collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.2 < atlas.size && a.3 < atlas.size, "{:?} leaves the atlas", a);
+            for b in &rects[i + 1..] {
+                let apart = a.2 < b.0 || b.2 < a.0 || a.3 < b.1 || b.3 < a.1;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn full_atlases_keep_their_glyphs_until_evicted() {
+        let mut atlas = roboto_atlas(32);
+        let placed: Vec<char> = ('A'..='Z').take_while(|&ch| atlas.ensure_glyph(ch, 24)).collect();
+        assert!(!placed.is_empty() && atlas.full);
+
+        // Glyphs queued this frame stay where they are
+        assert!(placed.iter().all(|&ch| atlas.glyph_metrics.contains_key(&(ch, 24))));
+
+        atlas.evict_if_full();
+        assert!(!atlas.full && atlas.glyph_metrics.is_empty());
+        assert!(atlas.ensure_glyph('Z', 24));
+        assert_eq!((atlas.glyph_metrics[&('Z', 24)].x, atlas.glyph_metrics[&('Z', 24)].y), (0, 0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn wrapped_text_breaks_onto_a_second_line_below_the_first() {
+        let (device, queue) = test_gpu();
+        let mut text = TextRenderer::new(&device, &queue, &test_surface_config(64, 64)).unwrap();
+
+        // Built-in font: 8px glyphs, half-width spaces, so "hello world" is 84px wide
+        assert_eq!(text.wrap_text("hello world", 8.0, 100.0), "hello world");
+        assert_eq!(text.wrap_text("hello world", 8.0, 50.0), "hello\nworld");
+        assert_eq!(text.wrap_text("hi\nthere", 8.0, 50.0), "hi\nthere");
+
+        text.draw_text_wrapped("hello world", 10.0, 20.0, 8.0, [1.0; 4], 50.0);
+        let mut tops: Vec<f32> = text.vertices.chunks(4).map(|quad| quad[0].position[1]).collect();
+        tops.dedup();
+        assert_eq!(tops, [20.0, 28.0]);
+        // Both lines start at x
+        assert_eq!(text.vertices[0].position[0], 10.0);
+        assert_eq!(text.vertices[5 * 4].position[0], 10.0);
+    }
+
+
+    #[test]
+    fn text_is_drawn_in_one_call_per_font_run() {
+        let (device, queue) = test_gpu();
+        let mut text = TextRenderer::new(&device, &queue, &test_surface_config(64, 64)).unwrap();
+        text.load_font(&device, "Roboto", ROBOTO).unwrap();
+
+        text.draw_text("ab", 0.0, 0.0, 16.0, [1.0; 4]);
+        assert!(text.set_font("Roboto"));
+        text.draw_text("cd", 0.0, 20.0, 16.0, [1.0; 4]);
+        text.set_layer(1);
+        assert!(text.set_font("default"));
+        text.draw_text("ef", 0.0, 40.0, 16.0, [1.0; 4]);
+        // Back on layer 0, so this joins the Roboto run queued there earlier
+        text.set_layer(0);
+        text.set_font("Roboto");
+        text.draw_text("gh", 0.0, 60.0, 16.0, [1.0; 4]);
+        text.prepare(&queue, &mut Camera::new(64.0, 64.0));
+
+        assert_eq!(text.frame_fonts, ["default", "Roboto"]);
+        let draws: Vec<_> = text.draws.iter().map(|draw| (draw.layer, draw.font, draw.indices.clone())).collect();
+        assert_eq!(draws, [(0, 0, 0..12), (0, 1, 12..36), (1, 0, 36..48)]);
+    }
+
+    #[test]
+    fn unloaded_fonts_leave_the_current_one_in_use() {
+        let (device, queue) = test_gpu();
+        let mut text = TextRenderer::new(&device, &queue, &test_surface_config(64, 64)).unwrap();
+        // The Wii theme's font only exists once a game ships it
+        assert!(!text.set_font("RodinNTLG"));
+        assert_eq!(text.current_font, "default");
+
+        text.load_font(&device, "RodinNTLG", ROBOTO).unwrap();
+        assert!(text.set_font("RodinNTLG"));
+        assert_eq!(text.current_font, "RodinNTLG");
+    }
+}
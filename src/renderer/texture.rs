@@ -1,7 +1,40 @@
 // src/renderer/texture.rs
 use image::GenericImageView;
+use serde::{Deserialize, Serialize};
 use crate::errors::CacaoError;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_TEXTURE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How a texture is sampled when drawn larger or smaller than its pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FilterMode {
+    /// Hard pixel edges, for pixel art
+    #[default]
+    Nearest,
+    /// Smooth blending between pixels
+    Linear,
+}
+
+impl FilterMode {
+    pub fn sampler_descriptor(self) -> wgpu::SamplerDescriptor<'static> {
+        let filter = match self {
+            FilterMode::Nearest => wgpu::FilterMode::Nearest,
+            FilterMode::Linear => wgpu::FilterMode::Linear,
+        };
+        wgpu::SamplerDescriptor {
+            label: Some("Sprite Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Texture {
@@ -9,9 +42,10 @@ pub struct Texture {
 }
 
 struct TextureInner {
+    id: u64,
     texture: wgpu::Texture,
     view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
+    filter: FilterMode,
     width: u32,
     height: u32,
 }
@@ -24,7 +58,7 @@ impl Texture {
         label: &str,
     ) -> Result<Self, CacaoError> {
         let img = image::load_from_memory(bytes)
-            .map_err(|e| CacaoError::RenderError(format!("Failed to load image: {}", e)))?;
+            .map_err(|e| CacaoError::RenderError(format!("Failed to load image {}: {}", label, e)))?;
         
         Self::from_image(device, queue, &img, Some(label))
     }
@@ -35,7 +69,21 @@ impl Texture {
         img: &image::DynamicImage,
         label: Option<&str>,
     ) -> Result<Self, CacaoError> {
-        let rgba = img.to_rgba8();
+        Self::from_image_filtered(device, queue, img, label, FilterMode::default())
+    }
+
+    /// `filter` is what sprites made from this texture sample with unless they pick their own.
+    pub fn from_image_filtered(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        label: Option<&str>,
+        filter: FilterMode,
+    ) -> Result<Self, CacaoError> {
+        // Sprites blend with premultiplied alpha, so bake it into the texel data.
+        // wgpu accepts any texture size, so non-power-of-two images upload as-is.
+        let mut rgba = img.to_rgba8();
+        premultiply_alpha(&mut rgba);
         let dimensions = img.dimensions();
 
         let size = wgpu::Extent3d {
@@ -72,21 +120,13 @@ impl Texture {
         );
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
 
         Ok(Self {
             inner: Arc::new(TextureInner {
+                id: NEXT_TEXTURE_ID.fetch_add(1, Ordering::Relaxed),
                 texture,
                 view,
-                sampler,
+                filter,
                 width: dimensions.0,
                 height: dimensions.1,
             })
@@ -97,8 +137,8 @@ impl Texture {
         &self.inner.view
     }
 
-    pub fn sampler(&self) -> &wgpu::Sampler {
-        &self.inner.sampler
+    pub fn filter_mode(&self) -> FilterMode {
+        self.inner.filter
     }
 
     pub fn width(&self) -> u32 {
@@ -108,4 +148,83 @@ impl Texture {
     pub fn height(&self) -> u32 {
         self.inner.height
     }
-}
\ No newline at end of file
+
+    /// Bytes the texture's pixels take up on the GPU.
+    pub fn memory_size(&self) -> usize {
+        let bytes_per_pixel = self.inner.texture.format().block_size(None).unwrap_or(4);
+        self.inner.width as usize * self.inner.height as usize * bytes_per_pixel as usize
+    }
+
+    /// Unique for the lifetime of the process; clones share it.
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+}
+
+fn premultiply_alpha(rgba: &mut image::RgbaImage) {
+    for pixel in rgba.pixels_mut() {
+        let alpha = pixel[3] as u16;
+        for channel in 0..3 {
+            pixel[channel] = ((pixel[channel] as u16 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_modes_build_matching_samplers() {
+        let nearest = FilterMode::Nearest.sampler_descriptor();
+        assert_eq!(nearest.mag_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(nearest.min_filter, wgpu::FilterMode::Nearest);
+
+        let linear = FilterMode::Linear.sampler_descriptor();
+        assert_eq!(linear.mag_filter, wgpu::FilterMode::Linear);
+        assert_eq!(linear.min_filter, wgpu::FilterMode::Linear);
+    }
+
+
+    fn png(image: &image::RgbaImage) -> Vec<u8> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageOutputFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn two_by_two() -> image::RgbaImage {
+        image::RgbaImage::from_raw(2, 2, vec![
+            255, 255, 255, 255,   200, 100, 50, 128,
+            255, 0, 0, 0,         10, 20, 30, 255,
+        ]).unwrap()
+    }
+
+    #[test]
+    fn alpha_is_premultiplied_into_the_colour() {
+        let mut rgba = image::load_from_memory(&png(&two_by_two())).unwrap().to_rgba8();
+        premultiply_alpha(&mut rgba);
+
+        assert_eq!(rgba.into_raw(), vec![
+            255, 255, 255, 255,   100, 50, 25, 128,
+            0, 0, 0, 0,           10, 20, 30, 255,
+        ]);
+    }
+
+    #[test]
+    fn png_bytes_upload_at_their_own_size() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let texture = Texture::from_bytes(&device, &queue, &png(&two_by_two()), "tiny.png").unwrap();
+
+        assert_eq!((texture.width(), texture.height()), (2, 2));
+        assert_eq!(texture.memory_size(), 16);
+    }
+
+    #[test]
+    fn decode_errors_name_the_image() {
+        let (device, queue) = crate::renderer::test_gpu();
+        let Err(error) = Texture::from_bytes(&device, &queue, b"not a png", "hero.png") else {
+            panic!("garbage decoded as an image");
+        };
+        assert!(error.to_string().contains("hero.png"), "{}", error);
+    }
+}
@@ -0,0 +1,57 @@
+// src/renderer/view_uniforms.rs
+use super::camera::{Camera, CoordinateSpace};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ViewUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+/// The view-projection uniform each 2D pipeline binds at group 0, one per coordinate space.
+pub struct ViewUniforms {
+    world: (wgpu::Buffer, wgpu::BindGroup),
+    screen: (wgpu::Buffer, wgpu::BindGroup),
+}
+
+impl ViewUniforms {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, label: &str) -> Self {
+        let create = |space: &str| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{} {} Uniform Buffer", label, space)),
+                size: std::mem::size_of::<ViewUniform>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+                label: Some(&format!("{} {} Uniform Bind Group", label, space)),
+            });
+            (buffer, bind_group)
+        };
+
+        Self {
+            world: create("World"),
+            screen: create("Screen"),
+        }
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, camera: &mut Camera) {
+        for (space, (buffer, _)) in [(CoordinateSpace::World, &self.world), (CoordinateSpace::Screen, &self.screen)] {
+            let uniform = ViewUniform {
+                view_proj: camera.matrix_for(space).to_cols_array_2d(),
+            };
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[uniform]));
+        }
+    }
+
+    pub fn bind_group(&self, space: CoordinateSpace) -> &wgpu::BindGroup {
+        match space {
+            CoordinateSpace::World => &self.world.1,
+            CoordinateSpace::Screen => &self.screen.1,
+        }
+    }
+}
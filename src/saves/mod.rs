@@ -1,21 +1,40 @@
 // src/saves/mod.rs
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use aes_gcm::{Aes256Gcm, Nonce, aead::{Aead, KeyInit}};
 use sha2::{Sha256, Digest};
 use rand::RngCore;
 use crate::errors::CacaoError;
 
+// Copies of the most recent saves kept next to save.dat as `save.dat.bak.1` (newest) and up
+const MAX_BACKUPS: u32 = 3;
+const SALT_FILE: &str = "save.salt";
+const SALT_LEN: usize = 16;
+const KDF_ROUNDS: u32 = 100_000;
+
+/// Upgrades save data written by an older version of a game in place.
+pub type SaveMigration<'a> = Box<dyn Fn(&mut HashMap<String, SaveValue>) -> Result<(), CacaoError> + 'a>;
+
+/// One game's save format: the steps that upgrade its older saves. Saves are written at
+/// the highest version any step moves to, or 1 when there are none.
+#[derive(Default)]
+pub struct SaveMigrations<'a> {
+    steps: Vec<(u32, u32, SaveMigration<'a>)>,
+}
+
 pub struct SaveManager {
     saves_dir: PathBuf,
     current_game_id: Option<String>,
     current_save_data: HashMap<String, SaveValue>,
     encryption_key: Option<[u8; 32]>,
+    // Key from before per-game salts, only used to read old saves
+    legacy_encryption_key: Option<[u8; 32]>,
+    // Version new saves are written at, from the migrations the save was loaded with
+    save_version: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SaveValue {
     String(String),
     Integer(i64),
@@ -25,6 +44,14 @@ pub enum SaveValue {
     Object(HashMap<String, SaveValue>),
 }
 
+#[derive(Debug, Clone)]
+pub struct SaveInfo {
+    pub slot: u32,
+    pub path: PathBuf,
+    pub timestamp: u64,
+    pub is_backup: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 struct SaveFileData {
     version: u32,
@@ -32,6 +59,9 @@ struct SaveFileData {
     data: HashMap<String, SaveValue>,
     checksum: String,
     timestamp: u64,
+    // Set when the file only opened with the legacy key; never written
+    #[serde(skip)]
+    legacy_key: bool,
 }
 
 impl SaveManager {
@@ -41,17 +71,55 @@ impl SaveManager {
             current_game_id: None,
             current_save_data: HashMap::new(),
             encryption_key: None,
+            legacy_encryption_key: None,
+            save_version: 1,
         }
     }
 
+    /// Switches to `game_id`'s saves, starting from empty data. Call `load` next to read
+    /// the game's save once its migrations are known.
     pub fn set_game_context(&mut self, game_id: String, secret_key: &str) -> Result<(), CacaoError> {
-        self.current_game_id = Some(game_id.clone());
-        self.encryption_key = Some(derive_encryption_key(secret_key));
-        
-        let game_save_dir = self.saves_dir.join(format!("{}_saves", sanitize_game_id(&game_id)));
-        std::fs::create_dir_all(&game_save_dir)?;
+        let save_dir = self.get_game_save_dir(&game_id);
+        std::fs::create_dir_all(&save_dir)?;
+
+        let salt = load_or_create_salt(&save_dir.join(SALT_FILE))?;
+        self.current_game_id = Some(game_id);
+        self.encryption_key = Some(derive_encryption_key(secret_key, &salt));
+        self.legacy_encryption_key = Some(derive_legacy_encryption_key(secret_key));
+        self.current_save_data.clear();
+        self.save_version = 1;
+        Ok(())
+    }
+
+    /// Reads the current game's save, if it has one. Older saves are upgraded with
+    /// `migrations` and rewritten at its version, keeping the original as a backup.
+    pub fn load(&mut self, migrations: &SaveMigrations) -> Result<(), CacaoError> {
+        let game_id = self.current_game_id.as_ref()
+            .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
+
+        self.save_version = migrations.version();
+        let save_file_path = self.get_save_file_path(game_id);
         
-        self.load_save_data()?;
+        if !save_file_path.exists() {
+            log::info!("No existing save file found for game: {}", game_id);
+            return Ok(());
+        }
+
+        let save_file_data = match self.read_save_file(&save_file_path) {
+            Ok(data) => data,
+            Err(e @ (CacaoError::SaveDecryptError(_) | CacaoError::SaveCorruptError(_))) => {
+                self.recover_from_backup(&save_file_path, e)?
+            }
+            Err(e) => return Err(e),
+        };
+        let outdated = save_file_data.version != self.save_version || save_file_data.legacy_key;
+        self.current_save_data = migrations.migrate(save_file_data)?;
+        log::info!("Save data loaded for game: {}", game_id);
+
+        if outdated {
+            self.rotate_backups(&save_file_path)?;
+            self.write_save_file(&save_file_path)?;
+        }
         Ok(())
     }
 
@@ -68,6 +136,51 @@ impl SaveManager {
         self.current_save_data.get(key)
     }
 
+    /// Writes `value` at a dot-separated path like `player.stats.hp`, creating any
+    /// missing objects along the way. Fails if a step of the path isn't an object.
+    pub fn write_path(&mut self, path: &str, value: SaveValue) -> Result<(), CacaoError> {
+        if self.current_game_id.is_none() {
+            return Err(CacaoError::CryptoError("No game context set".to_string()));
+        }
+
+        let keys: Vec<&str> = path.split('.').collect();
+        if keys.iter().any(|key| key.is_empty()) {
+            return Err(invalid_path(path, "empty key"));
+        }
+
+        let (last, parents) = keys.split_last().unwrap();
+        let mut fields = &mut self.current_save_data;
+        for key in parents {
+            let entry = fields.entry(key.to_string())
+                .or_insert_with(|| SaveValue::Object(HashMap::new()));
+            fields = match entry {
+                SaveValue::Object(inner) => inner,
+                _ => return Err(invalid_path(path, &format!("'{}' is not an object", key))),
+            };
+        }
+
+        fields.insert(last.to_string(), value);
+        Ok(())
+    }
+
+    /// Reads the value at a dot-separated path, or `None` if any step is missing.
+    pub fn read_path(&self, path: &str) -> Option<&SaveValue> {
+        let mut keys = path.split('.');
+        let mut value = self.current_save_data.get(keys.next()?)?;
+        for key in keys {
+            match value {
+                SaveValue::Object(fields) => value = fields.get(key)?,
+                _ => return None,
+            }
+        }
+        Some(value)
+    }
+
+    /// Every value in the current game's save.
+    pub fn data(&self) -> &HashMap<String, SaveValue> {
+        &self.current_save_data
+    }
+
     pub fn exists(&self, key: &str) -> bool {
         self.current_save_data.contains_key(key)
     }
@@ -80,15 +193,195 @@ impl SaveManager {
         self.current_save_data.clear();
     }
 
+    /// Moves the previous save into the backup rotation, then writes the new one, so the
+    /// last `MAX_BACKUPS` saves before this one can each be restored.
     pub fn save_to_disk(&self) -> Result<(), CacaoError> {
         let game_id = self.current_game_id.as_ref()
             .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
 
+        let save_file_path = self.get_save_file_path(game_id);
+        self.rotate_backups(&save_file_path)?;
+        self.write_save_file(&save_file_path)
+    }
+
+    pub fn list_backups(&self) -> Vec<SaveInfo> {
+        let game_id = match self.current_game_id.as_ref() {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        let save_file_path = self.get_save_file_path(game_id);
+        let mut backups = Vec::new();
+        for index in 1..=MAX_BACKUPS {
+            let path = backup_path(&save_file_path, index);
+            if !path.exists() {
+                continue;
+            }
+            match self.read_save_file(&path) {
+                Ok(data) => backups.push(SaveInfo {
+                    slot: index,
+                    path,
+                    timestamp: data.timestamp,
+                    is_backup: true,
+                }),
+                Err(e) => log::warn!("⚠️ Skipping unreadable backup {}: {}", index, e),
+            }
+        }
+        backups
+    }
+
+    /// Swaps backup `index` with the current save and reloads it. Backup 1 is the save
+    /// before the current one.
+    pub fn restore_backup(&mut self, index: u32, migrations: &SaveMigrations) -> Result<(), CacaoError> {
+        let game_id = self.current_game_id.as_ref()
+            .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
+
+        let save_file_path = self.get_save_file_path(game_id);
+        let backup = backup_path(&save_file_path, index);
+        if !backup.exists() {
+            return Err(CacaoError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Backup {} does not exist", index),
+            )));
+        }
+
+        // Validate before touching anything on disk
+        self.read_save_file(&backup)?;
+
+        if save_file_path.exists() {
+            let swap_path = save_file_path.with_extension("dat.swap");
+            std::fs::rename(&save_file_path, &swap_path)?;
+            std::fs::rename(&backup, &save_file_path)?;
+            std::fs::rename(&swap_path, &backup)?;
+        } else {
+            std::fs::rename(&backup, &save_file_path)?;
+        }
+
+        log::info!("♻️ Restored backup {} for game: {}", index, game_id);
+        self.load(migrations)
+    }
+
+    pub fn save_to_slot(&self, slot: u32) -> Result<(), CacaoError> {
+        let game_id = self.current_game_id.as_ref()
+            .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
+
+        let slot_path = self.get_slot_file_path(game_id, slot);
+        self.write_save_file(&slot_path)
+    }
+
+    pub fn load_from_slot(&mut self, slot: u32, migrations: &SaveMigrations) -> Result<(), CacaoError> {
+        let game_id = self.current_game_id.as_ref()
+            .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
+
+        let slot_path = self.get_slot_file_path(game_id, slot);
+        if !slot_path.exists() {
+            return Err(CacaoError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Save slot {} is empty", slot),
+            )));
+        }
+
+        let save_file_data = self.read_save_file(&slot_path)?;
+        self.save_version = migrations.version();
+        let outdated = save_file_data.version != self.save_version || save_file_data.legacy_key;
+        self.current_save_data = migrations.migrate(save_file_data)?;
+        log::info!("Save slot {} loaded for game: {}", slot, game_id);
+
+        if outdated {
+            self.write_save_file(&slot_path)?;
+        }
+        Ok(())
+    }
+
+    pub fn list_slots(&self) -> Vec<SaveInfo> {
+        let game_id = match self.current_game_id.as_ref() {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        let entries = match std::fs::read_dir(self.get_game_save_dir(game_id)) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut slots = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let slot = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("slot_"))
+                .and_then(|name| name.strip_suffix(".dat"))
+                .and_then(|n| n.parse::<u32>().ok());
+
+            if let Some(slot) = slot {
+                match self.read_save_file(&path) {
+                    Ok(data) => slots.push(SaveInfo {
+                        slot,
+                        path,
+                        timestamp: data.timestamp,
+                        is_backup: false,
+                    }),
+                    Err(e) => log::warn!("⚠️ Skipping unreadable save slot {}: {}", slot, e),
+                }
+            }
+        }
+
+        slots.sort_by_key(|info| info.slot);
+        slots
+    }
+
+    /// Falls back to the newest readable backup when the main save can't be read.
+    /// The broken save is left in place; it gets rotated out on the next save.
+    fn recover_from_backup(&self, save_file_path: &Path, error: CacaoError) -> Result<SaveFileData, CacaoError> {
+        log::error!("❌ Save file unreadable: {}", error);
+
+        for index in 1..=MAX_BACKUPS {
+            let path = backup_path(save_file_path, index);
+            if !path.exists() {
+                continue;
+            }
+            match self.read_save_file(&path) {
+                Ok(data) => {
+                    log::warn!("♻️ Recovered save data from backup {}", index);
+                    return Ok(data);
+                }
+                Err(e) => log::warn!("⚠️ Backup {} is unreadable too: {}", index, e),
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Shifts every backup down one, dropping the oldest, and copies the save in as backup 1.
+    /// Does nothing before the first save, when there is nothing to keep.
+    fn rotate_backups(&self, save_file_path: &Path) -> Result<(), CacaoError> {
+        if !save_file_path.exists() {
+            return Ok(());
+        }
+
+        let oldest = backup_path(save_file_path, MAX_BACKUPS);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (1..MAX_BACKUPS).rev() {
+            let from = backup_path(save_file_path, index);
+            if from.exists() {
+                std::fs::rename(&from, backup_path(save_file_path, index + 1))?;
+            }
+        }
+        std::fs::copy(save_file_path, backup_path(save_file_path, 1))?;
+        Ok(())
+    }
+
+    fn write_save_file(&self, path: &Path) -> Result<(), CacaoError> {
+        let game_id = self.current_game_id.as_ref()
+            .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
+
         let encryption_key = self.encryption_key.as_ref()
             .ok_or_else(|| CacaoError::CryptoError("No encryption key available".to_string()))?;
 
         let save_file_data = SaveFileData {
-            version: 1,
+            version: self.save_version,
             game_id: game_id.clone(),
             data: self.current_save_data.clone(),
             checksum: self.calculate_checksum()?,
@@ -96,6 +389,7 @@ impl SaveManager {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            legacy_key: false,
         };
 
         let serialized_data = bincode::serialize(&save_file_data)
@@ -103,50 +397,59 @@ impl SaveManager {
 
         let encrypted_data = encrypt_data(&serialized_data, encryption_key)?;
 
-        let save_file_path = self.get_save_file_path(game_id);
-        std::fs::write(&save_file_path, &encrypted_data)?;
+        std::fs::write(path, &encrypted_data)?;
 
-        log::info!("Save data written to: {}", save_file_path.display());
+        log::info!("Save data written to: {}", path.display());
         Ok(())
     }
 
-    fn load_save_data(&mut self) -> Result<(), CacaoError> {
+    fn read_save_file(&self, path: &Path) -> Result<SaveFileData, CacaoError> {
         let game_id = self.current_game_id.as_ref()
             .ok_or_else(|| CacaoError::CryptoError("No game context set".to_string()))?;
 
         let encryption_key = self.encryption_key.as_ref()
             .ok_or_else(|| CacaoError::CryptoError("No encryption key available".to_string()))?;
 
-        let save_file_path = self.get_save_file_path(game_id);
-        
-        if !save_file_path.exists() {
-            log::info!("No existing save file found for game: {}", game_id);
-            return Ok(());
-        }
-
-        let encrypted_data = std::fs::read(&save_file_path)?;
-        let decrypted_data = decrypt_data(&encrypted_data, encryption_key)?;
+        let encrypted_data = std::fs::read(path)?;
+        let (decrypted_data, legacy_key) = match decrypt_data(&encrypted_data, encryption_key) {
+            Ok(data) => (data, false),
+            Err(CacaoError::SaveDecryptError(msg)) => {
+                let legacy = self.legacy_encryption_key.as_ref()
+                    .and_then(|key| decrypt_data(&encrypted_data, key).ok());
+                match legacy {
+                    Some(data) => (data, true),
+                    None => return Err(CacaoError::SaveDecryptError(msg)),
+                }
+            }
+            Err(e) => return Err(e),
+        };
 
-        let save_file_data: SaveFileData = bincode::deserialize(&decrypted_data)
-            .map_err(|e| CacaoError::CryptoError(format!("Failed to deserialize save data: {}", e)))?;
+        let mut save_file_data: SaveFileData = bincode::deserialize(&decrypted_data)
+            .map_err(|e| CacaoError::SaveCorruptError(format!("Failed to deserialize save data: {}", e)))?;
+        save_file_data.legacy_key = legacy_key;
 
         let expected_checksum = calculate_data_checksum(&save_file_data.data)?;
         if save_file_data.checksum != expected_checksum {
-            return Err(CacaoError::CryptoError("Save file checksum mismatch - data may be corrupted".to_string()));
+            return Err(CacaoError::SaveCorruptError("Save checksum mismatch (corrupted)".to_string()));
         }
 
         if save_file_data.game_id != *game_id {
             return Err(CacaoError::CryptoError("Save file game ID mismatch".to_string()));
         }
 
-        self.current_save_data = save_file_data.data;
-        log::info!("Save data loaded for game: {}", game_id);
-        Ok(())
+        Ok(save_file_data)
+    }
+
+    fn get_game_save_dir(&self, game_id: &str) -> PathBuf {
+        self.saves_dir.join(format!("{}_saves", sanitize_game_id(game_id)))
     }
 
     fn get_save_file_path(&self, game_id: &str) -> PathBuf {
-        let game_save_dir = self.saves_dir.join(format!("{}_saves", sanitize_game_id(game_id)));
-        game_save_dir.join("save.dat")
+        self.get_game_save_dir(game_id).join("save.dat")
+    }
+
+    fn get_slot_file_path(&self, game_id: &str, slot: u32) -> PathBuf {
+        self.get_game_save_dir(game_id).join(format!("slot_{}.dat", slot))
     }
 
     fn calculate_checksum(&self) -> Result<String, CacaoError> {
@@ -215,6 +518,61 @@ impl SaveManager {
     }
 }
 
+impl<'a> SaveMigrations<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a step that upgrades saves from version `from` to `to`.
+    pub fn register_migration(
+        &mut self,
+        from: u32,
+        to: u32,
+        migration: impl Fn(&mut HashMap<String, SaveValue>) -> Result<(), CacaoError> + 'a,
+    ) {
+        if to <= from {
+            log::warn!("⚠️ Ignoring save migration from {} to {}: it has to move forward", from, to);
+            return;
+        }
+        self.steps.push((from, to, Box::new(migration)));
+    }
+
+    pub fn version(&self) -> u32 {
+        self.steps.iter().map(|&(_, to, _)| to).max().unwrap_or(1)
+    }
+
+    /// Runs migrations until the data reaches `version()`.
+    fn migrate(&self, save_file_data: SaveFileData) -> Result<HashMap<String, SaveValue>, CacaoError> {
+        let target = self.version();
+        let mut version = save_file_data.version;
+        let mut data = save_file_data.data;
+
+        if version > target {
+            return Err(CacaoError::SaveVersionError(format!(
+                "Save file version {} is newer than the supported version {}", version, target
+            )));
+        }
+
+        while version < target {
+            let Some((_, to, migration)) = self.steps.iter().find(|(from, _, _)| *from == version) else {
+                return Err(CacaoError::SaveVersionError(format!("No migration for save file version {}", version)));
+            };
+            migration(&mut data)?;
+            log::info!("🔄 Migrated save data from version {} to {}", version, to);
+            version = *to;
+        }
+
+        Ok(data)
+    }
+}
+
+fn invalid_path(path: &str, reason: &str) -> CacaoError {
+    CacaoError::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid save path {}: {}", path, reason),
+    ))
+}
+
 fn encrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError> {
     let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| CacaoError::CryptoError(format!("Failed to init cipher: {:?}", e)))?;
@@ -235,7 +593,7 @@ fn encrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError> {
 
 fn decrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError> {
     if data.len() < 12 {
-        return Err(CacaoError::CryptoError("Invalid encrypted data: too short".to_string()));
+        return Err(CacaoError::SaveCorruptError("Invalid encrypted data: too short".to_string()));
     }
 
     let cipher = Aes256Gcm::new_from_slice(key)
@@ -244,21 +602,83 @@ fn decrypt_data(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CacaoError> {
     let nonce = Nonce::from_slice(&data[0..12]);
     let encrypted_data = &data[12..];
 
+    // GCM can't tell a wrong key from damaged ciphertext; a wrong key is far more common
     let decrypted = cipher.decrypt(nonce, encrypted_data)
-        .map_err(|e| CacaoError::CryptoError(format!("Decryption failed: {}", e)))?;
+        .map_err(|e| CacaoError::SaveDecryptError(format!("Save decrypt failed (wrong key?): {}", e)))?;
 
     Ok(decrypted)
 }
 
 fn calculate_data_checksum(data: &HashMap<String, SaveValue>) -> Result<String, CacaoError> {
-    let serialized = bincode::serialize(data)
-        .map_err(|e| CacaoError::CryptoError(format!("Failed to serialize data for checksum: {}", e)))?;
-    
+    // Hash keys in sorted order: HashMap iteration order changes between runs
     let mut hasher = Sha256::new();
-    hasher.update(&serialized);
+    hash_object(&mut hasher, data);
     Ok(format!("{:x}", hasher.finalize()))
 }
 
+fn hash_object(hasher: &mut Sha256, object: &HashMap<String, SaveValue>) {
+    let mut keys: Vec<&String> = object.keys().collect();
+    keys.sort();
+    hasher.update((keys.len() as u64).to_le_bytes());
+    for key in keys {
+        hasher.update((key.len() as u64).to_le_bytes());
+        hasher.update(key.as_bytes());
+        hash_value(hasher, &object[key]);
+    }
+}
+
+fn hash_value(hasher: &mut Sha256, value: &SaveValue) {
+    match value {
+        SaveValue::String(s) => {
+            hasher.update([0u8]);
+            hasher.update((s.len() as u64).to_le_bytes());
+            hasher.update(s.as_bytes());
+        }
+        SaveValue::Integer(i) => {
+            hasher.update([1u8]);
+            hasher.update(i.to_le_bytes());
+        }
+        SaveValue::Float(f) => {
+            hasher.update([2u8]);
+            hasher.update(f.to_le_bytes());
+        }
+        SaveValue::Boolean(b) => {
+            hasher.update([3u8, *b as u8]);
+        }
+        SaveValue::Array(items) => {
+            hasher.update([4u8]);
+            hasher.update((items.len() as u64).to_le_bytes());
+            for item in items {
+                hash_value(hasher, item);
+            }
+        }
+        SaveValue::Object(fields) => {
+            hasher.update([5u8]);
+            hash_object(hasher, fields);
+        }
+    }
+}
+
+fn backup_path(save_file_path: &Path, index: u32) -> PathBuf {
+    let mut name = save_file_path.as_os_str().to_owned();
+    name.push(format!(".bak.{}", index));
+    PathBuf::from(name)
+}
+
+/// The salt isn't secret; it only has to differ between games and stay put.
+fn load_or_create_salt(path: &Path) -> Result<[u8; SALT_LEN], CacaoError> {
+    if path.exists() {
+        let bytes = std::fs::read(path)?;
+        return bytes.try_into()
+            .map_err(|_| CacaoError::SaveCorruptError(format!("Invalid save salt: {}", path.display())));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    std::fs::write(path, salt)?;
+    Ok(salt)
+}
+
 fn sanitize_game_id(game_id: &str) -> String {
     game_id
         .chars()
@@ -269,7 +689,16 @@ fn sanitize_game_id(game_id: &str) -> String {
         .collect()
 }
 
-fn derive_encryption_key(secret_key: &str) -> [u8; 32] {
+/// PBKDF2 over the game's secret key and its own salt, so a leaked key for one game
+/// doesn't unlock another's saves.
+fn derive_encryption_key(secret_key: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(secret_key.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+/// The fixed-salt key saves were encrypted with before per-game salts.
+fn derive_legacy_encryption_key(secret_key: &str) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(secret_key.as_bytes());
     hasher.update(b"cacao_engine_salt");
@@ -277,4 +706,250 @@ fn derive_encryption_key(secret_key: &str) -> [u8; 32] {
     let mut key = [0u8; 32];
     key.copy_from_slice(&hash[..]);
     key
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_saves_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("cacao_saves_{}", uuid::Uuid::new_v4()))
+    }
+
+    fn manager_for(saves_dir: &Path, game_id: &str, secret_key: &str) -> SaveManager {
+        let mut saves = SaveManager::new(saves_dir.to_path_buf());
+        saves.set_game_context(game_id.to_string(), secret_key).unwrap();
+        saves.load(&SaveMigrations::new()).unwrap();
+        saves
+    }
+
+    fn backup_files(saves: &SaveManager) -> Vec<PathBuf> {
+        let save_file = saves.get_save_file_path(saves.current_game_id.as_ref().unwrap());
+        (1..=MAX_BACKUPS + 1).map(|i| backup_path(&save_file, i)).filter(|path| path.exists()).collect()
+    }
+
+    // The round stored in each backup, newest first
+    fn backup_rounds(saves: &SaveManager) -> Vec<i64> {
+        let save_file = saves.get_save_file_path(saves.current_game_id.as_ref().unwrap());
+        backup_files(saves)
+            .iter()
+            .map(|path| match saves.read_save_file(path).unwrap().data["round"] {
+                SaveValue::Integer(round) => round,
+                ref other => panic!("unexpected value {:?} in {}", other, save_file.display()),
+            })
+            .collect()
+    }
+
+    fn save_round(saves: &mut SaveManager, round: i64) {
+        saves.write_int("round".to_string(), round).unwrap();
+        saves.save_to_disk().unwrap();
+    }
+
+    #[test]
+    fn first_save_has_no_backup() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+        save_round(&mut saves, 0);
+        assert!(backup_files(&saves).is_empty());
+    }
+
+    #[test]
+    fn three_saves_leave_three_backups_and_the_fourth_drops_the_oldest() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+        save_round(&mut saves, 0);
+
+        for round in 1..=3 {
+            save_round(&mut saves, round);
+        }
+        // Each backup holds an earlier save, never a copy of the current one
+        assert_eq!(backup_rounds(&saves), vec![2, 1, 0]);
+
+        save_round(&mut saves, 4);
+        assert_eq!(backup_rounds(&saves), vec![3, 2, 1]);
+        assert!(saves.list_backups().iter().all(|info| info.is_backup));
+    }
+
+    #[test]
+    fn restore_backup_brings_back_an_older_save() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+
+        saves.write_string("name".to_string(), "first".to_string()).unwrap();
+        saves.save_to_disk().unwrap();
+        saves.write_string("name".to_string(), "second".to_string()).unwrap();
+        saves.save_to_disk().unwrap();
+
+        saves.restore_backup(1, &SaveMigrations::new()).unwrap();
+        assert_eq!(saves.read_string("name", ""), "first");
+        assert!(saves.restore_backup(MAX_BACKUPS + 1, &SaveMigrations::new()).is_err());
+    }
+
+    #[test]
+    fn slots_load_back_independently() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+
+        saves.write_string("level".to_string(), "forest".to_string()).unwrap();
+        saves.save_to_slot(0).unwrap();
+        saves.write_string("level".to_string(), "castle".to_string()).unwrap();
+        saves.save_to_slot(1).unwrap();
+
+        saves.load_from_slot(0, &SaveMigrations::new()).unwrap();
+        assert_eq!(saves.read_string("level", ""), "forest");
+        saves.load_from_slot(1, &SaveMigrations::new()).unwrap();
+        assert_eq!(saves.read_string("level", ""), "castle");
+
+        let slots: Vec<u32> = saves.list_slots().iter().map(|info| info.slot).collect();
+        assert_eq!(slots, vec![0, 1]);
+        assert!(saves.load_from_slot(2, &SaveMigrations::new()).is_err());
+    }
+
+    #[test]
+    fn deep_paths_create_objects_and_read_back() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+
+        saves.write_path("player.stats.hp", SaveValue::Integer(12)).unwrap();
+        saves.write_path("player.name", SaveValue::String("Ada".to_string())).unwrap();
+
+        assert_eq!(saves.read_path("player.stats.hp"), Some(&SaveValue::Integer(12)));
+        assert_eq!(saves.read_path("player.name"), Some(&SaveValue::String("Ada".to_string())));
+        assert!(matches!(saves.read("player"), Some(SaveValue::Object(fields)) if fields.len() == 2));
+    }
+
+    #[test]
+    fn missing_or_blocked_paths_are_reported() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+        saves.write_path("player.name", SaveValue::String("Ada".to_string())).unwrap();
+
+        assert_eq!(saves.read_path("player.stats.hp"), None);
+        assert_eq!(saves.read_path("player.name.first"), None);
+        assert!(saves.write_path("player.name.first", SaveValue::Boolean(true)).is_err());
+        assert!(saves.write_path("player..hp", SaveValue::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn wrong_key_is_a_decrypt_error() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+        saves.write_int("coins".to_string(), 5).unwrap();
+        saves.save_to_disk().unwrap();
+
+        let mut other = SaveManager::new(dir.clone());
+        other.set_game_context("game".to_string(), "other key").unwrap();
+        let error = other.load(&SaveMigrations::new()).unwrap_err();
+        assert!(matches!(error, CacaoError::SaveDecryptError(_)), "unexpected error: {:?}", error);
+    }
+
+    fn read_error(saves: &SaveManager, path: &Path) -> CacaoError {
+        match saves.read_save_file(path) {
+            Ok(_) => panic!("{} should not have been readable", path.display()),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn checksum_mismatch_is_a_corrupt_error() {
+        let dir = temp_saves_dir();
+        let saves = manager_for(&dir, "game", "key");
+
+        // Decrypts fine, but the data doesn't match its checksum
+        let tampered = SaveFileData {
+            version: 1,
+            game_id: "game".to_string(),
+            data: HashMap::from([("coins".to_string(), SaveValue::Integer(9999))]),
+            checksum: calculate_data_checksum(&HashMap::new()).unwrap(),
+            timestamp: 0,
+            legacy_key: false,
+        };
+        let bytes = encrypt_data(&bincode::serialize(&tampered).unwrap(), saves.encryption_key.as_ref().unwrap()).unwrap();
+        let path = saves.get_save_file_path("game");
+        std::fs::write(&path, bytes).unwrap();
+
+        let error = read_error(&saves, &path);
+        assert!(matches!(error, CacaoError::SaveCorruptError(_)), "unexpected error: {:?}", error);
+
+        std::fs::write(&path, b"short").unwrap();
+        let error = read_error(&saves, &path);
+        assert!(matches!(error, CacaoError::SaveCorruptError(_)), "unexpected error: {:?}", error);
+    }
+
+    #[test]
+    fn games_sharing_a_secret_key_get_different_save_keys() {
+        let dir = temp_saves_dir();
+        let first = manager_for(&dir, "first", "shared key");
+        let second = manager_for(&dir, "second", "shared key");
+        assert_ne!(first.encryption_key, second.encryption_key);
+
+        // The salt is kept, so the same game gets the same key next time
+        let again = manager_for(&dir, "first", "shared key");
+        assert_eq!(first.encryption_key, again.encryption_key);
+    }
+
+    fn rename_coins_to_gold<'a>() -> SaveMigrations<'a> {
+        let mut migrations = SaveMigrations::new();
+        migrations.register_migration(1, 2, |data| {
+            if let Some(coins) = data.remove("coins") {
+                data.insert("gold".to_string(), coins);
+            }
+            Ok(())
+        });
+        migrations
+    }
+
+    fn file_version(saves: &SaveManager) -> u32 {
+        saves.read_save_file(&saves.get_save_file_path("game")).unwrap().version
+    }
+
+    #[test]
+    fn older_saves_are_migrated_on_load_and_rewritten() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "game", "key");
+        saves.write_int("coins".to_string(), 7).unwrap();
+        saves.save_to_disk().unwrap();
+        assert_eq!(file_version(&saves), 1);
+
+        let mut saves = SaveManager::new(dir.clone());
+        saves.set_game_context("game".to_string(), "key").unwrap();
+        saves.load(&rename_coins_to_gold()).unwrap();
+
+        assert_eq!(saves.read("gold"), Some(&SaveValue::Integer(7)));
+        assert_eq!(saves.read("coins"), None);
+        assert_eq!(file_version(&saves), 2);
+        // The v1 original is kept as a backup
+        assert_eq!(backup_files(&saves).len(), 1);
+    }
+
+    #[test]
+    fn newer_saves_are_a_version_error() {
+        let dir = temp_saves_dir();
+        let mut saves = SaveManager::new(dir.clone());
+        saves.set_game_context("game".to_string(), "key").unwrap();
+        saves.load(&rename_coins_to_gold()).unwrap();
+        saves.write_int("gold".to_string(), 7).unwrap();
+        saves.save_to_disk().unwrap();
+
+        let mut saves = SaveManager::new(dir.clone());
+        saves.set_game_context("game".to_string(), "key").unwrap();
+        let error = saves.load(&SaveMigrations::new()).unwrap_err();
+        assert!(matches!(error, CacaoError::SaveVersionError(_)), "unexpected error: {:?}", error);
+    }
+
+    #[test]
+    fn migrations_belong_to_the_game_that_loads_them() {
+        let dir = temp_saves_dir();
+        let mut saves = manager_for(&dir, "first", "key");
+        saves.load(&rename_coins_to_gold()).unwrap();
+        saves.write_int("coins".to_string(), 1).unwrap();
+        saves.save_to_disk().unwrap();
+
+        // Another game on the same manager still saves at version 1
+        saves.set_game_context("second".to_string(), "key").unwrap();
+        saves.load(&SaveMigrations::new()).unwrap();
+        saves.write_int("coins".to_string(), 1).unwrap();
+        saves.save_to_disk().unwrap();
+        assert_eq!(saves.read_save_file(&saves.get_save_file_path("second")).unwrap().version, 1);
+    }
+}
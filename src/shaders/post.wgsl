@@ -0,0 +1,63 @@
+// src/shaders/post.wgsl
+struct PostParams {
+    values: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> params: PostParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle big enough to cover the screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+// values: threshold, intensity, radius in pixels
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(source, source_sampler, in.uv);
+    let step = params.values.z * 0.5 / vec2<f32>(textureDimensions(source));
+
+    var glow = vec3<f32>(0.0);
+    var total = 0.0;
+    for (var x = -2; x <= 2; x = x + 1) {
+        for (var y = -2; y <= 2; y = y + 1) {
+            let weight = exp(-f32(x * x + y * y) / 4.0);
+            let sample = textureSample(source, source_sampler, in.uv + vec2<f32>(f32(x), f32(y)) * step).rgb;
+            glow = glow + max(sample - vec3<f32>(params.values.x), vec3<f32>(0.0)) * weight;
+            total = total + weight;
+        }
+    }
+
+    return vec4<f32>(base.rgb + glow / total * params.values.y, base.a);
+}
+
+// values: strength
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(source, source_sampler, in.uv);
+    let shade = 1.0 - smoothstep(0.4, 0.85, distance(in.uv, vec2<f32>(0.5))) * params.values.x;
+    return vec4<f32>(base.rgb * shade, base.a);
+}
+
+// values: strength, spacing in pixels
+@fragment
+fn fs_scanlines(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(source, source_sampler, in.uv);
+    let wave = 0.5 + 0.5 * cos(in.clip_position.y * 6.2831853 / max(params.values.y, 1.0));
+    let shade = 1.0 - wave * params.values.x;
+    return vec4<f32>(base.rgb * shade, base.a);
+}
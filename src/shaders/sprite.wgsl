@@ -4,28 +4,47 @@ struct VertexInput {
     @location(1) tex_coords: vec2<f32>,
 }
 
+// One instance per sprite; the transform arrives as four columns
+struct InstanceInput {
+    @location(2) transform_0: vec4<f32>,
+    @location(3) transform_1: vec4<f32>,
+    @location(4) transform_2: vec4<f32>,
+    @location(5) transform_3: vec4<f32>,
+    @location(6) color: vec4<f32>,
+    // Region of the texture to sample: xy is the top-left corner, zw the size
+    @location(7) uv_rect: vec4<f32>,
+}
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
 }
 
-struct SpriteUniform {
+struct CameraUniform {
     view_proj: mat4x4<f32>,
-    transform: mat4x4<f32>,
-    color: vec4<f32>,
 }
 
 @group(0) @binding(0)
-var<uniform> sprite_uniform: SpriteUniform;
+var<uniform> camera: CameraUniform;
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
+    let transform = mat4x4<f32>(
+        instance.transform_0,
+        instance.transform_1,
+        instance.transform_2,
+        instance.transform_3,
+    );
+
     var out: VertexOutput;
-    let world_position = sprite_uniform.transform * vec4<f32>(model.position, 0.0, 1.0);
-    out.clip_position = sprite_uniform.view_proj * world_position;
-    out.tex_coords = model.tex_coords;
+    let world_position = transform * vec4<f32>(model.position, 0.0, 1.0);
+    out.clip_position = camera.view_proj * world_position;
+    out.tex_coords = instance.uv_rect.xy + model.tex_coords * instance.uv_rect.zw;
+    out.color = instance.color;
     return out;
 }
 
@@ -38,5 +57,7 @@ var s_diffuse: sampler;
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let tex_color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    return tex_color * sprite_uniform.color;
-}
\ No newline at end of file
+    // Textures are premultiplied on upload, so the straight-alpha tint has to be too
+    let tint = vec4<f32>(in.color.rgb * in.color.a, in.color.a);
+    return tex_color * tint;
+}